@@ -0,0 +1,222 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input,
+    parse_quote,
+    Data,
+    DeriveInput,
+    Error,
+    Fields,
+    Index,
+};
+
+#[proc_macro_derive(FixedLayout)]
+pub fn derive_fixed_layout(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_fixed_layout(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(EncodedSize)]
+pub fn derive_encoded_size(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_encoded_size(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn fields_size(fields: &Fields) -> (TokenStream2, TokenStream2) {
+    let types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let min = quote! {
+        0 #(+ <#types as ::abcode::size::EncodedSize>::MIN_SIZE)*
+    };
+    let max = quote! {
+        {
+            let max = ::std::option::Option::Some(0);
+            #(
+                let max = ::abcode::size::add_max(
+                    max,
+                    <#types as ::abcode::size::EncodedSize>::MAX_SIZE,
+                );
+            )*
+            max
+        }
+    };
+    (min, max)
+}
+
+fn expand_encoded_size(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let (min, max) = match &input.data {
+        Data::Struct(data) => fields_size(&data.fields),
+        Data::Enum(data) if data.variants.is_empty() => {
+            (quote!(0), quote!(::std::option::Option::Some(0)))
+        },
+        Data::Enum(data) => {
+            let sizes: Vec<_> = data
+                .variants
+                .iter()
+                .map(|variant| fields_size(&variant.fields))
+                .collect();
+            let mins = sizes.iter().map(|(min, _)| min);
+            let maxes = sizes.iter().map(|(_, max)| max);
+            let min = quote! {
+                {
+                    let min = usize::MAX;
+                    #(let min = ::abcode::size::smaller_min(min, #mins);)*
+                    4 + min
+                }
+            };
+            let max = quote! {
+                {
+                    let max = ::std::option::Option::Some(0);
+                    #(let max = ::abcode::size::larger_max(max, #maxes);)*
+                    ::abcode::size::add_max(::std::option::Option::Some(4), max)
+                }
+            };
+            (min, max)
+        },
+        Data::Union(_) => Err(Error::new_spanned(
+            &input.ident,
+            "EncodedSize cannot be derived for unions",
+        ))?,
+    };
+
+    let types: Vec<_> = match &input.data {
+        Data::Struct(data) => {
+            data.fields.iter().map(|f| f.ty.clone()).collect()
+        },
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter().map(|f| f.ty.clone()))
+            .collect(),
+        Data::Union(_) => Vec::new(),
+    };
+    if !input.generics.params.is_empty() {
+        let where_clause = input.generics.make_where_clause();
+        for ty in types {
+            where_clause
+                .predicates
+                .push(parse_quote!(#ty: ::abcode::size::EncodedSize));
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::abcode::size::EncodedSize
+            for #name #ty_generics #where_clause
+        {
+            const MIN_SIZE: usize = #min;
+            const MAX_SIZE: ::std::option::Option<usize> = #max;
+        }
+    })
+}
+
+fn expand_fixed_layout(input: DeriveInput) -> Result<TokenStream2, Error> {
+    if !input.generics.params.is_empty() {
+        Err(Error::new_spanned(
+            &input.generics,
+            "FixedLayout cannot be derived for generic types",
+        ))?;
+    }
+    let Data::Struct(data) = &input.data else {
+        Err(Error::new_spanned(
+            &input.ident,
+            "FixedLayout can only be derived for structs",
+        ))?
+    };
+
+    let name = &input.ident;
+    let types: Vec<_> = data.fields.iter().map(|field| &field.ty).collect();
+    let members: Vec<_> = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = Index::from(index);
+                quote!(#index)
+            },
+        })
+        .collect();
+    let construct = match &data.fields {
+        Fields::Unit => quote!(Self),
+        _ => {
+            let decoders = types.iter().map(|ty| {
+                quote! {
+                    {
+                        let size = <#ty as FixedLayout>::SIZE;
+                        let value = <#ty as FixedLayout>::decode_fixed(
+                            &buf[offset .. offset + size],
+                        )?;
+                        offset += size;
+                        value
+                    }
+                }
+            });
+            let names: Vec<_> = (0 .. types.len())
+                .map(|index| format_ident!("field{}", index))
+                .collect();
+            let assign = match &data.fields {
+                Fields::Named(_) => quote!(Self { #(#members: #names,)* }),
+                _ => quote!(Self(#(#names,)*)),
+            };
+            quote! {
+                let mut offset = 0;
+                #(let #names = #decoders;)*
+                let _ = offset;
+                #assign
+            }
+        },
+    };
+
+    Ok(quote! {
+        impl ::abcode::fixed::FixedLayout for #name {
+            const SIZE: usize =
+                0 #(+ <#types as ::abcode::fixed::FixedLayout>::SIZE)*;
+
+            fn encode_fixed(&self, buf: &mut [u8]) {
+                use ::abcode::fixed::FixedLayout;
+                let mut offset = 0;
+                #(
+                    let size = <#types as FixedLayout>::SIZE;
+                    FixedLayout::encode_fixed(
+                        &self.#members,
+                        &mut buf[offset .. offset + size],
+                    );
+                    offset += size;
+                )*
+                let _ = (buf, offset);
+            }
+
+            fn decode_fixed(
+                buf: &[u8],
+            ) -> ::std::result::Result<Self, ::abcode::de::Error> {
+                use ::abcode::fixed::FixedLayout;
+                let buf = buf
+                    .get(.. <Self as FixedLayout>::SIZE)
+                    .ok_or(::abcode::de::Error::PrematureEof)?;
+                let _ = buf;
+                Ok({ #construct })
+            }
+        }
+
+        impl #name {
+            pub const ENCODED_SIZE: usize =
+                <Self as ::abcode::fixed::FixedLayout>::SIZE;
+
+            pub fn to_bytes(&self) -> [u8; Self::ENCODED_SIZE] {
+                let mut bytes = [0; Self::ENCODED_SIZE];
+                ::abcode::fixed::FixedLayout::encode_fixed(self, &mut bytes);
+                bytes
+            }
+
+            pub fn from_bytes(
+                bytes: &[u8; Self::ENCODED_SIZE],
+            ) -> ::std::result::Result<Self, ::abcode::de::Error> {
+                ::abcode::fixed::FixedLayout::decode_fixed(bytes)
+            }
+        }
+    })
+}
@@ -0,0 +1,225 @@
+use std::{io::SeekFrom, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+use tokio::io::{
+    AsyncRead,
+    AsyncReadExt,
+    AsyncSeek,
+    AsyncSeekExt,
+    AsyncWrite,
+    AsyncWriteExt,
+};
+
+use crate::{
+    de,
+    index::{self, FOOTER_SIZE},
+    ser,
+};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Failed to write archive")]
+    Ser(
+        #[from]
+        #[source]
+        ser::Error,
+    ),
+    #[error("Failed to read archive")]
+    De(
+        #[from]
+        #[source]
+        de::Error,
+    ),
+    #[error("Archive key at position {0} was inserted more than once")]
+    DuplicateKey(usize),
+}
+
+#[derive(Debug)]
+pub struct ArchiveBuilder<W, K> {
+    device: W,
+    config: ser::Config,
+    offset: u64,
+    index: Vec<(K, u64, u64)>,
+}
+
+impl<W, K> ArchiveBuilder<W, K>
+where
+    W: AsyncWrite + Unpin,
+    K: Serialize + Ord,
+{
+    pub fn new(device: W) -> Self {
+        Self::with_config(device, ser::Config::default())
+    }
+
+    pub fn with_config(device: W, config: ser::Config) -> Self {
+        Self { device, config, offset: 0, index: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub async fn insert<V>(&mut self, key: K, value: &V) -> Result<(), Error>
+    where
+        V: ?Sized + Serialize,
+    {
+        let record = self.config.serialize_into_buffer(value)?;
+        self.device.write_all(&record).await.map_err(ser::Error::from)?;
+        let len = record.len() as u64;
+        self.index.push((key, self.offset, len));
+        self.offset += len;
+        Ok(())
+    }
+
+    pub async fn finish(mut self) -> Result<W, Error> {
+        self.index.sort_by(|(left, ..), (right, ..)| left.cmp(right));
+        let duplicate =
+            self.index.windows(2).position(|window| window[0].0 == window[1].0);
+        if let Some(position) = duplicate {
+            Err(Error::DuplicateKey(position + 1))?;
+        }
+        let mut trailer = self.config.serialize_into_buffer(&self.index)?;
+        trailer.extend_from_slice(&index::encode_footer(
+            index::ARCHIVE_MAGIC,
+            self.offset,
+            self.index.len() as u64,
+        ));
+        self.device.write_all(&trailer).await.map_err(ser::Error::from)?;
+        self.device.flush().await.map_err(ser::Error::from)?;
+        Ok(self.device)
+    }
+}
+
+#[derive(Debug)]
+pub struct Archive<R, K> {
+    device: R,
+    config: de::Config,
+    index: Vec<(K, u64, u64)>,
+}
+
+impl<R, K> Archive<R, K>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+    K: DeserializeOwned + Ord,
+{
+    pub async fn open(device: R) -> Result<Self, Error> {
+        Self::open_with_config(device, de::Config::default()).await
+    }
+
+    pub async fn open_with_config(
+        mut device: R,
+        config: de::Config,
+    ) -> Result<Self, Error> {
+        let size =
+            device.seek(SeekFrom::End(0)).await.map_err(de::Error::from)?;
+        let end = size
+            .checked_sub(FOOTER_SIZE as u64)
+            .ok_or(de::Error::MissingIndex)?;
+        let mut footer = [0; FOOTER_SIZE];
+        device.seek(SeekFrom::Start(end)).await.map_err(de::Error::from)?;
+        device.read_exact(&mut footer).await.map_err(de::Error::from)?;
+        let (index_offset, _) =
+            index::decode_footer(index::ARCHIVE_MAGIC, &footer)
+                .ok_or(de::Error::MissingIndex)?;
+        let index_len =
+            end.checked_sub(index_offset).ok_or(de::Error::MissingIndex)?;
+        let index_len = usize::try_from(index_len)
+            .map_err(|_| de::Error::ExcessiveSize(index_len))?;
+        let mut buf = vec![0; index_len];
+        device
+            .seek(SeekFrom::Start(index_offset))
+            .await
+            .map_err(de::Error::from)?;
+        device.read_exact(&mut buf).await.map_err(de::Error::from)?;
+        let index = config.deserialize_buffer(&buf)?;
+        Ok(Self { device, config, index })
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.index.iter().map(|(key, ..)| key)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.position(key).is_some()
+    }
+
+    pub async fn get<V>(&mut self, key: &K) -> Result<Option<V>, Error>
+    where
+        V: DeserializeOwned,
+    {
+        match self.position(key) {
+            Some(position) => self.read_at(position).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn entries<V>(&mut self) -> Entries<'_, R, K, V> {
+        Entries { archive: self, position: 0, marker: PhantomData }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.device
+    }
+
+    fn position(&self, key: &K) -> Option<usize> {
+        self.index.binary_search_by(|(probe, ..)| probe.cmp(key)).ok()
+    }
+
+    async fn read_at<V>(&mut self, position: usize) -> Result<V, Error>
+    where
+        V: DeserializeOwned,
+    {
+        let (_, offset, len) = &self.index[position];
+        let len = usize::try_from(*len)
+            .map_err(|_| de::Error::ExcessiveSize(*len))?;
+        let mut buf = vec![0; len];
+        self.device
+            .seek(SeekFrom::Start(*offset))
+            .await
+            .map_err(de::Error::from)?;
+        self.device.read_exact(&mut buf).await.map_err(de::Error::from)?;
+        Ok(self.config.deserialize_buffer(&buf)?)
+    }
+}
+
+#[derive(Debug)]
+pub struct Entries<'a, R, K, V> {
+    archive: &'a mut Archive<R, K>,
+    position: usize,
+    marker: PhantomData<fn() -> V>,
+}
+
+impl<'a, R, K, V> Entries<'a, R, K, V>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+    K: DeserializeOwned + Ord,
+    V: DeserializeOwned,
+{
+    pub async fn next(&mut self) -> Option<Result<(&K, V), Error>> {
+        let position = self.position;
+        if position >= self.archive.len() {
+            return None;
+        }
+        self.position += 1;
+        match self.archive.read_at(position).await {
+            Ok(value) => Some(Ok((&self.archive.index[position].0, value))),
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::io::Cursor;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{Archive, ArchiveBuilder, Error};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    payload: Vec<u16>,
+}
+
+fn snapshot(version: u32) -> Snapshot {
+    Snapshot { version, payload: (0 .. version as u16 % 50).collect() }
+}
+
+#[tokio::test]
+async fn build_and_lookup() -> Result<()> {
+    let mut builder = ArchiveBuilder::new(Vec::new());
+    for version in (0 .. 100).rev() {
+        builder
+            .insert(format!("snap-{:03}", version), &snapshot(version))
+            .await?;
+    }
+    assert_eq!(builder.len(), 100);
+    let buf = builder.finish().await?;
+
+    let mut archive = Archive::<_, String>::open(Cursor::new(buf)).await?;
+    assert_eq!(archive.len(), 100);
+    assert!(archive.contains_key(&"snap-042".to_owned()));
+    assert_eq!(archive.keys().next().map(String::as_str), Some("snap-000"));
+
+    let found: Option<Snapshot> = archive.get(&"snap-042".to_owned()).await?;
+    assert_eq!(found, Some(snapshot(42)));
+    let missing: Option<Snapshot> = archive.get(&"snap-100".to_owned()).await?;
+    assert_eq!(missing, None);
+
+    let mut entries = archive.entries::<Snapshot>();
+    let mut count = 0;
+    while let Some(entry) = entries.next().await {
+        let (key, value) = entry?;
+        assert_eq!(key, &format!("snap-{:03}", count));
+        assert_eq!(value, snapshot(count));
+        count += 1;
+    }
+    assert_eq!(count, 100);
+    Ok(())
+}
+
+#[tokio::test]
+async fn archive_errors() -> Result<()> {
+    let mut builder = ArchiveBuilder::new(Vec::new());
+    builder.insert(1_u8, "a").await?;
+    builder.insert(2_u8, "b").await?;
+    builder.insert(1_u8, "c").await?;
+    assert!(matches!(builder.finish().await, Err(Error::DuplicateKey(1))));
+
+    let plain = crate::serialize_into_buffer(vec![(1_u8, "a")])?;
+    let error = Archive::<_, u8>::open(Cursor::new(plain)).await.unwrap_err();
+    assert!(matches!(error, Error::De(crate::de::Error::MissingIndex)));
+
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_self_describing();
+    let mut builder = ArchiveBuilder::with_config(Vec::new(), ser_config);
+    builder.insert(7_u64, &snapshot(7)).await?;
+    let buf = builder.finish().await?;
+    let mut de_config = crate::de::Config::new();
+    de_config.with_self_describing().with_hard_eof();
+    let mut archive =
+        Archive::<_, u64>::open_with_config(Cursor::new(buf), de_config)
+            .await?;
+    let found: Option<Snapshot> = archive.get(&7).await?;
+    assert_eq!(found, Some(snapshot(7)));
+    Ok(())
+}
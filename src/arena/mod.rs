@@ -0,0 +1,342 @@
+use std::{fmt, marker::PhantomData};
+
+use bumpalo::{collections, Bump};
+use serde::de::{
+    self,
+    Deserialize,
+    DeserializeSeed,
+    Deserializer,
+    SeqAccess,
+    Visitor,
+};
+
+use crate::de::{Config, Error};
+
+#[cfg(test)]
+mod test;
+
+pub trait ArenaDeserialize<'bump>: Sized {
+    fn deserialize_in<'de, D>(
+        arena: &'bump Bump,
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>;
+}
+
+#[derive(Debug)]
+pub struct ArenaSeed<'bump, T> {
+    arena: &'bump Bump,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'bump, T> ArenaSeed<'bump, T> {
+    pub fn new(arena: &'bump Bump) -> Self {
+        Self { arena, marker: PhantomData }
+    }
+}
+
+impl<'bump, T> Clone for ArenaSeed<'bump, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'bump, T> Copy for ArenaSeed<'bump, T> {}
+
+impl<'de, 'bump, T> DeserializeSeed<'de> for ArenaSeed<'bump, T>
+where
+    T: ArenaDeserialize<'bump>,
+{
+    type Value = T;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize_in(self.arena, deserializer)
+    }
+}
+
+pub fn deserialize_buffer_in<'bump, T>(
+    arena: &'bump Bump,
+    buf: &[u8],
+) -> Result<T, Error>
+where
+    T: ArenaDeserialize<'bump>,
+{
+    Config::default().deserialize_buffer_in(arena, buf)
+}
+
+macro_rules! arena_deserialize_plain {
+    ($($ty:ty),*) => {
+        $(
+            impl<'bump> ArenaDeserialize<'bump> for $ty {
+                fn deserialize_in<'de, D>(
+                    _arena: &'bump Bump,
+                    deserializer: D,
+                ) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    Deserialize::deserialize(deserializer)
+                }
+            }
+        )*
+    };
+}
+
+arena_deserialize_plain!(
+    (),
+    bool,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    f32,
+    f64,
+    char
+);
+
+struct StrVisitor<'bump> {
+    arena: &'bump Bump,
+}
+
+impl<'de, 'bump> Visitor<'de> for StrVisitor<'bump> {
+    type Value = &'bump str;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.arena.alloc_str(v))
+    }
+}
+
+impl<'bump> ArenaDeserialize<'bump> for &'bump str {
+    fn deserialize_in<'de, D>(
+        arena: &'bump Bump,
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(StrVisitor { arena })
+    }
+}
+
+impl<'bump> ArenaDeserialize<'bump> for collections::String<'bump> {
+    fn deserialize_in<'de, D>(
+        arena: &'bump Bump,
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = <&str>::deserialize_in(arena, deserializer)?;
+        Ok(collections::String::from_str_in(string, arena))
+    }
+}
+
+struct BytesVisitor<'bump> {
+    arena: &'bump Bump,
+}
+
+impl<'de, 'bump> Visitor<'de> for BytesVisitor<'bump> {
+    type Value = &'bump [u8];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a byte array")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.arena.alloc_slice_copy(v))
+    }
+}
+
+impl<'bump> ArenaDeserialize<'bump> for &'bump [u8] {
+    fn deserialize_in<'de, D>(
+        arena: &'bump Bump,
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(BytesVisitor { arena })
+    }
+}
+
+struct VecVisitor<'bump, T> {
+    arena: &'bump Bump,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'de, 'bump, T> Visitor<'de> for VecVisitor<'bump, T>
+where
+    T: ArenaDeserialize<'bump> + 'bump,
+{
+    type Value = collections::Vec<'bump, T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let capacity = seq.size_hint().unwrap_or(0).min(4096);
+        let mut elements =
+            collections::Vec::with_capacity_in(capacity, self.arena);
+        while let Some(element) =
+            seq.next_element_seed(ArenaSeed::new(self.arena))?
+        {
+            elements.push(element);
+        }
+        Ok(elements)
+    }
+}
+
+impl<'bump, T> ArenaDeserialize<'bump> for collections::Vec<'bump, T>
+where
+    T: ArenaDeserialize<'bump> + 'bump,
+{
+    fn deserialize_in<'de, D>(
+        arena: &'bump Bump,
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(VecVisitor { arena, marker: PhantomData })
+    }
+}
+
+struct OptionVisitor<'bump, T> {
+    arena: &'bump Bump,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'de, 'bump, T> Visitor<'de> for OptionVisitor<'bump, T>
+where
+    T: ArenaDeserialize<'bump>,
+{
+    type Value = Option<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an option")
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize_in(self.arena, deserializer).map(Some)
+    }
+}
+
+impl<'bump, T> ArenaDeserialize<'bump> for Option<T>
+where
+    T: ArenaDeserialize<'bump>,
+{
+    fn deserialize_in<'de, D>(
+        arena: &'bump Bump,
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer
+            .deserialize_option(OptionVisitor { arena, marker: PhantomData })
+    }
+}
+
+macro_rules! arena_deserialize_tuple {
+    ($len:literal => $($name:ident),+) => {
+        impl<'bump, $($name),+> ArenaDeserialize<'bump> for ($($name,)+)
+        where
+            $($name: ArenaDeserialize<'bump>,)+
+        {
+            fn deserialize_in<'de, D>(
+                arena: &'bump Bump,
+                deserializer: D,
+            ) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                struct TupleVisitor<'bump, $($name),+> {
+                    arena: &'bump Bump,
+                    marker: PhantomData<fn() -> ($($name,)+)>,
+                }
+
+                impl<'de, 'bump, $($name),+> Visitor<'de>
+                    for TupleVisitor<'bump, $($name),+>
+                where
+                    $($name: ArenaDeserialize<'bump>,)+
+                {
+                    type Value = ($($name,)+);
+
+                    fn expecting(
+                        &self,
+                        formatter: &mut fmt::Formatter,
+                    ) -> fmt::Result {
+                        write!(formatter, "a tuple of size {}", $len)
+                    }
+
+                    #[allow(non_snake_case)]
+                    fn visit_seq<A>(
+                        self,
+                        mut seq: A,
+                    ) -> Result<Self::Value, A::Error>
+                    where
+                        A: SeqAccess<'de>,
+                    {
+                        let mut index = 0;
+                        $(
+                            let $name = seq
+                                .next_element_seed(ArenaSeed::new(self.arena))?
+                                .ok_or_else(|| {
+                                    de::Error::invalid_length(index, &self)
+                                })?;
+                            index += 1;
+                        )+
+                        let _ = index;
+                        Ok(($($name,)+))
+                    }
+                }
+
+                deserializer.deserialize_tuple(
+                    $len,
+                    TupleVisitor { arena, marker: PhantomData },
+                )
+            }
+        }
+    };
+}
+
+arena_deserialize_tuple!(1 => T0);
+arena_deserialize_tuple!(2 => T0, T1);
+arena_deserialize_tuple!(3 => T0, T1, T2);
+arena_deserialize_tuple!(4 => T0, T1, T2, T3);
+arena_deserialize_tuple!(5 => T0, T1, T2, T3, T4);
+arena_deserialize_tuple!(6 => T0, T1, T2, T3, T4, T5);
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use bumpalo::{collections, Bump};
+use serde::{Deserializer, Serialize};
+
+use super::{deserialize_buffer_in, ArenaDeserialize};
+
+#[derive(Debug, Serialize)]
+struct OwnedRecord {
+    name: String,
+    tags: Vec<String>,
+    blob: serde_bytes_like::Bytes,
+    parent: Option<u32>,
+}
+
+mod serde_bytes_like {
+    use serde::{Serialize, Serializer};
+
+    #[derive(Debug)]
+    pub struct Bytes(pub Vec<u8>);
+
+    impl Serialize for Bytes {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Record<'bump> {
+    name: &'bump str,
+    tags: collections::Vec<'bump, &'bump str>,
+    blob: &'bump [u8],
+    parent: Option<u32>,
+}
+
+impl<'bump> ArenaDeserialize<'bump> for Record<'bump> {
+    fn deserialize_in<'de, D>(
+        arena: &'bump Bump,
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (name, tags, blob, parent) =
+            ArenaDeserialize::deserialize_in(arena, deserializer)?;
+        Ok(Self { name, tags, blob, parent })
+    }
+}
+
+fn owned(index: u32) -> OwnedRecord {
+    OwnedRecord {
+        name: format!("record-{}", index),
+        tags: (0 .. index % 4).map(|tag| format!("tag-{}", tag)).collect(),
+        blob: serde_bytes_like::Bytes(vec![index as u8; 3]),
+        parent: index.checked_sub(1),
+    }
+}
+
+#[test]
+fn records_in_arena() -> Result<()> {
+    let records: Vec<_> = (0 .. 50).map(owned).collect();
+    let buf = crate::serialize_into_buffer(&records)?;
+
+    let arena = Bump::new();
+    let decoded: collections::Vec<Record> =
+        deserialize_buffer_in(&arena, &buf)?;
+    assert_eq!(decoded.len(), records.len());
+    for (record, expected) in decoded.iter().zip(&records) {
+        assert_eq!(record.name, expected.name);
+        assert_eq!(record.tags.len(), expected.tags.len());
+        assert!(record.tags.iter().zip(&expected.tags).all(|(a, b)| a == b));
+        assert_eq!(record.blob, &expected.blob.0[..]);
+        assert_eq!(record.parent, expected.parent);
+    }
+    assert!(arena.allocated_bytes() > 0);
+    Ok(())
+}
+
+#[test]
+fn arena_respects_config() -> Result<()> {
+    let buf = crate::serialize_into_buffer(("abc", 1_u8))?;
+    let arena = Bump::new();
+
+    let error = crate::de::Config::new()
+        .with_hard_eof()
+        .deserialize_buffer_in::<&str>(&arena, &buf)
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::TrailingBytes { count: 1 }
+    ));
+
+    let (text, byte): (collections::String, u8) =
+        crate::de::Config::new().deserialize_buffer_in(&arena, &buf)?;
+    assert_eq!(text, "abc");
+    assert_eq!(byte, 1);
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use crate::de::Error;
+
+#[cfg(test)]
+mod test;
+
+const ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const PAD: u8 = b'=';
+const BEGIN: &str = "-----BEGIN ";
+const END: &str = "-----END ";
+const DASHES: &str = "-----";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Armor {
+    label: Option<String>,
+    line_width: Option<usize>,
+}
+
+impl Armor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_header(&mut self, label: impl Into<String>) -> &mut Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_line_width(&mut self, line_width: usize) -> &mut Self {
+        self.line_width = Some(line_width.max(4) / 4 * 4);
+        self
+    }
+
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let body = encode_base64(bytes);
+        let mut text = String::with_capacity(body.len() + body.len() / 32);
+        if let Some(label) = &self.label {
+            text.extend([BEGIN, label, DASHES, "\n"]);
+        }
+        match self.line_width {
+            Some(line_width) => {
+                for line in body.as_bytes().chunks(line_width) {
+                    text.push_str(
+                        std::str::from_utf8(line).expect("base64 is ASCII"),
+                    );
+                    text.push('\n');
+                }
+            },
+            None => text.push_str(&body),
+        }
+        if let Some(label) = &self.label {
+            if self.line_width.is_none() {
+                text.push('\n');
+            }
+            text.extend([END, label, DASHES, "\n"]);
+        }
+        text
+    }
+
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>, Error> {
+        let text = text.trim();
+        let body = match text.strip_prefix(BEGIN) {
+            Some(rest) => {
+                let (label, rest) = rest
+                    .split_once('\n')
+                    .and_then(|(line, rest)| {
+                        Some((line.trim_end().strip_suffix(DASHES)?, rest))
+                    })
+                    .ok_or(Error::InvalidArmor("malformed header line"))?;
+                if self
+                    .label
+                    .as_deref()
+                    .is_some_and(|expected| expected != label)
+                {
+                    Err(Error::InvalidArmor("unexpected header label"))?;
+                }
+                let (body, footer) = rest
+                    .rsplit_once(END)
+                    .ok_or(Error::InvalidArmor("missing footer line"))?;
+                if footer.strip_suffix(DASHES) != Some(label) {
+                    Err(Error::InvalidArmor("footer does not match header"))?;
+                }
+                body
+            },
+            None => text,
+        };
+        decode_base64(body)
+    }
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = [0; 3];
+        group[.. chunk.len()].copy_from_slice(chunk);
+        let bits = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+        for index in 0 .. 4 {
+            if index <= chunk.len() {
+                let sextet = (bits >> (18 - 6 * index)) & 0x3f;
+                encoded.push(char::from(ALPHABET[sextet as usize]));
+            } else {
+                encoded.push(char::from(PAD));
+            }
+        }
+    }
+    encoded
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, Error> {
+    let symbols: Vec<u8> =
+        text.bytes().filter(|byte| !byte.is_ascii_whitespace()).collect();
+    if !symbols.len().is_multiple_of(4) {
+        Err(Error::InvalidArmor("truncated base64 group"))?;
+    }
+    let mut decoded = Vec::with_capacity(symbols.len() / 4 * 3);
+    let groups = symbols.chunks(4).count();
+    for (position, group) in symbols.chunks(4).enumerate() {
+        let padding =
+            group.iter().rev().take_while(|&&byte| byte == PAD).count();
+        if padding > 2 || (padding > 0 && position + 1 != groups) {
+            Err(Error::InvalidArmor("misplaced base64 padding"))?;
+        }
+        let mut bits = 0_u32;
+        for &symbol in &group[.. 4 - padding] {
+            let sextet = ALPHABET
+                .iter()
+                .position(|&candidate| candidate == symbol)
+                .ok_or(Error::InvalidArmor("invalid base64 symbol"))?;
+            bits = bits << 6 | sextet as u32;
+        }
+        bits <<= 6 * padding;
+        decoded.extend_from_slice(&bits.to_be_bytes()[1 .. 4 - padding]);
+    }
+    Ok(decoded)
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+
+use super::{decode_base64, encode_base64, Armor};
+use crate::{de, ser};
+
+#[test]
+fn base64_vectors() -> Result<()> {
+    let vectors = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+    for (plain, encoded) in vectors {
+        assert_eq!(encode_base64(plain.as_bytes()), encoded);
+        assert_eq!(decode_base64(encoded)?, plain.as_bytes());
+    }
+    let bytes: Vec<u8> = (0 ..= 255).collect();
+    assert_eq!(decode_base64(&encode_base64(&bytes))?, bytes);
+    Ok(())
+}
+
+#[test]
+fn armored_roundtrip() -> Result<()> {
+    let value = (String::from("service.name"), vec![1_u64, 2, 3], Some('x'));
+    let text = crate::serialize_armored(&value)?;
+    assert!(text.bytes().all(|byte| byte.is_ascii_graphic()));
+    let decoded: (String, Vec<u64>, Option<char>) =
+        crate::deserialize_armored(&text)?;
+    assert_eq!(decoded, value);
+
+    let mut armor = Armor::new();
+    armor.with_header("ABCODE CONFIG").with_line_width(30);
+    let text = ser::Config::new().serialize_armored(&value, &armor)?;
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "-----BEGIN ABCODE CONFIG-----");
+    assert_eq!(lines[lines.len() - 1], "-----END ABCODE CONFIG-----");
+    assert!(lines[1 .. lines.len() - 1].iter().all(|line| line.len() <= 28));
+
+    let decoded: (String, Vec<u64>, Option<char>) =
+        de::Config::new().deserialize_armored(&text, &armor)?;
+    assert_eq!(decoded, value);
+    let decoded: (String, Vec<u64>, Option<char>) =
+        crate::deserialize_armored(&text.replace('\n', "\r\n"))?;
+    assert_eq!(decoded, value);
+    Ok(())
+}
+
+#[test]
+fn armored_errors() -> Result<()> {
+    let mut armor = Armor::new();
+    armor.with_header("ONE");
+    let text = armor.encode(b"payload");
+
+    let mut other = Armor::new();
+    other.with_header("TWO");
+    let error = other.decode(&text).unwrap_err();
+    assert!(matches!(error, de::Error::InvalidArmor(_)));
+
+    let truncated = text.replace("-----END ONE-----", "");
+    assert!(matches!(
+        armor.decode(&truncated),
+        Err(de::Error::InvalidArmor("missing footer line"))
+    ));
+    assert!(matches!(
+        Armor::new().decode("Zm9v!A=="),
+        Err(de::Error::InvalidArmor("invalid base64 symbol"))
+    ));
+    assert!(matches!(
+        Armor::new().decode("Zg==Zm9v"),
+        Err(de::Error::InvalidArmor("misplaced base64 padding"))
+    ));
+    Ok(())
+}
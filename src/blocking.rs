@@ -0,0 +1,106 @@
+use std::{
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    runtime::{Handle, RuntimeFlavor},
+    task::{self, JoinHandle},
+    time,
+};
+
+use crate::progress::Progress;
+
+#[derive(Debug, Clone, Default)]
+pub enum BlockingStrategy {
+    #[default]
+    SpawnBlocking,
+    BlockInPlace,
+    Handle(Handle),
+}
+
+impl BlockingStrategy {
+    pub(crate) fn spawn<F, T>(&self, job: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        match self {
+            Self::Handle(handle) => handle.spawn_blocking(job),
+            Self::SpawnBlocking | Self::BlockInPlace => {
+                task::spawn_blocking(job)
+            },
+        }
+    }
+
+    pub(crate) fn in_place_handle(&self) -> Option<Handle> {
+        match self {
+            Self::BlockInPlace => Handle::try_current().ok().filter(|handle| {
+                handle.runtime_flavor() == RuntimeFlavor::MultiThread
+            }),
+            Self::SpawnBlocking | Self::Handle(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct BlockingIo<'a, D> {
+    device: &'a mut D,
+    handle: Handle,
+    timeout: Option<Duration>,
+    progress: Option<Progress>,
+}
+
+impl<'a, D> BlockingIo<'a, D> {
+    pub(crate) fn new(device: &'a mut D, handle: Handle) -> Self {
+        Self { device, handle, timeout: None, progress: None }
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub(crate) fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    fn record_io(&self, count: usize) {
+        if let Some(progress) = &self.progress {
+            progress.record_io(count);
+        }
+    }
+}
+
+impl<D> Read for BlockingIo<'_, D>
+where
+    D: AsyncRead + Unpin,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.device.read(buf);
+        let count = match self.timeout {
+            Some(duration) => self
+                .handle
+                .block_on(time::timeout(duration, read))
+                .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??,
+            None => self.handle.block_on(read)?,
+        };
+        self.record_io(count);
+        Ok(count)
+    }
+}
+
+impl<D> Write for BlockingIo<'_, D>
+where
+    D: AsyncWrite + Unpin,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let count = self.handle.block_on(self.device.write(buf))?;
+        self.record_io(count);
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.handle.block_on(self.device.flush())
+    }
+}
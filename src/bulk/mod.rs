@@ -0,0 +1,168 @@
+use std::{fmt, marker::PhantomData};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(test)]
+mod test;
+
+const NAME_PREFIX: &str = "$abcode::bulk::";
+
+pub(crate) fn element_size(name: &str) -> Option<usize> {
+    let suffix = name.strip_prefix(NAME_PREFIX)?;
+    suffix.strip_prefix('f').unwrap_or(suffix).parse().ok()
+}
+
+pub(crate) fn is_float(name: &str) -> bool {
+    name.strip_prefix(NAME_PREFIX).is_some_and(|suffix| suffix.starts_with('f'))
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+pub trait Primitive: Copy + sealed::Sealed {
+    const SIZE: usize;
+
+    const NEWTYPE_NAME: &'static str;
+
+    fn extend_le(self, bytes: &mut Vec<u8>);
+
+    fn from_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_primitive {
+    ($($ty:ty => $name:literal, $size:literal),* $(,)?) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl Primitive for $ty {
+                const SIZE: usize = $size;
+
+                const NEWTYPE_NAME: &'static str =
+                    concat!("$abcode::bulk::", $name);
+
+                fn extend_le(self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn from_le(bytes: &[u8]) -> Self {
+                    let mut buf = [0; $size];
+                    buf.copy_from_slice(bytes);
+                    <$ty>::from_le_bytes(buf)
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive! {
+    u16 => "2", 2,
+    i16 => "2", 2,
+    u32 => "4", 4,
+    i32 => "4", 4,
+    u64 => "8", 8,
+    i64 => "8", 8,
+    u128 => "16", 16,
+    i128 => "16", 16,
+    f32 => "f4", 4,
+    f64 => "f8", 8,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, PartialOrd)]
+pub struct Bulk<T>(pub Vec<T>);
+
+impl<T> Serialize for Bulk<T>
+where
+    T: Primitive,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Bulk<T>
+where
+    T: Primitive,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(deserializer).map(Self)
+    }
+}
+
+pub fn serialize<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Primitive,
+    S: Serializer,
+{
+    let mut bytes = Vec::with_capacity(values.len() * T::SIZE);
+    for value in values {
+        value.extend_le(&mut bytes);
+    }
+    serializer.serialize_newtype_struct(T::NEWTYPE_NAME, &RawBytes(&bytes))
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: Primitive,
+    D: Deserializer<'de>,
+{
+    deserializer
+        .deserialize_newtype_struct(T::NEWTYPE_NAME, BulkVisitor(PhantomData))
+}
+
+struct RawBytes<'a>(&'a [u8]);
+
+impl Serialize for RawBytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+struct BulkVisitor<T>(PhantomData<T>);
+
+impl<'de, T> de::Visitor<'de> for BulkVisitor<T>
+where
+    T: Primitive,
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a byte buffer of {}-byte elements", T::SIZE)
+    }
+
+    fn visit_newtype_struct<D>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(self)
+    }
+
+    fn visit_bytes<E>(self, bytes: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        if !bytes.len().is_multiple_of(T::SIZE) {
+            Err(E::invalid_length(bytes.len(), &self))?;
+        }
+        Ok(bytes.chunks_exact(T::SIZE).map(T::from_le).collect())
+    }
+
+    fn visit_byte_buf<E>(self, bytes: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_bytes(&bytes[..])
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Bulk;
+
+#[tokio::test]
+async fn serialize_bulk_matches_seq() -> Result<()> {
+    let values = vec![1.5_f32, -2.0, 1024.25];
+    let mut expected = Vec::new();
+    crate::serialize(&mut expected, values.clone()).await?;
+
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, Bulk(values)).await?;
+    assert_eq!(buf, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn deserialize_bulk_from_seq() -> Result<()> {
+    let values = vec![0x1234_u16, 0xfedc, 7];
+    let buf = crate::serialize_into_buffer(values.clone())?;
+
+    let decoded: Bulk<u16> = crate::deserialize(&buf[..]).await?;
+    assert_eq!(decoded.0, values);
+
+    let decoded: Bulk<u16> = crate::deserialize_buffer(&buf[..])?;
+    assert_eq!(decoded.0, values);
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_with_attribute_roundtrip() -> Result<()> {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct PointCloud {
+        name: String,
+        #[serde(with = "crate::bulk")]
+        points: Vec<f64>,
+        #[serde(with = "crate::bulk")]
+        ids: Vec<i64>,
+    }
+
+    let value = PointCloud {
+        name: "cloud".to_owned(),
+        points: vec![0.5, -1.25, 3.0, 1e10],
+        ids: vec![],
+    };
+
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, value.clone()).await?;
+    let decoded: PointCloud = crate::deserialize(&buf[..]).await?;
+    assert_eq!(decoded, value);
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_inside_unsized_seq() -> Result<()> {
+    struct Unsized(Vec<Bulk<u32>>);
+
+    impl Serialize for Unsized {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            serializer.collect_seq(self.0.iter().filter(|_| true))
+        }
+    }
+
+    let value = Unsized(vec![Bulk(vec![1, 2]), Bulk(vec![3])]);
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, value).await?;
+    let decoded: Vec<Vec<u32>> = crate::deserialize_buffer(&buf[..])?;
+    assert_eq!(decoded, vec![vec![1, 2], vec![3]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_self_describing() -> Result<()> {
+    let value = Bulk(vec![1_u32, 2, 3]);
+    let mut config = crate::ser::Config::new();
+    config.with_self_describing();
+    let buf = config.serialize_into_buffer(&value)?;
+    assert_eq!(buf[0], 16);
+
+    let decoded: Bulk<u32> = crate::de::Config::new()
+        .with_self_describing()
+        .deserialize_buffer(&buf)?;
+    assert_eq!(decoded.0, value.0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_floats_rejected() -> Result<()> {
+    let error = crate::ser::Config::float_free()
+        .serialize_into_buffer(Bulk(vec![1.0_f32, 2.0]))
+        .unwrap_err();
+    assert!(matches!(error, crate::ser::Error::FloatRejected));
+
+    let buf = crate::serialize_into_buffer(Bulk(vec![1.0_f64]))?;
+    let error = crate::de::Config::float_free()
+        .deserialize_buffer::<Bulk<f64>>(&buf)
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::FloatRejected));
+
+    let buf = crate::ser::Config::float_free()
+        .serialize_into_buffer(Bulk(vec![1_u32, 2]))?;
+    let value: Bulk<u32> =
+        crate::de::Config::float_free().deserialize(&buf[..]).await?;
+    assert_eq!(value.0, [1, 2]);
+    Ok(())
+}
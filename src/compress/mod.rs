@@ -0,0 +1,220 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Compression {
+    #[default]
+    None,
+    #[cfg(feature = "zstd")]
+    Zstd,
+    #[cfg(feature = "lz4")]
+    Lz4,
+    #[cfg(feature = "deflate")]
+    Deflate,
+}
+
+impl Compression {
+    pub fn id(self) -> u8 {
+        match self {
+            Self::None => 0,
+            #[cfg(feature = "zstd")]
+            Self::Zstd => 1,
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => 2,
+            #[cfg(feature = "deflate")]
+            Self::Deflate => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::None),
+            #[cfg(feature = "zstd")]
+            1 => Some(Self::Zstd),
+            #[cfg(feature = "lz4")]
+            2 => Some(Self::Lz4),
+            #[cfg(feature = "deflate")]
+            3 => Some(Self::Deflate),
+            _ => None,
+        }
+    }
+
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut encoder = self.encoder(Vec::new())?;
+        encoder.write_all(data)?;
+        encoder.finish()
+    }
+
+    pub fn decompress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decoder = self.decoder(data)?;
+        let mut buffer = Vec::new();
+        decoder.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    pub(crate) fn encoder<W>(self, writer: W) -> io::Result<Encoder<W>>
+    where
+        W: Write,
+    {
+        Ok(match self {
+            Self::None => Encoder::Plain(writer),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Encoder::Zstd(zstd::stream::write::Encoder::new(
+                writer,
+                zstd::DEFAULT_COMPRESSION_LEVEL,
+            )?),
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => {
+                Encoder::Lz4(lz4_flex::frame::FrameEncoder::new(writer))
+            },
+            #[cfg(feature = "deflate")]
+            Self::Deflate => {
+                Encoder::Deflate(flate2::write::DeflateEncoder::new(
+                    writer,
+                    flate2::Compression::default(),
+                ))
+            },
+        })
+    }
+
+    pub(crate) fn decoder<R>(self, reader: R) -> io::Result<Decoder<R>>
+    where
+        R: Read,
+    {
+        Ok(match self {
+            Self::None => Decoder::Plain(reader),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => {
+                Decoder::Zstd(zstd::stream::read::Decoder::new(reader)?)
+            },
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => {
+                Decoder::Lz4(lz4_flex::frame::FrameDecoder::new(reader))
+            },
+            #[cfg(feature = "deflate")]
+            Self::Deflate => {
+                Decoder::Deflate(flate2::read::DeflateDecoder::new(reader))
+            },
+        })
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => write!(f, "zstd"),
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => write!(f, "lz4"),
+            #[cfg(feature = "deflate")]
+            Self::Deflate => write!(f, "deflate"),
+        }
+    }
+}
+
+pub(crate) enum Encoder<W>
+where
+    W: Write,
+{
+    Plain(W),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    #[cfg(feature = "lz4")]
+    Lz4(lz4_flex::frame::FrameEncoder<W>),
+    #[cfg(feature = "deflate")]
+    Deflate(flate2::write::DeflateEncoder<W>),
+}
+
+impl<W> Encoder<W>
+where
+    W: Write,
+{
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        match self {
+            Self::Plain(writer) => writer,
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.get_mut(),
+            #[cfg(feature = "lz4")]
+            Self::Lz4(encoder) => encoder.get_mut(),
+            #[cfg(feature = "deflate")]
+            Self::Deflate(encoder) => encoder.get_mut(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> io::Result<W> {
+        match self {
+            Self::Plain(writer) => Ok(writer),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.finish(),
+            #[cfg(feature = "lz4")]
+            Self::Lz4(encoder) => encoder.finish().map_err(io::Error::other),
+            #[cfg(feature = "deflate")]
+            Self::Deflate(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W> Write for Encoder<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(writer) => writer.write(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.write(buf),
+            #[cfg(feature = "lz4")]
+            Self::Lz4(encoder) => encoder.write(buf),
+            #[cfg(feature = "deflate")]
+            Self::Deflate(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(writer) => writer.flush(),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(encoder) => encoder.flush(),
+            #[cfg(feature = "lz4")]
+            Self::Lz4(encoder) => encoder.flush(),
+            #[cfg(feature = "deflate")]
+            Self::Deflate(encoder) => encoder.flush(),
+        }
+    }
+}
+
+pub(crate) enum Decoder<R>
+where
+    R: Read,
+{
+    Plain(R),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::read::Decoder<'static, io::BufReader<R>>),
+    #[cfg(feature = "lz4")]
+    Lz4(lz4_flex::frame::FrameDecoder<R>),
+    #[cfg(feature = "deflate")]
+    Deflate(flate2::read::DeflateDecoder<R>),
+}
+
+impl<R> Read for Decoder<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(reader) => reader.read(buf),
+            #[cfg(feature = "zstd")]
+            Self::Zstd(decoder) => decoder.read(buf),
+            #[cfg(feature = "lz4")]
+            Self::Lz4(decoder) => decoder.read(buf),
+            #[cfg(feature = "deflate")]
+            Self::Deflate(decoder) => decoder.read(buf),
+        }
+    }
+}
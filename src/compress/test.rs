@@ -0,0 +1,131 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Compression;
+use crate::{de, fingerprint::Fingerprint, ser};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Archive {
+    name: String,
+    lines: Vec<String>,
+    samples: Vec<u64>,
+}
+
+fn archive() -> Archive {
+    Archive {
+        name: "archive".to_owned(),
+        lines: (0 .. 200).map(|i| format!("line number {}", i % 7)).collect(),
+        samples: (0 .. 500).map(|i| i % 3).collect(),
+    }
+}
+
+fn algorithms() -> Vec<Compression> {
+    vec![
+        Compression::None,
+        #[cfg(feature = "zstd")]
+        Compression::Zstd,
+        #[cfg(feature = "lz4")]
+        Compression::Lz4,
+        #[cfg(feature = "deflate")]
+        Compression::Deflate,
+    ]
+}
+
+#[test]
+fn ids_roundtrip() {
+    for compression in algorithms() {
+        assert_eq!(Compression::from_id(compression.id()), Some(compression));
+    }
+    assert_eq!(Compression::from_id(0xff), None);
+}
+
+#[test]
+fn buffer_roundtrip() -> Result<()> {
+    let value = archive();
+    let plain = ser::serialize_into_buffer(&value)?;
+    for compression in algorithms() {
+        let buf = ser::Config::new()
+            .with_compression(compression)
+            .serialize_into_buffer(&value)?;
+        assert_eq!(buf[0], compression.id());
+        if compression != Compression::None {
+            assert!(buf.len() * 5 < plain.len(), "{}", compression);
+        }
+        let decoded: Archive =
+            de::Config::new().with_compression().deserialize_buffer(&buf)?;
+        assert_eq!(decoded, value);
+    }
+    Ok(())
+}
+
+#[test]
+fn writer_roundtrip() -> Result<()> {
+    let value = archive();
+    for compression in algorithms() {
+        let mut config = ser::Config::new();
+        config.with_compression(compression);
+        let mut buf = Vec::new();
+        config.serialize_into_writer(&mut buf, &value)?;
+        assert_eq!(buf[0], compression.id());
+        let decoded: Archive =
+            de::Config::new().with_compression().deserialize_buffer(&buf)?;
+        assert_eq!(decoded, value);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn async_roundtrip() -> Result<()> {
+    let value = archive();
+    for compression in algorithms() {
+        let mut buf = Vec::new();
+        ser::Config::new()
+            .with_compression(compression)
+            .serialize(&mut buf, value.clone())
+            .await?;
+        let from_buffer = ser::Config::new()
+            .with_compression(compression)
+            .serialize_into_buffer(&value)?;
+        assert_eq!(buf[0], compression.id());
+
+        let decoded: Archive = de::Config::new()
+            .with_compression()
+            .with_hard_eof()
+            .deserialize(&from_buffer[..])
+            .await?;
+        assert_eq!(decoded, value);
+        let decoded: Archive =
+            de::Config::new().with_compression().deserialize(&buf[..]).await?;
+        assert_eq!(decoded, value);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn header_follows_fingerprint() -> Result<()> {
+    let value = archive();
+    let fingerprint = Fingerprint::of(&value)?;
+    let compression = algorithms().pop().unwrap_or_default();
+    let buf = ser::Config::new()
+        .with_fingerprint(fingerprint)
+        .with_compression(compression)
+        .serialize_into_buffer(&value)?;
+    assert_eq!(&buf[.. 8], &fingerprint.to_le_bytes());
+    assert_eq!(buf[8], compression.id());
+
+    let mut config = de::Config::new();
+    config.with_fingerprint(fingerprint).with_compression();
+    assert_eq!(config.deserialize_buffer::<Archive>(&buf)?, value);
+    assert_eq!(config.deserialize::<Archive, _>(&buf[..]).await?, value);
+    Ok(())
+}
+
+#[test]
+fn unknown_algorithm() {
+    let error = de::Config::new()
+        .with_compression()
+        .deserialize_buffer::<u8>(&[0xff, 1])
+        .unwrap_err();
+    assert!(matches!(error.inner(), de::Error::UnknownCompression(0xff)));
+    assert_eq!(error.offset(), Some(1));
+}
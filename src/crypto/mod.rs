@@ -0,0 +1,422 @@
+use std::{
+    fmt,
+    future,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305,
+    Nonce,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::{de, ser};
+
+#[cfg(test)]
+mod test;
+
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const FRAME_SIZE: usize = 16 * 1024;
+const FINAL_FLAG: u32 = 1 << 31;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Authentication of encrypted frame {0} failed")]
+    Authentication(u64),
+    #[error("Encrypted frame of {0} bytes exceeds the frame limit")]
+    FrameTooLarge(usize),
+    #[error("Encrypted stream ended before its final frame")]
+    Truncated,
+    #[error("Encrypted stream continues after its final frame")]
+    TrailingFrame,
+    #[error("Encrypted stream carries data past the decoded value")]
+    TrailingData,
+    #[error("Frame {0} could not be sealed")]
+    Seal(u64),
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        let kind = match error {
+            Error::Truncated => io::ErrorKind::UnexpectedEof,
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, error)
+    }
+}
+
+#[derive(Clone)]
+pub struct Key([u8; 32]);
+
+impl Key {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn generate() -> Self {
+        Self(ChaCha20Poly1305::generate_key(&mut OsRng).into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Key").field(&"..").finish()
+    }
+}
+
+/// How the nonce of each stream is picked. Frame nonces are the stream
+/// nonce with the frame counter mixed into its last bytes, so a stream
+/// nonce must never be reused under the same key: there is deliberately
+/// no caller-chosen nonce.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NoncePolicy {
+    #[default]
+    Random,
+}
+
+impl NoncePolicy {
+    fn base(self) -> [u8; NONCE_SIZE] {
+        match self {
+            Self::Random => ChaCha20Poly1305::generate_nonce(&mut OsRng).into(),
+        }
+    }
+}
+
+struct FrameCipher {
+    cipher: ChaCha20Poly1305,
+    base: [u8; NONCE_SIZE],
+    counter: u64,
+}
+
+impl FrameCipher {
+    fn new(key: &Key, base: [u8; NONCE_SIZE]) -> Self {
+        Self { cipher: ChaCha20Poly1305::new(&key.0.into()), base, counter: 0 }
+    }
+
+    fn nonce(&self) -> Nonce {
+        let mut nonce = self.base;
+        for (byte, mask) in
+            nonce[NONCE_SIZE - 8 ..].iter_mut().zip(self.counter.to_le_bytes())
+        {
+            *byte ^= mask;
+        }
+        nonce.into()
+    }
+
+    fn seal(
+        &mut self,
+        plain: &[u8],
+        last: bool,
+        out: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let payload = Payload { msg: plain, aad: &[u8::from(last)] };
+        let sealed = self
+            .cipher
+            .encrypt(&self.nonce(), payload)
+            .map_err(|_| Error::Seal(self.counter))?;
+        let mut header = sealed.len() as u32;
+        if last {
+            header |= FINAL_FLAG;
+        }
+        out.extend_from_slice(&header.to_le_bytes());
+        out.extend_from_slice(&sealed);
+        self.counter += 1;
+        Ok(())
+    }
+
+    fn open(&mut self, sealed: &[u8], last: bool) -> io::Result<Vec<u8>> {
+        let payload = Payload { msg: sealed, aad: &[u8::from(last)] };
+        let plain = self
+            .cipher
+            .decrypt(&self.nonce(), payload)
+            .map_err(|_| Error::Authentication(self.counter))?;
+        self.counter += 1;
+        Ok(plain)
+    }
+}
+
+pub struct SealWriter<W> {
+    device: W,
+    cipher: FrameCipher,
+    plain: Vec<u8>,
+    sealed: Vec<u8>,
+    written: usize,
+    finished: bool,
+}
+
+impl<W> SealWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub fn new(device: W, key: &Key, nonce_policy: NoncePolicy) -> Self {
+        let base = nonce_policy.base();
+        Self {
+            device,
+            cipher: FrameCipher::new(key, base),
+            plain: Vec::with_capacity(FRAME_SIZE),
+            sealed: base.to_vec(),
+            written: 0,
+            finished: false,
+        }
+    }
+
+    pub async fn finish(&mut self) -> io::Result<()> {
+        future::poll_fn(|cx| self.poll_finish(cx)).await
+    }
+
+    pub fn into_inner(self) -> W {
+        self.device
+    }
+
+    fn poll_drain(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while self.written < self.sealed.len() {
+            let count = ready!(Pin::new(&mut self.device)
+                .poll_write(cx, &self.sealed[self.written ..]))?;
+            if count == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.written += count;
+        }
+        self.sealed.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_finish(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        ready!(self.poll_drain(cx))?;
+        if !self.finished {
+            self.cipher.seal(&self.plain, true, &mut self.sealed)?;
+            self.plain.clear();
+            self.finished = true;
+            ready!(self.poll_drain(cx))?;
+        }
+        Pin::new(&mut self.device).poll_flush(cx)
+    }
+}
+
+impl<W> AsyncWrite for SealWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(Err(Error::TrailingFrame.into()));
+        }
+        ready!(this.poll_drain(cx))?;
+        if this.plain.len() == FRAME_SIZE {
+            this.cipher.seal(&this.plain, false, &mut this.sealed)?;
+            this.plain.clear();
+            ready!(this.poll_drain(cx))?;
+        }
+        let count = buf.len().min(FRAME_SIZE - this.plain.len());
+        this.plain.extend_from_slice(&buf[.. count]);
+        Poll::Ready(Ok(count))
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_drain(cx))?;
+        if !this.finished && !this.plain.is_empty() {
+            this.cipher.seal(&this.plain, false, &mut this.sealed)?;
+            this.plain.clear();
+            ready!(this.poll_drain(cx))?;
+        }
+        Pin::new(&mut this.device).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_finish(cx))?;
+        Pin::new(&mut this.device).poll_shutdown(cx)
+    }
+}
+
+pub struct OpenReader<R> {
+    device: R,
+    key: Key,
+    cipher: Option<FrameCipher>,
+    frame: Vec<u8>,
+    filled: usize,
+    header: Option<u32>,
+    plain: Vec<u8>,
+    cursor: usize,
+    finished: bool,
+    checked: bool,
+}
+
+impl<R> OpenReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    pub fn new(device: R, key: &Key) -> Self {
+        Self {
+            device,
+            key: key.clone(),
+            cipher: None,
+            frame: Vec::new(),
+            filled: 0,
+            header: None,
+            plain: Vec::new(),
+            cursor: 0,
+            finished: false,
+            checked: false,
+        }
+    }
+
+    /// Checks that nothing is left unread: neither plaintext up to the
+    /// final frame, nor bytes after it.
+    pub async fn finish(&mut self) -> io::Result<()> {
+        future::poll_fn(|cx| self.poll_finish(cx)).await
+    }
+
+    pub fn into_inner(self) -> R {
+        self.device
+    }
+
+    fn poll_finish(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        loop {
+            if self.cursor < self.plain.len() {
+                return Poll::Ready(Err(Error::TrailingData.into()));
+            }
+            if self.finished {
+                return self.poll_trailing(cx);
+            }
+            ready!(self.poll_next_frame(cx))?;
+        }
+    }
+
+    fn poll_trailing(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        if !self.checked {
+            let mut byte = [0];
+            let mut buf = ReadBuf::new(&mut byte);
+            ready!(Pin::new(&mut self.device).poll_read(cx, &mut buf))?;
+            if !buf.filled().is_empty() {
+                return Poll::Ready(Err(Error::TrailingFrame.into()));
+            }
+            self.checked = true;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_fill(
+        &mut self,
+        cx: &mut Context,
+        len: usize,
+    ) -> Poll<io::Result<()>> {
+        self.frame.resize(len, 0);
+        while self.filled < len {
+            let mut buf = ReadBuf::new(&mut self.frame[self.filled ..]);
+            ready!(Pin::new(&mut self.device).poll_read(cx, &mut buf))?;
+            let count = buf.filled().len();
+            if count == 0 {
+                return Poll::Ready(Err(Error::Truncated.into()));
+            }
+            self.filled += count;
+        }
+        self.filled = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_next_frame(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        if self.cipher.is_none() {
+            ready!(self.poll_fill(cx, NONCE_SIZE))?;
+            let mut base = [0; NONCE_SIZE];
+            base.copy_from_slice(&self.frame);
+            self.cipher = Some(FrameCipher::new(&self.key, base));
+        }
+        let header = match self.header {
+            Some(header) => header,
+            None => {
+                ready!(self.poll_fill(cx, 4))?;
+                let mut bytes = [0; 4];
+                bytes.copy_from_slice(&self.frame);
+                let header = u32::from_le_bytes(bytes);
+                self.header = Some(header);
+                header
+            },
+        };
+        let len = (header & !FINAL_FLAG) as usize;
+        if len > FRAME_SIZE + TAG_SIZE {
+            return Poll::Ready(Err(Error::FrameTooLarge(len).into()));
+        }
+        ready!(self.poll_fill(cx, len))?;
+        let last = header & FINAL_FLAG != 0;
+        if let Some(cipher) = &mut self.cipher {
+            self.plain = cipher.open(&self.frame, last)?;
+        }
+        self.cursor = 0;
+        self.header = None;
+        self.finished = last;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<R> AsyncRead for OpenReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        while this.cursor == this.plain.len() && !this.finished {
+            ready!(this.poll_next_frame(cx))?;
+        }
+        if this.cursor == this.plain.len() {
+            return this.poll_trailing(cx);
+        }
+        let count = buf.remaining().min(this.plain.len() - this.cursor);
+        buf.put_slice(&this.plain[this.cursor .. this.cursor + count]);
+        this.cursor += count;
+        Poll::Ready(Ok(()))
+    }
+}
+
+pub async fn serialize_encrypted<T, W>(
+    device: W,
+    key: &Key,
+    nonce_policy: NoncePolicy,
+    value: T,
+) -> Result<(), ser::Error>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize + Send + 'static,
+{
+    ser::Config::default()
+        .serialize_encrypted(device, key, nonce_policy, value)
+        .await
+}
+
+pub async fn deserialize_encrypted<'de, T, R>(
+    device: R,
+    key: &Key,
+) -> Result<T, de::Error>
+where
+    R: AsyncRead + Unpin,
+    T: Deserialize<'de> + Send + 'static,
+{
+    de::Config::default().deserialize_encrypted(device, key).await
+}
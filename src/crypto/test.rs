@@ -0,0 +1,149 @@
+use std::io;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use super::{
+    deserialize_encrypted,
+    serialize_encrypted,
+    Error,
+    Key,
+    NoncePolicy,
+    SealWriter,
+};
+use crate::de;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Secret {
+    owner: String,
+    payload: Vec<u8>,
+}
+
+fn secret(len: usize) -> Secret {
+    Secret {
+        owner: "confidential".to_owned(),
+        payload: (0 .. len).map(|i| (i % 251) as u8).collect(),
+    }
+}
+
+fn crypto_error(error: &de::Error) -> Option<&Error> {
+    match error.inner() {
+        de::Error::IO(error) => error.get_ref()?.downcast_ref(),
+        _ => None,
+    }
+}
+
+#[tokio::test]
+async fn encrypted_roundtrip() -> Result<()> {
+    let key = Key::generate();
+    for len in [0, 10, 40_000] {
+        let value = secret(len);
+        let mut buf = Vec::new();
+        serialize_encrypted(&mut buf, &key, NoncePolicy::Random, value.clone())
+            .await?;
+        assert!(!buf.windows(12).any(|window| window == b"confidential"));
+
+        let decoded: Secret = de::Config::new()
+            .with_hard_eof()
+            .deserialize_encrypted(&buf[..], &key)
+            .await?;
+        assert_eq!(decoded, value);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn nonces_are_not_reused() -> Result<()> {
+    let key = Key::from_bytes([7; 32]);
+    let mut first = Vec::new();
+    serialize_encrypted(&mut first, &key, NoncePolicy::Random, secret(100))
+        .await?;
+    let mut second = Vec::new();
+    serialize_encrypted(&mut second, &key, NoncePolicy::Random, secret(100))
+        .await?;
+    assert_ne!(&first[.. 12], &second[.. 12]);
+    assert_ne!(first, second);
+    Ok(())
+}
+
+#[tokio::test]
+async fn tampering_is_detected() -> Result<()> {
+    let key = Key::generate();
+    let mut buf = Vec::new();
+    serialize_encrypted(&mut buf, &key, NoncePolicy::Random, secret(64))
+        .await?;
+    let last = buf.len() - 1;
+    buf[last] ^= 1;
+
+    let error =
+        deserialize_encrypted::<Secret, _>(&buf[..], &key).await.unwrap_err();
+    assert!(matches!(crypto_error(&error), Some(Error::Authentication(0))));
+
+    let error = deserialize_encrypted::<Secret, _>(&buf[..], &Key::generate())
+        .await
+        .unwrap_err();
+    assert!(matches!(crypto_error(&error), Some(Error::Authentication(0))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn truncation_is_detected() -> Result<()> {
+    let key = Key::generate();
+    let mut buf = Vec::new();
+    serialize_encrypted(&mut buf, &key, NoncePolicy::Random, secret(40_000))
+        .await?;
+    let frame = 12 + 4 + super::FRAME_SIZE + super::TAG_SIZE;
+    buf.truncate(frame);
+
+    let error =
+        deserialize_encrypted::<Secret, _>(&buf[..], &key).await.unwrap_err();
+    assert!(matches!(crypto_error(&error), Some(Error::Truncated)));
+    match error.inner() {
+        de::Error::IO(error) => {
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof)
+        },
+        error => panic!("unexpected error {}", error),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn final_frame_is_required() -> Result<()> {
+    let key = Key::generate();
+    let message = crate::serialize_into_buffer(secret(10))?;
+    let mut buf = Vec::new();
+    let mut writer = SealWriter::new(&mut buf, &key, NoncePolicy::Random);
+    writer.write_all(&message).await?;
+    writer.flush().await?;
+    drop(writer);
+    let error =
+        deserialize_encrypted::<Secret, _>(&buf[..], &key).await.unwrap_err();
+    assert!(matches!(crypto_error(&error), Some(Error::Truncated)));
+
+    let mut buf = Vec::new();
+    let mut writer = SealWriter::new(&mut buf, &key, NoncePolicy::Random);
+    writer.write_all(&message).await?;
+    writer.write_all(&[0xab]).await?;
+    writer.finish().await?;
+    let error =
+        deserialize_encrypted::<Secret, _>(&buf[..], &key).await.unwrap_err();
+    assert!(matches!(crypto_error(&error), Some(Error::TrailingData)));
+
+    let mut buf = Vec::new();
+    serialize_encrypted(&mut buf, &key, NoncePolicy::Random, secret(10))
+        .await?;
+    let decoded: Secret = deserialize_encrypted(&buf[..], &key).await?;
+    assert_eq!(decoded, secret(10));
+    buf.push(0);
+    let error =
+        deserialize_encrypted::<Secret, _>(&buf[..], &key).await.unwrap_err();
+    assert!(matches!(crypto_error(&error), Some(Error::TrailingFrame)));
+    let error = de::Config::new()
+        .with_hard_eof()
+        .deserialize_encrypted::<Secret, _>(&buf[..], &key)
+        .await
+        .unwrap_err();
+    assert!(matches!(crypto_error(&error), Some(Error::TrailingFrame)));
+    Ok(())
+}
@@ -1,15 +1,77 @@
-use serde::{de::IntoDeserializer, Deserialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    hash::BuildHasher,
+    io::{self, Read},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+#[cfg(feature = "bytes")]
+use bytes::Buf;
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+use serde::{
+    de::{IgnoredAny, IntoDeserializer},
+    Deserialize,
+};
 use smallvec::SmallVec;
 use tokio::{
-    io::{AsyncRead, AsyncReadExt},
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, ReadBuf},
     sync::mpsc,
+    time,
+};
+
+use super::{Error, Limit, Path, Segment};
+use crate::{
+    compress::Compression,
+    fingerprint::Fingerprint,
+    inspect::Tracer,
+    intern,
+    metadata::{self, Metadata},
+    progress::Progress,
+    repr::{self, Override},
+    tag::Tag,
+    validate,
+    varint::{self, IntEncoding},
 };
 
-use super::Error;
+pub trait KeyHasher: fmt::Debug + Send + Sync {
+    fn hash_key(&self, key: &[u8]) -> u64;
+}
+
+impl<H> KeyHasher for H
+where
+    H: BuildHasher + fmt::Debug + Send + Sync,
+{
+    fn hash_key(&self, key: &[u8]) -> u64 {
+        self.hash_one(key)
+    }
+}
 
 pub trait DeserializationSource {
     fn recv_raw_data(&mut self, buf: &mut [u8]) -> Result<(), Error>;
 
+    fn offset(&self) -> u64;
+
+    fn remaining_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn skip_raw_data(&mut self, len: usize) -> Result<(), Error> {
+        let mut buf = [0; 256];
+        let mut remaining = len;
+        while remaining > 0 {
+            let count = remaining.min(buf.len());
+            self.recv_raw_data(&mut buf[.. count])?;
+            remaining -= count;
+        }
+        Ok(())
+    }
+
     fn recv_u64(&mut self) -> Result<u64, Error> {
         let mut buf = [0; 8];
         self.recv_raw_data(&mut buf)?;
@@ -33,12 +95,52 @@ pub trait DeserializationSource {
     }
 }
 
+impl<S> DeserializationSource for &mut S
+where
+    S: DeserializationSource + ?Sized,
+{
+    fn recv_raw_data(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        (**self).recv_raw_data(buf)
+    }
+
+    fn offset(&self) -> u64 {
+        (**self).offset()
+    }
+
+    fn remaining_len(&self) -> Option<usize> {
+        (**self).remaining_len()
+    }
+
+    fn skip_raw_data(&mut self, len: usize) -> Result<(), Error> {
+        (**self).skip_raw_data(len)
+    }
+
+    fn recv_u64(&mut self) -> Result<u64, Error> {
+        (**self).recv_u64()
+    }
+
+    fn recv_i64(&mut self) -> Result<i64, Error> {
+        (**self).recv_i64()
+    }
+}
+
 pub type ChannelBytes = SmallVec<[u8; 16]>;
 
+/// Serves byte requests from a [`ChannelSource`] out of an async device.
+///
+/// Exactly the requested bytes are read, so the device is left positioned
+/// right after the decoded message. [`ChannelBackend::run`] must be polled
+/// concurrently with the deserializer and completes once the source is
+/// dropped.
 #[derive(Debug)]
 pub struct ChannelBackend<R> {
     device: R,
     hard_eof: bool,
+    count_trailing: bool,
+    read_timeout: Option<Duration>,
+    watermarks: Option<Watermarks>,
+    offset: u64,
+    progress: Option<Progress>,
     response_sender: mpsc::Sender<ChannelBytes>,
     request_receiver: mpsc::Receiver<usize>,
 }
@@ -52,22 +154,178 @@ where
         response_sender: mpsc::Sender<ChannelBytes>,
         request_receiver: mpsc::Receiver<usize>,
     ) -> Self {
-        Self { device, hard_eof: false, response_sender, request_receiver }
+        Self {
+            device,
+            hard_eof: false,
+            count_trailing: false,
+            read_timeout: None,
+            watermarks: None,
+            offset: 0,
+            progress: None,
+            response_sender,
+            request_receiver,
+        }
     }
 
     pub fn set_hard_eof(&mut self, on: bool) {
         self.hard_eof = on;
     }
 
-    pub async fn run(mut self) -> Result<(), Error> {
+    pub fn set_count_trailing(&mut self, on: bool) {
+        self.count_trailing = on;
+    }
+
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    pub fn set_watermarks(&mut self, watermarks: Option<Watermarks>) {
+        self.watermarks = watermarks;
+    }
+
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn into_device(self) -> R {
+        self.device
+    }
+
+    pub async fn serve(&mut self, message_start: u64) -> Result<bool, Error> {
+        let result = self.serve_request(message_start).await;
+        result.map_err(|error| error.at(self.offset, Path::default()))
+    }
+
+    async fn serve_request(
+        &mut self,
+        message_start: u64,
+    ) -> Result<bool, Error> {
+        let size =
+            self.request_receiver.recv().await.ok_or(Error::Disconnected)?;
+        let mut bytes = ChannelBytes::from_elem(0, size);
+        let mut cursor = &mut bytes[..];
+        while !cursor.is_empty() {
+            let count =
+                read_device(&mut self.device, cursor, self.read_timeout)
+                    .await?;
+            if count == 0 {
+                if self.offset == message_start {
+                    return Ok(false);
+                }
+                Err(Error::PrematureEof)?
+            }
+            self.offset += count as u64;
+            if let Some(progress) = &self.progress {
+                progress.record_io(count);
+            }
+            cursor = &mut cursor[count ..];
+        }
+        self.response_sender
+            .send(bytes)
+            .await
+            .map_err(|_| Error::Disconnected)?;
+        Ok(true)
+    }
+
+    pub async fn run(mut self) -> Result<u64, Error> {
+        // Bytes read ahead past the message would be lost with the backend,
+        // so reading ahead is left to devices that are read to the end
+        // anyway.
+        let result = match self.watermarks {
+            Some(watermarks) if self.hard_eof || self.count_trailing => {
+                self.transfer_ahead(watermarks).await
+            },
+            _ => self.transfer().await,
+        };
+        result.map_err(|error| error.at(self.offset, Path::default()))
+    }
+
+    async fn transfer_ahead(
+        &mut self,
+        watermarks: Watermarks,
+    ) -> Result<u64, Error> {
+        let mut ahead = VecDeque::with_capacity(watermarks.high);
+        let mut chunk = Vec::new();
+        let mut pending = None;
+        let mut filling = true;
+        let mut eof = false;
+
+        loop {
+            if let Some(size) = pending {
+                if ahead.len() >= size {
+                    let bytes: ChannelBytes = ahead.drain(.. size).collect();
+                    self.response_sender
+                        .send(bytes)
+                        .await
+                        .map_err(|_| Error::Disconnected)?;
+                    pending = None;
+                    filling |= ahead.len() <= watermarks.low;
+                    continue;
+                }
+                if eof {
+                    Err(Error::PrematureEof)?;
+                }
+            }
+
+            if eof || (pending.is_none() && !filling) {
+                match self.request_receiver.recv().await {
+                    Some(size) => pending = Some(size),
+                    None => break,
+                }
+                continue;
+            }
+
+            let wanted = pending.unwrap_or(0).max(watermarks.high);
+            chunk.resize(wanted.saturating_sub(ahead.len()).max(1), 0);
+            let timeout = pending.and(self.read_timeout);
+            tokio::select! {
+                request = self.request_receiver.recv(), if pending.is_none() => {
+                    match request {
+                        Some(size) => pending = Some(size),
+                        None => break,
+                    }
+                },
+                count = read_device(&mut self.device, &mut chunk, timeout) => {
+                    let count = count?;
+                    if count == 0 {
+                        eof = true;
+                    }
+                    ahead.extend(&chunk[.. count]);
+                    self.offset += count as u64;
+                    if let Some(progress) = &self.progress {
+                        progress.record_io(count);
+                    }
+                    filling &= ahead.len() < watermarks.high;
+                },
+            }
+        }
+
+        if !self.hard_eof && !self.count_trailing {
+            return Ok(0);
+        }
+        let drained = if eof { 0 } else { self.drain().await? };
+        self.trailing(ahead.len() as u64 + drained)
+    }
+
+    async fn transfer(&mut self) -> Result<u64, Error> {
         while let Some(size) = self.request_receiver.recv().await {
             let mut bytes = ChannelBytes::from_elem(0, size);
             let mut cursor = &mut bytes[..];
             while !cursor.is_empty() {
-                let count = self.device.read(&mut cursor).await?;
+                let count =
+                    read_device(&mut self.device, cursor, self.read_timeout)
+                        .await?;
                 if self.hard_eof && count == 0 {
                     Err(Error::PrematureEof)?
                 }
+                self.offset += count as u64;
+                if let Some(progress) = &self.progress {
+                    progress.record_io(count);
+                }
                 cursor = &mut cursor[count ..];
             }
             self.response_sender
@@ -75,18 +333,98 @@ where
                 .await
                 .map_err(|_| Error::Disconnected)?;
         }
-        if self.hard_eof {
-            let mut buf = [0];
-            if self.device.read(&mut buf).await? != 0 {
-                Err(Error::ExpectedEof(buf[0]))?
+        if !self.hard_eof && !self.count_trailing {
+            return Ok(0);
+        }
+        let count = self.drain().await?;
+        self.trailing(count)
+    }
+
+    fn trailing(&self, count: u64) -> Result<u64, Error> {
+        if self.hard_eof && count > 0 {
+            Err(Error::TrailingBytes { count })?
+        }
+        Ok(count)
+    }
+
+    async fn drain(&mut self) -> Result<u64, Error> {
+        let mut buf = [0; 4096];
+        let mut count = 0;
+        loop {
+            match read_device(&mut self.device, &mut buf, self.read_timeout)
+                .await?
+            {
+                0 => break Ok(count),
+                read => count += read as u64,
             }
         }
-        Ok(())
     }
 }
 
+impl<R> ChannelBackend<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    pub async fn run_buffered(mut self) -> Result<u64, Error> {
+        let result = self.transfer_buffered().await;
+        result.map_err(|error| error.at(self.offset, Path::default()))
+    }
+
+    async fn transfer_buffered(&mut self) -> Result<u64, Error> {
+        while let Some(size) = self.request_receiver.recv().await {
+            let mut bytes = ChannelBytes::with_capacity(size);
+            while bytes.len() < size {
+                let available =
+                    with_timeout(self.device.fill_buf(), self.read_timeout)
+                        .await?;
+                if available.is_empty() {
+                    Err(Error::PrematureEof)?
+                }
+                let count = available.len().min(size - bytes.len());
+                bytes.extend_from_slice(&available[.. count]);
+                self.device.consume(count);
+                self.offset += count as u64;
+                if let Some(progress) = &self.progress {
+                    progress.record_io(count);
+                }
+            }
+            self.response_sender
+                .send(bytes)
+                .await
+                .map_err(|_| Error::Disconnected)?;
+        }
+        if !self.hard_eof && !self.count_trailing {
+            return Ok(0);
+        }
+        let mut count = 0;
+        loop {
+            let available =
+                with_timeout(self.device.fill_buf(), self.read_timeout).await?;
+            if available.is_empty() {
+                break self.trailing(count);
+            }
+            let read = available.len();
+            self.device.consume(read);
+            count += read as u64;
+        }
+    }
+}
+
+/// Read-ahead bounds of a [`ChannelBackend`]: it refills its buffer up to
+/// `high` bytes once it drains to `low` bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Watermarks {
+    pub low: usize,
+    pub high: usize,
+}
+
+/// Source that requests bytes from a [`ChannelBackend`].
+///
+/// Receiving blocks the current thread, so a deserializer reading from this
+/// source must run outside of the async runtime (e.g. on a dedicated thread).
 #[derive(Debug)]
 pub struct ChannelSource {
+    offset: u64,
     request_sender: mpsc::Sender<usize>,
     response_receiver: mpsc::Receiver<ChannelBytes>,
 }
@@ -96,7 +434,7 @@ impl ChannelSource {
         request_sender: mpsc::Sender<usize>,
         response_receiver: mpsc::Receiver<ChannelBytes>,
     ) -> Self {
-        Self { request_sender, response_receiver }
+        Self { offset: 0, request_sender, response_receiver }
     }
 }
 
@@ -110,131 +448,1389 @@ impl DeserializationSource for ChannelSource {
             .blocking_recv()
             .ok_or(Error::PrematureEof)?;
         buf.copy_from_slice(&vector[..]);
+        self.offset += buf.len() as u64;
         Ok(())
     }
+
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+pub async fn read_device<R>(
+    device: &mut R,
+    buf: &mut [u8],
+    timeout: Option<Duration>,
+) -> Result<usize, Error>
+where
+    R: AsyncRead + Unpin,
+{
+    with_timeout(device.read(buf), timeout).await
+}
+
+pub async fn with_timeout<F, T>(
+    future: F,
+    timeout: Option<Duration>,
+) -> Result<T, Error>
+where
+    F: Future<Output = io::Result<T>>,
+{
+    let output = match timeout {
+        Some(duration) => time::timeout(duration, future)
+            .await
+            .map_err(|_| Error::TimedOut)??,
+        None => future.await?,
+    };
+    Ok(output)
 }
 
 #[derive(Debug)]
-pub struct BufferSource<B = Vec<u8>> {
-    buffer: B,
-    cursor: usize,
+pub struct PipeBackend<R> {
+    device: R,
+    chunk_size: usize,
+    read_timeout: Option<Duration>,
+    progress: Option<Progress>,
+    sender: mpsc::Sender<Vec<u8>>,
 }
 
-impl<B> BufferSource<B>
+impl<R> PipeBackend<R>
 where
-    B: AsRef<[u8]>,
+    R: AsyncRead + Unpin,
 {
-    pub fn new(buffer: B) -> Self {
-        Self { buffer, cursor: 0 }
+    pub fn new(
+        device: R,
+        chunk_size: usize,
+        sender: mpsc::Sender<Vec<u8>>,
+    ) -> Self {
+        Self { device, chunk_size, read_timeout: None, progress: None, sender }
     }
 
-    pub fn ensure_eof(&self) -> Result<(), Error> {
-        match self.buffer.as_ref().get(self.cursor) {
-            None => Ok(()),
-            Some(found) => Err(Error::ExpectedEof(*found)),
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    pub async fn run(mut self) -> Result<(), Error> {
+        loop {
+            let mut chunk = vec![0; self.chunk_size];
+            let count =
+                read_device(&mut self.device, &mut chunk, self.read_timeout)
+                    .await?;
+            if count == 0 {
+                break;
+            }
+            if let Some(progress) = &self.progress {
+                progress.record_io(count);
+            }
+            chunk.truncate(count);
+            if self.sender.send(chunk).await.is_err() {
+                break;
+            }
         }
+        Ok(())
     }
 }
 
-impl<B> DeserializationSource for BufferSource<B>
+#[derive(Debug)]
+pub struct PipeReader {
+    receiver: mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    cursor: usize,
+}
+
+impl PipeReader {
+    pub fn new(receiver: mpsc::Receiver<Vec<u8>>) -> Self {
+        Self { receiver, chunk: Vec::new(), cursor: 0 }
+    }
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.cursor == self.chunk.len() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.cursor = 0;
+                },
+                None => return Ok(0),
+            }
+        }
+        let count = buf.len().min(self.chunk.len() - self.cursor);
+        buf[.. count]
+            .copy_from_slice(&self.chunk[self.cursor .. self.cursor + count]);
+        self.cursor += count;
+        Ok(count)
+    }
+}
+
+#[derive(Debug)]
+pub struct ChainReader<I>
 where
-    B: AsRef<[u8]>,
+    I: Iterator,
 {
-    fn recv_raw_data(&mut self, buf: &mut [u8]) -> Result<(), Error> {
-        let new_cursor = self.cursor + buf.len();
-        let source = self
-            .buffer
-            .as_ref()
-            .get(self.cursor .. new_cursor)
-            .ok_or(Error::PrematureEof)?;
-        buf.copy_from_slice(source);
-        self.cursor = new_cursor;
-        Ok(())
+    parts: I,
+    current: Option<I::Item>,
+    index: usize,
+}
+
+impl<I> ChainReader<I>
+where
+    I: Iterator,
+{
+    pub fn new<P>(parts: P) -> Self
+    where
+        P: IntoIterator<IntoIter = I>,
+    {
+        Self { parts: parts.into_iter(), current: None, index: 0 }
+    }
+
+    pub fn parts_read(&self) -> usize {
+        self.index
     }
 }
 
-#[derive(Debug)]
-pub struct Deserializer<S> {
-    source: S,
+impl<I> AsyncRead for ChainReader<I>
+where
+    I: Iterator + Unpin,
+    I::Item: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            let current = match &mut this.current {
+                Some(current) => current,
+                None => match this.parts.next() {
+                    Some(part) => {
+                        this.index += 1;
+                        this.current.insert(part)
+                    },
+                    None => return Poll::Ready(Ok(())),
+                },
+            };
+            let filled = buf.filled().len();
+            match Pin::new(current).poll_read(cx, buf) {
+                Poll::Ready(Ok(())) if buf.filled().len() == filled => {
+                    this.current = None;
+                },
+                other => return other,
+            }
+        }
+    }
 }
 
-impl<S> Deserializer<S>
+#[cfg(feature = "stream")]
+pub struct StreamReader<S, B> {
+    stream: Pin<Box<S>>,
+    chunk: Option<B>,
+    cursor: usize,
+}
+
+#[cfg(feature = "stream")]
+impl<S, B> StreamReader<S, B> {
+    pub fn new(stream: S) -> Self {
+        Self { stream: Box::pin(stream), chunk: None, cursor: 0 }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S, B> fmt::Debug for StreamReader<S, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StreamReader")
+            .field("buffered", &self.chunk.is_some())
+            .field("cursor", &self.cursor)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S, B, E> AsyncBufRead for StreamReader<S, B>
 where
-    S: DeserializationSource,
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]> + Unpin,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
 {
-    pub fn new(source: S) -> Self {
-        Self { source }
+    fn poll_fill_buf(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        loop {
+            let exhausted = this
+                .chunk
+                .as_ref()
+                .is_none_or(|chunk| this.cursor >= chunk.as_ref().len());
+            if !exhausted {
+                break;
+            }
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => {
+                    this.chunk = Some(chunk);
+                    this.cursor = 0;
+                },
+                Poll::Ready(Some(Err(error))) => {
+                    return Poll::Ready(Err(io::Error::other(error)));
+                },
+                Poll::Ready(None) => {
+                    this.chunk = None;
+                    return Poll::Ready(Ok(&[]));
+                },
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        let chunk = this.chunk.as_ref().map_or(&[][..], AsRef::as_ref);
+        Poll::Ready(Ok(&chunk[this.cursor ..]))
     }
 
-    pub fn source(&self) -> &S {
-        &self.source
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.get_mut().cursor += amt;
     }
 }
 
-impl<'a, 'de, S> serde::de::Deserializer<'de> for &'a mut Deserializer<S>
+#[cfg(feature = "stream")]
+impl<S, B, E> AsyncRead for StreamReader<S, B>
 where
-    S: DeserializationSource,
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]> + Unpin,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
 {
-    type Error = Error;
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        let available = match self.as_mut().poll_fill_buf(cx) {
+            Poll::Ready(Ok(available)) => available,
+            Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+            Poll::Pending => return Poll::Pending,
+        };
+        let count = buf.remaining().min(available.len());
+        buf.put_slice(&available[.. count]);
+        self.consume(count);
+        Poll::Ready(Ok(()))
+    }
+}
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: serde::de::Visitor<'de>,
-    {
-        Err(Error::UnsupportedAny)
+#[derive(Debug)]
+pub struct ReaderSource<R> {
+    reader: R,
+    offset: u64,
+}
+
+impl<R> ReaderSource<R>
+where
+    R: Read,
+{
+    pub fn new(reader: R) -> Self {
+        Self { reader, offset: 0 }
     }
 
-    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: serde::de::Visitor<'de>,
-    {
-        let mut buf = [0];
-        self.source.recv_raw_data(&mut buf)?;
-        visitor.visit_bool(buf[0] != 0)
+    pub fn ensure_eof(&mut self) -> Result<(), Error> {
+        match self.count_trailing()? {
+            0 => Ok(()),
+            count => Err(Error::TrailingBytes { count }),
+        }
     }
 
-    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: serde::de::Visitor<'de>,
-    {
-        let mut buf = [0];
-        self.source.recv_raw_data(&mut buf)?;
-        visitor.visit_i8(i8::from_le_bytes(buf))
+    pub fn count_trailing(&mut self) -> Result<u64, Error> {
+        Ok(io::copy(&mut self.reader, &mut io::sink())?)
     }
+}
 
-    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: serde::de::Visitor<'de>,
-    {
-        let mut buf = [0; 2];
-        self.source.recv_raw_data(&mut buf)?;
-        visitor.visit_i16(i16::from_le_bytes(buf))
+impl<R> DeserializationSource for ReaderSource<R>
+where
+    R: Read,
+{
+    fn recv_raw_data(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        self.reader.read_exact(buf).map_err(|error| match error.kind() {
+            io::ErrorKind::UnexpectedEof => Error::PrematureEof,
+            _ => Error::IO(error),
+        })?;
+        self.offset += buf.len() as u64;
+        Ok(())
     }
 
-    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: serde::de::Visitor<'de>,
-    {
-        let mut buf = [0; 4];
-        self.source.recv_raw_data(&mut buf)?;
-        visitor.visit_i32(i32::from_le_bytes(buf))
+    fn offset(&self) -> u64 {
+        self.offset
     }
+}
 
-    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-    where
-        V: serde::de::Visitor<'de>,
-    {
-        let mut buf = [0; 8];
-        self.source.recv_raw_data(&mut buf)?;
-        visitor.visit_i64(i64::from_le_bytes(buf))
+#[cfg(feature = "bytes")]
+#[derive(Debug)]
+pub struct BufSource<B> {
+    buf: B,
+    offset: u64,
+}
+
+#[cfg(feature = "bytes")]
+impl<B> BufSource<B>
+where
+    B: Buf,
+{
+    pub fn new(buf: B) -> Self {
+        Self { buf, offset: 0 }
     }
 
-    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+
+    pub fn ensure_eof(&self) -> Result<(), Error> {
+        match self.buf.remaining() {
+            0 => Ok(()),
+            count => Err(Error::TrailingBytes { count: count as u64 }),
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<B> DeserializationSource for BufSource<B>
+where
+    B: Buf,
+{
+    fn recv_raw_data(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        if self.buf.remaining() < buf.len() {
+            Err(Error::PrematureEof)?;
+        }
+        self.buf.copy_to_slice(buf);
+        self.offset += buf.len() as u64;
+        Ok(())
+    }
+
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn remaining_len(&self) -> Option<usize> {
+        Some(self.buf.remaining())
+    }
+
+    fn skip_raw_data(&mut self, len: usize) -> Result<(), Error> {
+        if self.buf.remaining() < len {
+            Err(Error::PrematureEof)?;
+        }
+        self.buf.advance(len);
+        self.offset += len as u64;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BufferSource<B = Vec<u8>> {
+    buffer: B,
+    cursor: usize,
+}
+
+impl<B> BufferSource<B>
+where
+    B: AsRef<[u8]>,
+{
+    pub fn new(buffer: B) -> Self {
+        Self { buffer, cursor: 0 }
+    }
+
+    pub fn ensure_eof(&self) -> Result<(), Error> {
+        match self.buffer.as_ref().len() - self.cursor {
+            0 => Ok(()),
+            count => Err(Error::TrailingBytes { count: count as u64 }),
+        }
+    }
+
+    pub fn get_ref(&self) -> &B {
+        &self.buffer
+    }
+
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+
+    pub fn remaining(&self) -> &[u8] {
+        &self.buffer.as_ref()[self.cursor ..]
+    }
+
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_position(&mut self, position: usize) -> Result<(), Error> {
+        if position > self.buffer.as_ref().len() {
+            Err(Error::PrematureEof)?;
+        }
+        self.cursor = position;
+        Ok(())
+    }
+}
+
+impl<B> DeserializationSource for BufferSource<B>
+where
+    B: AsRef<[u8]>,
+{
+    fn recv_raw_data(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        let new_cursor = self.cursor + buf.len();
+        let source = self
+            .buffer
+            .as_ref()
+            .get(self.cursor .. new_cursor)
+            .ok_or(Error::PrematureEof)?;
+        buf.copy_from_slice(source);
+        self.cursor = new_cursor;
+        Ok(())
+    }
+
+    fn offset(&self) -> u64 {
+        self.cursor as u64
+    }
+
+    fn remaining_len(&self) -> Option<usize> {
+        Some(self.remaining().len())
+    }
+
+    fn skip_raw_data(&mut self, len: usize) -> Result<(), Error> {
+        let new_cursor = self
+            .cursor
+            .checked_add(len)
+            .filter(|new_cursor| *new_cursor <= self.buffer.as_ref().len())
+            .ok_or(Error::PrematureEof)?;
+        self.cursor = new_cursor;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Limits {
+    pub max_seq_len: Option<usize>,
+    pub max_bytes_len: Option<usize>,
+    pub max_map_len: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub max_prealloc: Option<usize>,
+    pub max_total_bytes: Option<usize>,
+}
+
+impl Limits {
+    fn get(&self, limit: Limit) -> Option<usize> {
+        match limit {
+            Limit::SeqLen => self.max_seq_len,
+            Limit::BytesLen => self.max_bytes_len,
+            Limit::MapLen => self.max_map_len,
+            Limit::TotalBytes => self.max_total_bytes,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Deserializer<S> {
+    source: S,
+    limits: Limits,
+    strict: bool,
+    field_presence: bool,
+    field_count: bool,
+    variant_names: bool,
+    variant_fallbacks: HashMap<&'static str, &'static str>,
+    self_describing: bool,
+    canonical: bool,
+    finite_floats: bool,
+    canonical_nan: bool,
+    reject_floats: bool,
+    utf8_chars: bool,
+    str_chars: bool,
+    lenient_chars: bool,
+    int_encoding: IntEncoding,
+    tagged_fields: bool,
+    field_tags: Arc<HashMap<&'static str, HashMap<&'static str, u32>>>,
+    key_hasher: Option<Arc<dyn KeyHasher>>,
+    pending_tag: Option<Tag>,
+    depth: usize,
+    consumed: usize,
+    recordings: Vec<Vec<u8>>,
+    interned_strings: Option<Vec<Arc<str>>>,
+    presence_bitmap: bool,
+    presence_slot: bool,
+    path: Vec<Segment>,
+    tracer: Option<Tracer>,
+    progress: Option<Progress>,
+}
+
+impl<S> Deserializer<S>
+where
+    S: DeserializationSource,
+{
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            limits: Limits::default(),
+            strict: false,
+            field_presence: false,
+            field_count: false,
+            variant_names: false,
+            variant_fallbacks: HashMap::new(),
+            self_describing: false,
+            canonical: false,
+            finite_floats: false,
+            canonical_nan: false,
+            reject_floats: false,
+            utf8_chars: false,
+            str_chars: false,
+            lenient_chars: false,
+            int_encoding: IntEncoding::Fixint,
+            tagged_fields: false,
+            field_tags: Arc::default(),
+            key_hasher: None,
+            pending_tag: None,
+            depth: 0,
+            consumed: 0,
+            recordings: Vec::new(),
+            interned_strings: None,
+            presence_bitmap: false,
+            presence_slot: false,
+            path: Vec::new(),
+            tracer: None,
+            progress: None,
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn set_strict(&mut self, on: bool) {
+        self.strict = on;
+    }
+
+    pub fn set_field_presence(&mut self, on: bool) {
+        self.field_presence = on;
+    }
+
+    pub fn set_field_count(&mut self, on: bool) {
+        self.field_count = on;
+    }
+
+    pub fn set_variant_names(&mut self, on: bool) {
+        self.variant_names = on;
+    }
+
+    pub fn set_variant_fallbacks(
+        &mut self,
+        fallbacks: HashMap<&'static str, &'static str>,
+    ) {
+        self.variant_fallbacks = fallbacks;
+    }
+
+    pub fn set_self_describing(&mut self, on: bool) {
+        self.self_describing = on;
+    }
+
+    pub fn set_canonical(&mut self, on: bool) {
+        self.canonical = on;
+    }
+
+    pub fn set_finite_floats(&mut self, on: bool) {
+        self.finite_floats = on;
+    }
+
+    pub fn set_canonical_nan(&mut self, on: bool) {
+        self.canonical_nan = on;
+    }
+
+    pub fn set_reject_floats(&mut self, on: bool) {
+        self.reject_floats = on;
+    }
+
+    pub fn set_utf8_chars(&mut self, on: bool) {
+        self.utf8_chars = on;
+    }
+
+    pub fn set_str_chars(&mut self, on: bool) {
+        self.str_chars = on;
+    }
+
+    pub fn set_lenient_chars(&mut self, on: bool) {
+        self.lenient_chars = on;
+    }
+
+    pub fn set_int_encoding(&mut self, encoding: IntEncoding) {
+        self.int_encoding = encoding;
+    }
+
+    pub fn set_tagged_fields(&mut self, on: bool) {
+        self.tagged_fields = on;
+    }
+
+    pub fn set_field_tags(
+        &mut self,
+        tags: Arc<HashMap<&'static str, HashMap<&'static str, u32>>>,
+    ) {
+        self.field_tags = tags;
+    }
+
+    pub fn set_key_hasher(&mut self, hasher: Option<Arc<dyn KeyHasher>>) {
+        self.key_hasher = hasher;
+    }
+
+    pub fn set_interned_strings(&mut self, on: bool) {
+        self.interned_strings = on.then(Vec::new);
+    }
+
+    pub fn set_presence_bitmap(&mut self, on: bool) {
+        self.presence_bitmap = on;
+    }
+
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    pub(crate) fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    pub(crate) fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    pub(crate) fn expect_fingerprint(
+        &mut self,
+        expected: Fingerprint,
+    ) -> Result<(), Error> {
+        let mut buf = [0; 8];
+        self.recv_raw_data(&mut buf)?;
+        let found = Fingerprint::from_le_bytes(buf);
+        if found != expected {
+            Err(Error::FingerprintMismatch { expected, found })?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn expect_compression(&mut self) -> Result<Compression, Error> {
+        let mut buf = [0];
+        self.recv_raw_data(&mut buf)?;
+        Compression::from_id(buf[0]).ok_or(Error::UnknownCompression(buf[0]))
+    }
+
+    pub(crate) fn recv_metadata(&mut self) -> Result<Metadata, Error> {
+        let mut len = [0; 4];
+        self.recv_raw_data(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        if len.checked_add(4).is_none_or(|total| total > metadata::MAX_LEN) {
+            Err(Error::ExcessiveSize(len as u64))?;
+        }
+        let mut body = vec![0; len];
+        self.recv_raw_data(&mut body)?;
+        Metadata::decode(&body).ok_or(Error::InvalidMetadata)
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    pub fn into_source(self) -> S {
+        self.source
+    }
+
+    pub fn path(&self) -> Path {
+        Path::new(self.path.clone())
+    }
+
+    fn in_segment<T, F>(
+        &mut self,
+        segment: Segment,
+        visit: F,
+    ) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        self.push_segment(segment);
+        let value = visit(self)?;
+        self.pop_segment();
+        Ok(value)
+    }
+
+    fn push_segment(&mut self, segment: Segment) {
+        self.path.push(segment);
+        if let Some(tracer) = &mut self.tracer {
+            tracer.open(segment, self.source.offset());
+        }
+    }
+
+    fn pop_segment(&mut self) {
+        self.path.pop();
+        if let Some(tracer) = &mut self.tracer {
+            tracer.close(self.source.offset());
+        }
+    }
+
+    pub(crate) fn reset_consumed(&mut self) {
+        self.consumed = 0;
+    }
+
+    fn consume(&mut self, len: usize) -> Result<(), Error> {
+        self.consumed = self.consumed.saturating_add(len);
+        match self.limits.max_total_bytes {
+            Some(max) if self.consumed > max => Err(Error::LimitExceeded {
+                limit: Limit::TotalBytes,
+                max,
+                found: self.consumed,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn recv_raw_data(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        if let Some(progress) = &self.progress {
+            progress.record_codec();
+        }
+        self.consume(buf.len())?;
+        self.presence_slot = false;
+        self.source.recv_raw_data(buf)?;
+        if let Some(recording) = self.recordings.last_mut() {
+            recording.extend_from_slice(buf);
+        }
+        Ok(())
+    }
+
+    fn skip_raw_data(&mut self, len: usize) -> Result<(), Error> {
+        if self.recordings.is_empty() {
+            if let Some(progress) = &self.progress {
+                progress.record_codec();
+            }
+            self.consume(len)?;
+            return self.source.skip_raw_data(len);
+        }
+        let mut buf = [0; 256];
+        let mut remaining = len;
+        while remaining > 0 {
+            let count = remaining.min(buf.len());
+            self.recv_raw_data(&mut buf[.. count])?;
+            remaining -= count;
+        }
+        Ok(())
+    }
+
+    fn recv_tag(&mut self) -> Result<Tag, Error> {
+        if let Some(tag) = self.pending_tag.take() {
+            return Ok(tag);
+        }
+        let mut buf = [0];
+        self.recv_raw_data(&mut buf)?;
+        Tag::from_byte(buf[0]).ok_or(Error::InvalidTag(buf[0]))
+    }
+
+    fn recv_varint(&mut self) -> Result<u128, Error> {
+        if self.int_encoding == IntEncoding::Leb128 {
+            return self.recv_leb128();
+        }
+        let mut buf = [0; varint::MAX_ENCODED_LEN];
+        self.recv_raw_data(&mut buf[.. 1])?;
+        let len = varint::payload_len(buf[0])
+            .ok_or(Error::InvalidVarintMarker(buf[0]))?;
+        self.recv_raw_data(&mut buf[1 ..= len])?;
+        let value = varint::decode(&buf[..= len]);
+        if self.strict && varint::encoded_len(value) != len + 1 {
+            Err(Error::NonMinimalVarint(value))?;
+        }
+        Ok(value)
+    }
+
+    fn recv_leb128(&mut self) -> Result<u128, Error> {
+        let mut value = 0;
+        for index in 0 .. varint::MAX_ENCODED_LEN {
+            let mut buf = [0];
+            self.recv_raw_data(&mut buf)?;
+            let shift = index * 7;
+            let bits = u128::from(buf[0] & 0x7f);
+            if bits << shift >> shift != bits {
+                Err(Error::VarintTooLong)?;
+            }
+            value |= bits << shift;
+            if buf[0] & 0x80 == 0 {
+                if self.strict && index > 0 && buf[0] == 0 {
+                    Err(Error::NonMinimalVarint(value))?;
+                }
+                return Ok(value);
+            }
+        }
+        Err(Error::VarintTooLong)
+    }
+
+    fn recv_varint_as<T>(&mut self) -> Result<T, Error>
+    where
+        T: TryFrom<u128>,
+    {
+        let value = self.recv_varint()?;
+        T::try_from(value).map_err(|_| Error::VarintOverflow(value))
+    }
+
+    fn recv_zigzag_as<T>(&mut self) -> Result<T, Error>
+    where
+        T: TryFrom<i128>,
+    {
+        let value = self.recv_varint()?;
+        T::try_from(varint::unzigzag(value))
+            .map_err(|_| Error::VarintOverflow(value))
+    }
+
+    fn recv_usize(&mut self) -> Result<usize, Error> {
+        if self.int_encoding != IntEncoding::Fixint {
+            return self.recv_varint_as();
+        }
+        let mut buf = [0; 8];
+        self.recv_raw_data(&mut buf)?;
+        let bits = u64::from_le_bytes(buf);
+        usize::try_from(bits).map_err(|_| Error::ExcessiveSize(bits))
+    }
+
+    fn recv_utf8_char(&mut self) -> Result<char, Error> {
+        let mut buf = [0; 4];
+        self.recv_raw_data(&mut buf[.. 1])?;
+        let len = match buf[0] {
+            0x00 ..= 0x7f => 1,
+            0xc0 ..= 0xdf => 2,
+            0xe0 ..= 0xef => 3,
+            0xf0 ..= 0xf7 => 4,
+            _ => Err(Error::InvalidUtf8Char(buf[.. 1].to_vec()))?,
+        };
+        self.recv_raw_data(&mut buf[1 .. len])?;
+        str::from_utf8(&buf[.. len])
+            .ok()
+            .and_then(|text| text.chars().next())
+            .ok_or_else(|| Error::InvalidUtf8Char(buf[.. len].to_vec()))
+    }
+
+    fn recv_str_char(&mut self) -> Result<char, Error> {
+        let len = self.recv_usize()?;
+        if len > 4 {
+            Err(Error::CharTooLong(len))?;
+        }
+        let mut buf = [0; 4];
+        self.recv_raw_data(&mut buf[.. len])?;
+        let mut chars = str::from_utf8(&buf[.. len])
+            .map_err(|_| Error::InvalidUtf8Char(buf[.. len].to_vec()))?
+            .chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(ch),
+            _ => Err(Error::InvalidUtf8Char(buf[.. len].to_vec())),
+        }
+    }
+
+    fn recorded<T, F>(&mut self, visit: F) -> Result<(T, Vec<u8>), Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        self.recordings.push(Vec::new());
+        let result = visit(self);
+        let recording = self.recordings.pop().unwrap_or_default();
+        if let Some(parent) = self.recordings.last_mut() {
+            parent.extend_from_slice(&recording);
+        }
+        Ok((result?, recording))
+    }
+
+    fn nested<T, F>(&mut self, visit: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        if let Some(max_depth) = self.limits.max_depth {
+            if self.depth >= max_depth {
+                Err(Error::DepthLimitExceeded(max_depth))?;
+            }
+        }
+        self.depth += 1;
+        if let Some(progress) = &self.progress {
+            progress.record_depth(self.depth);
+        }
+        let result = visit(self);
+        self.depth -= 1;
+        if let Some(progress) = &self.progress {
+            progress.record_depth(self.depth);
+        }
+        result
+    }
+
+    fn visit_struct<'de, V>(
+        &mut self,
+        name: Option<&'static str>,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.tagged_fields {
+            return visitor
+                .visit_map(TaggedFieldsAccess::new(name, fields, self));
+        }
+        if self.presence_bitmap {
+            let mut bitmap = vec![0; fields.len().div_ceil(8)];
+            self.recv_raw_data(&mut bitmap)?;
+            return visitor
+                .visit_map(FieldsAccess::with_bitmap(fields, bitmap, self));
+        }
+        let len = if self.field_count {
+            let len = self.recv_usize()?;
+            if len > fields.len() {
+                Err(Error::TooManyFields {
+                    expected: fields.len(),
+                    found: len,
+                })?;
+            }
+            len
+        } else {
+            fields.len()
+        };
+        if self.field_presence {
+            visitor.visit_map(FieldsAccess::new(&fields[.. len], self))
+        } else {
+            visitor.visit_seq(ProductAccess::new(
+                len,
+                ProductKind::Struct(fields),
+                self,
+            ))
+        }
+    }
+
+    fn tagged_field(
+        &self,
+        name: Option<&'static str>,
+        fields: &'static [&'static str],
+        tag: u32,
+    ) -> Option<usize> {
+        let tagged = name
+            .and_then(|name| self.field_tags.get(name))
+            .and_then(|tags| tags.iter().find(|(_, found)| **found == tag));
+        match tagged {
+            Some((field, _)) => fields.iter().position(|known| known == field),
+            None => (tag as usize)
+                .checked_sub(1)
+                .filter(|index| *index < fields.len()),
+        }
+    }
+
+    fn variant_fallback(
+        &self,
+        name: &'static str,
+        variants: &'static [&'static str],
+    ) -> Option<&'static str> {
+        let fallback = self.variant_fallbacks.get(name)?;
+        variants.iter().copied().find(|variant| variant == fallback)
+    }
+
+    fn visit_named_variant<'de, V>(
+        &mut self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let has_value = match self.recv_tag()? {
+            Tag::Str => false,
+            Tag::Map => {
+                let len = self.recv_usize()?;
+                if len != 1 {
+                    Err(<Error as serde::de::Error>::invalid_length(
+                        len,
+                        &"a single variant entry",
+                    ))?;
+                }
+                true
+            },
+            tag => Err(Error::InvalidTag(tag as u8))?,
+        };
+        let variant = if has_value {
+            String::deserialize(&mut *self)?
+        } else {
+            self.untagged(|this| String::deserialize(this))?
+        };
+        let (variant, fallback) =
+            match variants.iter().find(|known| **known == variant) {
+                Some(known) => (*known, false),
+                None => match self.variant_fallback(name, variants) {
+                    Some(fallback) => (fallback, true),
+                    None => Err(Error::UnknownVariantName { name, variant })?,
+                },
+            };
+        visitor.visit_enum(NamedSumAccess {
+            deserializer: self,
+            variant,
+            has_value,
+            fallback,
+        })
+    }
+
+    fn skip_value(&mut self) -> Result<(), Error> {
+        match self.recv_tag()? {
+            Tag::Unit | Tag::None => Ok(()),
+            Tag::Bool | Tag::I8 | Tag::U8 => self.skip_raw_data(1),
+            Tag::Char if self.str_chars => self.recv_str_char().map(drop),
+            Tag::Char if self.utf8_chars => self.recv_utf8_char().map(drop),
+            Tag::I16
+            | Tag::U16
+            | Tag::I32
+            | Tag::U32
+            | Tag::Char
+            | Tag::I64
+            | Tag::U64
+            | Tag::I128
+            | Tag::U128
+                if self.int_encoding != IntEncoding::Fixint =>
+            {
+                self.recv_varint().map(drop)
+            },
+            Tag::I16 | Tag::U16 => self.skip_raw_data(2),
+            Tag::F32 | Tag::F64 if self.reject_floats => {
+                Err(Error::FloatRejected)
+            },
+            Tag::I32 | Tag::U32 | Tag::F32 | Tag::Char => self.skip_raw_data(4),
+            Tag::I64 | Tag::U64 | Tag::F64 => self.skip_raw_data(8),
+            Tag::I128 | Tag::U128 => self.skip_raw_data(16),
+            Tag::Str if self.interned_strings.is_some() => {
+                self.recv_interned_str().map(drop)
+            },
+            Tag::Str | Tag::Bytes => {
+                let len = self.recv_len(Limit::BytesLen)?;
+                self.skip_raw_data(len)
+            },
+            Tag::Some => self.nested(|this| this.skip_value()),
+            Tag::Seq => {
+                let len = self.recv_len(Limit::SeqLen)?;
+                self.nested(|this| {
+                    (0 .. len).try_for_each(|_| this.skip_value())
+                })
+            },
+            Tag::Map => {
+                let len = self.recv_len(Limit::MapLen)?;
+                self.nested(|this| {
+                    (0 .. len).try_for_each(|_| {
+                        this.skip_value()?;
+                        this.skip_value()
+                    })
+                })
+            },
+        }
+    }
+
+    fn overriding<T, F>(&mut self, repr: Override, visit: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        let outer = (
+            self.int_encoding,
+            self.self_describing,
+            self.utf8_chars,
+            self.str_chars,
+        );
+        match repr {
+            Override::IntEncoding(encoding) => self.int_encoding = encoding,
+            Override::Untagged => self.self_describing = false,
+            Override::Utf8Chars => {
+                self.utf8_chars = true;
+                self.str_chars = false;
+            },
+        }
+        let result = visit(self);
+        (
+            self.int_encoding,
+            self.self_describing,
+            self.utf8_chars,
+            self.str_chars,
+        ) = outer;
+        result
+    }
+
+    fn untagged<T, F>(&mut self, visit: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        self.self_describing = false;
+        let result = visit(self);
+        self.self_describing = true;
+        result
+    }
+
+    fn recv_len(&mut self, limit: Limit) -> Result<usize, Error> {
+        let len = self.recv_usize()?;
+        match self.limits.get(limit) {
+            Some(max) if len > max => {
+                Err(Error::LimitExceeded { limit, max, found: len })
+            },
+            _ => Ok(len),
+        }
+    }
+
+    fn prealloc(&self, len: usize) -> usize {
+        self.limits.max_prealloc.map_or(len, |max| len.min(max))
+    }
+
+    fn recv_vec(&mut self, len: usize) -> Result<Vec<u8>, Error> {
+        let step = self.prealloc(len).max(1);
+        if step >= len {
+            let mut buf = vec![0; len];
+            self.recv_raw_data(&mut buf)?;
+            return Ok(buf);
+        }
+        let mut buf = Vec::with_capacity(step);
+        while buf.len() < len {
+            let start = buf.len();
+            buf.resize(start + step.min(len - start), 0);
+            self.recv_raw_data(&mut buf[start ..])?;
+        }
+        Ok(buf)
+    }
+
+    fn ensure_available(&self, len: usize) -> Result<(), Error> {
+        match self.source.remaining_len() {
+            Some(remaining) if len > remaining => {
+                Err(Error::LengthExceedsInput { len, remaining })
+            },
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn recv_bytes_header(&mut self) -> Result<usize, Error> {
+        self.recv_blob_header(Tag::Bytes)
+    }
+
+    pub(crate) fn recv_str_header(&mut self) -> Result<usize, Error> {
+        self.recv_blob_header(Tag::Str)
+    }
+
+    fn recv_blob_header(&mut self, expected: Tag) -> Result<usize, Error> {
+        if self.self_describing {
+            let tag = self.recv_tag()?;
+            if tag != expected {
+                Err(Error::InvalidTag(tag as u8))?;
+            }
+        }
+        if expected == Tag::Str && self.interned_strings.is_some() {
+            let reference = self.recv_usize()?;
+            if reference != 0 {
+                Err(Error::UnknownInternedString(reference))?;
+            }
+        }
+        self.recv_len(Limit::BytesLen)
+    }
+
+    fn skip_blob(&mut self, expected: Tag) -> Result<(), Error> {
+        if self.self_describing {
+            let tag = self.recv_tag()?;
+            if tag != expected {
+                Err(Error::InvalidTag(tag as u8))?;
+            }
+        }
+        if expected == Tag::Str && self.interned_strings.is_some() {
+            return self.recv_interned_str().map(drop);
+        }
+        let len = self.recv_len(Limit::BytesLen)?;
+        self.ensure_available(len)?;
+        if expected == Tag::Bytes {
+            return self.skip_raw_data(len);
+        }
+        let mut buf = [0; 256];
+        let mut carried = 0;
+        let mut remaining = len;
+        while remaining > 0 {
+            let count = remaining.min(buf.len() - carried);
+            let filled = carried + count;
+            self.recv_raw_data(&mut buf[carried .. filled])?;
+            remaining -= count;
+            carried = match str::from_utf8(&buf[.. filled]) {
+                Ok(_) => 0,
+                Err(error) if error.error_len().is_none() && remaining > 0 => {
+                    let valid = error.valid_up_to();
+                    buf.copy_within(valid .. filled, 0);
+                    filled - valid
+                },
+                Err(_) => {
+                    return String::from_utf8(buf[.. filled].to_vec())
+                        .map(drop)
+                        .map_err(Error::Utf8);
+                },
+            };
+        }
+        Ok(())
+    }
+
+    fn recv_byte_buf(&mut self) -> Result<Vec<u8>, Error> {
+        let len = self.recv_len(Limit::BytesLen)?;
+        self.ensure_available(len)?;
+        self.recv_vec(len)
+    }
+
+    fn recv_interned_str(&mut self) -> Result<Arc<str>, Error> {
+        let reference = self.recv_usize()?;
+        let table = self.interned_strings.as_deref().unwrap_or_default();
+        if let Some(index) = reference.checked_sub(1) {
+            return table
+                .get(index)
+                .cloned()
+                .ok_or(Error::UnknownInternedString(reference));
+        }
+        let buf = self.recv_byte_buf()?;
+        let string = Arc::<str>::from(String::from_utf8(buf)?);
+        if let Some(table) = &mut self.interned_strings {
+            table.push(string.clone());
+        }
+        Ok(string)
+    }
+}
+
+#[allow(clippy::needless_lifetimes)]
+impl<'a, 'de, S> serde::de::Deserializer<'de> for &'a mut Deserializer<S>
+where
+    S: DeserializationSource,
+{
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if !self.self_describing {
+            Err(Error::UnsupportedAny)?;
+        }
+        match self.recv_tag()? {
+            Tag::Unit => visitor.visit_unit(),
+            Tag::Bool => self.untagged(|this| this.deserialize_bool(visitor)),
+            Tag::I8 => self.untagged(|this| this.deserialize_i8(visitor)),
+            Tag::I16 => self.untagged(|this| this.deserialize_i16(visitor)),
+            Tag::I32 => self.untagged(|this| this.deserialize_i32(visitor)),
+            Tag::I64 => self.untagged(|this| this.deserialize_i64(visitor)),
+            Tag::I128 => self.untagged(|this| this.deserialize_i128(visitor)),
+            Tag::U8 => self.untagged(|this| this.deserialize_u8(visitor)),
+            Tag::U16 => self.untagged(|this| this.deserialize_u16(visitor)),
+            Tag::U32 => self.untagged(|this| this.deserialize_u32(visitor)),
+            Tag::U64 => self.untagged(|this| this.deserialize_u64(visitor)),
+            Tag::U128 => self.untagged(|this| this.deserialize_u128(visitor)),
+            Tag::F32 => self.untagged(|this| this.deserialize_f32(visitor)),
+            Tag::F64 => self.untagged(|this| this.deserialize_f64(visitor)),
+            Tag::Char => self.untagged(|this| this.deserialize_char(visitor)),
+            Tag::Str => self.untagged(|this| this.deserialize_string(visitor)),
+            Tag::Bytes => {
+                self.untagged(|this| this.deserialize_byte_buf(visitor))
+            },
+            Tag::None => visitor.visit_none(),
+            Tag::Some => self.nested(|this| visitor.visit_some(this)),
+            Tag::Seq => {
+                let len = self.recv_len(Limit::SeqLen)?;
+                self.nested(|this| {
+                    visitor.visit_seq(ProductAccess::new(
+                        len,
+                        ProductKind::Seq,
+                        this,
+                    ))
+                })
+            },
+            Tag::Map => {
+                let len = self.recv_len(Limit::MapLen)?;
+                self.nested(|this| {
+                    visitor.visit_map(ProductAccess::new(
+                        len,
+                        ProductKind::Map,
+                        this,
+                    ))
+                })
+            },
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        let mut buf = [0];
+        self.recv_raw_data(&mut buf)?;
+        match buf[0] {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            byte if self.strict => Err(Error::InvalidBool(byte)),
+            _ => visitor.visit_bool(true),
+        }
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        let mut buf = [0];
+        self.recv_raw_data(&mut buf)?;
+        visitor.visit_i8(i8::from_le_bytes(buf))
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.int_encoding != IntEncoding::Fixint {
+            return visitor.visit_i16(self.recv_zigzag_as()?);
+        }
+        let mut buf = [0; 2];
+        self.recv_raw_data(&mut buf)?;
+        visitor.visit_i16(i16::from_le_bytes(buf))
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.int_encoding != IntEncoding::Fixint {
+            return visitor.visit_i32(self.recv_zigzag_as()?);
+        }
+        let mut buf = [0; 4];
+        self.recv_raw_data(&mut buf)?;
+        visitor.visit_i32(i32::from_le_bytes(buf))
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.int_encoding != IntEncoding::Fixint {
+            return visitor.visit_i64(self.recv_zigzag_as()?);
+        }
+        let mut buf = [0; 8];
+        self.recv_raw_data(&mut buf)?;
+        visitor.visit_i64(i64::from_le_bytes(buf))
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.int_encoding != IntEncoding::Fixint {
+            return visitor.visit_i128(self.recv_zigzag_as()?);
+        }
         let mut buf = [0; 16];
-        self.source.recv_raw_data(&mut buf)?;
+        self.recv_raw_data(&mut buf)?;
         visitor.visit_i128(i128::from_le_bytes(buf))
     }
 
@@ -242,8 +1838,11 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
         let mut buf = [0];
-        self.source.recv_raw_data(&mut buf)?;
+        self.recv_raw_data(&mut buf)?;
         visitor.visit_u8(u8::from_le_bytes(buf))
     }
 
@@ -251,8 +1850,14 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.int_encoding != IntEncoding::Fixint {
+            return visitor.visit_u16(self.recv_varint_as()?);
+        }
         let mut buf = [0; 2];
-        self.source.recv_raw_data(&mut buf)?;
+        self.recv_raw_data(&mut buf)?;
         visitor.visit_u16(u16::from_le_bytes(buf))
     }
 
@@ -260,8 +1865,14 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.int_encoding != IntEncoding::Fixint {
+            return visitor.visit_u32(self.recv_varint_as()?);
+        }
         let mut buf = [0; 4];
-        self.source.recv_raw_data(&mut buf)?;
+        self.recv_raw_data(&mut buf)?;
         visitor.visit_u32(u32::from_le_bytes(buf))
     }
 
@@ -269,8 +1880,14 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.int_encoding != IntEncoding::Fixint {
+            return visitor.visit_u64(self.recv_varint_as()?);
+        }
         let mut buf = [0; 8];
-        self.source.recv_raw_data(&mut buf)?;
+        self.recv_raw_data(&mut buf)?;
         visitor.visit_u64(u64::from_le_bytes(buf))
     }
 
@@ -278,8 +1895,14 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.int_encoding != IntEncoding::Fixint {
+            return visitor.visit_u128(self.recv_varint_as()?);
+        }
         let mut buf = [0; 16];
-        self.source.recv_raw_data(&mut buf)?;
+        self.recv_raw_data(&mut buf)?;
         visitor.visit_u128(u128::from_le_bytes(buf))
     }
 
@@ -287,35 +1910,93 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.reject_floats {
+            Err(Error::FloatRejected)?;
+        }
         let mut buf = [0; 4];
-        self.source.recv_raw_data(&mut buf)?;
-        visitor.visit_f32(f32::from_le_bytes(buf))
+        self.recv_raw_data(&mut buf)?;
+        let value = f32::from_le_bytes(buf);
+        if self.finite_floats && !value.is_finite() {
+            Err(Error::NonFiniteFloat(f64::from(value)))?;
+        }
+        if self.canonical_nan
+            && value.is_nan()
+            && value.to_bits() != f32::NAN.to_bits()
+        {
+            Err(Error::NonCanonicalNan(u64::from(value.to_bits())))?;
+        }
+        visitor.visit_f32(value)
     }
 
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.reject_floats {
+            Err(Error::FloatRejected)?;
+        }
         let mut buf = [0; 8];
-        self.source.recv_raw_data(&mut buf)?;
-        visitor.visit_f64(f64::from_le_bytes(buf))
+        self.recv_raw_data(&mut buf)?;
+        let value = f64::from_le_bytes(buf);
+        if self.finite_floats && !value.is_finite() {
+            Err(Error::NonFiniteFloat(value))?;
+        }
+        if self.canonical_nan
+            && value.is_nan()
+            && value.to_bits() != f64::NAN.to_bits()
+        {
+            Err(Error::NonCanonicalNan(value.to_bits()))?;
+        }
+        visitor.visit_f64(value)
     }
 
     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        let codepoint = u32::deserialize(self)?;
-        let ch = char::try_from(codepoint)
-            .map_err(|_| Error::InvalidCodePoint(codepoint))?;
-        visitor.visit_char(ch)
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        let result = if self.str_chars {
+            self.recv_str_char()
+        } else if self.utf8_chars {
+            self.recv_utf8_char()
+        } else {
+            let mut buf = [0; 4];
+            self.recv_raw_data(&mut buf)?;
+            let codepoint = u32::from_le_bytes(buf);
+            char::try_from(codepoint)
+                .map_err(|_| Error::InvalidCodePoint(codepoint))
+        };
+        match result {
+            Err(Error::InvalidCodePoint(_) | Error::InvalidUtf8Char(_))
+                if self.lenient_chars =>
+            {
+                visitor.visit_char(char::REPLACEMENT_CHARACTER)
+            },
+            result => visitor.visit_char(result?),
+        }
     }
 
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        let string = String::deserialize(self)?;
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.interned_strings.is_some() {
+            let string = self.recv_interned_str()?;
+            return intern::offer(string, |string| visitor.visit_str(string));
+        }
+        let buf = self.recv_byte_buf()?;
+        let string = String::from_utf8(buf).map_err(Error::Utf8)?;
         visitor.visit_str(&string[..])
     }
 
@@ -323,7 +2004,14 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
-        let buf = Vec::<u8>::deserialize(self)?;
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        if self.interned_strings.is_some() {
+            let string = self.recv_interned_str()?;
+            return intern::offer(string, |string| visitor.visit_str(string));
+        }
+        let buf = self.recv_byte_buf()?;
         let string = String::from_utf8(buf).map_err(Error::Utf8)?;
         visitor.visit_string(string)
     }
@@ -332,7 +2020,10 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
-        let buf = Vec::<u8>::deserialize(self)?;
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        let buf = self.recv_byte_buf()?;
         visitor.visit_bytes(&buf[..])
     }
 
@@ -343,9 +2034,10 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
-        let len = self.source.recv_usize()?;
-        let mut buf = vec![0; len];
-        self.source.recv_raw_data(&mut buf)?;
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        let buf = self.recv_byte_buf()?;
         visitor.visit_byte_buf(buf)
     }
 
@@ -353,11 +2045,24 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
-        let tag = u8::deserialize(&mut *self)?;
-        if tag == 0 {
-            visitor.visit_none()
-        } else {
-            visitor.visit_some(self)
+        if self.presence_slot {
+            self.presence_slot = false;
+            return self.nested(|this| visitor.visit_some(this));
+        }
+        if self.self_describing {
+            return match self.recv_tag()? {
+                Tag::None | Tag::Unit => visitor.visit_none(),
+                Tag::Some => self.nested(|this| visitor.visit_some(this)),
+                tag => {
+                    self.pending_tag = Some(tag);
+                    self.nested(|this| visitor.visit_some(this))
+                },
+            };
+        }
+        match u8::deserialize(&mut *self)? {
+            0 => visitor.visit_none(),
+            tag if self.strict && tag != 1 => Err(Error::InvalidOptionTag(tag)),
+            _ => self.nested(|this| visitor.visit_some(this)),
         }
     }
 
@@ -365,6 +2070,9 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
         visitor.visit_unit()
     }
 
@@ -376,26 +2084,60 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
         visitor.visit_unit()
     }
 
     fn deserialize_newtype_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_newtype_struct(self)
+        self.presence_slot = false;
+        if let Some(tag) = validate::skipped_blob(name) {
+            self.skip_blob(tag)?;
+            return visitor.visit_unit();
+        }
+        #[cfg(feature = "bulk")]
+        if self.reject_floats && crate::bulk::is_float(name) {
+            Err(Error::FloatRejected)?;
+        }
+        #[cfg(feature = "bulk")]
+        if let Some(element_size) =
+            crate::bulk::element_size(name).filter(|_| !self.self_describing)
+        {
+            let len = self.recv_len(Limit::SeqLen)?;
+            let byte_len = len
+                .checked_mul(element_size)
+                .ok_or(Error::ExcessiveSize(len as u64))?;
+            self.ensure_available(byte_len)?;
+            let buf = self.recv_vec(byte_len)?;
+            return visitor.visit_byte_buf(buf);
+        }
+        if let Some(repr) = repr::override_for(name) {
+            return self.overriding(repr, |this| {
+                this.nested(|this| visitor.visit_newtype_struct(this))
+            });
+        }
+        self.nested(|this| visitor.visit_newtype_struct(this))
     }
 
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        let len = self.source.recv_usize()?;
-        visitor.visit_seq(ProductAccess { remaining: len, deserializer: self })
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        let len = self.recv_len(Limit::SeqLen)?;
+        self.nested(|this| {
+            visitor.visit_seq(ProductAccess::new(len, ProductKind::Seq, this))
+        })
     }
 
     fn deserialize_tuple<V>(
@@ -406,7 +2148,12 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_seq(ProductAccess { remaining: len, deserializer: self })
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        self.nested(|this| {
+            visitor.visit_seq(ProductAccess::new(len, ProductKind::Tuple, this))
+        })
     }
 
     fn deserialize_tuple_struct<V>(
@@ -418,42 +2165,59 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_seq(ProductAccess { remaining: len, deserializer: self })
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        self.nested(|this| {
+            visitor.visit_seq(ProductAccess::new(len, ProductKind::Tuple, this))
+        })
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        let len = self.source.recv_usize()?;
-        visitor.visit_map(ProductAccess { remaining: len, deserializer: self })
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        let len = self.recv_len(Limit::MapLen)?;
+        self.nested(|this| {
+            visitor.visit_map(ProductAccess::new(len, ProductKind::Map, this))
+        })
     }
 
     fn deserialize_struct<V>(
         self,
-        _name: &'static str,
+        name: &'static str,
         fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_seq(ProductAccess {
-            remaining: fields.len(),
-            deserializer: self,
-        })
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
+        self.nested(|this| this.visit_struct(Some(name), fields, visitor))
     }
 
     fn deserialize_enum<V>(
         self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
+        name: &'static str,
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_enum(SumAccess { deserializer: self })
+        if self.self_describing {
+            return self.nested(|this| {
+                this.visit_named_variant(name, variants, visitor)
+            });
+        }
+        self.nested(|this| {
+            visitor.visit_enum(SumAccess { deserializer: this, name, variants })
+        })
     }
 
     fn deserialize_identifier<V>(
@@ -463,17 +2227,24 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
+        if self.self_describing {
+            return self.deserialize_any(visitor);
+        }
         visitor.visit_unit()
     }
 
     fn deserialize_ignored_any<V>(
         self,
-        _visitor: V,
+        visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: serde::de::Visitor<'de>,
     {
-        Err(Error::UnsupportedAny)
+        if !self.self_describing {
+            Err(Error::UnsupportedAny)?;
+        }
+        self.skip_value()?;
+        visitor.visit_unit()
     }
 
     fn is_human_readable(&self) -> bool {
@@ -481,10 +2252,70 @@ where
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+enum ProductKind {
+    Seq,
+    Tuple,
+    Struct(&'static [&'static str]),
+    Map,
+}
+
 #[derive(Debug)]
 struct ProductAccess<'a, S> {
     remaining: usize,
+    index: usize,
+    kind: ProductKind,
     deserializer: &'a mut Deserializer<S>,
+    last_key: Option<Vec<u8>>,
+    seen_keys: HashMap<u64, Vec<Vec<u8>>>,
+}
+
+impl<'a, S> ProductAccess<'a, S>
+where
+    S: DeserializationSource,
+{
+    fn new(
+        remaining: usize,
+        kind: ProductKind,
+        deserializer: &'a mut Deserializer<S>,
+    ) -> Self {
+        Self {
+            remaining,
+            index: 0,
+            kind,
+            deserializer,
+            last_key: None,
+            seen_keys: HashMap::new(),
+        }
+    }
+
+    fn check_key(&mut self, key: Vec<u8>) -> Result<(), Error> {
+        if let Some(hasher) = &self.deserializer.key_hasher {
+            let keys = self.seen_keys.entry(hasher.hash_key(&key)).or_default();
+            if keys.contains(&key) {
+                Err(Error::DuplicateMapKey(self.index))?;
+            }
+            keys.push(key.clone());
+        }
+        if self.deserializer.canonical {
+            if self.last_key.as_ref().is_some_and(|last| *last >= key) {
+                Err(Error::UnsortedMapKeys)?;
+            }
+            self.last_key = Some(key);
+        }
+        Ok(())
+    }
+
+    fn element_segment(&self) -> Segment {
+        match self.kind {
+            ProductKind::Seq | ProductKind::Map => Segment::Index(self.index),
+            ProductKind::Tuple => Segment::Position(self.index),
+            ProductKind::Struct(fields) => match fields.get(self.index) {
+                Some(field) => Segment::Field(field),
+                None => Segment::Position(self.index),
+            },
+        }
+    }
 }
 
 impl<'a, 'de, S> serde::de::SeqAccess<'de> for ProductAccess<'a, S>
@@ -503,11 +2334,24 @@ where
         let Some(adjusted_remaining) = self.remaining.checked_sub(1) else {
             return Ok(None);
         };
+        if let Some(progress) = &self.deserializer.progress {
+            progress.record_element();
+        }
 
-        let element = seed.deserialize(&mut *self.deserializer)?;
+        self.deserializer.presence_slot = false;
+        let segment = self.element_segment();
+        let element =
+            self.deserializer.in_segment(segment, |deserializer| {
+                seed.deserialize(deserializer)
+            })?;
         self.remaining = adjusted_remaining;
+        self.index += 1;
         Ok(Some(element))
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.deserializer.prealloc(self.remaining))
+    }
 }
 
 impl<'a, 'de, S> serde::de::MapAccess<'de> for ProductAccess<'a, S>
@@ -526,8 +2370,26 @@ where
         let Some(adjusted_remaining) = self.remaining.checked_sub(1) else {
             return Ok(None);
         };
+        if let Some(progress) = &self.deserializer.progress {
+            progress.record_element();
+        }
 
-        let element = seed.deserialize(&mut *self.deserializer)?;
+        let segment = Segment::MapKey(self.index);
+        let element = if self.deserializer.canonical
+            || self.deserializer.key_hasher.is_some()
+        {
+            let (element, key) =
+                self.deserializer.in_segment(segment, |deserializer| {
+                    deserializer
+                        .recorded(|deserializer| seed.deserialize(deserializer))
+                })?;
+            self.check_key(key)?;
+            element
+        } else {
+            self.deserializer.in_segment(segment, |deserializer| {
+                seed.deserialize(deserializer)
+            })?
+        };
         self.remaining = adjusted_remaining;
         Ok(Some(element))
     }
@@ -536,13 +2398,310 @@ where
     where
         V: serde::de::DeserializeSeed<'de>,
     {
-        seed.deserialize(&mut *self.deserializer)
+        let segment = self.element_segment();
+        let value = self.deserializer.in_segment(segment, |deserializer| {
+            seed.deserialize(deserializer)
+        })?;
+        self.index += 1;
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.deserializer.prealloc(self.remaining))
+    }
+}
+
+#[derive(Debug)]
+struct FieldsAccess<'a, S> {
+    fields: &'static [&'static str],
+    index: usize,
+    bitmap: Option<Vec<u8>>,
+    deserializer: &'a mut Deserializer<S>,
+}
+
+impl<'a, S> FieldsAccess<'a, S>
+where
+    S: DeserializationSource,
+{
+    fn new(
+        fields: &'static [&'static str],
+        deserializer: &'a mut Deserializer<S>,
+    ) -> Self {
+        Self { fields, index: 0, bitmap: None, deserializer }
+    }
+
+    fn with_bitmap(
+        fields: &'static [&'static str],
+        bitmap: Vec<u8>,
+        deserializer: &'a mut Deserializer<S>,
+    ) -> Self {
+        Self { fields, index: 0, bitmap: Some(bitmap), deserializer }
+    }
+
+    fn is_present(&mut self, field: &'static str) -> Result<bool, Error> {
+        if let Some(bitmap) = &self.bitmap {
+            let byte = bitmap[self.index / 8];
+            return Ok(byte & (1 << (self.index % 8)) != 0);
+        }
+        self.deserializer.in_segment(Segment::Field(field), |this| {
+            match u8::deserialize(&mut *this)? {
+                0 => Ok(false),
+                tag if this.strict && tag != 1 => {
+                    Err(Error::InvalidOptionTag(tag))
+                },
+                _ => Ok(true),
+            }
+        })
+    }
+}
+
+impl<'a, 'de, S> serde::de::MapAccess<'de> for FieldsAccess<'a, S>
+where
+    S: DeserializationSource,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        while let Some(&field) = self.fields.get(self.index) {
+            match self.is_present(field)? {
+                false => self.index += 1,
+                true => {
+                    let index = self.index as u64;
+                    return seed
+                        .deserialize(index.into_deserializer())
+                        .map(Some);
+                },
+            }
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let segment = match self.fields.get(self.index) {
+            Some(field) => Segment::Field(field),
+            None => Segment::Position(self.index),
+        };
+        self.deserializer.presence_slot = self.bitmap.is_some();
+        let value = self
+            .deserializer
+            .in_segment(segment, |deserializer| seed.deserialize(deserializer));
+        self.deserializer.presence_slot = false;
+        self.index += 1;
+        value
+    }
+}
+
+#[derive(Debug)]
+struct TaggedFieldsAccess<'a, S> {
+    name: Option<&'static str>,
+    fields: &'static [&'static str],
+    current: (u32, usize, usize),
+    deserializer: &'a mut Deserializer<S>,
+}
+
+impl<'a, S> TaggedFieldsAccess<'a, S>
+where
+    S: DeserializationSource,
+{
+    fn new(
+        name: Option<&'static str>,
+        fields: &'static [&'static str],
+        deserializer: &'a mut Deserializer<S>,
+    ) -> Self {
+        Self { name, fields, current: (0, 0, 0), deserializer }
+    }
+}
+
+impl<'a, 'de, S> serde::de::MapAccess<'de> for TaggedFieldsAccess<'a, S>
+where
+    S: DeserializationSource,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: serde::de::DeserializeSeed<'de>,
+    {
+        loop {
+            let tag = u32::deserialize(&mut *self.deserializer)?;
+            if tag == 0 {
+                return Ok(None);
+            }
+            let len = self.deserializer.recv_len(Limit::BytesLen)?;
+            match self.deserializer.tagged_field(self.name, self.fields, tag) {
+                Some(index) => {
+                    self.current = (tag, index, len);
+                    return seed
+                        .deserialize((index as u64).into_deserializer())
+                        .map(Some);
+                },
+                None => self.deserializer.skip_raw_data(len)?,
+            }
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let (tag, index, len) = self.current;
+        let start = self.deserializer.source.offset();
+        let value = self
+            .deserializer
+            .in_segment(Segment::Field(self.fields[index]), |deserializer| {
+                seed.deserialize(deserializer)
+            })?;
+        let consumed = self.deserializer.source.offset() - start;
+        let rest = (len as u64)
+            .checked_sub(consumed)
+            .ok_or(Error::FieldOverrun { tag, len })?;
+        self.deserializer.skip_raw_data(rest as usize)?;
+        Ok(value)
     }
 }
 
 #[derive(Debug)]
 struct SumAccess<'a, S> {
     deserializer: &'a mut Deserializer<S>,
+    name: &'static str,
+    variants: &'static [&'static str],
+}
+
+impl<'a, S> SumAccess<'a, S>
+where
+    S: DeserializationSource,
+{
+    fn leave<T>(self, result: Result<T, Error>) -> Result<T, Error> {
+        let value = result?;
+        self.deserializer.pop_segment();
+        Ok(value)
+    }
+}
+
+#[derive(Debug)]
+struct NamedSumAccess<'a, S> {
+    deserializer: &'a mut Deserializer<S>,
+    variant: &'static str,
+    has_value: bool,
+    fallback: bool,
+}
+
+impl<'a, S> NamedSumAccess<'a, S>
+where
+    S: DeserializationSource,
+{
+    fn leave<T>(self, result: Result<T, Error>) -> Result<T, Error> {
+        let value = result?;
+        self.deserializer.pop_segment();
+        Ok(value)
+    }
+
+    fn expect_value(&self) -> Result<(), Error> {
+        if self.has_value {
+            Ok(())
+        } else {
+            Err(serde::de::Error::invalid_type(
+                serde::de::Unexpected::UnitVariant,
+                &"a variant with a value",
+            ))
+        }
+    }
+}
+
+impl<'a, 'de, S> serde::de::EnumAccess<'de> for NamedSumAccess<'a, S>
+where
+    S: DeserializationSource,
+{
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: serde::de::DeserializeSeed<'de>,
+    {
+        let result: Result<_, Error> =
+            seed.deserialize(self.variant.into_deserializer());
+        let val = result?;
+        self.deserializer.push_segment(Segment::Variant(self.variant));
+        Ok((val, self))
+    }
+}
+
+impl<'a, 'de, S> serde::de::VariantAccess<'de> for NamedSumAccess<'a, S>
+where
+    S: DeserializationSource,
+{
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        let result = if self.has_value && self.fallback {
+            IgnoredAny::deserialize(&mut *self.deserializer).map(|_| ())
+        } else if self.has_value {
+            <()>::deserialize(&mut *self.deserializer)
+        } else {
+            Ok(())
+        };
+        self.leave(result)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: serde::de::DeserializeSeed<'de>,
+    {
+        self.expect_value()?;
+        let result = seed.deserialize(&mut *self.deserializer);
+        self.leave(result)
+    }
+
+    fn tuple_variant<V>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.expect_value()?;
+        let result = serde::de::Deserializer::deserialize_tuple(
+            &mut *self.deserializer,
+            len,
+            visitor,
+        );
+        self.leave(result)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        self.expect_value()?;
+        let result = serde::de::Deserializer::deserialize_struct(
+            &mut *self.deserializer,
+            "",
+            fields,
+            visitor,
+        );
+        self.leave(result)
+    }
 }
 
 impl<'a, 'de, S> serde::de::EnumAccess<'de> for SumAccess<'a, S>
@@ -559,10 +2718,32 @@ where
     where
         V: serde::de::DeserializeSeed<'de>,
     {
-        let tag: u32 = u32::deserialize(&mut *self.deserializer)?;
+        let name = self.name;
+        let variants = self.variants;
+        let known = if self.deserializer.variant_names {
+            let variant = String::deserialize(&mut *self.deserializer)?;
+            match variants.iter().position(|known| *known == variant) {
+                Some(index) => Ok(index),
+                None => Err(Error::UnknownVariantName { name, variant }),
+            }
+        } else {
+            let index = u32::deserialize(&mut *self.deserializer)?;
+            usize::try_from(index)
+                .ok()
+                .filter(|index| *index < variants.len())
+                .ok_or(Error::UnknownVariant {
+                    name,
+                    index,
+                    count: variants.len(),
+                })
+        };
+        // Without self-description an unknown variant's payload cannot be
+        // skipped, so falling back here would misread the rest of the input.
+        let index = known?;
         let result: Result<_, Error> =
-            seed.deserialize(tag.into_deserializer());
+            seed.deserialize((index as u32).into_deserializer());
         let val = result?;
+        self.deserializer.push_segment(Segment::Variant(variants[index]));
         Ok((val, self))
     }
 }
@@ -574,14 +2755,15 @@ where
     type Error = Error;
 
     fn unit_variant(self) -> Result<(), Self::Error> {
-        Ok(())
+        self.leave(Ok(()))
     }
 
     fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
     where
         T: serde::de::DeserializeSeed<'de>,
     {
-        seed.deserialize(self.deserializer)
+        let result = seed.deserialize(&mut *self.deserializer);
+        self.leave(result)
     }
 
     fn tuple_variant<V>(
@@ -592,10 +2774,12 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_seq(ProductAccess {
-            remaining: len,
-            deserializer: &mut *self.deserializer,
-        })
+        let result = visitor.visit_seq(ProductAccess::new(
+            len,
+            ProductKind::Tuple,
+            &mut *self.deserializer,
+        ));
+        self.leave(result)
     }
 
     fn struct_variant<V>(
@@ -606,9 +2790,7 @@ where
     where
         V: serde::de::Visitor<'de>,
     {
-        visitor.visit_seq(ProductAccess {
-            remaining: fields.len(),
-            deserializer: &mut *self.deserializer,
-        })
+        let result = self.deserializer.visit_struct(None, fields, visitor);
+        self.leave(result)
     }
 }
@@ -4,4 +4,48 @@ mod public;
 #[cfg(test)]
 mod test;
 
-pub use public::{deserialize, deserialize_buffer, Config, ConfigError, Error};
+#[cfg(feature = "bytes")]
+pub use internal::BufSource;
+#[cfg(feature = "stream")]
+pub use internal::StreamReader;
+pub use internal::{
+    BufferSource,
+    ChainReader,
+    ChannelBackend,
+    ChannelBytes,
+    ChannelSource,
+    DeserializationSource,
+    Deserializer,
+    KeyHasher,
+};
+#[cfg(feature = "stream")]
+pub use public::deserialize_stream;
+pub use public::{
+    deserialize,
+    deserialize_armored,
+    deserialize_blob,
+    deserialize_buffer,
+    deserialize_buffered,
+    deserialize_bytes_into,
+    deserialize_chain,
+    deserialize_from_file,
+    deserialize_nth,
+    deserialize_parallel,
+    deserialize_str_into,
+    recv_datagram_from,
+    skip_value,
+    validate,
+    AsyncDeserializer,
+    BlobReader,
+    Config,
+    ConfigBuilder,
+    ConfigError,
+    Error,
+    ErrorKind,
+    IncrementalDecoder,
+    Limit,
+    Options,
+    ParallelDecoder,
+    Path,
+    Segment,
+};
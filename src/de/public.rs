@@ -1,36 +1,191 @@
-use std::{fmt, panic, string::FromUtf8Error};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
+    future::Future,
+    hash::RandomState,
+    io::SeekFrom,
+    marker::PhantomData,
+    net::SocketAddr,
+    panic,
+    path::Path as FsPath,
+    pin::Pin,
+    string::FromUtf8Error,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
 
-use serde::Deserialize;
+#[cfg(feature = "bumpalo")]
+use bumpalo::Bump;
+#[cfg(feature = "bytes")]
+use bytes::Buf;
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+use serde::{de::DeserializeSeed, Deserialize};
 use thiserror::Error;
+#[cfg(unix)]
+use tokio::net::{unix, UnixDatagram};
 use tokio::{
-    io::{self, AsyncRead},
-    sync::mpsc,
-    task,
+    fs,
+    io::{
+        self,
+        AsyncBufRead,
+        AsyncRead,
+        AsyncReadExt,
+        AsyncSeek,
+        AsyncSeekExt,
+        AsyncWrite,
+        AsyncWriteExt,
+        ReadBuf,
+    },
+    net::UdpSocket,
+    runtime::Handle,
+    sync::{mpsc, oneshot},
+    task::{self, JoinHandle},
 };
 
+#[cfg(feature = "bytes")]
+use super::internal::BufSource;
+#[cfg(feature = "stream")]
+use super::internal::StreamReader;
 use super::internal::{
+    read_device,
+    with_timeout,
     BufferSource,
+    ChainReader,
     ChannelBackend,
     ChannelSource,
+    DeserializationSource,
     Deserializer,
+    KeyHasher,
+    Limits,
+    PipeBackend,
+    PipeReader,
+    ReaderSource,
+    Watermarks,
+};
+#[cfg(feature = "bumpalo")]
+use crate::arena::{ArenaDeserialize, ArenaSeed};
+#[cfg(feature = "crypto")]
+use crate::crypto::{Key, OpenReader};
+#[cfg(feature = "async-compression")]
+use crate::stream_compression::{CompressedReader, StreamCompression};
+use crate::{
+    armor::Armor,
+    blocking::{BlockingIo, BlockingStrategy},
+    compress::Compression,
+    fingerprint::Fingerprint,
+    index::{self, FOOTER_SIZE},
+    inspect::{Inspection, Node, Tracer},
+    metadata::Metadata,
+    progress::Progress,
+    schema::Schema,
+    validate::{Shape, ShapeSeed},
+    varint::IntEncoding,
 };
 
+const PIPE_CHUNK_SIZE: usize = 4096;
+const INCREMENTAL_CHUNK_SIZE: usize = 4096;
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+const DEFAULT_MTU: usize = 1472;
+const PARALLEL_BACKLOG: usize = 16;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Any deserialization is not supported")]
     UnsupportedAny,
     #[error("Reader reached end of input too early")]
     PrematureEof,
-    #[error("Reader expected end of input, found {0}")]
-    ExpectedEof(u8),
+    #[error("Reader expected end of input, found {count} trailing bytes")]
+    TrailingBytes { count: u64 },
+    #[error("Reading from deserialization source timed out")]
+    TimedOut,
     #[error("Deserializer disconnected losing bytes")]
     Disconnected,
     #[error("Size {0} is too big for this machine")]
     ExcessiveSize(u64),
     #[error("Size difference {0} is too big in magnitude for this machine")]
     ExcessiveSizeDiff(i64),
+    #[error("Length {found} exceeds the {limit} limit of {max}")]
+    LimitExceeded { limit: Limit, max: usize, found: usize },
+    #[error("Datagram of {len} bytes exceeds the MTU of {mtu} bytes")]
+    DatagramTooLarge { len: usize, mtu: usize },
+    #[error("Length {len} exceeds the {remaining} bytes of remaining input")]
+    LengthExceedsInput { len: usize, remaining: usize },
+    #[error("Nesting depth exceeds the limit of {0}")]
+    DepthLimitExceeded(usize),
+    #[error("Byte {0} is not a valid boolean")]
+    InvalidBool(u8),
+    #[error("Byte {0} is not a valid option tag")]
+    InvalidOptionTag(u8),
+    #[error("Struct has {found} fields, expected at most {expected}")]
+    TooManyFields { expected: usize, found: usize },
+    #[error(
+        "Variant index {index} is out of range for {name} with {count} \
+         variants"
+    )]
+    UnknownVariant { name: &'static str, index: u32, count: usize },
+    #[error("Variant {variant:?} is not a variant of {name}")]
+    UnknownVariantName { name: &'static str, variant: String },
+    #[error("Type fingerprint {found} does not match the expected {expected}")]
+    FingerprintMismatch { expected: Fingerprint, found: Fingerprint },
+    #[error("Metadata block is malformed")]
+    InvalidMetadata,
+    #[error("Armored text is malformed: {0}")]
+    InvalidArmor(&'static str),
+    #[error("Compression algorithm {0} is unknown or not enabled")]
+    UnknownCompression(u8),
+    #[error("Compression {0} is not supported by this decoder")]
+    UnsupportedCompression(Compression),
+    #[error("Byte {0} is not a valid type tag")]
+    InvalidTag(u8),
+    #[error("Input does not end with an element index")]
+    MissingIndex,
+    #[error("Element {index} is out of range for an index of {len}")]
+    IndexOutOfRange { index: u64, len: u64 },
+    #[error("Interned string reference {0} was never defined")]
+    UnknownInternedString(usize),
+    #[error("Map keys are not in canonical order")]
+    UnsortedMapKeys,
+    #[error("Map key at index {0} duplicates an earlier key")]
+    DuplicateMapKey(usize),
+    #[error("Float {0} is not finite")]
+    NonFiniteFloat(f64),
+    #[error("NaN with bits {0:#x} is not the canonical NaN")]
+    NonCanonicalNan(u64),
+    #[error("Floating point values are not allowed")]
+    FloatRejected,
+    #[error("Bytes {0:02x?} are not a valid UTF-8 character")]
+    InvalidUtf8Char(Vec<u8>),
+    #[error("Byte {0} is not a valid varint marker")]
+    InvalidVarintMarker(u8),
+    #[error("Varint exceeds the maximum encoded length")]
+    VarintTooLong,
+    #[error("Varint {0} does not fit the expected integer type")]
+    VarintOverflow(u128),
+    #[error("Varint {0} is not minimally encoded")]
+    NonMinimalVarint(u128),
+    #[error("Character of {0} bytes is longer than any UTF-8 character")]
+    CharTooLong(usize),
+    #[error("Field with tag {tag} read past its length of {len} bytes")]
+    FieldOverrun { tag: u32, len: usize },
     #[error("Codepoint {0} is invalid")]
     InvalidCodePoint(u32),
+    #[error("Invalid length {found}, expected {expected}")]
+    InvalidLength { expected: String, found: usize },
+    #[error("Invalid type {found}, expected {expected}")]
+    InvalidType { expected: String, found: String },
+    #[error("Invalid value {found}, expected {expected}")]
+    InvalidValue { expected: String, found: String },
+    #[error("Variant {variant:?} is not one of {expected:?}")]
+    UnexpectedVariant { variant: String, expected: &'static [&'static str] },
+    #[error("Field {field:?} is not one of {expected:?}")]
+    UnknownField { field: String, expected: &'static [&'static str] },
+    #[error("Field {0:?} is missing")]
+    MissingField(&'static str),
+    #[error("Field {0:?} is duplicated")]
+    DuplicateField(&'static str),
     #[error(transparent)]
     Utf8(#[from] FromUtf8Error),
     #[error("I/O error reading from deserialization source")]
@@ -41,6 +196,231 @@ pub enum Error {
     ),
     #[error("{0}")]
     Custom(String),
+    #[error("{source} (at {}byte offset {offset})", path_context(.path))]
+    At { offset: u64, path: Path, source: Box<Error> },
+}
+
+fn path_context(path: &Path) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("{}, ", path)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Segment {
+    Index(usize),
+    Position(usize),
+    Field(&'static str),
+    MapKey(usize),
+    Variant(&'static str),
+}
+
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "[{}]", index),
+            Self::Position(position) => write!(f, ".{}", position),
+            Self::Field(name) => write!(f, ".{}", name),
+            Self::MapKey(index) => write!(f, "<key {}>", index),
+            Self::Variant(name) => write!(f, "::{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Path {
+    segments: Vec<Segment>,
+}
+
+impl Path {
+    pub(crate) fn new(segments: Vec<Segment>) -> Self {
+        Self { segments }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments[..]
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for segment in &self.segments {
+            write!(f, "{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Eof,
+    TrailingData,
+    Io,
+    TimedOut,
+    Limit,
+    InvalidData,
+    Mismatch,
+    InvalidInput,
+    Unsupported,
+    Custom,
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::PrematureEof => ErrorKind::Eof,
+            Self::TrailingBytes { .. } => ErrorKind::TrailingData,
+            Self::Disconnected => ErrorKind::Io,
+            Self::IO(error) => match error.kind() {
+                io::ErrorKind::UnexpectedEof => ErrorKind::Eof,
+                io::ErrorKind::TimedOut => ErrorKind::TimedOut,
+                _ => ErrorKind::Io,
+            },
+            Self::TimedOut => ErrorKind::TimedOut,
+            Self::ExcessiveSize(_)
+            | Self::ExcessiveSizeDiff(_)
+            | Self::LimitExceeded { .. }
+            | Self::DatagramTooLarge { .. }
+            | Self::LengthExceedsInput { .. }
+            | Self::DepthLimitExceeded(_) => ErrorKind::Limit,
+            Self::InvalidBool(_)
+            | Self::InvalidOptionTag(_)
+            | Self::InvalidTag(_)
+            | Self::MissingIndex
+            | Self::InvalidMetadata
+            | Self::InvalidArmor(_)
+            | Self::UnknownInternedString(_)
+            | Self::UnsortedMapKeys
+            | Self::DuplicateMapKey(_)
+            | Self::NonFiniteFloat(_)
+            | Self::NonCanonicalNan(_)
+            | Self::FloatRejected
+            | Self::InvalidUtf8Char(_)
+            | Self::InvalidVarintMarker(_)
+            | Self::VarintTooLong
+            | Self::VarintOverflow(_)
+            | Self::NonMinimalVarint(_)
+            | Self::CharTooLong(_)
+            | Self::FieldOverrun { .. }
+            | Self::InvalidCodePoint(_)
+            | Self::Utf8(_) => ErrorKind::InvalidData,
+            Self::TooManyFields { .. }
+            | Self::UnknownVariant { .. }
+            | Self::UnknownVariantName { .. }
+            | Self::FingerprintMismatch { .. }
+            | Self::InvalidLength { .. }
+            | Self::InvalidType { .. }
+            | Self::InvalidValue { .. }
+            | Self::UnexpectedVariant { .. }
+            | Self::UnknownField { .. }
+            | Self::MissingField(_)
+            | Self::DuplicateField(_) => ErrorKind::Mismatch,
+            Self::IndexOutOfRange { .. } => ErrorKind::InvalidInput,
+            Self::UnsupportedAny
+            | Self::UnknownCompression(_)
+            | Self::UnsupportedCompression(_) => ErrorKind::Unsupported,
+            Self::Custom(_) => ErrorKind::Custom,
+            Self::At { source, .. } => source.kind(),
+        }
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.kind() == ErrorKind::Eof
+    }
+
+    pub fn is_io(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Io | ErrorKind::TimedOut)
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        self.kind() == ErrorKind::TimedOut
+    }
+
+    pub fn is_limit(&self) -> bool {
+        self.kind() == ErrorKind::Limit
+    }
+
+    pub fn is_data(&self) -> bool {
+        matches!(self.kind(), ErrorKind::InvalidData | ErrorKind::Mismatch)
+    }
+
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::At { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::At { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    pub fn inner(&self) -> &Self {
+        match self {
+            Self::At { source, .. } => source.inner(),
+            _ => self,
+        }
+    }
+
+    pub fn into_inner(self) -> Self {
+        match self {
+            Self::At { source, .. } => source.into_inner(),
+            _ => self,
+        }
+    }
+
+    pub(crate) fn at(self, offset: u64, path: Path) -> Self {
+        match self {
+            Self::At { .. } => self,
+            _ => Self::At { offset, path, source: Box::new(self) },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Limit {
+    SeqLen,
+    BytesLen,
+    MapLen,
+    TotalBytes,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SeqLen => write!(f, "sequence length"),
+            Self::BytesLen => write!(f, "byte length"),
+            Self::MapLen => write!(f, "map length"),
+            Self::TotalBytes => write!(f, "total byte count"),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        let kind = match error.inner() {
+            Error::IO(inner) => inner.kind(),
+            inner => match inner.kind() {
+                ErrorKind::Eof => io::ErrorKind::UnexpectedEof,
+                ErrorKind::TimedOut => io::ErrorKind::TimedOut,
+                _ => io::ErrorKind::InvalidData,
+            },
+        };
+        match error {
+            Error::IO(inner) => inner,
+            error => io::Error::new(kind, error),
+        }
+    }
 }
 
 impl serde::de::Error for Error {
@@ -50,27 +430,158 @@ impl serde::de::Error for Error {
     {
         Self::Custom(msg.to_string())
     }
+
+    fn invalid_type(
+        unexpected: serde::de::Unexpected,
+        expected: &dyn serde::de::Expected,
+    ) -> Self {
+        Self::InvalidType {
+            expected: expected.to_string(),
+            found: unexpected.to_string(),
+        }
+    }
+
+    fn invalid_value(
+        unexpected: serde::de::Unexpected,
+        expected: &dyn serde::de::Expected,
+    ) -> Self {
+        Self::InvalidValue {
+            expected: expected.to_string(),
+            found: unexpected.to_string(),
+        }
+    }
+
+    fn invalid_length(len: usize, expected: &dyn serde::de::Expected) -> Self {
+        Self::InvalidLength { expected: expected.to_string(), found: len }
+    }
+
+    fn unknown_variant(
+        variant: &str,
+        expected: &'static [&'static str],
+    ) -> Self {
+        Self::UnexpectedVariant { variant: variant.to_owned(), expected }
+    }
+
+    fn unknown_field(field: &str, expected: &'static [&'static str]) -> Self {
+        Self::UnknownField { field: field.to_owned(), expected }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::MissingField(field)
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        Self::DuplicateField(field)
+    }
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum ConfigError {
     #[error("Buffer limit {0} is too low")]
     BufLimitTooLow(usize),
+    #[error("Low watermark {low} must not exceed high watermark {high}")]
+    InvertedWatermarks { low: usize, high: usize },
+    #[error("Field {0} uses the reserved tag 0")]
+    ReservedFieldTag(&'static str),
+    #[error("Options {0} and {1} cannot be combined")]
+    ConflictingOptions(&'static str, &'static str),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Options {
+    pub hard_eof: Option<bool>,
+    pub read_timeout: Option<Duration>,
+    pub max_seq_len: Option<usize>,
+    pub max_bytes_len: Option<usize>,
+    pub max_map_len: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub max_prealloc: Option<usize>,
+    pub max_total_bytes: Option<usize>,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     hard_eof: bool,
+    strict: bool,
+    field_presence: bool,
+    field_count: bool,
+    presence_bitmap: bool,
+    variant_names: bool,
+    variant_fallbacks: Arc<HashMap<&'static str, &'static str>>,
+    self_describing: bool,
+    sorted_map_keys: bool,
+    finite_floats: bool,
+    canonical_nan: bool,
+    reject_floats: bool,
+    utf8_chars: bool,
+    str_chars: bool,
+    lenient_chars: bool,
+    interned_strings: bool,
+    int_encoding: IntEncoding,
+    tagged_fields: bool,
+    field_tags: Arc<HashMap<&'static str, HashMap<&'static str, u32>>>,
+    key_hasher: Option<Arc<dyn KeyHasher>>,
+    fingerprint: Option<Fingerprint>,
+    metadata: bool,
+    compression: bool,
+    #[cfg(feature = "async-compression")]
+    stream_compression: Option<StreamCompression>,
     request_channel_limit: usize,
     response_channel_limit: usize,
+    read_timeout: Option<Duration>,
+    watermarks: Option<Watermarks>,
+    mtu: usize,
+    limits: Limits,
+    progress: Option<Progress>,
+    blocking: BlockingStrategy,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             hard_eof: false,
+            strict: false,
+            field_presence: false,
+            field_count: false,
+            presence_bitmap: false,
+            variant_names: false,
+            variant_fallbacks: Arc::default(),
+            self_describing: false,
+            sorted_map_keys: false,
+            finite_floats: false,
+            canonical_nan: false,
+            reject_floats: false,
+            utf8_chars: false,
+            str_chars: false,
+            lenient_chars: false,
+            interned_strings: false,
+            int_encoding: IntEncoding::Fixint,
+            tagged_fields: false,
+            field_tags: Arc::default(),
+            key_hasher: None,
+            fingerprint: None,
+            metadata: false,
+            compression: false,
+            #[cfg(feature = "async-compression")]
+            stream_compression: None,
             request_channel_limit: 1,
             response_channel_limit: 1,
+            read_timeout: None,
+            watermarks: None,
+            mtu: DEFAULT_MTU,
+            limits: Limits::default(),
+            progress: None,
+            blocking: BlockingStrategy::default(),
         }
     }
 }
@@ -80,11 +591,263 @@ impl Config {
         Self::default()
     }
 
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    pub fn to_builder(&self) -> ConfigBuilder {
+        ConfigBuilder::from(self.clone())
+    }
+
+    pub(crate) fn headerless(&self) -> Self {
+        Self {
+            fingerprint: None,
+            metadata: false,
+            compression: false,
+            ..self.clone()
+        }
+    }
+
+    fn overridden(&self, options: &Options) -> Cow<'_, Self> {
+        if options.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        let mut config = self.clone();
+        if let Some(hard_eof) = options.hard_eof {
+            config.hard_eof = hard_eof;
+        }
+        if let Some(timeout) = options.read_timeout {
+            config.read_timeout = Some(timeout);
+        }
+        let limits = &mut config.limits;
+        limits.max_seq_len = options.max_seq_len.or(limits.max_seq_len);
+        limits.max_bytes_len = options.max_bytes_len.or(limits.max_bytes_len);
+        limits.max_map_len = options.max_map_len.or(limits.max_map_len);
+        limits.max_depth = options.max_depth.or(limits.max_depth);
+        limits.max_prealloc = options.max_prealloc.or(limits.max_prealloc);
+        limits.max_total_bytes =
+            options.max_total_bytes.or(limits.max_total_bytes);
+        Cow::Owned(config)
+    }
+
+    /// Runs the checks of [`ConfigBuilder::build`], for configs assembled
+    /// through the `&mut self` setters.
+    pub fn ensure_valid(&self) -> Result<(), ConfigError> {
+        if self.request_channel_limit == 0 {
+            Err(ConfigError::BufLimitTooLow(self.request_channel_limit))?;
+        }
+        if self.response_channel_limit == 0 {
+            Err(ConfigError::BufLimitTooLow(self.response_channel_limit))?;
+        }
+        if self.mtu == 0 {
+            Err(ConfigError::BufLimitTooLow(self.mtu))?;
+        }
+        if let Some(Watermarks { low, high }) = self.watermarks {
+            if high == 0 {
+                Err(ConfigError::BufLimitTooLow(high))?;
+            }
+            if low > high {
+                Err(ConfigError::InvertedWatermarks { low, high })?;
+            }
+        }
+        if self.presence_bitmap && self.field_count {
+            Err(ConfigError::ConflictingOptions(
+                "presence_bitmap",
+                "field_count",
+            ))?;
+        }
+        if self.utf8_chars && self.str_chars {
+            Err(ConfigError::ConflictingOptions("utf8_chars", "str_chars"))?;
+        }
+        if self.finite_floats && self.reject_floats {
+            Err(ConfigError::ConflictingOptions(
+                "finite_floats",
+                "floats_rejected",
+            ))?;
+        }
+        if self.canonical_nan && self.reject_floats {
+            Err(ConfigError::ConflictingOptions(
+                "canonical_nan",
+                "floats_rejected",
+            ))?;
+        }
+        if self.int_encoding != IntEncoding::Fixint
+            && self.field_presence
+            && self.field_count
+        {
+            Err(ConfigError::ConflictingOptions(
+                "int_encoding",
+                "field_presence with field_count",
+            ))?;
+        }
+        let tags = self.field_tags.values().flat_map(|tags| tags.iter());
+        for (field, tag) in tags {
+            if *tag == 0 {
+                Err(ConfigError::ReservedFieldTag(field))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn canonical() -> Self {
+        Self {
+            hard_eof: true,
+            strict: true,
+            sorted_map_keys: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn float_free() -> Self {
+        Self { reject_floats: true, ..Self::canonical() }
+    }
+
+    pub fn deterministic() -> Self {
+        Self { canonical_nan: true, ..Self::canonical() }
+    }
+
+    pub fn bincode() -> Self {
+        Self { strict: true, utf8_chars: true, ..Self::default() }
+    }
+
+    pub fn postcard() -> Self {
+        Self {
+            strict: true,
+            str_chars: true,
+            int_encoding: IntEncoding::Leb128,
+            ..Self::default()
+        }
+    }
+
     pub fn with_hard_eof(&mut self) -> &mut Self {
         self.hard_eof = true;
         self
     }
 
+    pub fn with_strict_scalars(&mut self) -> &mut Self {
+        self.strict = true;
+        self
+    }
+
+    pub fn with_field_presence(&mut self) -> &mut Self {
+        self.field_presence = true;
+        self
+    }
+
+    pub fn with_field_count(&mut self) -> &mut Self {
+        self.field_count = true;
+        self
+    }
+
+    pub fn with_presence_bitmap(&mut self) -> &mut Self {
+        self.presence_bitmap = true;
+        self
+    }
+
+    pub fn with_variant_names(&mut self) -> &mut Self {
+        self.variant_names = true;
+        self
+    }
+
+    /// Decodes unknown variants of `enum_name` as `variant`, skipping their
+    /// payload. Only self-describing input can be skipped reliably, so other
+    /// modes still report the unknown variant.
+    pub fn with_variant_fallback(
+        &mut self,
+        enum_name: &'static str,
+        variant: &'static str,
+    ) -> &mut Self {
+        Arc::make_mut(&mut self.variant_fallbacks).insert(enum_name, variant);
+        self
+    }
+
+    pub fn with_self_describing(&mut self) -> &mut Self {
+        self.self_describing = true;
+        self
+    }
+
+    pub fn with_sorted_map_keys(&mut self) -> &mut Self {
+        self.sorted_map_keys = true;
+        self
+    }
+
+    pub fn with_finite_floats(&mut self) -> &mut Self {
+        self.finite_floats = true;
+        self
+    }
+
+    pub fn with_canonical_nan(&mut self) -> &mut Self {
+        self.canonical_nan = true;
+        self
+    }
+
+    pub fn with_floats_rejected(&mut self) -> &mut Self {
+        self.reject_floats = true;
+        self
+    }
+
+    pub fn with_utf8_chars(&mut self) -> &mut Self {
+        self.utf8_chars = true;
+        self
+    }
+
+    pub fn with_str_chars(&mut self) -> &mut Self {
+        self.str_chars = true;
+        self
+    }
+
+    pub fn with_lenient_chars(&mut self) -> &mut Self {
+        self.lenient_chars = true;
+        self
+    }
+
+    pub fn with_interned_strings(&mut self) -> &mut Self {
+        self.interned_strings = true;
+        self
+    }
+
+    pub fn with_int_encoding(&mut self, encoding: IntEncoding) -> &mut Self {
+        self.int_encoding = encoding;
+        self
+    }
+
+    pub fn with_tagged_fields(&mut self) -> &mut Self {
+        self.tagged_fields = true;
+        self
+    }
+
+    pub fn with_field_tags<I>(
+        &mut self,
+        struct_name: &'static str,
+        tags: I,
+    ) -> Result<&mut Self, ConfigError>
+    where
+        I: IntoIterator<Item = (&'static str, u32)>,
+    {
+        let mut struct_tags = HashMap::new();
+        for (field, tag) in tags {
+            if tag == 0 {
+                Err(ConfigError::ReservedFieldTag(field))?;
+            }
+            struct_tags.insert(field, tag);
+        }
+        Arc::make_mut(&mut self.field_tags).insert(struct_name, struct_tags);
+        self.tagged_fields = true;
+        Ok(self)
+    }
+
+    pub fn with_unique_map_keys(&mut self) -> &mut Self {
+        self.with_key_hasher(RandomState::new())
+    }
+
+    pub fn with_key_hasher<H>(&mut self, hasher: H) -> &mut Self
+    where
+        H: KeyHasher + 'static,
+    {
+        self.key_hasher = Some(Arc::new(hasher));
+        self
+    }
+
     pub fn with_request_channel_limit(&mut self, limit: usize) -> &mut Self {
         self.request_channel_limit = limit;
         self
@@ -95,59 +858,1880 @@ impl Config {
         self
     }
 
-    pub async fn deserialize<'de, T, R>(&self, device: R) -> Result<T, Error>
-    where
-        R: AsyncRead + Unpin,
-        T: Deserialize<'de> + Send + 'static,
-    {
-        let (request_sender, request_receiver) =
-            mpsc::channel(self.request_channel_limit);
-        let (response_sender, response_receiver) =
-            mpsc::channel(self.response_channel_limit);
+    pub fn with_read_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
 
-        let mut backend =
-            ChannelBackend::new(device, response_sender, request_receiver);
-        backend.set_hard_eof(self.hard_eof);
+    pub fn with_mtu(&mut self, byte_count: usize) -> &mut Self {
+        self.mtu = byte_count;
+        self
+    }
 
-        let mut deserializer = Deserializer::new(ChannelSource::new(
-            request_sender,
-            response_receiver,
-        ));
+    /// Reads up to `high` bytes ahead of the deserializer, refilling once
+    /// fewer than `low` remain. Fewer, larger reads raise throughput on
+    /// devices with costly reads, at the price of latency: a refill waits
+    /// for the device even when the deserializer needs a single byte.
+    ///
+    /// Read-ahead only happens with [`Config::with_hard_eof`] or when
+    /// counting trailing bytes, as anything read past the message would
+    /// otherwise be lost for the next read from the device.
+    pub fn with_watermarks(
+        &mut self,
+        low: usize,
+        high: usize,
+    ) -> Result<&mut Self, ConfigError> {
+        if high == 0 {
+            Err(ConfigError::BufLimitTooLow(high))?;
+        }
+        if low > high {
+            Err(ConfigError::InvertedWatermarks { low, high })?;
+        }
+        self.watermarks = Some(Watermarks { low, high });
+        Ok(self)
+    }
 
-        let block_handle =
-            task::spawn_blocking(move || T::deserialize(&mut deserializer));
+    pub fn with_max_seq_len(&mut self, len: usize) -> &mut Self {
+        self.limits.max_seq_len = Some(len);
+        self
+    }
 
-        backend.run().await?;
-        match block_handle.await {
-            Ok(actual_result) => actual_result,
-            Err(error) => panic::resume_unwind(error.into_panic()),
-        }
+    pub fn with_max_bytes_len(&mut self, len: usize) -> &mut Self {
+        self.limits.max_bytes_len = Some(len);
+        self
     }
 
-    pub fn deserialize_buffer<'de, T>(&self, buf: &[u8]) -> Result<T, Error>
+    pub fn with_max_map_len(&mut self, len: usize) -> &mut Self {
+        self.limits.max_map_len = Some(len);
+        self
+    }
+
+    pub fn with_max_depth(&mut self, depth: usize) -> &mut Self {
+        self.limits.max_depth = Some(depth);
+        self
+    }
+
+    pub fn with_max_prealloc(&mut self, len: usize) -> &mut Self {
+        self.limits.max_prealloc = Some(len);
+        self
+    }
+
+    pub fn with_max_total_bytes(&mut self, len: usize) -> &mut Self {
+        self.limits.max_total_bytes = Some(len);
+        self
+    }
+
+    pub fn with_fingerprint(&mut self, fingerprint: Fingerprint) -> &mut Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    pub fn with_metadata(&mut self) -> &mut Self {
+        self.metadata = true;
+        self
+    }
+
+    pub fn with_compression(&mut self) -> &mut Self {
+        self.compression = true;
+        self
+    }
+
+    #[cfg(feature = "async-compression")]
+    pub fn with_stream_compression(
+        &mut self,
+        compression: StreamCompression,
+    ) -> &mut Self {
+        self.stream_compression = Some(compression);
+        self
+    }
+
+    #[cfg(feature = "async-compression")]
+    pub fn compressed_reader<R>(
+        &self,
+        device: R,
+    ) -> CompressedReader<io::BufReader<R>>
     where
-        T: Deserialize<'de>,
+        R: AsyncRead,
     {
-        let mut deserializer = Deserializer::new(BufferSource::new(buf));
-        let value = T::deserialize(&mut deserializer)?;
-        if self.hard_eof {
-            deserializer.source().ensure_eof()?;
+        let device = io::BufReader::new(device);
+        match self.stream_compression {
+            Some(compression) => compression.reader(device),
+            None => CompressedReader::Plain(device),
         }
-        Ok(value)
     }
-}
 
-pub async fn deserialize<'de, T, R>(device: R) -> Result<T, Error>
-where
-    R: AsyncRead + Unpin,
+    pub fn with_progress(&mut self, progress: Progress) -> &mut Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn with_blocking_strategy(
+        &mut self,
+        strategy: BlockingStrategy,
+    ) -> &mut Self {
+        self.blocking = strategy;
+        self
+    }
+
+    pub fn incremental(&self) -> IncrementalDecoder {
+        IncrementalDecoder { config: self.clone(), buffer: Vec::new() }
+    }
+
+    pub async fn deserialize_blob<R>(
+        &self,
+        device: R,
+    ) -> Result<BlobReader<R>, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let (device, len, _) = self
+            .read_blob_header(device, |this| this.recv_bytes_header())
+            .await?;
+        Ok(BlobReader::new(device, len))
+    }
+
+    pub async fn deserialize_bytes_into<R, W>(
+        &self,
+        device: R,
+        mut writer: W,
+    ) -> Result<u64, Error>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut blob = self.deserialize_blob(device).await?;
+        let count = io::copy(&mut blob, &mut writer).await?;
+        writer.flush().await?;
+        Ok(count)
+    }
+
+    pub async fn deserialize_str_into<R, W>(
+        &self,
+        device: R,
+        mut writer: W,
+    ) -> Result<u64, Error>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let (device, len, mut offset) = self
+            .read_blob_header(device, |this| this.recv_str_header())
+            .await?;
+        let mut blob = BlobReader::new(device, len);
+        let mut chunk = vec![0; STREAM_CHUNK_SIZE];
+        let mut pending = 0;
+        loop {
+            let count = blob.read(&mut chunk[pending ..]).await?;
+            let filled = pending + count;
+            let valid = match std::str::from_utf8(&chunk[.. filled]) {
+                Ok(_) => filled,
+                Err(error) if count > 0 && error.error_len().is_none() => {
+                    error.valid_up_to()
+                },
+                Err(error) => {
+                    let start = error.valid_up_to();
+                    let end =
+                        start + error.error_len().unwrap_or(filled - start);
+                    Err(Error::InvalidUtf8Char(chunk[start .. end].to_vec())
+                        .at(offset + start as u64, Path::default()))?
+                },
+            };
+            writer.write_all(&chunk[.. valid]).await?;
+            if count == 0 {
+                break;
+            }
+            chunk.copy_within(valid .. filled, 0);
+            pending = filled - valid;
+            offset += valid as u64;
+        }
+        writer.flush().await?;
+        Ok(len)
+    }
+
+    async fn read_blob_header<R, F>(
+        &self,
+        mut device: R,
+        recv_header: F,
+    ) -> Result<(R, u64, u64), Error>
+    where
+        R: AsyncRead + Unpin,
+        F: Fn(&mut Deserializer<BufferSource<&[u8]>>) -> Result<usize, Error>,
+    {
+        let mut prefix = Vec::new();
+        loop {
+            let mut deserializer =
+                Deserializer::new(BufferSource::new(&prefix[..]));
+            self.configure(&mut deserializer);
+            let attempt =
+                self.read_header(&mut deserializer).and_then(|compression| {
+                    if compression != Compression::None {
+                        Err(Error::UnsupportedCompression(compression))?;
+                    }
+                    recv_header(&mut deserializer)
+                });
+            match attempt {
+                Ok(len) => {
+                    return Ok((device, len as u64, prefix.len() as u64))
+                },
+                Err(Error::PrematureEof) => (),
+                Err(error) => {
+                    return Err(error.at(prefix.len() as u64, Path::default()))
+                },
+            }
+
+            let mut byte = [0];
+            let count = read_device(&mut device, &mut byte, self.read_timeout)
+                .await
+                .map_err(|error| {
+                    error.at(prefix.len() as u64, Path::default())
+                })?;
+            if count == 0 {
+                Err(Error::PrematureEof
+                    .at(prefix.len() as u64, Path::default()))?;
+            }
+            if let Some(progress) = &self.progress {
+                progress.record_io(count);
+            }
+            prefix.push(byte[0]);
+        }
+    }
+
+    pub fn async_deserializer<R>(&self, device: R) -> AsyncDeserializer<R>
+    where
+        R: AsyncRead + Unpin,
+    {
+        AsyncDeserializer::with_config(device, self.clone())
+    }
+
+    pub fn deserializer<'a>(
+        &self,
+        buf: &'a [u8],
+    ) -> Deserializer<BufferSource<&'a [u8]>> {
+        let mut deserializer = Deserializer::new(BufferSource::new(buf));
+        self.configure(&mut deserializer);
+        deserializer
+    }
+
+    pub async fn deserialize<'de, T, R>(&self, device: R) -> Result<T, Error>
+    where
+        R: AsyncRead + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        let (value, _) = self.deserialize_counting(device, false).await?;
+        Ok(value)
+    }
+
+    pub async fn deserialize_from_file<'de, T, P>(
+        &self,
+        path: P,
+    ) -> Result<T, Error>
+    where
+        P: AsRef<FsPath>,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        let file = fs::File::open(path).await?;
+        self.deserialize(io::BufReader::new(file)).await
+    }
+
+    pub async fn indexed_len<R>(&self, mut device: R) -> Result<u64, Error>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+    {
+        let (_, len) = read_index_footer(&mut device).await?;
+        Ok(len)
+    }
+
+    pub async fn deserialize_nth<'de, T, R>(
+        &self,
+        mut device: R,
+        index: u64,
+    ) -> Result<T, Error>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        let (index_offset, len) = read_index_footer(&mut device).await?;
+        if index >= len {
+            Err(Error::IndexOutOfRange { index, len })?;
+        }
+        self.check_indexed_fingerprint(&mut device).await?;
+        let mut offset = [0; 8];
+        device.seek(SeekFrom::Start(index_offset + index * 8)).await?;
+        device.read_exact(&mut offset).await?;
+        device.seek(SeekFrom::Start(u64::from_le_bytes(offset))).await?;
+        let config = Self { hard_eof: false, ..self.element_config() };
+        config.deserialize(device).await
+    }
+
+    pub async fn deserialize_parallel<'de, T, R>(
+        &self,
+        device: R,
+        workers: usize,
+    ) -> Result<ParallelDecoder<T, R>, Error>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        self.decode_parallel::<T, R>(device, workers, true).await
+    }
+
+    pub async fn deserialize_parallel_unordered<'de, T, R>(
+        &self,
+        device: R,
+        workers: usize,
+    ) -> Result<ParallelDecoder<T, R>, Error>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        self.decode_parallel::<T, R>(device, workers, false).await
+    }
+
+    async fn decode_parallel<'de, T, R>(
+        &self,
+        mut device: R,
+        workers: usize,
+        ordered: bool,
+    ) -> Result<ParallelDecoder<T, R>, Error>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        let (index_offset, len) = read_index_footer(&mut device).await?;
+        let size = device.seek(SeekFrom::End(0)).await?;
+        let table_len = len
+            .checked_mul(8)
+            .filter(|table_len| {
+                index_offset
+                    .checked_add(*table_len + FOOTER_SIZE as u64)
+                    .is_some_and(|end| end == size)
+            })
+            .ok_or(Error::MissingIndex)?;
+        self.check_indexed_fingerprint(&mut device).await?;
+
+        let mut table = vec![0; table_len as usize];
+        device.seek(SeekFrom::Start(index_offset)).await?;
+        device.read_exact(&mut table).await?;
+        let mut offsets: Vec<u64> = table
+            .chunks_exact(8)
+            .map(|bytes| {
+                u64::from_le_bytes(bytes.try_into().expect("8-byte chunk"))
+            })
+            .collect();
+        offsets.push(index_offset);
+        if offsets.windows(2).any(|window| window[0] > window[1]) {
+            Err(Error::MissingIndex)?;
+        }
+
+        // Elements are contiguous, so the device only needs one seek;
+        // each element is then read right before a worker decodes it.
+        device.seek(SeekFrom::Start(offsets[0])).await?;
+
+        let workers = workers.clamp(1, (len as usize).max(1));
+        let mut jobs = Vec::new();
+        let mut receivers = Vec::new();
+        let mut shared_sender = None;
+        if !ordered {
+            let (sender, receiver) = mpsc::channel(PARALLEL_BACKLOG * workers);
+            receivers.push(receiver);
+            shared_sender = Some(sender);
+        }
+        let config = Self { hard_eof: true, ..self.element_config() };
+        for _ in 0 .. workers {
+            let sender = match &shared_sender {
+                Some(sender) => sender.clone(),
+                None => {
+                    let (sender, receiver) = mpsc::channel(PARALLEL_BACKLOG);
+                    receivers.push(receiver);
+                    sender
+                },
+            };
+            let (job_sender, mut job_receiver) =
+                mpsc::channel::<(u64, Vec<u8>)>(PARALLEL_BACKLOG);
+            jobs.push(job_sender);
+            let config = config.clone();
+            self.blocking.spawn(move || {
+                while let Some((index, element)) = job_receiver.blocking_recv()
+                {
+                    let result = config
+                        .deserialize_buffer::<T>(&element)
+                        .map(|value| (index, value));
+                    let failed = result.is_err();
+                    if sender.blocking_send(result).is_err() || failed {
+                        break;
+                    }
+                }
+            });
+        }
+        Ok(ParallelDecoder {
+            device,
+            offsets,
+            max_element_len: self.limits.max_total_bytes,
+            jobs,
+            fed: 0,
+            received: 0,
+            receivers,
+            turn: 0,
+        })
+    }
+
+    async fn check_indexed_fingerprint<R>(
+        &self,
+        device: &mut R,
+    ) -> Result<(), Error>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+    {
+        if let Some(expected) = self.fingerprint {
+            let mut bytes = [0; 8];
+            device.seek(SeekFrom::Start(0)).await?;
+            device.read_exact(&mut bytes).await?;
+            let found = Fingerprint::from_le_bytes(bytes);
+            if found != expected {
+                Err(Error::FingerprintMismatch { expected, found })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn element_config(&self) -> Self {
+        Self {
+            fingerprint: None,
+            metadata: false,
+            compression: false,
+            ..self.clone()
+        }
+    }
+
+    pub async fn deserialize_with_options<'de, T, R>(
+        &self,
+        device: R,
+        options: &Options,
+    ) -> Result<T, Error>
+    where
+        R: AsyncRead + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        self.overridden(options).deserialize(device).await
+    }
+
+    pub async fn deserialize_with_trailing<'de, T, R>(
+        &self,
+        device: R,
+    ) -> Result<(T, u64), Error>
+    where
+        R: AsyncRead + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        self.deserialize_counting(device, true).await
+    }
+
+    async fn deserialize_counting<'de, T, R>(
+        &self,
+        device: R,
+        count_trailing: bool,
+    ) -> Result<(T, u64), Error>
+    where
+        R: AsyncRead + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        if self.compression {
+            return self.deserialize_compressed(device, count_trailing).await;
+        }
+        if let Some(handle) = self.blocking.in_place_handle() {
+            return task::block_in_place(|| {
+                self.deserialize_in_place(handle, device, count_trailing)
+            });
+        }
+
+        let (backend, deserializer) =
+            self.channel_backend(device, count_trailing);
+        self.run_channel(deserializer, backend.run()).await
+    }
+
+    fn deserialize_in_place<'de, T, R>(
+        &self,
+        handle: Handle,
+        mut device: R,
+        count_trailing: bool,
+    ) -> Result<(T, u64), Error>
+    where
+        R: AsyncRead + Unpin,
+        T: Deserialize<'de>,
+    {
+        let mut reader = BlockingIo::new(&mut device, handle);
+        reader.set_timeout(self.read_timeout);
+        reader.set_progress(self.progress.clone());
+        let mut deserializer = Deserializer::new(ReaderSource::new(reader));
+        self.configure(&mut deserializer);
+        deserializer.set_progress(self.progress.clone());
+        let value = decode_source(self.header(), &mut deserializer)?;
+        let result = if count_trailing {
+            deserializer.source_mut().count_trailing()
+        } else if self.hard_eof {
+            deserializer.source_mut().ensure_eof().map(|()| 0)
+        } else {
+            Ok(0)
+        };
+        let trailing = result.map_err(|error| {
+            error.at(deserializer.source().offset(), deserializer.path())
+        })?;
+        Ok((value, trailing))
+    }
+
+    pub async fn deserialize_buffered<'de, T, R>(
+        &self,
+        device: R,
+    ) -> Result<T, Error>
+    where
+        R: AsyncBufRead + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        if self.compression {
+            let (value, _) = self.deserialize_compressed(device, false).await?;
+            return Ok(value);
+        }
+        let (backend, deserializer) = self.channel_backend(device, false);
+        let (value, _) =
+            self.run_channel(deserializer, backend.run_buffered()).await?;
+        Ok(value)
+    }
+
+    pub async fn deserialize_chain<'de, T, P>(
+        &self,
+        parts: P,
+    ) -> Result<T, Error>
+    where
+        P: IntoIterator,
+        P::IntoIter: Unpin,
+        P::Item: AsyncRead + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        self.deserialize(ChainReader::new(parts)).await
+    }
+
+    #[cfg(feature = "stream")]
+    pub async fn deserialize_stream<'de, T, S, B, E>(
+        &self,
+        stream: S,
+    ) -> Result<T, Error>
+    where
+        S: Stream<Item = Result<B, E>>,
+        B: AsRef<[u8]> + Unpin,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        self.deserialize_buffered(StreamReader::new(stream)).await
+    }
+
+    pub fn channel<R>(
+        &self,
+        device: R,
+    ) -> (ChannelBackend<R>, Deserializer<ChannelSource>)
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.channel_backend(device, false)
+    }
+
+    pub fn deserialize_from<'de, T, S>(
+        &self,
+        deserializer: &mut Deserializer<S>,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+        S: DeserializationSource,
+    {
+        decode_source(self.header(), deserializer)
+    }
+
+    fn channel_backend<R>(
+        &self,
+        device: R,
+        count_trailing: bool,
+    ) -> (ChannelBackend<R>, Deserializer<ChannelSource>)
+    where
+        R: AsyncRead + Unpin,
+    {
+        let (request_sender, request_receiver) =
+            mpsc::channel(self.request_channel_limit);
+        let (response_sender, response_receiver) =
+            mpsc::channel(self.response_channel_limit);
+
+        let mut backend =
+            ChannelBackend::new(device, response_sender, request_receiver);
+        backend.set_hard_eof(self.hard_eof && !count_trailing);
+        backend.set_count_trailing(count_trailing);
+        backend.set_read_timeout(self.read_timeout);
+        backend.set_watermarks(self.watermarks);
+        backend.set_progress(self.progress.clone());
+
+        let mut deserializer = Deserializer::new(ChannelSource::new(
+            request_sender,
+            response_receiver,
+        ));
+        self.configure(&mut deserializer);
+        deserializer.set_progress(self.progress.clone());
+        (backend, deserializer)
+    }
+
+    async fn run_channel<'de, T, F>(
+        &self,
+        mut deserializer: Deserializer<ChannelSource>,
+        backend: F,
+    ) -> Result<(T, u64), Error>
+    where
+        T: Deserialize<'de> + Send + 'static,
+        F: Future<Output = Result<u64, Error>>,
+    {
+        let header = self.header();
+        let block_handle = self
+            .blocking
+            .spawn(move || decode_source(header, &mut deserializer));
+
+        let backend_result = backend.await;
+        let codec_result = match block_handle.await {
+            Ok(actual_result) => actual_result,
+            Err(error) => panic::resume_unwind(error.into_panic()),
+        };
+        match (codec_result, backend_result) {
+            (Err(error), Err(backend_error))
+                if matches!(error.inner(), Error::PrematureEof) =>
+            {
+                Err(backend_error)
+            },
+            (Err(error), _) => Err(error),
+            (Ok(_), Err(error)) => Err(error),
+            (Ok(value), Ok(trailing)) => Ok((value, trailing)),
+        }
+    }
+
+    #[cfg(feature = "crypto")]
+    pub async fn deserialize_encrypted<'de, T, R>(
+        &self,
+        device: R,
+        key: &Key,
+    ) -> Result<T, Error>
+    where
+        R: AsyncRead + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        let mut reader = OpenReader::new(device, key);
+        let value = self.deserialize(&mut reader).await?;
+        reader.finish().await?;
+        Ok(value)
+    }
+
+    async fn deserialize_compressed<'de, T, R>(
+        &self,
+        device: R,
+        count_trailing: bool,
+    ) -> Result<(T, u64), Error>
+    where
+        R: AsyncRead + Unpin,
+        T: Deserialize<'de> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(self.response_channel_limit);
+
+        let mut backend = PipeBackend::new(device, PIPE_CHUNK_SIZE, sender);
+        backend.set_read_timeout(self.read_timeout);
+        backend.set_progress(self.progress.clone());
+
+        let config = self.clone();
+        let block_handle = self.blocking.spawn(move || {
+            let mut reader = PipeReader::new(receiver);
+            let mut deserializer =
+                Deserializer::new(ReaderSource::new(&mut reader));
+            let compression =
+                config.read_header(&mut deserializer).map_err(|error| {
+                    error
+                        .at(deserializer.source().offset(), deserializer.path())
+                })?;
+
+            let mut deserializer = Deserializer::new(ReaderSource::new(
+                compression.decoder(reader)?,
+            ));
+            config.configure(&mut deserializer);
+            deserializer.set_progress(config.progress.clone());
+            let result = T::deserialize(&mut deserializer).and_then(|value| {
+                if count_trailing {
+                    let trailing =
+                        deserializer.source_mut().count_trailing()?;
+                    return Ok((value, trailing));
+                }
+                if config.hard_eof {
+                    deserializer.source_mut().ensure_eof()?;
+                }
+                Ok((value, 0))
+            });
+            result.map_err(|error| {
+                error.at(deserializer.source().offset(), deserializer.path())
+            })
+        });
+
+        let backend_result = backend.run().await;
+        let codec_result = match block_handle.await {
+            Ok(actual_result) => actual_result,
+            Err(error) => panic::resume_unwind(error.into_panic()),
+        };
+        match (codec_result, backend_result) {
+            (Err(error), Err(backend_error))
+                if matches!(error.inner(), Error::PrematureEof) =>
+            {
+                Err(backend_error)
+            },
+            (Err(error), _) => Err(error),
+            (Ok(_), Err(error)) => Err(error),
+            (Ok(value), Ok(())) => Ok(value),
+        }
+    }
+
+    pub async fn recv_datagram<'de, T>(
+        &self,
+        socket: &UdpSocket,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut buf = vec![0; self.mtu + 1];
+        let len =
+            with_timeout(socket.recv(&mut buf), self.read_timeout).await?;
+        self.decode_datagram(&buf[.. len])
+    }
+
+    pub async fn recv_datagram_from<'de, T>(
+        &self,
+        socket: &UdpSocket,
+    ) -> Result<(T, SocketAddr), Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut buf = vec![0; self.mtu + 1];
+        let (len, source) =
+            with_timeout(socket.recv_from(&mut buf), self.read_timeout).await?;
+        Ok((self.decode_datagram(&buf[.. len])?, source))
+    }
+
+    #[cfg(unix)]
+    pub async fn recv_unix_datagram<'de, T>(
+        &self,
+        socket: &UnixDatagram,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut buf = vec![0; self.mtu + 1];
+        let len =
+            with_timeout(socket.recv(&mut buf), self.read_timeout).await?;
+        self.decode_datagram(&buf[.. len])
+    }
+
+    #[cfg(unix)]
+    pub async fn recv_unix_datagram_from<'de, T>(
+        &self,
+        socket: &UnixDatagram,
+    ) -> Result<(T, unix::SocketAddr), Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut buf = vec![0; self.mtu + 1];
+        let (len, source) =
+            with_timeout(socket.recv_from(&mut buf), self.read_timeout).await?;
+        Ok((self.decode_datagram(&buf[.. len])?, source))
+    }
+
+    fn decode_datagram<'de, T>(&self, datagram: &[u8]) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        if datagram.len() > self.mtu {
+            Err(Error::DatagramTooLarge {
+                len: datagram.len(),
+                mtu: self.mtu,
+            })?;
+        }
+        self.deserialize_buffer(datagram)
+    }
+
+    pub fn deserialize_buffer_with_options<'de, T>(
+        &self,
+        buf: &[u8],
+        options: &Options,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        self.overridden(options).deserialize_buffer(buf)
+    }
+
+    pub fn deserialize_buffer<'de, T>(&self, buf: &[u8]) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut deserializer = self.open_buffer(buf)?;
+        self.decode_buffer(&mut deserializer, PhantomData::<T>).map_err(
+            |error| {
+                error.at(deserializer.source().offset(), deserializer.path())
+            },
+        )
+    }
+
+    pub fn validate<'de, T>(&self, buf: &[u8]) -> Result<(), Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut deserializer = self.open_buffer(buf)?;
+        let result = match Shape::of::<T>() {
+            Some(shape) => {
+                self.decode_buffer(&mut deserializer, ShapeSeed(&shape))
+            },
+            None => self
+                .decode_buffer(&mut deserializer, PhantomData::<T>)
+                .map(drop),
+        };
+        result.map_err(|error| {
+            error.at(deserializer.source().offset(), deserializer.path())
+        })
+    }
+
+    pub fn skip_value<S>(
+        &self,
+        source: &mut S,
+        schema: &Schema,
+    ) -> Result<(), Error>
+    where
+        S: DeserializationSource,
+    {
+        let mut deserializer = Deserializer::new(source);
+        self.configure(&mut deserializer);
+        ShapeSeed(&Shape::from(schema)).deserialize(&mut deserializer).map_err(
+            |error| {
+                error.at(deserializer.source().offset(), deserializer.path())
+            },
+        )
+    }
+
+    pub fn deserialize_armored<'de, T>(
+        &self,
+        text: &str,
+        armor: &Armor,
+    ) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        self.deserialize_buffer(&armor.decode(text)?)
+    }
+
+    pub fn deserialize_buffer_with_trailing<'de, T>(
+        &self,
+        buf: &[u8],
+    ) -> Result<(T, usize), Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut deserializer = self.open_buffer(buf)?;
+        let value = T::deserialize(&mut deserializer).map_err(|error| {
+            error.at(deserializer.source().offset(), deserializer.path())
+        })?;
+        Ok((value, deserializer.source().remaining().len()))
+    }
+
+    pub fn read_metadata(&self, buf: &[u8]) -> Result<Metadata, Error> {
+        let mut deserializer = Deserializer::new(BufferSource::new(buf));
+        let result = self
+            .fingerprint
+            .map_or(Ok(()), |expected| {
+                deserializer.expect_fingerprint(expected)
+            })
+            .and_then(|()| deserializer.recv_metadata());
+        result.map_err(|error| {
+            error.at(deserializer.source().offset(), deserializer.path())
+        })
+    }
+
+    pub fn deserialize_buffer_with_metadata<'de, T>(
+        &self,
+        buf: &[u8],
+    ) -> Result<(Metadata, T), Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let metadata = self.read_metadata(buf)?;
+        let config = Self { metadata: true, ..self.clone() };
+        Ok((metadata, config.deserialize_buffer(buf)?))
+    }
+
+    #[cfg(feature = "bytes")]
+    pub fn deserialize_buf<'de, T, B>(&self, buf: B) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+        B: Buf,
+    {
+        let mut deserializer = Deserializer::new(BufSource::new(buf));
+        self.configure(&mut deserializer);
+        let compression =
+            self.read_header(&mut deserializer).map_err(|error| {
+                error.at(deserializer.source().offset(), deserializer.path())
+            })?;
+        if compression == Compression::None {
+            let result = T::deserialize(&mut deserializer).and_then(|value| {
+                if self.hard_eof {
+                    deserializer.source().ensure_eof()?;
+                }
+                Ok(value)
+            });
+            return result.map_err(|error| {
+                error.at(deserializer.source().offset(), deserializer.path())
+            });
+        }
+
+        let reader = deserializer.into_source().into_inner().reader();
+        let mut deserializer =
+            Deserializer::new(ReaderSource::new(compression.decoder(reader)?));
+        self.configure(&mut deserializer);
+        let result = T::deserialize(&mut deserializer).and_then(|value| {
+            if self.hard_eof {
+                deserializer.source_mut().ensure_eof()?;
+            }
+            Ok(value)
+        });
+        result.map_err(|error| {
+            error.at(deserializer.source().offset(), deserializer.path())
+        })
+    }
+
+    #[cfg(feature = "bumpalo")]
+    pub fn deserialize_buffer_in<'bump, T>(
+        &self,
+        arena: &'bump Bump,
+        buf: &[u8],
+    ) -> Result<T, Error>
+    where
+        T: ArenaDeserialize<'bump>,
+    {
+        let mut deserializer = self.open_buffer(buf)?;
+        self.decode_buffer(&mut deserializer, ArenaSeed::new(arena)).map_err(
+            |error| {
+                error.at(deserializer.source().offset(), deserializer.path())
+            },
+        )
+    }
+
+    pub fn inspect<'a, 'de, T>(&self, buf: &'a [u8]) -> Inspection<'a>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut deserializer = match self.open_buffer(buf) {
+            Ok(deserializer) => deserializer,
+            Err(error) => {
+                return Inspection::new(
+                    Cow::Borrowed(buf),
+                    Node::default(),
+                    Some(error),
+                )
+            },
+        };
+        deserializer.set_tracer(Some(Tracer::new()));
+        let result = self.decode_buffer(&mut deserializer, PhantomData::<T>);
+        let offset = deserializer.source().offset();
+        let error =
+            result.err().map(|error| error.at(offset, deserializer.path()));
+        let root = match deserializer.take_tracer() {
+            Some(tracer) => tracer.finish(offset),
+            None => Default::default(),
+        };
+        Inspection::new(deserializer.into_source().into_inner(), root, error)
+    }
+
+    fn open_buffer<'a>(
+        &self,
+        buf: &'a [u8],
+    ) -> Result<Deserializer<BufferSource<Cow<'a, [u8]>>>, Error> {
+        let mut deserializer =
+            Deserializer::new(BufferSource::new(Cow::Borrowed(buf)));
+        self.configure(&mut deserializer);
+        let compression = self
+            .read_header(&mut deserializer)
+            .and_then(|compression| {
+                if compression == Compression::None {
+                    return Ok(None);
+                }
+                let body = compression
+                    .decompress(deserializer.source().remaining())?;
+                Ok(Some(body))
+            })
+            .map_err(|error| {
+                error.at(deserializer.source().offset(), deserializer.path())
+            })?;
+        if let Some(body) = compression {
+            deserializer =
+                Deserializer::new(BufferSource::new(Cow::Owned(body)));
+            self.configure(&mut deserializer);
+        }
+        Ok(deserializer)
+    }
+
+    fn decode_prefix<'de, T>(&self, buf: &[u8]) -> Result<(T, usize), Error>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut deserializer = Deserializer::new(BufferSource::new(buf));
+        self.configure(&mut deserializer);
+        let value = self
+            .read_header(&mut deserializer)
+            .and_then(|compression| {
+                // A compressed body has no length prefix, so where it ends
+                // in a buffered stream is unknown.
+                if compression != Compression::None {
+                    Err(Error::UnsupportedCompression(compression))?;
+                }
+                T::deserialize(&mut deserializer)
+            })
+            .map_err(|error| {
+                error.at(deserializer.source().offset(), deserializer.path())
+            })?;
+        Ok((value, deserializer.source().offset() as usize))
+    }
+
+    fn header(&self) -> Header {
+        Header { fingerprint: self.fingerprint, metadata: self.metadata }
+    }
+
+    fn read_header<S>(
+        &self,
+        deserializer: &mut Deserializer<S>,
+    ) -> Result<Compression, Error>
+    where
+        S: DeserializationSource,
+    {
+        self.header().skip(deserializer)?;
+        if self.compression {
+            deserializer.expect_compression()
+        } else {
+            Ok(Compression::None)
+        }
+    }
+
+    fn decode_buffer<'de, D, B>(
+        &self,
+        deserializer: &mut Deserializer<BufferSource<B>>,
+        seed: D,
+    ) -> Result<D::Value, Error>
+    where
+        D: DeserializeSeed<'de>,
+        B: AsRef<[u8]>,
+    {
+        let value = seed.deserialize(&mut *deserializer)?;
+        if self.hard_eof {
+            deserializer.source().ensure_eof()?;
+        }
+        Ok(value)
+    }
+
+    fn configure<S>(&self, deserializer: &mut Deserializer<S>)
+    where
+        S: DeserializationSource,
+    {
+        deserializer.set_limits(self.limits);
+        deserializer.set_strict(self.strict);
+        deserializer.set_field_presence(self.field_presence);
+        deserializer.set_field_count(self.field_count);
+        deserializer.set_presence_bitmap(self.presence_bitmap);
+        deserializer.set_variant_names(self.variant_names);
+        deserializer
+            .set_variant_fallbacks(HashMap::clone(&self.variant_fallbacks));
+        deserializer.set_self_describing(self.self_describing);
+        deserializer.set_canonical(self.sorted_map_keys);
+        deserializer.set_finite_floats(self.finite_floats);
+        deserializer.set_canonical_nan(self.canonical_nan);
+        deserializer.set_reject_floats(self.reject_floats);
+        deserializer.set_utf8_chars(self.utf8_chars);
+        deserializer.set_str_chars(self.str_chars);
+        deserializer.set_lenient_chars(self.lenient_chars);
+        deserializer.set_interned_strings(self.interned_strings);
+        deserializer.set_int_encoding(self.int_encoding);
+        deserializer.set_tagged_fields(self.tagged_fields);
+        deserializer.set_field_tags(self.field_tags.clone());
+        deserializer.set_key_hasher(self.key_hasher.clone());
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+    error: Option<ConfigError>,
+}
+
+impl From<Config> for ConfigBuilder {
+    fn from(config: Config) -> Self {
+        Self { config, error: None }
+    }
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_hard_eof(mut self) -> Self {
+        self.config.with_hard_eof();
+        self
+    }
+
+    pub fn with_strict_scalars(mut self) -> Self {
+        self.config.with_strict_scalars();
+        self
+    }
+
+    pub fn with_field_presence(mut self) -> Self {
+        self.config.with_field_presence();
+        self
+    }
+
+    pub fn with_field_count(mut self) -> Self {
+        self.config.with_field_count();
+        self
+    }
+
+    pub fn with_presence_bitmap(mut self) -> Self {
+        self.config.with_presence_bitmap();
+        self
+    }
+
+    pub fn with_variant_names(mut self) -> Self {
+        self.config.with_variant_names();
+        self
+    }
+
+    pub fn with_variant_fallback(
+        mut self,
+        enum_name: &'static str,
+        variant: &'static str,
+    ) -> Self {
+        self.config.with_variant_fallback(enum_name, variant);
+        self
+    }
+
+    pub fn with_self_describing(mut self) -> Self {
+        self.config.with_self_describing();
+        self
+    }
+
+    pub fn with_sorted_map_keys(mut self) -> Self {
+        self.config.with_sorted_map_keys();
+        self
+    }
+
+    pub fn with_finite_floats(mut self) -> Self {
+        self.config.with_finite_floats();
+        self
+    }
+
+    pub fn with_canonical_nan(mut self) -> Self {
+        self.config.with_canonical_nan();
+        self
+    }
+
+    pub fn with_floats_rejected(mut self) -> Self {
+        self.config.with_floats_rejected();
+        self
+    }
+
+    pub fn with_utf8_chars(mut self) -> Self {
+        self.config.with_utf8_chars();
+        self
+    }
+
+    pub fn with_str_chars(mut self) -> Self {
+        self.config.with_str_chars();
+        self
+    }
+
+    pub fn with_lenient_chars(mut self) -> Self {
+        self.config.with_lenient_chars();
+        self
+    }
+
+    pub fn with_interned_strings(mut self) -> Self {
+        self.config.with_interned_strings();
+        self
+    }
+
+    pub fn with_int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.config.with_int_encoding(encoding);
+        self
+    }
+
+    pub fn with_tagged_fields(mut self) -> Self {
+        self.config.with_tagged_fields();
+        self
+    }
+
+    pub fn with_field_tags<I>(self, struct_name: &'static str, tags: I) -> Self
+    where
+        I: IntoIterator<Item = (&'static str, u32)>,
+    {
+        self.try_set(|config| config.with_field_tags(struct_name, tags))
+    }
+
+    pub fn with_unique_map_keys(mut self) -> Self {
+        self.config.with_unique_map_keys();
+        self
+    }
+
+    pub fn with_key_hasher<H>(mut self, hasher: H) -> Self
+    where
+        H: KeyHasher + 'static,
+    {
+        self.config.with_key_hasher(hasher);
+        self
+    }
+
+    pub fn with_request_channel_limit(mut self, limit: usize) -> Self {
+        self.config.with_request_channel_limit(limit);
+        self
+    }
+
+    pub fn with_response_channel_limit(mut self, limit: usize) -> Self {
+        self.config.with_response_channel_limit(limit);
+        self
+    }
+
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.config.with_read_timeout(timeout);
+        self
+    }
+
+    pub fn with_mtu(mut self, byte_count: usize) -> Self {
+        self.config.with_mtu(byte_count);
+        self
+    }
+
+    /// See [`Config::with_watermarks`].
+    pub fn with_watermarks(self, low: usize, high: usize) -> Self {
+        self.try_set(|config| config.with_watermarks(low, high))
+    }
+
+    pub fn with_max_seq_len(mut self, len: usize) -> Self {
+        self.config.with_max_seq_len(len);
+        self
+    }
+
+    pub fn with_max_bytes_len(mut self, len: usize) -> Self {
+        self.config.with_max_bytes_len(len);
+        self
+    }
+
+    pub fn with_max_map_len(mut self, len: usize) -> Self {
+        self.config.with_max_map_len(len);
+        self
+    }
+
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.config.with_max_depth(depth);
+        self
+    }
+
+    pub fn with_max_prealloc(mut self, len: usize) -> Self {
+        self.config.with_max_prealloc(len);
+        self
+    }
+
+    pub fn with_max_total_bytes(mut self, len: usize) -> Self {
+        self.config.with_max_total_bytes(len);
+        self
+    }
+
+    pub fn with_fingerprint(mut self, fingerprint: Fingerprint) -> Self {
+        self.config.with_fingerprint(fingerprint);
+        self
+    }
+
+    pub fn with_metadata(mut self) -> Self {
+        self.config.with_metadata();
+        self
+    }
+
+    pub fn with_compression(mut self) -> Self {
+        self.config.with_compression();
+        self
+    }
+
+    #[cfg(feature = "async-compression")]
+    pub fn with_stream_compression(
+        mut self,
+        compression: StreamCompression,
+    ) -> Self {
+        self.config.with_stream_compression(compression);
+        self
+    }
+
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.config.with_progress(progress);
+        self
+    }
+
+    pub fn with_blocking_strategy(
+        mut self,
+        strategy: BlockingStrategy,
+    ) -> Self {
+        self.config.with_blocking_strategy(strategy);
+        self
+    }
+
+    pub fn build(self) -> Result<Config, ConfigError> {
+        if let Some(error) = self.error {
+            Err(error)?;
+        }
+        self.config.ensure_valid()?;
+        Ok(self.config)
+    }
+
+    // Setters shared with `Config` check their arguments right away; the
+    // first failure is kept until `build`.
+    fn try_set<F>(mut self, set: F) -> Self
+    where
+        F: FnOnce(&mut Config) -> Result<&mut Config, ConfigError>,
+    {
+        if self.error.is_none() {
+            if let Err(error) = set(&mut self.config) {
+                self.error = Some(error);
+            }
+        }
+        self
+    }
+}
+
+async fn read_index_footer<R>(device: &mut R) -> Result<(u64, u64), Error>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let size = device.seek(SeekFrom::End(0)).await?;
+    let end =
+        size.checked_sub(FOOTER_SIZE as u64).ok_or(Error::MissingIndex)?;
+    let mut footer = [0; FOOTER_SIZE];
+    device.seek(SeekFrom::Start(end)).await?;
+    device.read_exact(&mut footer).await?;
+    index::decode_footer(index::INDEX_MAGIC, &footer).ok_or(Error::MissingIndex)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    fingerprint: Option<Fingerprint>,
+    metadata: bool,
+}
+
+impl Header {
+    fn skip<S>(self, deserializer: &mut Deserializer<S>) -> Result<(), Error>
+    where
+        S: DeserializationSource,
+    {
+        if let Some(expected) = self.fingerprint {
+            deserializer.expect_fingerprint(expected)?;
+        }
+        if self.metadata {
+            deserializer.recv_metadata()?;
+        }
+        Ok(())
+    }
+}
+
+fn decode_source<'de, T, S>(
+    header: Header,
+    deserializer: &mut Deserializer<S>,
+) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+    S: DeserializationSource,
+{
+    let result = header
+        .skip(deserializer)
+        .and_then(|()| T::deserialize(&mut *deserializer));
+    result.map_err(|error| {
+        error.at(deserializer.source().offset(), deserializer.path())
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct IncrementalDecoder {
+    config: Config,
+    buffer: Vec<u8>,
+}
+
+impl IncrementalDecoder {
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer[..]
+    }
+
+    pub fn into_buffered(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    pub async fn deserialize<'de, T, R>(
+        &mut self,
+        device: &mut R,
+    ) -> Result<T, Error>
+    where
+        R: AsyncRead + Unpin,
+        T: Deserialize<'de>,
+    {
+        loop {
+            match self.config.decode_prefix(&self.buffer) {
+                Ok((value, consumed)) => {
+                    self.buffer.drain(.. consumed);
+                    return Ok(value);
+                },
+                Err(error) if Self::is_incomplete(&error) => (),
+                Err(error) => return Err(error),
+            }
+
+            // Reads grow with the buffer so a large message is re-decoded a
+            // logarithmic number of times.
+            let mut chunk =
+                vec![0; self.buffer.len().max(INCREMENTAL_CHUNK_SIZE)];
+            let count =
+                read_device(device, &mut chunk, self.config.read_timeout)
+                    .await
+                    .map_err(|error| {
+                        error.at(self.buffer.len() as u64, Path::default())
+                    })?;
+            if count == 0 {
+                Err(Error::PrematureEof
+                    .at(self.buffer.len() as u64, Path::default()))?;
+            }
+            if let Some(progress) = &self.config.progress {
+                progress.record_io(count);
+            }
+            self.buffer.extend_from_slice(&chunk[.. count]);
+        }
+    }
+
+    fn is_incomplete(error: &Error) -> bool {
+        match error.inner() {
+            Error::PrematureEof | Error::LengthExceedsInput { .. } => true,
+            Error::IO(error) => error.kind() == io::ErrorKind::UnexpectedEof,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParallelDecoder<T, R> {
+    device: R,
+    offsets: Vec<u64>,
+    max_element_len: Option<usize>,
+    jobs: Vec<mpsc::Sender<(u64, Vec<u8>)>>,
+    fed: usize,
+    received: usize,
+    receivers: Vec<mpsc::Receiver<Result<(u64, T), Error>>>,
+    turn: usize,
+}
+
+impl<T, R> ParallelDecoder<T, R>
+where
+    R: AsyncRead + Unpin,
+{
+    pub async fn next(&mut self) -> Option<Result<(u64, T), Error>> {
+        if let Err(error) = self.feed().await {
+            self.jobs.clear();
+            self.receivers.clear();
+            return Some(Err(error));
+        }
+        let count = self.receivers.len();
+        let result = self.receivers.get_mut(self.turn)?.recv().await;
+        match &result {
+            Some(Ok(_)) => {
+                self.turn = (self.turn + 1) % count;
+                self.received += 1;
+            },
+            Some(Err(_)) | None => {
+                self.jobs.clear();
+                self.receivers.clear();
+            },
+        }
+        result
+    }
+
+    async fn feed(&mut self) -> Result<(), Error> {
+        let len = self.offsets.len() - 1;
+        let window = self.received + PARALLEL_BACKLOG * self.jobs.len();
+        while !self.jobs.is_empty() && self.fed < len.min(window) {
+            let start = self.offsets[self.fed];
+            let size = self.offsets[self.fed + 1] - start;
+            let element_len = usize::try_from(size)
+                .map_err(|_| Error::ExcessiveSize(size))?;
+            if let Some(max) = self.max_element_len {
+                if element_len > max {
+                    Err(Error::LimitExceeded {
+                        limit: Limit::TotalBytes,
+                        max,
+                        found: element_len,
+                    }
+                    .at(start, Path::default()))?;
+                }
+            }
+            let mut element = vec![0; element_len];
+            self.device.read_exact(&mut element).await.map_err(|error| {
+                Error::from(error).at(start, Path::default())
+            })?;
+            let worker = self.fed % self.jobs.len();
+            self.jobs[worker]
+                .send((self.fed as u64, element))
+                .await
+                .map_err(|_| Error::Disconnected)?;
+            self.fed += 1;
+        }
+        if self.fed == len {
+            self.jobs.clear();
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct BlobReader<R> {
+    device: R,
+    len: u64,
+    remaining: u64,
+}
+
+impl<R> BlobReader<R> {
+    fn new(device: R, len: u64) -> Self {
+        Self { device, len, remaining: len }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    pub fn into_inner(self) -> R {
+        self.device
+    }
+}
+
+impl<R> AsyncRead for BlobReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let limit = usize::try_from(this.remaining)
+            .unwrap_or(usize::MAX)
+            .min(buf.remaining());
+        if limit == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        let mut limited = ReadBuf::new(buf.initialize_unfilled_to(limit));
+        ready!(Pin::new(&mut this.device).poll_read(cx, &mut limited))?;
+        let count = limited.filled().len();
+        if count == 0 {
+            return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+        }
+        buf.advance(count);
+        this.remaining -= count as u64;
+        Poll::Ready(Ok(()))
+    }
+}
+
+type Job = Box<dyn FnOnce(&mut Deserializer<ChannelSource>) + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SessionState {
+    Ready,
+    Poisoned,
+    Finished,
+}
+
+pub struct AsyncDeserializer<R> {
+    config: Arc<Config>,
+    backend: ChannelBackend<R>,
+    deserializer: Option<Deserializer<ChannelSource>>,
+    jobs: Option<mpsc::Sender<Job>>,
+    worker: Option<JoinHandle<()>>,
+    state: SessionState,
+}
+
+impl<R> AsyncDeserializer<R>
+where
+    R: AsyncRead + Unpin,
+{
+    pub fn new(device: R) -> Self {
+        Self::with_config(device, Config::default())
+    }
+
+    pub fn with_config(device: R, config: Config) -> Self {
+        let (request_sender, request_receiver) =
+            mpsc::channel(config.request_channel_limit);
+        let (response_sender, response_receiver) =
+            mpsc::channel(config.response_channel_limit);
+
+        let mut backend =
+            ChannelBackend::new(device, response_sender, request_receiver);
+        backend.set_read_timeout(config.read_timeout);
+        backend.set_progress(config.progress.clone());
+
+        let mut deserializer = Deserializer::new(ChannelSource::new(
+            request_sender,
+            response_receiver,
+        ));
+        config.configure(&mut deserializer);
+        deserializer.set_progress(config.progress.clone());
+
+        Self {
+            config: Arc::new(config),
+            backend,
+            deserializer: Some(deserializer),
+            jobs: None,
+            worker: None,
+            state: SessionState::Ready,
+        }
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.backend.offset()
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.state == SessionState::Poisoned
+    }
+
+    pub fn into_inner(self) -> R {
+        self.backend.into_device()
+    }
+
+    pub async fn next<'de, T>(&mut self) -> Result<Option<T>, Error>
+    where
+        T: Deserialize<'de> + Send + 'static,
+    {
+        match self.state {
+            SessionState::Ready => (),
+            SessionState::Poisoned => Err(Error::Disconnected)?,
+            SessionState::Finished => return Ok(None),
+        }
+        self.state = SessionState::Poisoned;
+
+        let config = self.config.clone();
+        let (result_sender, mut result_receiver) = oneshot::channel();
+        let job: Job = Box::new(move |deserializer| {
+            deserializer.reset_consumed();
+            let result = config
+                .read_header(deserializer)
+                .and_then(|compression| {
+                    if compression != Compression::None {
+                        Err(Error::UnsupportedCompression(compression))?;
+                    }
+                    T::deserialize(&mut *deserializer)
+                })
+                .map_err(|error| {
+                    error
+                        .at(deserializer.source().offset(), deserializer.path())
+                });
+            let _ = result_sender.send(result);
+        });
+        let sent = match self.jobs() {
+            Some(jobs) => jobs.send(job).await.is_ok(),
+            None => false,
+        };
+        if !sent {
+            Err(Error::Disconnected.at(self.offset(), Path::default()))?;
+        }
+
+        let message_start = self.backend.offset();
+        loop {
+            tokio::select! {
+                biased;
+                result = &mut result_receiver => {
+                    let value = match result {
+                        Ok(result) => result?,
+                        Err(_) => return Err(self.worker_failure().await),
+                    };
+                    self.state = SessionState::Ready;
+                    return Ok(Some(value));
+                },
+                served = self.backend.serve(message_start) => {
+                    if !served? {
+                        self.state = SessionState::Finished;
+                        return Ok(None);
+                    }
+                },
+            }
+        }
+    }
+
+    fn jobs(&mut self) -> Option<&mpsc::Sender<Job>> {
+        if let Some(mut deserializer) = self.deserializer.take() {
+            let (job_sender, mut job_receiver) = mpsc::channel::<Job>(1);
+            self.worker = Some(self.config.blocking.spawn(move || {
+                while let Some(job) = job_receiver.blocking_recv() {
+                    job(&mut deserializer);
+                }
+            }));
+            self.jobs = Some(job_sender);
+        }
+        self.jobs.as_ref()
+    }
+
+    async fn worker_failure(&mut self) -> Error {
+        self.jobs = None;
+        if let Some(worker) = self.worker.take() {
+            if let Err(error) = worker.await {
+                panic::resume_unwind(error.into_panic());
+            }
+        }
+        Error::Disconnected.at(self.offset(), Path::default())
+    }
+}
+
+impl<R> fmt::Debug for AsyncDeserializer<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AsyncDeserializer")
+            .field("config", &self.config)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+pub async fn deserialize<'de, T, R>(device: R) -> Result<T, Error>
+where
+    R: AsyncRead + Unpin,
     T: Deserialize<'de> + Send + 'static,
 {
     Config::default().deserialize(device).await
 }
 
+pub async fn deserialize_from_file<'de, T, P>(path: P) -> Result<T, Error>
+where
+    P: AsRef<FsPath>,
+    T: Deserialize<'de> + Send + 'static,
+{
+    Config::default().deserialize_from_file(path).await
+}
+
+pub async fn deserialize_buffered<'de, T, R>(device: R) -> Result<T, Error>
+where
+    R: AsyncBufRead + Unpin,
+    T: Deserialize<'de> + Send + 'static,
+{
+    Config::default().deserialize_buffered(device).await
+}
+
+pub async fn deserialize_chain<'de, T, P>(parts: P) -> Result<T, Error>
+where
+    P: IntoIterator,
+    P::IntoIter: Unpin,
+    P::Item: AsyncRead + Unpin,
+    T: Deserialize<'de> + Send + 'static,
+{
+    Config::default().deserialize_chain(parts).await
+}
+
+#[cfg(feature = "stream")]
+pub async fn deserialize_stream<'de, T, S, B, E>(stream: S) -> Result<T, Error>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]> + Unpin,
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    T: Deserialize<'de> + Send + 'static,
+{
+    Config::default().deserialize_stream(stream).await
+}
+
+pub async fn deserialize_nth<'de, T, R>(
+    device: R,
+    index: u64,
+) -> Result<T, Error>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+    T: Deserialize<'de> + Send + 'static,
+{
+    Config::default().deserialize_nth(device, index).await
+}
+
+pub async fn deserialize_parallel<'de, T, R>(
+    device: R,
+    workers: usize,
+) -> Result<ParallelDecoder<T, R>, Error>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+    T: Deserialize<'de> + Send + 'static,
+{
+    Config::default().deserialize_parallel(device, workers).await
+}
+
+pub async fn deserialize_blob<R>(device: R) -> Result<BlobReader<R>, Error>
+where
+    R: AsyncRead + Unpin,
+{
+    Config::default().deserialize_blob(device).await
+}
+
+pub async fn deserialize_bytes_into<R, W>(
+    device: R,
+    writer: W,
+) -> Result<u64, Error>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    Config::default().deserialize_bytes_into(device, writer).await
+}
+
+pub async fn deserialize_str_into<R, W>(
+    device: R,
+    writer: W,
+) -> Result<u64, Error>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    Config::default().deserialize_str_into(device, writer).await
+}
+
+pub async fn recv_datagram_from<'de, T>(
+    socket: &UdpSocket,
+) -> Result<(T, SocketAddr), Error>
+where
+    T: Deserialize<'de>,
+{
+    Config::default().recv_datagram_from(socket).await
+}
+
 pub fn deserialize_buffer<'de, T>(buf: &[u8]) -> Result<T, Error>
 where
     T: Deserialize<'de>,
 {
     Config::default().deserialize_buffer(buf)
 }
+
+pub fn validate<'de, T>(buf: &[u8]) -> Result<(), Error>
+where
+    T: Deserialize<'de>,
+{
+    Config::default().validate::<T>(buf)
+}
+
+pub fn skip_value<S>(source: &mut S, schema: &Schema) -> Result<(), Error>
+where
+    S: DeserializationSource,
+{
+    Config::default().skip_value(source, schema)
+}
+
+pub fn deserialize_armored<'de, T>(text: &str) -> Result<T, Error>
+where
+    T: Deserialize<'de>,
+{
+    Config::default().deserialize_armored(text, &Armor::default())
+}
@@ -4,6 +4,7 @@ use anyhow::Result;
 use serde::Deserialize;
 
 #[tokio::test]
+#[allow(clippy::bool_assert_comparison)]
 async fn deserialize_bool() -> Result<()> {
     let buf = [0_u8];
     let value: bool = crate::deserialize(&buf[..] as &[_]).await?;
@@ -66,6 +67,7 @@ async fn deserialize_i32() -> Result<()> {
 }
 
 #[tokio::test]
+#[allow(clippy::unusual_byte_groupings)]
 async fn deserialize_u64() -> Result<()> {
     let buf = [0x1a_u8, 0xef, 0x78, 0x56, 0xab, 0xcd, 0x12, 0x34];
     let value: u64 = crate::deserialize(&buf[..] as &[_]).await?;
@@ -82,6 +84,7 @@ async fn deserialize_i64() -> Result<()> {
 }
 
 #[tokio::test]
+#[allow(clippy::unusual_byte_groupings)]
 async fn deserialize_u128() -> Result<()> {
     let buf = [
         0x73_u8, 0xd2, 0xe1, 0xf0, 0xe5, 0xd4, 0xc3, 0xb2, 0x1a, 0xef, 0x78,
@@ -197,7 +200,7 @@ async fn deserialize_newtype_struct() -> Result<()> {
 async fn deserialize_seq_empty() -> Result<()> {
     let buf = [0; 8];
     let value: Vec<i16> = crate::deserialize(&buf[..]).await?;
-    assert_eq!(value, &[]);
+    assert_eq!(value, &[0_i16; 0]);
     Ok(())
 }
 
@@ -361,3 +364,976 @@ async fn deserialize_struct_synchronous() -> Result<()> {
     );
     Ok(())
 }
+
+#[tokio::test]
+async fn seq_len_limit() -> Result<()> {
+    let buf: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+    let result: Result<Vec<u32>, _> = crate::de::Config::default()
+        .with_max_seq_len(16)
+        .deserialize(buf)
+        .await;
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::LimitExceeded {
+            limit: crate::de::Limit::SeqLen,
+            max: 16,
+            found: 0x7fff_ffff_ffff_ffff,
+        })
+    ));
+
+    let buf: &[u8] = &[2, 0, 0, 0, 0, 0, 0, 0, 7, 9];
+    let value: Vec<u8> = crate::de::Config::default()
+        .with_max_seq_len(2)
+        .deserialize_buffer(buf)?;
+    assert_eq!(value, &[7, 9]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn bytes_len_limit() -> Result<()> {
+    let buf: &[u8] = &[4, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', b'c', b'd'];
+    let result: Result<String, _> = crate::de::Config::default()
+        .with_max_bytes_len(3)
+        .deserialize(buf)
+        .await;
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::LimitExceeded {
+            limit: crate::de::Limit::BytesLen,
+            max: 3,
+            found: 4,
+        })
+    ));
+
+    let value: String = crate::de::Config::default()
+        .with_max_bytes_len(4)
+        .deserialize_buffer(buf)?;
+    assert_eq!(value, "abcd");
+    Ok(())
+}
+
+#[tokio::test]
+async fn map_len_limit() -> Result<()> {
+    let buf: &[u8] = &[1, 0, 0, 0, 0, 0, 0, 0, 3, 4];
+    let result: Result<BTreeMap<u8, u8>, _> = crate::de::Config::default()
+        .with_max_map_len(0)
+        .deserialize_buffer(buf);
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::LimitExceeded {
+            limit: crate::de::Limit::MapLen,
+            max: 0,
+            found: 1,
+        })
+    ));
+
+    let value: BTreeMap<u8, u8> = crate::de::Config::default()
+        .with_max_map_len(1)
+        .deserialize(buf)
+        .await?;
+    assert_eq!(value, BTreeMap::from([(3, 4)]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn deserialize_with_progress() -> Result<()> {
+    let progress = crate::Progress::new();
+    let buf: &[u8] = &[1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 7, 9];
+    let value: Vec<Vec<u8>> = crate::de::Config::default()
+        .with_progress(progress.clone())
+        .deserialize(buf)
+        .await?;
+
+    assert_eq!(value, vec![vec![7, 9]]);
+    assert_eq!(progress.bytes(), buf.len() as u64);
+    assert_eq!(progress.elements(), 3);
+    assert_eq!(progress.depth(), 0);
+    assert!(progress.last_io_activity().is_some());
+    assert!(progress.last_codec_activity().is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn depth_limit() -> Result<()> {
+    #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+    enum Expr {
+        Lit(u8),
+        Neg(Box<Expr>),
+    }
+
+    let mut buf = Vec::new();
+    for _ in 0 .. 3 {
+        buf.extend_from_slice(&[1, 0, 0, 0]);
+    }
+    buf.extend_from_slice(&[0, 0, 0, 0, 5]);
+
+    let value: Expr = crate::de::Config::default()
+        .with_max_depth(4)
+        .deserialize_buffer(&buf)?;
+    assert_eq!(
+        value,
+        Expr::Neg(Box::new(Expr::Neg(Box::new(Expr::Neg(Box::new(
+            Expr::Lit(5)
+        ))))))
+    );
+
+    let result: Result<Expr, _> = crate::de::Config::default()
+        .with_max_depth(3)
+        .deserialize(&buf[..])
+        .await;
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::DepthLimitExceeded(3))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn depth_limit_option_chain() -> Result<()> {
+    #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+    struct List(Option<Box<List>>);
+
+    let buf = vec![1_u8; 4096];
+    let result: Result<List, _> = crate::de::Config::default()
+        .with_max_depth(64)
+        .deserialize_buffer(&buf);
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::DepthLimitExceeded(64))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn strict_scalars() -> Result<()> {
+    let buf: &[u8] = &[2];
+    let value: bool = crate::deserialize_buffer(buf)?;
+    assert!(value);
+    let result: Result<bool, _> = crate::de::Config::default()
+        .with_strict_scalars()
+        .deserialize(buf)
+        .await;
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::InvalidBool(2))
+    ));
+
+    let buf: &[u8] = &[7, 4];
+    let value: Option<u8> = crate::deserialize_buffer(buf)?;
+    assert_eq!(value, Some(4));
+    let result: Result<Option<u8>, _> = crate::de::Config::default()
+        .with_strict_scalars()
+        .deserialize_buffer(buf);
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::InvalidOptionTag(7))
+    ));
+
+    let buf: &[u8] = &[1, 1, 4];
+    let value: (bool, Option<u8>) = crate::de::Config::default()
+        .with_strict_scalars()
+        .deserialize_buffer(buf)?;
+    assert_eq!(value, (true, Some(4)));
+    Ok(())
+}
+
+#[test]
+fn length_exceeding_input() -> Result<()> {
+    let mut buf = u64::MAX.to_le_bytes().to_vec();
+    buf.extend_from_slice(b"abc");
+    let error = crate::deserialize_buffer::<String>(&buf).unwrap_err();
+    assert_eq!(error.offset(), Some(8));
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::LengthExceedsInput { len: usize::MAX, remaining: 3 }
+    ));
+
+    let buf = crate::serialize_into_buffer("abc")?;
+    let error = crate::deserialize_buffer::<String>(&buf[.. buf.len() - 1])
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::LengthExceedsInput { len: 3, remaining: 2 }
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn error_offsets() -> Result<()> {
+    let buf: &[u8] = &[1, 0, 0, 0, 0, 0, 0, 0, 3, 0];
+    let error = crate::deserialize_buffer::<(Vec<u8>, u16)>(buf).unwrap_err();
+    assert_eq!(error.offset(), Some(9));
+    assert!(matches!(error.inner(), crate::de::Error::PrematureEof));
+    assert_eq!(
+        error.to_string(),
+        "Reader reached end of input too early (at .1, byte offset 9)"
+    );
+
+    let error = crate::de::Config::default()
+        .with_hard_eof()
+        .deserialize::<(Vec<u8>, u16), _>(buf)
+        .await
+        .unwrap_err();
+    assert_eq!(error.offset(), Some(10));
+    assert!(matches!(error.into_inner(), crate::de::Error::PrematureEof));
+
+    let buf: &[u8] = &[b'a', 0, 0, 0, 0xff, 0xff, 0xff, 0xff];
+    let error = crate::deserialize::<(char, char), _>(buf).await.unwrap_err();
+    assert_eq!(error.offset(), Some(8));
+    assert!(matches!(
+        error.into_inner(),
+        crate::de::Error::InvalidCodePoint(0xffff_ffff)
+    ));
+
+    let error = crate::de::Config::default()
+        .with_hard_eof()
+        .deserialize_buffer::<u16>(&[1, 2, 3])
+        .unwrap_err();
+    assert_eq!(error.offset(), Some(2));
+    assert!(matches!(
+        error.into_inner(),
+        crate::de::Error::TrailingBytes { count: 1 }
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn error_paths() -> Result<()> {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Item {
+        name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    enum Slot {
+        Empty,
+        Filled { items: Vec<Item> },
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Player {
+        id: u8,
+        inventory: BTreeMap<u8, Slot>,
+    }
+
+    let mut buf = crate::serialize_into_buffer(2_usize)?;
+    buf.extend_from_slice(&[1]);
+    buf.extend_from_slice(&crate::serialize_into_buffer(0_usize)?);
+    buf.extend_from_slice(&[2]);
+    buf.extend_from_slice(&crate::serialize_into_buffer(1_usize)?);
+    buf.extend_from_slice(&[4, 1, 0, 0, 0]);
+    buf.extend_from_slice(&crate::serialize_into_buffer(1_usize)?);
+    buf.extend_from_slice(&crate::serialize_into_buffer(1_usize)?);
+    buf.extend_from_slice(&[0xff]);
+
+    let error = crate::deserialize_buffer::<Vec<Player>>(&buf).unwrap_err();
+    let path = error.path().map(ToString::to_string);
+    assert_eq!(path.as_deref(), Some("[1].inventory[0]::Filled.items[0].name"));
+    assert!(matches!(error.inner(), crate::de::Error::Utf8(_)));
+
+    let error =
+        crate::deserialize::<Vec<Player>, _>(&buf[..]).await.unwrap_err();
+    let path = error.path().map(ToString::to_string);
+    assert_eq!(path.as_deref(), Some("[1].inventory[0]::Filled.items[0].name"));
+
+    let buf: &[u8] = &[1, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0];
+    let error =
+        crate::deserialize_buffer::<BTreeMap<char, u8>>(buf).unwrap_err();
+    let path = error.path().map(ToString::to_string);
+    assert_eq!(path.as_deref(), Some("<key 0>"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn deserialize_with_field_presence() -> Result<()> {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Record {
+        id: u8,
+        #[serde(default)]
+        note: Option<u8>,
+        #[serde(default)]
+        tags: Vec<u8>,
+    }
+
+    let mut config = crate::de::Config::new();
+    config.with_field_presence();
+
+    let buf = [1, 7, 0, 0];
+    let value: Record = config.deserialize_buffer(&buf)?;
+    assert_eq!(value, Record { id: 7, note: None, tags: Vec::new() });
+
+    let buf = [1, 7, 1, 1, 3, 0];
+    let value: Record = config.deserialize(&buf[..]).await?;
+    assert_eq!(value, Record { id: 7, note: Some(3), tags: Vec::new() });
+
+    let buf = [0, 0, 0];
+    let error = config.deserialize_buffer::<Record>(&buf).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::MissingField("id")));
+
+    config.with_strict_scalars();
+    let buf = [2, 7, 0, 0];
+    let error = config.deserialize_buffer::<Record>(&buf).unwrap_err();
+    let path = error.path().map(ToString::to_string);
+    assert_eq!(path.as_deref(), Some(".id"));
+    assert!(matches!(error.inner(), crate::de::Error::InvalidOptionTag(2)));
+    Ok(())
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn deserialize_buf_chain() -> Result<()> {
+    use bytes::{Buf, Bytes};
+
+    let value = (String::from("chained"), vec![1_u32, 2, 3], 7_u8);
+    let encoded = crate::serialize_into_buffer(&value)?;
+    let (front, back) = encoded.split_at(11);
+    let chain =
+        Bytes::copy_from_slice(front).chain(Bytes::copy_from_slice(back));
+
+    let decoded: (String, Vec<u32>, u8) =
+        crate::de::Config::new().with_hard_eof().deserialize_buf(chain)?;
+    assert_eq!(decoded, value);
+
+    let truncated = Bytes::copy_from_slice(&encoded[.. encoded.len() - 1]);
+    let error = crate::de::Config::new()
+        .deserialize_buf::<(String, Vec<u32>, u8), _>(truncated)
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::PrematureEof));
+
+    let mut trailing = encoded.clone();
+    trailing.push(0xaa);
+    let error = crate::de::Config::new()
+        .with_hard_eof()
+        .deserialize_buf::<(String, Vec<u32>, u8), _>(&trailing[..])
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::TrailingBytes { count: 1 }
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_timeout_on_stalled_peer() -> Result<()> {
+    use std::time::Duration;
+
+    use tokio::io::AsyncWriteExt;
+
+    let encoded = crate::serialize_into_buffer((5_u32, String::from("slow")))?;
+    let (mut writer, reader) = tokio::io::duplex(64);
+    writer.write_all(&encoded[.. 6]).await?;
+
+    let error = crate::de::Config::new()
+        .with_read_timeout(Duration::from_millis(50))
+        .deserialize::<(u32, String), _>(reader)
+        .await
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::TimedOut));
+
+    let (mut writer, reader) = tokio::io::duplex(64);
+    writer.write_all(&encoded).await?;
+    let decoded: (u32, String) = crate::de::Config::new()
+        .with_read_timeout(Duration::from_millis(50))
+        .deserialize(reader)
+        .await?;
+    assert_eq!(decoded, (5, String::from("slow")));
+    drop(writer);
+    Ok(())
+}
+
+#[test]
+fn dropped_deserialize_releases_blocking_thread() -> Result<()> {
+    use std::time::Duration;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .max_blocking_threads(1)
+        .build()?;
+    runtime.block_on(async {
+        let (_writer, reader) = tokio::io::duplex(64);
+        let stalled = crate::deserialize::<u64, _>(reader);
+        let elapsed =
+            tokio::time::timeout(Duration::from_millis(20), stalled).await;
+        assert!(elapsed.is_err());
+
+        let buf = crate::serialize_into_buffer(7_u64)?;
+        let next = crate::deserialize::<u64, _>(&buf[..]);
+        let value =
+            tokio::time::timeout(Duration::from_secs(5), next).await??;
+        assert_eq!(value, 7);
+        Ok(())
+    })
+}
+
+#[tokio::test]
+async fn incremental_decode_resumes_after_cancel() -> Result<()> {
+    use std::time::Duration;
+
+    use tokio::io::AsyncWriteExt;
+
+    let first = crate::serialize_into_buffer((1_u8, String::from("first")))?;
+    let second = crate::serialize_into_buffer((2_u8, String::from("second")))?;
+    let (mut writer, mut reader) = tokio::io::duplex(256);
+    let mut decoder = crate::de::Config::new().incremental();
+
+    writer.write_all(&first[.. 10]).await?;
+    let attempt = tokio::time::timeout(
+        Duration::from_millis(20),
+        decoder.deserialize::<(u8, String), _>(&mut reader),
+    )
+    .await;
+    assert!(attempt.is_err());
+    assert_eq!(decoder.buffered(), &first[.. 10]);
+
+    writer.write_all(&first[10 ..]).await?;
+    writer.write_all(&second).await?;
+    drop(writer);
+    let value: (u8, String) = decoder.deserialize(&mut reader).await?;
+    assert_eq!(value, (1, String::from("first")));
+    let value: (u8, String) = decoder.deserialize(&mut reader).await?;
+    assert_eq!(value, (2, String::from("second")));
+    assert!(decoder.buffered().is_empty());
+
+    let error = decoder.deserialize::<u8, _>(&mut reader).await.unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::PrematureEof));
+    Ok(())
+}
+
+#[tokio::test]
+async fn watermarks_read_ahead() -> Result<()> {
+    let value: Vec<(u16, String)> =
+        (0 .. 200).map(|i| (i, format!("item-{}", i))).collect();
+    let buf = crate::serialize_into_buffer(&value)?;
+
+    for (low, high) in [(0, 1), (16, 64), (1024, 4096)] {
+        let mut config = crate::de::Config::new();
+        config.with_watermarks(low, high)?.with_hard_eof();
+        let decoded: Vec<(u16, String)> = config.deserialize(&buf[..]).await?;
+        assert_eq!(decoded, value);
+
+        let error = config
+            .deserialize::<Vec<(u16, String)>, _>(&buf[.. buf.len() - 1])
+            .await
+            .unwrap_err();
+        assert!(matches!(error.inner(), crate::de::Error::PrematureEof));
+
+        let mut trailing = buf.clone();
+        trailing.push(0xab);
+        let error = config
+            .deserialize::<Vec<(u16, String)>, _>(&trailing[..])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.inner(),
+            crate::de::Error::TrailingBytes { count: 1 }
+        ));
+
+        trailing.extend([1, 2]);
+        let error = config
+            .deserialize::<Vec<(u16, String)>, _>(&trailing[..])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error.inner(),
+            crate::de::Error::TrailingBytes { count: 3 }
+        ));
+    }
+
+    let mut two = buf.clone();
+    two.extend(crate::serialize_into_buffer(7_u32)?);
+    let mut config = crate::de::Config::new();
+    config.with_watermarks(16, 64)?;
+    let mut reader = &two[..];
+    let decoded: Vec<(u16, String)> = config.deserialize(&mut reader).await?;
+    assert_eq!(decoded, value);
+    assert_eq!(config.deserialize::<u32, _>(&mut reader).await?, 7);
+
+    assert!(matches!(
+        crate::de::Config::new().with_watermarks(8, 4),
+        Err(crate::de::ConfigError::InvertedWatermarks { low: 8, high: 4 })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn async_deserializer_session() -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let fingerprint = crate::fingerprint::Fingerprint::from_u64(0xfeed);
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_fingerprint(fingerprint);
+    let (mut writer, reader) = tokio::io::duplex(64);
+    let producer = tokio::spawn(async move {
+        for i in 0 .. 20_u32 {
+            let message = (i, format!("message-{}", i));
+            let buf = ser_config.serialize_into_buffer(&message)?;
+            writer.write_all(&buf).await?;
+        }
+        anyhow::Ok(())
+    });
+
+    let mut session = crate::de::Config::new()
+        .with_fingerprint(fingerprint)
+        .async_deserializer(reader);
+    for i in 0 .. 20_u32 {
+        let message: Option<(u32, String)> = session.next().await?;
+        assert_eq!(message, Some((i, format!("message-{}", i))));
+    }
+    producer.await??;
+    assert_eq!(session.next::<(u32, String)>().await?, None);
+    assert_eq!(session.next::<(u32, String)>().await?, None);
+    assert!(!session.is_poisoned());
+    Ok(())
+}
+
+#[tokio::test]
+async fn async_deserializer_leaves_remaining_bytes() -> Result<()> {
+    let mut buf = crate::serialize_into_buffer(7_u16)?;
+    buf.extend_from_slice(&crate::serialize_into_buffer("tail")?);
+    let mut session = crate::de::AsyncDeserializer::new(&buf[..]);
+    assert_eq!(session.next::<u16>().await?, Some(7));
+    assert_eq!(session.offset(), 2);
+    let rest = session.into_inner();
+    let tail: String = crate::deserialize(rest).await?;
+    assert_eq!(tail, "tail");
+    Ok(())
+}
+
+#[tokio::test]
+async fn async_deserializer_poisoned_by_truncation() -> Result<()> {
+    let mut buf = crate::serialize_into_buffer((1_u8, "one"))?;
+    let second = crate::serialize_into_buffer((2_u8, "two"))?;
+    buf.extend_from_slice(&second[.. second.len() - 1]);
+
+    let mut session = crate::de::AsyncDeserializer::new(&buf[..]);
+    assert_eq!(session.next::<(u8, String)>().await?, Some((1, "one".into())));
+    let error = session.next::<(u8, String)>().await.unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::PrematureEof));
+    assert!(session.is_poisoned());
+    let error = session.next::<(u8, String)>().await.unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::Disconnected));
+    Ok(())
+}
+
+struct Pairs(Vec<(&'static str, u32)>);
+
+impl serde::Serialize for Pairs {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.0.iter().map(|(key, value)| (key, value)))
+    }
+}
+
+#[derive(Debug)]
+struct ConstantHasher;
+
+impl crate::de::KeyHasher for ConstantHasher {
+    fn hash_key(&self, _key: &[u8]) -> u64 {
+        0
+    }
+}
+
+#[test]
+fn duplicate_map_keys() -> Result<()> {
+    use std::collections::HashMap;
+
+    let buf = crate::serialize_into_buffer(Pairs(vec![
+        ("role", 1),
+        ("name", 2),
+        ("role", 3),
+    ]))?;
+    let lenient: HashMap<String, u32> = crate::deserialize_buffer(&buf)?;
+    assert_eq!(lenient["role"], 3);
+
+    let error = crate::de::Config::new()
+        .with_unique_map_keys()
+        .deserialize_buffer::<HashMap<String, u32>>(&buf)
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::DuplicateMapKey(2)));
+
+    let error = crate::de::Config::new()
+        .with_key_hasher(ConstantHasher)
+        .deserialize_buffer::<HashMap<String, u32>>(&buf)
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::DuplicateMapKey(2)));
+
+    let unique = crate::serialize_into_buffer(Pairs(vec![("a", 1), ("b", 2)]))?;
+    let decoded: HashMap<String, u32> = crate::de::Config::new()
+        .with_key_hasher(ConstantHasher)
+        .deserialize_buffer(&unique)?;
+    assert_eq!(decoded.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn report_trailing_bytes() -> Result<()> {
+    let buf: &[u8] = &[0x34, 0x12, 0xaa, 0xbb, 0xcc];
+    let mut config = crate::de::Config::new();
+    config.with_hard_eof();
+
+    let (value, trailing): (u16, _) =
+        config.deserialize_buffer_with_trailing(buf)?;
+    assert_eq!((value, trailing), (0x1234, 3));
+    let (value, trailing): (u16, _) =
+        config.deserialize_with_trailing(buf).await?;
+    assert_eq!((value, trailing), (0x1234, 3));
+
+    let mut long = buf.to_vec();
+    long.extend_from_slice(&[0; 10_000]);
+    for (low, high) in [(0, 1), (1024, 4096)] {
+        config.with_watermarks(low, high)?;
+        let (value, trailing): (u16, _) =
+            config.deserialize_with_trailing(&long[..]).await?;
+        assert_eq!((value, trailing), (0x1234, long.len() as u64 - 2));
+    }
+
+    let (value, trailing): (u16, _) =
+        config.deserialize_with_trailing(&buf[.. 2]).await?;
+    assert_eq!((value, trailing), (0x1234, 0));
+    let error = config
+        .deserialize_with_trailing::<u16, _>(&buf[.. 1])
+        .await
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::PrematureEof));
+    Ok(())
+}
+
+#[tokio::test]
+async fn builder_config_is_shareable() -> Result<()> {
+    use crate::de::{Config, ConfigError};
+
+    fn assert_shareable<T: Clone + Send + Sync + 'static>(_: &T) {}
+
+    let config = Config::builder()
+        .with_hard_eof()
+        .with_variant_fallback("Shape", "Unknown")
+        .with_watermarks(16, 64)
+        .build()?;
+    assert_shareable(&config);
+
+    let buf = crate::serialize_into_buffer((7_u16, "abc"))?;
+    let tasks: Vec<_> = (0 .. 4)
+        .map(|_| {
+            let config = config.clone();
+            let buf = buf.clone();
+            tokio::spawn(async move {
+                config.deserialize::<(u16, String), _>(&buf[..]).await
+            })
+        })
+        .collect();
+    for task in tasks {
+        assert_eq!(task.await??, (7, "abc".to_owned()));
+    }
+
+    let error = Config::builder().with_watermarks(8, 4).build().unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigError::InvertedWatermarks { low: 8, high: 4 }
+    ));
+    let error =
+        Config::builder().with_request_channel_limit(0).build().unwrap_err();
+    assert!(matches!(error, ConfigError::BufLimitTooLow(0)));
+    let error = Config::canonical()
+        .to_builder()
+        .with_finite_floats()
+        .with_floats_rejected()
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigError::ConflictingOptions("finite_floats", "floats_rejected")
+    ));
+    let error = Config::builder()
+        .with_field_tags("Point", [("x", 0)])
+        .with_watermarks(8, 4)
+        .build()
+        .unwrap_err();
+    assert!(matches!(error, ConfigError::ReservedFieldTag("x")));
+
+    let mut config = Config::new();
+    config.with_utf8_chars().with_str_chars();
+    assert!(matches!(
+        config.ensure_valid(),
+        Err(ConfigError::ConflictingOptions("utf8_chars", "str_chars"))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn buffered_reader_source() -> Result<()> {
+    use tokio::io::{AsyncReadExt, BufReader};
+
+    let first = (300_u32, "straddles the reader buffer".to_owned());
+    let second = vec![-1_i64, 1 << 40];
+    let mut buf = crate::serialize_into_buffer(&first)?;
+    buf.extend(crate::serialize_into_buffer(&second)?);
+    buf.push(0xab);
+
+    let mut reader = BufReader::with_capacity(5, &buf[..]);
+    let decoded: (u32, String) =
+        crate::de::deserialize_buffered(&mut reader).await?;
+    assert_eq!(decoded, first);
+    let decoded: Vec<i64> =
+        crate::de::deserialize_buffered(&mut reader).await?;
+    assert_eq!(decoded, second);
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).await?;
+    assert_eq!(rest, [0xab]);
+
+    let error = crate::de::Config::new()
+        .with_hard_eof()
+        .deserialize_buffered::<(u32, String), _>(BufReader::new(
+            &buf[.. buf.len() - 1],
+        ))
+        .await
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::TrailingBytes { .. }));
+
+    let error =
+        crate::de::deserialize_buffered::<(u32, String), _>(&buf[.. 20])
+            .await
+            .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::PrematureEof));
+    Ok(())
+}
+
+#[tokio::test]
+async fn error_classification() -> Result<()> {
+    use crate::de::ErrorKind;
+
+    let buf = crate::serialize_into_buffer((7_u32, "text"))?;
+    let error = crate::de::Config::new()
+        .with_hard_eof()
+        .deserialize::<(u32, String), _>(&buf[.. 10])
+        .await
+        .unwrap_err();
+    assert!(error.is_eof());
+    assert!(!error.is_io());
+    assert_eq!(error.kind(), ErrorKind::Eof);
+
+    let error = crate::de::deserialize_buffer::<(u32, String)>(&buf[.. 10])
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Eof);
+
+    let error = crate::de::Config::new()
+        .with_max_bytes_len(2)
+        .deserialize_buffer::<(u32, String)>(&buf)
+        .unwrap_err();
+    assert!(error.is_limit());
+    assert!(error.offset().is_some());
+
+    let error = crate::de::Config::new()
+        .with_hard_eof()
+        .deserialize_buffer::<u32>(&buf)
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TrailingData);
+
+    let error = crate::de::Config::new()
+        .with_strict_scalars()
+        .deserialize_buffer::<bool>(&[2])
+        .unwrap_err();
+    assert!(error.is_data());
+    let error = crate::de::deserialize_buffer::<char>(&[0xff; 4]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+    let io = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+    assert!(crate::de::Error::from(io).is_io());
+    let io = std::io::Error::from(std::io::ErrorKind::TimedOut);
+    assert!(crate::de::Error::from(io).is_timeout());
+    assert!(crate::de::Error::TimedOut.is_io());
+    Ok(())
+}
+
+#[test]
+fn error_into_io_error() -> Result<()> {
+    let io = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+    let error = std::io::Error::from(crate::de::Error::from(io));
+    assert_eq!(error.kind(), std::io::ErrorKind::ConnectionReset);
+    assert_eq!(error.to_string(), "reset");
+
+    let error = crate::de::deserialize_buffer::<u32>(&[1, 2]).unwrap_err();
+    let error = std::io::Error::from(error);
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    let inner = error.into_inner().unwrap();
+    let inner = inner.downcast::<crate::de::Error>().unwrap();
+    assert!(matches!(inner.inner(), crate::de::Error::PrematureEof));
+
+    let error = crate::de::deserialize_buffer::<char>(&[0xff; 4]).unwrap_err();
+    let error = std::io::Error::from(error);
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[test]
+fn buffer_source_positioning() -> Result<()> {
+    use crate::de::{BufferSource, DeserializationSource, Deserializer};
+
+    let buf = crate::serialize_into_buffer((7_u32, "abc", 9_u16))?;
+    let mut deserializer = Deserializer::new(BufferSource::new(&buf[..]));
+    assert_eq!(u32::deserialize(&mut deserializer)?, 7);
+    let source = deserializer.source_mut();
+    assert_eq!(source.position(), 4);
+    assert_eq!(source.remaining().len(), buf.len() - 4);
+
+    let mark = source.position();
+    assert!(u64::deserialize(&mut deserializer).is_ok());
+    deserializer.source_mut().set_position(mark)?;
+    assert_eq!(String::deserialize(&mut deserializer)?, "abc");
+    assert_eq!(u16::deserialize(&mut deserializer)?, 9);
+    assert!(deserializer.source().remaining().is_empty());
+
+    let mut source = deserializer.into_source();
+    assert!(matches!(
+        source.set_position(buf.len() + 1),
+        Err(crate::de::Error::PrematureEof)
+    ));
+    source.set_position(0)?;
+    let mut scratch = [0; 4];
+    source.recv_raw_data(&mut scratch)?;
+    assert_eq!(scratch, 7_u32.to_le_bytes());
+    assert_eq!(source.into_inner(), &buf[..]);
+    Ok(())
+}
+
+struct HintProbe;
+
+impl<'de> serde::de::Visitor<'de> for HintProbe {
+    type Value = (Option<usize>, usize);
+
+    fn expecting(
+        &self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        write!(formatter, "a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let hint = seq.size_hint();
+        let mut count = 0;
+        while seq.next_element::<u32>()?.is_some() {
+            count += 1;
+        }
+        Ok((hint, count))
+    }
+}
+
+#[tokio::test]
+async fn prealloc_cap() -> Result<()> {
+    use serde::Deserializer as _;
+
+    let buf = crate::serialize_into_buffer(vec![7_u32; 1000])?;
+    let mut config = crate::de::Config::new();
+    let hint = config.deserializer(&buf).deserialize_seq(HintProbe)?;
+    assert_eq!(hint, (Some(1000), 1000));
+    config.with_max_prealloc(16);
+    let hint = config.deserializer(&buf).deserialize_seq(HintProbe)?;
+    assert_eq!(hint, (Some(16), 1000));
+
+    let text = "x".repeat(100);
+    let buf = crate::serialize_into_buffer(&text)?;
+    let decoded: String = config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, text);
+    let decoded: String = config.deserialize(&buf[..]).await?;
+    assert_eq!(decoded, text);
+
+    let mut forged = (1_u64 << 40).to_le_bytes().to_vec();
+    forged.extend_from_slice(b"short");
+    let error = config
+        .with_hard_eof()
+        .deserialize::<String, _>(&forged[..])
+        .await
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::PrematureEof));
+    Ok(())
+}
+
+#[tokio::test]
+async fn total_bytes_budget() -> Result<()> {
+    let nested = vec![vec![vec![1_u8]; 4]; 4];
+    let buf = crate::serialize_into_buffer(&nested)?;
+    let mut config = crate::de::Config::new();
+    config.with_max_total_bytes(buf.len());
+    let decoded: Vec<Vec<Vec<u8>>> = config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, nested);
+
+    config.with_max_total_bytes(64);
+    let error =
+        config.deserialize_buffer::<Vec<Vec<Vec<u8>>>>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::LimitExceeded {
+            limit: crate::de::Limit::TotalBytes,
+            max: 64,
+            ..
+        }
+    ));
+
+    let mut stream = buf.clone();
+    stream.extend_from_slice(&buf);
+    config.with_max_total_bytes(buf.len());
+    let mut session =
+        crate::de::AsyncDeserializer::with_config(&stream[..], config);
+    for _ in 0 .. 2 {
+        let decoded = session.next::<Vec<Vec<Vec<u8>>>>().await?;
+        assert_eq!(decoded.as_ref(), Some(&nested));
+    }
+    Ok(())
+}
+
+#[test]
+fn lenient_chars() -> Result<()> {
+    let buf = [b'a', 0, 0, 0, 0x00, 0xd8, 0, 0, 0xff, 0xff, 0xff, 0xff];
+    let mut config = crate::de::Config::new();
+    assert!(config.deserialize_buffer::<(char, char, char)>(&buf).is_err());
+    config.with_lenient_chars().with_strict_scalars();
+    let decoded: (char, char, char) = config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, ('a', '\u{fffd}', '\u{fffd}'));
+
+    let buf = [0xe2, 0x82, 0x41, b'z'];
+    config.with_utf8_chars();
+    let decoded: (char, char) = config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, ('\u{fffd}', 'z'));
+    Ok(())
+}
+
+#[cfg(feature = "lz4")]
+#[tokio::test]
+async fn incremental_decode_rejects_compression() -> Result<()> {
+    use crate::compress::Compression;
+
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_compression(Compression::Lz4);
+    let mut buf = ser_config.serialize_into_buffer(1_u32)?;
+    buf.extend(ser_config.serialize_into_buffer(2_u32)?);
+    let mut de_config = crate::de::Config::new();
+    de_config.with_compression();
+    let mut decoder = de_config.incremental();
+    let error = decoder.deserialize::<u32, _>(&mut &buf[..]).await.unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::UnsupportedCompression(Compression::Lz4)
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn incremental_decode_large_message() -> Result<()> {
+    let value: Vec<u32> = (0 .. 50_000).collect();
+    let mut buf = crate::serialize_into_buffer(&value)?;
+    buf.extend(crate::serialize_into_buffer(7_u8)?);
+    let mut decoder = crate::de::Config::new().incremental();
+    let mut reader = &buf[..];
+    let decoded: Vec<u32> = decoder.deserialize(&mut reader).await?;
+    assert_eq!(decoded, value);
+    assert_eq!(decoder.deserialize::<u8, _>(&mut reader).await?, 7);
+    assert!(decoder.buffered().is_empty());
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::{fmt, future::Future};
+
+use serde::{ser, Serialize, Serializer};
+use tokio::runtime::Handle;
+
+#[cfg(test)]
+mod test;
+
+pub trait AsyncSerialize {
+    type Value: Serialize;
+    type Error: fmt::Display;
+
+    fn produce(
+        &self,
+    ) -> impl Future<Output = Result<Self::Value, Self::Error>> + Send;
+}
+
+/// Serializes the value an [`AsyncSerialize`] producer yields, awaiting it
+/// only once the serializer reaches this position.
+///
+/// The future is driven on the runtime the serializer runs under, so this
+/// must be serialized through the async APIs (e.g.
+/// [`Config::serialize`](crate::ser::Config::serialize)), which run serde on
+/// the blocking pool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Deferred<T>(pub T);
+
+impl<T> Serialize for Deferred<T>
+where
+    T: AsyncSerialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let handle = Handle::try_current().map_err(|_| {
+            ser::Error::custom("deferred value serialized outside of a runtime")
+        })?;
+        let value =
+            handle.block_on(self.0.produce()).map_err(ser::Error::custom)?;
+        value.serialize(serializer)
+    }
+}
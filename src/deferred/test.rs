@@ -0,0 +1,88 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use super::{AsyncSerialize, Deferred};
+use crate::ser;
+
+#[derive(Debug)]
+struct RowQuery {
+    table: &'static str,
+    rows: u32,
+    queried: Arc<AtomicUsize>,
+}
+
+impl AsyncSerialize for RowQuery {
+    type Value = Vec<String>;
+    type Error = String;
+
+    async fn produce(&self) -> Result<Self::Value, Self::Error> {
+        tokio::task::yield_now().await;
+        if self.rows == 0 {
+            return Err(format!("table {} is empty", self.table));
+        }
+        self.queried.fetch_add(1, Ordering::SeqCst);
+        Ok((0 .. self.rows)
+            .map(|row| format!("{}-{}", self.table, row))
+            .collect())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    id: u32,
+    users: Deferred<RowQuery>,
+    orders: Deferred<RowQuery>,
+}
+
+fn report(queried: &Arc<AtomicUsize>, order_rows: u32) -> Report {
+    let query = |table, rows| {
+        Deferred(RowQuery { table, rows, queried: queried.clone() })
+    };
+    Report {
+        id: 7,
+        users: query("users", 3),
+        orders: query("orders", order_rows),
+    }
+}
+
+#[tokio::test]
+async fn deferred_fields_are_produced_while_serializing() -> Result<()> {
+    let queried = Arc::new(AtomicUsize::new(0));
+    let mut buf = Vec::new();
+    ser::Config::new().serialize(&mut buf, report(&queried, 2)).await?;
+    assert_eq!(queried.load(Ordering::SeqCst), 2);
+
+    let expected = crate::serialize_into_buffer((
+        7_u32,
+        vec!["users-0", "users-1", "users-2"],
+        vec!["orders-0", "orders-1"],
+    ))?;
+    assert_eq!(buf, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn deferred_errors_abort_serialization() -> Result<()> {
+    let queried = Arc::new(AtomicUsize::new(0));
+    let error = ser::Config::new()
+        .serialize(Vec::new(), report(&queried, 0))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, ser::Error::Custom(message) if message == "table orders is empty")
+    );
+    Ok(())
+}
+
+#[test]
+fn deferred_requires_runtime() {
+    let queried = Arc::new(AtomicUsize::new(0));
+    let error = crate::serialize_into_buffer(report(&queried, 2)).unwrap_err();
+    assert!(matches!(error, ser::Error::Custom(_)));
+    assert_eq!(queried.load(Ordering::SeqCst), 0);
+}
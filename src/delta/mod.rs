@@ -0,0 +1,125 @@
+use std::{fmt, marker::PhantomData};
+
+use serde::{
+    de::{self, SeqAccess},
+    ser::SerializeSeq,
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+};
+
+#[cfg(test)]
+mod test;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+pub trait Integer:
+    Copy + Serialize + for<'de> Deserialize<'de> + sealed::Sealed
+{
+    const ZERO: Self;
+
+    fn delta_from(self, previous: Self) -> Self;
+
+    fn undelta_from(self, previous: Self) -> Self;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl Integer for $ty {
+                const ZERO: Self = 0;
+
+                fn delta_from(self, previous: Self) -> Self {
+                    self.wrapping_sub(previous)
+                }
+
+                fn undelta_from(self, previous: Self) -> Self {
+                    self.wrapping_add(previous)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Delta<T>(pub Vec<T>);
+
+impl<T> Serialize for Delta<T>
+where
+    T: Integer,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Delta<T>
+where
+    T: Integer,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserialize(deserializer).map(Self)
+    }
+}
+
+pub fn serialize<T, S>(values: &[T], serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Integer,
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(values.len()))?;
+    let mut previous = T::ZERO;
+    for value in values {
+        seq.serialize_element(&value.delta_from(previous))?;
+        previous = *value;
+    }
+    seq.end()
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: Integer,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_seq(DeltaVisitor(PhantomData))
+}
+
+struct DeltaVisitor<T>(PhantomData<T>);
+
+impl<'de, T> de::Visitor<'de> for DeltaVisitor<T>
+where
+    T: Integer,
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a sequence of integer deltas")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let capacity = seq.size_hint().unwrap_or(0).min(4096);
+        let mut values = Vec::with_capacity(capacity);
+        let mut previous = T::ZERO;
+        while let Some(delta) = seq.next_element::<T>()? {
+            previous = delta.undelta_from(previous);
+            values.push(previous);
+        }
+        Ok(values)
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Delta;
+use crate::{de, ser, varint::IntEncoding};
+
+#[tokio::test]
+async fn delta_roundtrip() -> Result<()> {
+    let values = vec![-5_i32, -3, 0, 7, i32::MIN, i32::MAX, 12];
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, Delta(values.clone())).await?;
+    let decoded: Delta<i32> = crate::deserialize(&buf[..]).await?;
+    assert_eq!(decoded.0, values);
+
+    let decreasing = vec![u8::MAX, 3, 2, 0];
+    let buf = crate::serialize_into_buffer(Delta(decreasing.clone()))?;
+    let decoded: Delta<u8> = crate::deserialize_buffer(&buf)?;
+    assert_eq!(decoded.0, decreasing);
+
+    let buf = crate::serialize_into_buffer(Delta(Vec::<u64>::new()))?;
+    assert_eq!(buf, crate::serialize_into_buffer(Vec::<u64>::new())?);
+    Ok(())
+}
+
+#[test]
+fn delta_with_varints_shrinks_columns() -> Result<()> {
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Column {
+        name: String,
+        #[serde(with = "crate::delta")]
+        timestamps: Vec<u64>,
+    }
+
+    let timestamps: Vec<u64> =
+        (0 .. 1000).map(|index| 1_700_000_000_000 + index * 100).collect();
+    let column = Column { name: "ts".to_owned(), timestamps };
+    let mut ser_config = ser::Config::new();
+    ser_config.with_int_encoding(IntEncoding::Varint);
+    let mut de_config = de::Config::new();
+    de_config.with_int_encoding(IntEncoding::Varint);
+
+    let plain = ser_config.serialize_into_buffer(&column.timestamps)?;
+    let buf = ser_config.serialize_into_buffer(&column)?;
+    assert!(buf.len() * 5 < plain.len());
+    let decoded: Column = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, column);
+    Ok(())
+}
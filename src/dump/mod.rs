@@ -0,0 +1,143 @@
+use std::fmt::{self, Write};
+
+use serde::Serialize;
+
+use crate::ser::{Config, Error};
+
+#[cfg(test)]
+mod test;
+
+const BYTES_PER_LINE: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Label {
+    Text(String),
+    Element,
+    Key,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Annotation {
+    offset: usize,
+    depth: usize,
+    label: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Annotator {
+    annotations: Vec<Annotation>,
+    counters: Vec<usize>,
+}
+
+impl Annotator {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn push(&mut self, offset: usize, depth: usize, label: Label) {
+        let label = match label {
+            Label::Text(text) => {
+                self.counters.truncate(depth + 1);
+                text
+            },
+            Label::Element => format!("[{}]", self.next_index(depth)),
+            Label::Key => format!("key [{}]", self.next_index(depth)),
+        };
+        self.annotations.push(Annotation { offset, depth, label });
+    }
+
+    fn next_index(&mut self, depth: usize) -> usize {
+        if self.counters.len() <= depth {
+            self.counters.resize(depth + 1, 0);
+        }
+        self.counters.truncate(depth + 1);
+        let index = self.counters[depth];
+        self.counters[depth] += 1;
+        index
+    }
+}
+
+#[derive(Debug)]
+pub struct Dump {
+    buf: Vec<u8>,
+    annotations: Vec<Annotation>,
+    error: Option<Error>,
+}
+
+impl Dump {
+    pub(crate) fn new(
+        buf: Vec<u8>,
+        annotator: Annotator,
+        error: Option<Error>,
+    ) -> Self {
+        Self { buf, annotations: annotator.annotations, error }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.buf[..]
+    }
+
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    fn fmt_range(
+        &self,
+        f: &mut fmt::Formatter,
+        start: usize,
+        end: usize,
+        annotation: Option<&Annotation>,
+    ) -> fmt::Result {
+        let bytes = self.buf.get(start .. end).unwrap_or_default();
+        let mut chunks = bytes.chunks(BYTES_PER_LINE);
+        let first = chunks.next().unwrap_or_default();
+        let mut hex = String::new();
+        for byte in first {
+            write!(hex, " {:02x}", byte)?;
+        }
+        write!(f, "{:08x} {:2$}", start, hex, BYTES_PER_LINE * 3)?;
+        if let Some(annotation) = annotation {
+            write!(f, "  {:2$}{}", "", annotation.label, annotation.depth * 2)?;
+        }
+        writeln!(f)?;
+        for (index, chunk) in chunks.enumerate() {
+            let mut hex = String::new();
+            for byte in chunk {
+                write!(hex, " {:02x}", byte)?;
+            }
+            let offset = start + (index + 1) * BYTES_PER_LINE;
+            writeln!(f, "{:08x} {}", offset, hex)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Dump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let first = self
+            .annotations
+            .first()
+            .map_or(self.buf.len(), |annotation| annotation.offset);
+        if first > 0 {
+            self.fmt_range(f, 0, first, None)?;
+        }
+        for (index, annotation) in self.annotations.iter().enumerate() {
+            let end = self
+                .annotations
+                .get(index + 1)
+                .map_or(self.buf.len(), |next| next.offset);
+            self.fmt_range(f, annotation.offset, end, Some(annotation))?;
+        }
+        if let Some(error) = &self.error {
+            write!(f, "error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn dump<T>(value: &T) -> String
+where
+    T: ?Sized + Serialize,
+{
+    Config::default().dump(value).to_string()
+}
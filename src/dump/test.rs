@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::dump;
+use crate::{ser, varint::IntEncoding};
+
+#[derive(Debug, Serialize)]
+enum Level {
+    Info,
+    Warn(u8),
+}
+
+#[derive(Debug, Serialize)]
+struct Record {
+    id: u32,
+    name: String,
+    levels: Vec<Level>,
+    parent: Option<u16>,
+    attributes: BTreeMap<String, u8>,
+}
+
+fn record() -> Record {
+    Record {
+        id: 7,
+        name: "ab".to_owned(),
+        levels: vec![Level::Info, Level::Warn(3)],
+        parent: Some(1),
+        attributes: [("k".to_owned(), 9)].into_iter().collect(),
+    }
+}
+
+#[test]
+fn dump_annotates_structure() {
+    let expected = [
+        "00000000                                                   struct \
+         Record",
+        "00000000  07 00 00 00                                        id",
+        "00000004  02 00 00 00 00 00 00 00 61 62                      name",
+        "0000000e                                                     levels",
+        "0000000e  02 00 00 00 00 00 00 00                            seq (2)",
+        "00000016                                                       [0]",
+        "00000016  00 00 00 00                                          \
+         variant Info (0)",
+        "0000001a                                                       [1]",
+        "0000001a  01 00 00 00 03                                       \
+         variant Warn (1)",
+        "0000001f                                                     parent",
+        "0000001f  01 01 00                                           Some",
+        "00000022                                                     \
+         attributes",
+        "00000022  01 00 00 00 00 00 00 00                            map (1)",
+        "0000002a  01 00 00 00 00 00 00 00 6b                           key \
+         [0]",
+        "00000033  09                                                   value",
+    ];
+    assert_eq!(dump(&record()).lines().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn dump_matches_configured_encoding() -> anyhow::Result<()> {
+    let mut config = ser::Config::new();
+    config.with_int_encoding(IntEncoding::Varint);
+    let dumped = config.dump(&record());
+    assert_eq!(dumped.bytes(), config.serialize_into_buffer(record())?);
+    assert!(dumped.error().is_none());
+
+    let long = dump("a".repeat(20).as_str());
+    let lines: Vec<&str> = long.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("00000000  14 00 00 00"));
+    assert!(lines[1].starts_with("00000010  61 61 61 61"));
+
+    config.with_floats_rejected();
+    let dumped = config.dump(&(1_u8, 2.5_f64)).to_string();
+    assert!(dumped.ends_with("error: Floating point values are not allowed"));
+    Ok(())
+}
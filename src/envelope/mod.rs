@@ -0,0 +1,306 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    ops::BitOr,
+};
+
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+use crate::{de as decode, ser as encode};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Failed to encode envelope")]
+    Ser(
+        #[from]
+        #[source]
+        encode::Error,
+    ),
+    #[error("Failed to decode envelope")]
+    De(
+        #[from]
+        #[source]
+        decode::Error,
+    ),
+    #[error("Peer answered request {id} with an error: {message}")]
+    Remote { id: u64, message: String },
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+pub struct Flags(u8);
+
+impl Flags {
+    pub const RESPONSE: Self = Self(1);
+    pub const ERROR: Self = Self(2);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Envelope {
+    pub id: u64,
+    pub flags: Flags,
+    pub payload: Payload,
+}
+
+impl Envelope {
+    pub fn request<T>(
+        id: u64,
+        value: &T,
+        config: &encode::Config,
+    ) -> Result<Self, encode::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let payload = Payload(config.serialize_into_buffer(value)?);
+        Ok(Self { id, flags: Flags::empty(), payload })
+    }
+
+    pub fn response<T>(
+        &self,
+        value: &T,
+        config: &encode::Config,
+    ) -> Result<Self, encode::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let payload = Payload(config.serialize_into_buffer(value)?);
+        Ok(Self { id: self.id, flags: Flags::RESPONSE, payload })
+    }
+
+    pub fn error_response(&self, message: &str) -> Self {
+        Self {
+            id: self.id,
+            flags: Flags::RESPONSE | Flags::ERROR,
+            payload: Payload(message.as_bytes().to_vec()),
+        }
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.flags.contains(Flags::RESPONSE)
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.flags.contains(Flags::ERROR)
+    }
+
+    pub fn decode<'de, T>(&self, config: &decode::Config) -> Result<T, Error>
+    where
+        T: Deserialize<'de>,
+    {
+        if self.is_error() {
+            Err(Error::Remote {
+                id: self.id,
+                message: String::from_utf8_lossy(&self.payload.0).into_owned(),
+            })?;
+        }
+        Ok(config.deserialize_buffer(&self.payload.0)?)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Payload(pub Vec<u8>);
+
+impl Serialize for Payload {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+struct PayloadVisitor;
+
+impl<'de> Visitor<'de> for PayloadVisitor {
+    type Value = Payload;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an envelope payload")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Payload(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Payload(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for Payload {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(PayloadVisitor)
+    }
+}
+
+#[derive(Debug)]
+pub struct Endpoint<R, W> {
+    reader: R,
+    writer: W,
+    ser_config: encode::Config,
+    de_config: decode::Config,
+    next_id: u64,
+    stashed: HashMap<u64, Envelope>,
+    requests: VecDeque<Envelope>,
+}
+
+impl<R, W> Endpoint<R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    pub fn new(reader: R, writer: W) -> Self {
+        Self::with_config(
+            reader,
+            writer,
+            encode::Config::default(),
+            decode::Config::default(),
+        )
+    }
+
+    pub fn with_config(
+        reader: R,
+        writer: W,
+        ser_config: encode::Config,
+        de_config: decode::Config,
+    ) -> Self {
+        Self {
+            reader,
+            writer,
+            ser_config,
+            de_config,
+            next_id: 0,
+            stashed: HashMap::new(),
+            requests: VecDeque::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+
+    pub async fn send_request<T>(&mut self, value: &T) -> Result<u64, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let envelope = Envelope::request(id, value, &self.ser_config)?;
+        self.send(envelope).await?;
+        Ok(id)
+    }
+
+    pub async fn recv_response<T>(&mut self, id: u64) -> Result<T, Error>
+    where
+        T: DeserializeOwned,
+    {
+        loop {
+            if let Some(envelope) = self.stashed.remove(&id) {
+                return envelope.decode(&self.de_config);
+            }
+            let envelope = self.recv().await?;
+            if envelope.is_response() {
+                self.stashed.insert(envelope.id, envelope);
+            } else {
+                self.requests.push_back(envelope);
+            }
+        }
+    }
+
+    pub async fn call<T, U>(&mut self, value: &T) -> Result<U, Error>
+    where
+        T: ?Sized + Serialize,
+        U: DeserializeOwned,
+    {
+        let id = self.send_request(value).await?;
+        self.recv_response(id).await
+    }
+
+    pub async fn recv_request(&mut self) -> Result<Envelope, Error> {
+        if let Some(envelope) = self.requests.pop_front() {
+            return Ok(envelope);
+        }
+        loop {
+            let envelope = self.recv().await?;
+            if !envelope.is_response() {
+                break Ok(envelope);
+            }
+            self.stashed.insert(envelope.id, envelope);
+        }
+    }
+
+    pub async fn respond<T>(
+        &mut self,
+        request: &Envelope,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let envelope = request.response(value, &self.ser_config)?;
+        self.send(envelope).await
+    }
+
+    pub async fn respond_error(
+        &mut self,
+        request: &Envelope,
+        message: &str,
+    ) -> Result<(), Error> {
+        self.send(request.error_response(message)).await
+    }
+
+    pub async fn send(&mut self, envelope: Envelope) -> Result<(), Error> {
+        self.ser_config.serialize(&mut self.writer, envelope).await?;
+        self.writer.flush().await.map_err(encode::Error::from)?;
+        Ok(())
+    }
+
+    pub async fn recv(&mut self) -> Result<Envelope, Error> {
+        Ok(self.de_config.deserialize(&mut self.reader).await?)
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{Endpoint, Envelope, Error, Flags};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Request {
+    Echo(String),
+    Add(u32, u32),
+    Fail,
+}
+
+#[test]
+fn envelope_roundtrip() -> Result<()> {
+    let ser_config = crate::ser::Config::new();
+    let de_config = crate::de::Config::new();
+    let request = Envelope::request(7, &Request::Add(1, 2), &ser_config)?;
+    assert_eq!(request.flags, Flags::empty());
+    let buf = ser_config.serialize_into_buffer(&request)?;
+    let decoded: Envelope = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, request);
+    assert_eq!(decoded.decode::<Request>(&de_config)?, Request::Add(1, 2));
+
+    let response = decoded.response(&3_u32, &ser_config)?;
+    assert_eq!(response.id, 7);
+    assert!(response.is_response() && !response.is_error());
+
+    let failure = decoded.error_response("boom");
+    assert_eq!(failure.flags.bits(), 3);
+    let error = failure.decode::<u32>(&de_config).unwrap_err();
+    assert!(
+        matches!(error, Error::Remote { id: 7, message } if message == "boom")
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn responses_match_by_id() -> Result<()> {
+    let (client_side, server_side) = tokio::io::duplex(64);
+    let (reader, writer) = tokio::io::split(server_side);
+    let server = tokio::spawn(async move {
+        let mut endpoint = Endpoint::new(reader, writer);
+        let first = endpoint.recv_request().await?;
+        let second = endpoint.recv_request().await?;
+        let third = endpoint.recv_request().await?;
+        for request in [&third, &second, &first] {
+            match request.decode(&crate::de::Config::new())? {
+                Request::Echo(text) => endpoint.respond(request, &text).await?,
+                Request::Add(a, b) => {
+                    endpoint.respond(request, &(a + b)).await?
+                },
+                Request::Fail => {
+                    endpoint.respond_error(request, "nope").await?
+                },
+            }
+        }
+        Ok::<_, Error>(())
+    });
+
+    let (reader, writer) = tokio::io::split(client_side);
+    let mut endpoint = Endpoint::new(reader, writer);
+    let echo = endpoint.send_request(&Request::Echo("hi".to_owned())).await?;
+    let add = endpoint.send_request(&Request::Add(40, 2)).await?;
+    let fail = endpoint.send_request(&Request::Fail).await?;
+    assert_eq!((echo, add, fail), (0, 1, 2));
+
+    let text: String = endpoint.recv_response(echo).await?;
+    assert_eq!(text, "hi");
+    let sum: u32 = endpoint.recv_response(add).await?;
+    assert_eq!(sum, 42);
+    let error = endpoint.recv_response::<u32>(fail).await.unwrap_err();
+    assert!(matches!(error, Error::Remote { id: 2, .. }));
+    server.await??;
+    Ok(())
+}
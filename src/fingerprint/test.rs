@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Fingerprint;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Account {
+    id: u64,
+    name: String,
+    roles: Vec<String>,
+    limits: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "Account")]
+struct AccountNarrowId {
+    id: u32,
+    name: String,
+    roles: Vec<String>,
+    limits: BTreeMap<String, u32>,
+}
+
+fn account(id: u64, name: &str) -> Account {
+    Account {
+        id,
+        name: name.to_owned(),
+        roles: vec!["admin".to_owned(), "ops".to_owned()],
+        limits: BTreeMap::from([("disk".to_owned(), 10)]),
+    }
+}
+
+#[test]
+fn fingerprint_ignores_values() -> Result<()> {
+    let first = Fingerprint::of(&account(1, "ana"))?;
+    let second = Fingerprint::of(&account(99, "bruno"))?;
+    assert_eq!(first, second);
+
+    let mut many_roles = account(1, "ana");
+    many_roles.roles.extend((0 .. 100).map(|i| i.to_string()));
+    assert_eq!(Fingerprint::of(&many_roles)?, first);
+    Ok(())
+}
+
+#[test]
+fn fingerprint_detects_layout_changes() -> Result<()> {
+    let narrow = AccountNarrowId {
+        id: 1,
+        name: "ana".to_owned(),
+        roles: vec![],
+        limits: BTreeMap::new(),
+    };
+    assert_ne!(Fingerprint::of(&narrow)?, Fingerprint::of(&account(1, "ana"))?);
+    assert_ne!(
+        Fingerprint::of(&(1_u8, 2_u16))?,
+        Fingerprint::of(&(1_u16, 2_u8))?
+    );
+    Ok(())
+}
+
+#[test]
+fn fingerprint_is_stable() -> Result<()> {
+    let fingerprint = Fingerprint::of(&(1_u32, "x"))?;
+    assert_eq!(
+        fingerprint.to_string(),
+        format!("{:016x}", fingerprint.as_u64())
+    );
+    assert_eq!(
+        Fingerprint::from_le_bytes(fingerprint.to_le_bytes()),
+        fingerprint
+    );
+    assert_eq!(Fingerprint::of(&())?.as_u64(), 0x4b77_c94e_9ad1_be88);
+    Ok(())
+}
+
+#[tokio::test]
+async fn fingerprint_header_roundtrip() -> Result<()> {
+    let value = account(7, "ana");
+    let fingerprint = Fingerprint::of(&value)?;
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_fingerprint(fingerprint);
+    let mut de_config = crate::de::Config::new();
+    de_config.with_fingerprint(fingerprint).with_hard_eof();
+
+    let buf = ser_config.serialize_into_buffer(&value)?;
+    assert_eq!(&buf[.. 8], fingerprint.to_le_bytes());
+    assert_eq!(&buf[8 ..], crate::serialize_into_buffer(&value)?);
+    assert_eq!(de_config.deserialize_buffer::<Account>(&buf)?, value);
+
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, value.clone()).await?;
+    assert_eq!(streamed, buf);
+    assert_eq!(
+        de_config.deserialize::<Account, _>(&streamed[..]).await?,
+        value
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn fingerprint_header_mismatch() -> Result<()> {
+    let value = account(7, "ana");
+    let buf = crate::ser::Config::new()
+        .with_fingerprint(Fingerprint::of(&value)?)
+        .serialize_into_buffer(&value)?;
+
+    let expected = Fingerprint::from_u64(0);
+    let mut de_config = crate::de::Config::new();
+    de_config.with_fingerprint(expected);
+    let error =
+        de_config.deserialize_buffer::<AccountNarrowId>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::FingerprintMismatch { expected: e, .. } if *e == expected
+    ));
+    assert_eq!(error.offset(), Some(8));
+
+    let error = de_config
+        .deserialize::<AccountNarrowId, _>(&buf[..])
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::FingerprintMismatch { .. }
+    ));
+    Ok(())
+}
@@ -0,0 +1,128 @@
+#[cfg(feature = "derive")]
+pub use abcode_derive::FixedLayout;
+
+use crate::de::Error;
+
+#[cfg(test)]
+mod test;
+
+pub trait FixedLayout: Sized {
+    const SIZE: usize;
+
+    fn encode_fixed(&self, buf: &mut [u8]);
+
+    fn decode_fixed(buf: &[u8]) -> Result<Self, Error>;
+}
+
+pub fn encode<T>(value: &T) -> Vec<u8>
+where
+    T: FixedLayout,
+{
+    let mut buf = vec![0; T::SIZE];
+    value.encode_fixed(&mut buf);
+    buf
+}
+
+pub fn decode<T>(buf: &[u8]) -> Result<T, Error>
+where
+    T: FixedLayout,
+{
+    T::decode_fixed(buf)
+}
+
+fn fixed_bytes<const N: usize>(buf: &[u8]) -> Result<[u8; N], Error> {
+    buf.get(.. N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(Error::PrematureEof)
+}
+
+macro_rules! fixed_layout_number {
+    ($($ty:ty),*) => {
+        $(
+            impl FixedLayout for $ty {
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn encode_fixed(&self, buf: &mut [u8]) {
+                    buf[.. Self::SIZE].copy_from_slice(&self.to_le_bytes());
+                }
+
+                fn decode_fixed(buf: &[u8]) -> Result<Self, Error> {
+                    Ok(<$ty>::from_le_bytes(fixed_bytes(buf)?))
+                }
+            }
+        )*
+    };
+}
+
+fixed_layout_number!(
+    u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64
+);
+
+impl FixedLayout for () {
+    const SIZE: usize = 0;
+
+    fn encode_fixed(&self, _buf: &mut [u8]) {}
+
+    fn decode_fixed(_buf: &[u8]) -> Result<Self, Error> {
+        Ok(())
+    }
+}
+
+impl FixedLayout for bool {
+    const SIZE: usize = 1;
+
+    fn encode_fixed(&self, buf: &mut [u8]) {
+        buf[0] = u8::from(*self);
+    }
+
+    fn decode_fixed(buf: &[u8]) -> Result<Self, Error> {
+        let [byte] = fixed_bytes(buf)?;
+        Ok(byte != 0)
+    }
+}
+
+impl FixedLayout for char {
+    const SIZE: usize = 4;
+
+    fn encode_fixed(&self, buf: &mut [u8]) {
+        u32::from(*self).encode_fixed(buf);
+    }
+
+    fn decode_fixed(buf: &[u8]) -> Result<Self, Error> {
+        let code = u32::decode_fixed(buf)?;
+        char::from_u32(code).ok_or(Error::InvalidCodePoint(code))
+    }
+}
+
+impl<T, const N: usize> FixedLayout for [T; N]
+where
+    T: FixedLayout,
+{
+    const SIZE: usize = T::SIZE * N;
+
+    fn encode_fixed(&self, buf: &mut [u8]) {
+        for (element, chunk) in self.iter().zip(buf.chunks_mut(T::SIZE.max(1)))
+        {
+            element.encode_fixed(chunk);
+        }
+    }
+
+    fn decode_fixed(buf: &[u8]) -> Result<Self, Error> {
+        let buf = buf.get(.. Self::SIZE).ok_or(Error::PrematureEof)?;
+        let mut error = None;
+        let elements = std::array::from_fn(|index| {
+            let start = index * T::SIZE;
+            match T::decode_fixed(&buf[start .. start + T::SIZE]) {
+                Ok(element) => Some(element),
+                Err(cause) => {
+                    error.get_or_insert(cause);
+                    None
+                },
+            }
+        });
+        match error {
+            Some(error) => Err(error),
+            None => Ok(elements.map(Option::unwrap)),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+
+use super::{decode, encode, FixedLayout};
+
+#[test]
+fn primitives_match_serde_layout() -> Result<()> {
+    let value = [0x1234_u16, 0xabcd];
+    assert_eq!(encode(&value), crate::serialize_into_buffer(value)?);
+    assert_eq!(decode::<[u16; 2]>(&encode(&value))?, value);
+
+    let value = ['ç', 'x'];
+    assert_eq!(encode(&value), crate::serialize_into_buffer(value)?);
+    assert_eq!(decode::<[char; 2]>(&encode(&value))?, value);
+
+    assert_eq!(<[f64; 3]>::SIZE, 24);
+    assert!(decode::<bool>(&[2])?);
+    assert!(matches!(
+        decode::<u32>(&[1, 2, 3]),
+        Err(crate::de::Error::PrematureEof)
+    ));
+    assert!(matches!(
+        decode::<char>(&0xd800_u32.to_le_bytes()),
+        Err(crate::de::Error::InvalidCodePoint(0xd800))
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derived_layout() -> Result<()> {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Serialize,
+        Deserialize,
+        super::FixedLayout,
+    )]
+    struct Position(i32, i32);
+
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Serialize,
+        Deserialize,
+        super::FixedLayout,
+    )]
+    struct Marker;
+
+    #[derive(
+        Debug,
+        Clone,
+        Copy,
+        PartialEq,
+        Serialize,
+        Deserialize,
+        super::FixedLayout,
+    )]
+    struct Tick {
+        id: u64,
+        position: Position,
+        heading: f32,
+        active: bool,
+        marker: Marker,
+        history: [Position; 2],
+    }
+
+    let tick = Tick {
+        id: 42,
+        position: Position(-3, 7),
+        heading: 0.5,
+        active: true,
+        marker: Marker,
+        history: [Position(1, 1), Position(2, -2)],
+    };
+    assert_eq!(Tick::ENCODED_SIZE, 8 + 8 + 4 + 1 + 16);
+    let bytes: [u8; Tick::ENCODED_SIZE] = tick.to_bytes();
+    assert_eq!(&bytes[..], crate::serialize_into_buffer(tick)?);
+    assert_eq!(Tick::from_bytes(&bytes)?, tick);
+    assert_eq!(crate::deserialize_buffer::<Tick>(&bytes)?, tick);
+    assert!(matches!(
+        decode::<Tick>(&bytes[.. 10]),
+        Err(crate::de::Error::PrematureEof)
+    ));
+    Ok(())
+}
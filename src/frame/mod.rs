@@ -0,0 +1,342 @@
+use std::collections::VecDeque;
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{de, ser};
+
+#[cfg(test)]
+mod test;
+
+const LEN_SIZE: usize = 4;
+const CHECKSUM_SIZE: usize = 4;
+
+pub const SYNC_MARKER: [u8; 8] =
+    [0xab, 0xc0, 0xde, 0x5e, 0x7a, 0x11, 0xfe, 0xed];
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc =
+                if crc & 1 == 0 { crc >> 1 } else { 0xedb8_8320 ^ (crc >> 1) };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+pub fn checksum(chunks: &[&[u8]]) -> u32 {
+    let mut crc = !0_u32;
+    for byte in chunks.iter().flat_map(|chunk| chunk.iter()) {
+        crc =
+            CRC_TABLE[((crc ^ u32::from(*byte)) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Failed to write frame")]
+    Ser(
+        #[from]
+        #[source]
+        ser::Error,
+    ),
+    #[error("Failed to read frame")]
+    De(
+        #[from]
+        #[source]
+        de::Error,
+    ),
+    #[error(
+        "Frame {frame} checksum mismatch: expected {expected:08x}, found \
+         {found:08x}"
+    )]
+    ChecksumMismatch { frame: u64, expected: u32, found: u32 },
+    #[error("Frame {frame} is not preceded by a sync marker")]
+    MissingSyncMarker { frame: u64 },
+}
+
+#[derive(Debug)]
+pub struct FrameWriter<W> {
+    device: W,
+    config: ser::Config,
+    checksums: bool,
+    sync_markers: bool,
+    frames: u64,
+}
+
+impl<W> FrameWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub fn new(device: W) -> Self {
+        Self::with_config(device, ser::Config::default())
+    }
+
+    pub fn with_config(device: W, config: ser::Config) -> Self {
+        Self {
+            device,
+            config,
+            checksums: false,
+            sync_markers: false,
+            frames: 0,
+        }
+    }
+
+    pub fn with_checksums(&mut self) -> &mut Self {
+        self.checksums = true;
+        self
+    }
+
+    pub fn with_sync_markers(&mut self) -> &mut Self {
+        self.sync_markers = true;
+        self
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub async fn write<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let payload = self.config.serialize_into_buffer(value)?;
+        let len = u32::try_from(payload.len())
+            .map_err(|_| ser::Error::ExcessiveSize(payload.len()))?
+            .to_le_bytes();
+        let mut frame = Vec::with_capacity(
+            SYNC_MARKER.len() + LEN_SIZE + payload.len() + CHECKSUM_SIZE,
+        );
+        if self.sync_markers {
+            frame.extend_from_slice(&SYNC_MARKER);
+        }
+        frame.extend_from_slice(&len);
+        frame.extend_from_slice(&payload);
+        if self.checksums {
+            frame.extend_from_slice(&checksum(&[&len, &payload]).to_le_bytes());
+        }
+        self.device.write_all(&frame).await.map_err(ser::Error::from)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        self.device.flush().await.map_err(ser::Error::from)?;
+        Ok(())
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.device
+    }
+
+    pub fn into_inner(self) -> W {
+        self.device
+    }
+}
+
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    device: R,
+    config: de::Config,
+    checksums: bool,
+    sync_markers: bool,
+    recovery: bool,
+    max_frame_len: usize,
+    frames: u64,
+    pending: VecDeque<u8>,
+    consumed: Vec<u8>,
+    resync: bool,
+}
+
+impl<R> FrameReader<R>
+where
+    R: AsyncRead + Unpin,
+{
+    pub fn new(device: R) -> Self {
+        Self::with_config(device, de::Config::default())
+    }
+
+    pub fn with_config(device: R, config: de::Config) -> Self {
+        Self {
+            device,
+            config,
+            checksums: false,
+            sync_markers: false,
+            recovery: false,
+            max_frame_len: u32::MAX as usize,
+            frames: 0,
+            pending: VecDeque::new(),
+            consumed: Vec::new(),
+            resync: false,
+        }
+    }
+
+    pub fn with_checksums(&mut self) -> &mut Self {
+        self.checksums = true;
+        self
+    }
+
+    pub fn with_sync_markers(&mut self) -> &mut Self {
+        self.sync_markers = true;
+        self
+    }
+
+    pub fn with_recovery(&mut self) -> &mut Self {
+        self.recovery = true;
+        self.sync_markers = true;
+        self
+    }
+
+    pub fn with_max_frame_len(&mut self, len: usize) -> &mut Self {
+        self.max_frame_len = len;
+        self
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub async fn read<T>(&mut self) -> Result<Option<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        self.consumed.clear();
+        if self.resync {
+            if !self.scan_marker().await? {
+                return Ok(None);
+            }
+            self.resync = false;
+            self.consumed.clear();
+        } else if self.sync_markers {
+            let mut marker = [0; SYNC_MARKER.len()];
+            if !self.fill(&mut marker).await? {
+                return Ok(None);
+            }
+            if marker != SYNC_MARKER {
+                let frame = self.frames;
+                self.frames += 1;
+                return self.recover(Err(Error::MissingSyncMarker { frame }));
+            }
+            self.consumed.clear();
+        }
+        let result = self.read_frame().await;
+        self.recover(result)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.device
+    }
+
+    async fn read_frame<T>(&mut self) -> Result<Option<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let mut len = [0; LEN_SIZE];
+        if !self.fill(&mut len).await? {
+            if self.sync_markers {
+                Err(de::Error::PrematureEof)?
+            }
+            return Ok(None);
+        }
+        let frame = self.frames;
+        self.frames += 1;
+        let payload_len = u32::from_le_bytes(len) as usize;
+        if payload_len > self.max_frame_len {
+            Err(de::Error::ExcessiveSize(payload_len as u64))?
+        }
+        let mut payload = vec![0; payload_len];
+        self.fill_exact(&mut payload).await?;
+        if self.checksums {
+            let mut stored = [0; CHECKSUM_SIZE];
+            self.fill_exact(&mut stored).await?;
+            let expected = u32::from_le_bytes(stored);
+            let found = checksum(&[&len, &payload]);
+            if expected != found {
+                Err(Error::ChecksumMismatch { frame, expected, found })?
+            }
+        }
+        Ok(Some(self.config.deserialize_buffer(&payload)?))
+    }
+
+    fn recover<T>(
+        &mut self,
+        result: Result<Option<T>, Error>,
+    ) -> Result<Option<T>, Error> {
+        match &result {
+            Err(Error::De(de::Error::IO(_))) | Ok(_) => (),
+            Err(_) if self.recovery => {
+                for byte in self.consumed.drain(..).rev() {
+                    self.pending.push_front(byte);
+                }
+                self.resync = true;
+            },
+            Err(_) => (),
+        }
+        result
+    }
+
+    async fn scan_marker(&mut self) -> Result<bool, Error> {
+        let mut window = VecDeque::with_capacity(SYNC_MARKER.len());
+        let mut byte = [0];
+        while window != SYNC_MARKER {
+            if self.read_some(&mut byte).await? == 0 {
+                return Ok(false);
+            }
+            if window.len() == SYNC_MARKER.len() {
+                window.pop_front();
+            }
+            window.push_back(byte[0]);
+        }
+        Ok(true)
+    }
+
+    async fn fill_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        if !self.fill(buf).await? {
+            Err(de::Error::PrematureEof)?
+        }
+        Ok(())
+    }
+
+    async fn fill(&mut self, buf: &mut [u8]) -> Result<bool, Error> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let count = self.read_some(&mut buf[filled ..]).await?;
+            if count == 0 {
+                if filled == 0 {
+                    return Ok(false);
+                }
+                Err(de::Error::PrematureEof)?
+            }
+            filled += count;
+        }
+        Ok(true)
+    }
+
+    async fn read_some(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let count = if self.pending.is_empty() {
+            self.device.read(buf).await.map_err(de::Error::from)?
+        } else {
+            let count = buf.len().min(self.pending.len());
+            for (slot, byte) in buf.iter_mut().zip(self.pending.drain(.. count))
+            {
+                *slot = byte;
+            }
+            count
+        };
+        if self.recovery {
+            self.consumed.extend_from_slice(&buf[.. count]);
+        }
+        Ok(count)
+    }
+}
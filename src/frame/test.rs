@@ -0,0 +1,122 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{checksum, Error, FrameReader, FrameWriter, SYNC_MARKER};
+use crate::de;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    sequence: u64,
+    message: String,
+}
+
+fn entry(sequence: u64) -> Entry {
+    Entry { sequence, message: format!("entry number {}", sequence) }
+}
+
+#[test]
+fn checksum_matches_crc32() {
+    assert_eq!(checksum(&[b"123456789"]), 0xcbf4_3926);
+    assert_eq!(checksum(&[b"1234", b"56789"]), 0xcbf4_3926);
+    assert_eq!(checksum(&[]), 0);
+}
+
+#[tokio::test]
+async fn frames_roundtrip() -> Result<()> {
+    for checksums in [false, true] {
+        let mut writer = FrameWriter::new(Vec::new());
+        if checksums {
+            writer.with_checksums();
+        }
+        for sequence in 0 .. 10 {
+            writer.write(&entry(sequence)).await?;
+        }
+        assert_eq!(writer.frames(), 10);
+        let buf = writer.into_inner();
+
+        let mut reader = FrameReader::new(&buf[..]);
+        if checksums {
+            reader.with_checksums();
+        }
+        for sequence in 0 .. 10 {
+            assert_eq!(reader.read().await?, Some(entry(sequence)));
+        }
+        assert_eq!(reader.read::<Entry>().await?, None);
+        assert_eq!(reader.frames(), 10);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn corrupted_frame_is_skipped() -> Result<()> {
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.with_checksums();
+    let mut offsets = Vec::new();
+    for sequence in 0 .. 3 {
+        offsets.push(writer.get_ref().len());
+        writer.write(&entry(sequence)).await?;
+    }
+    let mut buf = writer.into_inner();
+    buf[offsets[1] + 6] ^= 0x10;
+
+    let mut reader = FrameReader::new(&buf[..]);
+    reader.with_checksums();
+    assert_eq!(reader.read().await?, Some(entry(0)));
+    let error = reader.read::<Entry>().await.unwrap_err();
+    assert!(matches!(error, Error::ChecksumMismatch { frame: 1, .. }));
+    assert_eq!(reader.read().await?, Some(entry(2)));
+    assert_eq!(reader.read::<Entry>().await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn frame_errors() -> Result<()> {
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.write(&entry(0)).await?;
+    let buf = writer.into_inner();
+
+    let mut reader = FrameReader::new(&buf[.. 2]);
+    let error = reader.read::<Entry>().await.unwrap_err();
+    assert!(matches!(error, Error::De(de::Error::PrematureEof)));
+
+    let mut reader = FrameReader::new(&buf[..]);
+    reader.with_max_frame_len(4);
+    let error = reader.read::<Entry>().await.unwrap_err();
+    assert!(matches!(error, Error::De(de::Error::ExcessiveSize(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn recovery_resyncs_after_corruption() -> Result<()> {
+    let mut writer = FrameWriter::new(Vec::new());
+    writer.with_checksums().with_sync_markers();
+    let mut offsets = Vec::new();
+    for sequence in 0 .. 6 {
+        offsets.push(writer.get_ref().len() + SYNC_MARKER.len());
+        writer.write(&entry(sequence)).await?;
+    }
+    let mut buf = writer.into_inner();
+    let mut unmarked = buf.clone();
+    unmarked[offsets[1] - 1] ^= 0xff;
+    buf[offsets[1] + 6] ^= 0x01;
+    buf[offsets[3] + 3] = 0x7f;
+    buf[offsets[4] - 1] ^= 0xff;
+
+    let mut reader = FrameReader::new(&buf[..]);
+    reader.with_checksums().with_recovery();
+    assert_eq!(reader.read().await?, Some(entry(0)));
+    let error = reader.read::<Entry>().await.unwrap_err();
+    assert!(matches!(error, Error::ChecksumMismatch { frame: 1, .. }));
+    assert_eq!(reader.read().await?, Some(entry(2)));
+    let error = reader.read::<Entry>().await.unwrap_err();
+    assert!(matches!(error, Error::De(de::Error::PrematureEof)));
+    assert_eq!(reader.read().await?, Some(entry(5)));
+    assert_eq!(reader.read::<Entry>().await?, None);
+
+    let mut reader = FrameReader::new(&unmarked[..]);
+    reader.with_checksums().with_sync_markers();
+    assert_eq!(reader.read().await?, Some(entry(0)));
+    let error = reader.read::<Entry>().await.unwrap_err();
+    assert!(matches!(error, Error::MissingSyncMarker { frame: 1 }));
+    Ok(())
+}
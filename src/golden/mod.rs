@@ -0,0 +1,334 @@
+use serde::{
+    ser::{
+        SerializeMap,
+        SerializeSeq,
+        SerializeStruct,
+        SerializeStructVariant,
+        SerializeTuple,
+        SerializeTupleStruct,
+        SerializeTupleVariant,
+    },
+    Serialize,
+    Serializer,
+};
+
+use crate::{de, ser, varint::IntEncoding};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Profile {
+    Default,
+    SelfDescribing,
+    FieldPresence,
+    FieldCount,
+    VariantNames,
+    SortedMapKeys,
+    Utf8Chars,
+    StrChars,
+    Varint,
+    Leb128,
+    TaggedFields,
+}
+
+impl Profile {
+    pub const ALL: [Self; 11] = [
+        Self::Default,
+        Self::SelfDescribing,
+        Self::FieldPresence,
+        Self::FieldCount,
+        Self::VariantNames,
+        Self::SortedMapKeys,
+        Self::Utf8Chars,
+        Self::StrChars,
+        Self::Varint,
+        Self::Leb128,
+        Self::TaggedFields,
+    ];
+
+    pub fn ser_config(self) -> ser::Config {
+        let mut config = ser::Config::new();
+        match self {
+            Self::Default => &mut config,
+            Self::SelfDescribing => config.with_self_describing(),
+            Self::FieldPresence => config.with_field_presence(),
+            Self::FieldCount => config.with_field_count(),
+            Self::VariantNames => config.with_variant_names(),
+            Self::SortedMapKeys => config.with_sorted_map_keys(),
+            Self::Utf8Chars => config.with_utf8_chars(),
+            Self::StrChars => config.with_str_chars(),
+            Self::Varint => config.with_int_encoding(IntEncoding::Varint),
+            Self::Leb128 => config.with_int_encoding(IntEncoding::Leb128),
+            Self::TaggedFields => config.with_tagged_fields(),
+        };
+        config
+    }
+
+    pub fn de_config(self) -> de::Config {
+        let mut config = de::Config::new();
+        config.with_hard_eof();
+        match self {
+            Self::Default => &mut config,
+            Self::SelfDescribing => config.with_self_describing(),
+            Self::FieldPresence => config.with_field_presence(),
+            Self::FieldCount => config.with_field_count(),
+            Self::VariantNames => config.with_variant_names(),
+            Self::SortedMapKeys => config.with_sorted_map_keys(),
+            Self::Utf8Chars => config.with_utf8_chars(),
+            Self::StrChars => config.with_str_chars(),
+            Self::Varint => config.with_int_encoding(IntEncoding::Varint),
+            Self::Leb128 => config.with_int_encoding(IntEncoding::Leb128),
+            Self::TaggedFields => config.with_tagged_fields(),
+        };
+        config
+    }
+}
+
+/// A value of the serde data model, spelled out with its exact types and
+/// names. Serializing an `Input` describes it; [`Input::as_value`] is the
+/// value itself.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Input {
+    Unit,
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    Str(&'static str),
+    Bytes(&'static [u8]),
+    None,
+    Some(Box<Input>),
+    Seq(Vec<Input>),
+    Tuple(Vec<Input>),
+    Map(Vec<(Input, Input)>),
+    UnitStruct(&'static str),
+    NewtypeStruct(&'static str, Box<Input>),
+    TupleStruct(&'static str, Vec<Input>),
+    Struct(&'static str, Vec<(&'static str, Input)>),
+    UnitVariant(&'static str, u32, &'static str),
+    NewtypeVariant(&'static str, u32, &'static str, Box<Input>),
+    TupleVariant(&'static str, u32, &'static str, Vec<Input>),
+    StructVariant(&'static str, u32, &'static str, Vec<(&'static str, Input)>),
+}
+
+impl Input {
+    pub fn as_value(&self) -> AsValue<'_> {
+        AsValue(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AsValue<'a>(&'a Input);
+
+impl Serialize for AsValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            Input::Unit => serializer.serialize_unit(),
+            Input::Bool(value) => serializer.serialize_bool(*value),
+            Input::I8(value) => serializer.serialize_i8(*value),
+            Input::I16(value) => serializer.serialize_i16(*value),
+            Input::I32(value) => serializer.serialize_i32(*value),
+            Input::I64(value) => serializer.serialize_i64(*value),
+            Input::I128(value) => serializer.serialize_i128(*value),
+            Input::U8(value) => serializer.serialize_u8(*value),
+            Input::U16(value) => serializer.serialize_u16(*value),
+            Input::U32(value) => serializer.serialize_u32(*value),
+            Input::U64(value) => serializer.serialize_u64(*value),
+            Input::U128(value) => serializer.serialize_u128(*value),
+            Input::F32(value) => serializer.serialize_f32(*value),
+            Input::F64(value) => serializer.serialize_f64(*value),
+            Input::Char(value) => serializer.serialize_char(*value),
+            Input::Str(value) => serializer.serialize_str(value),
+            Input::Bytes(value) => serializer.serialize_bytes(value),
+            Input::None => serializer.serialize_none(),
+            Input::Some(inner) => serializer.serialize_some(&inner.as_value()),
+            Input::Seq(elements) => {
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for element in elements {
+                    seq.serialize_element(&element.as_value())?;
+                }
+                seq.end()
+            },
+            Input::Tuple(elements) => {
+                let mut tuple = serializer.serialize_tuple(elements.len())?;
+                for element in elements {
+                    tuple.serialize_element(&element.as_value())?;
+                }
+                tuple.end()
+            },
+            Input::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(&key.as_value(), &value.as_value())?;
+                }
+                map.end()
+            },
+            Input::UnitStruct(name) => serializer.serialize_unit_struct(name),
+            Input::NewtypeStruct(name, inner) => {
+                serializer.serialize_newtype_struct(name, &inner.as_value())
+            },
+            Input::TupleStruct(name, elements) => {
+                let mut tuple =
+                    serializer.serialize_tuple_struct(name, elements.len())?;
+                for element in elements {
+                    tuple.serialize_field(&element.as_value())?;
+                }
+                tuple.end()
+            },
+            Input::Struct(name, fields) => {
+                let mut record =
+                    serializer.serialize_struct(name, fields.len())?;
+                for (key, value) in fields {
+                    record.serialize_field(key, &value.as_value())?;
+                }
+                record.end()
+            },
+            Input::UnitVariant(name, index, variant) => {
+                serializer.serialize_unit_variant(name, *index, variant)
+            },
+            Input::NewtypeVariant(name, index, variant, inner) => serializer
+                .serialize_newtype_variant(
+                    name,
+                    *index,
+                    variant,
+                    &inner.as_value(),
+                ),
+            Input::TupleVariant(name, index, variant, elements) => {
+                let mut tuple = serializer.serialize_tuple_variant(
+                    name,
+                    *index,
+                    variant,
+                    elements.len(),
+                )?;
+                for element in elements {
+                    tuple.serialize_field(&element.as_value())?;
+                }
+                tuple.end()
+            },
+            Input::StructVariant(name, index, variant, fields) => {
+                let mut record = serializer.serialize_struct_variant(
+                    name,
+                    *index,
+                    variant,
+                    fields.len(),
+                )?;
+                for (key, value) in fields {
+                    record.serialize_field(key, &value.as_value())?;
+                }
+                record.end()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Vector {
+    pub name: &'static str,
+    pub profile: Profile,
+    pub input: Input,
+    pub encoding: Vec<u8>,
+}
+
+pub fn samples() -> Vec<(&'static str, Input)> {
+    let person = |name, age| {
+        Input::Struct(
+            "Person",
+            vec![("name", Input::Str(name)), ("age", Input::Some(age))],
+        )
+    };
+    vec![
+        ("unit", Input::Unit),
+        ("bool", Input::Bool(true)),
+        ("i8", Input::I8(-5)),
+        ("i16", Input::I16(-300)),
+        ("i32", Input::I32(-70_000)),
+        ("i64", Input::I64(i64::MIN)),
+        ("i128", Input::I128(-1)),
+        ("u8", Input::U8(200)),
+        ("u16", Input::U16(300)),
+        ("u32", Input::U32(70_000)),
+        ("u64", Input::U64(1 << 40)),
+        ("u128", Input::U128(u128::MAX)),
+        ("f32", Input::F32(1.5)),
+        ("f64", Input::F64(-0.25)),
+        ("char", Input::Char('λ')),
+        ("str", Input::Str("héllo")),
+        ("bytes", Input::Bytes(&[0, 1, 255])),
+        ("none", Input::None),
+        ("some", Input::Some(Box::new(Input::U8(7)))),
+        ("seq", Input::Seq(vec![Input::U16(1), Input::U16(2), Input::U16(3)])),
+        ("tuple", Input::Tuple(vec![Input::U8(1), Input::Str("a")])),
+        (
+            "map",
+            Input::Map(vec![
+                (Input::Str("b"), Input::U32(2)),
+                (Input::Str("a"), Input::U32(1)),
+            ]),
+        ),
+        ("unit_struct", Input::UnitStruct("Marker")),
+        (
+            "newtype_struct",
+            Input::NewtypeStruct("Meters", Box::new(Input::U32(5))),
+        ),
+        (
+            "tuple_struct",
+            Input::TupleStruct("Point", vec![Input::I16(-1), Input::I16(1)]),
+        ),
+        ("struct", person("ana", Box::new(Input::U8(30)))),
+        ("unit_variant", Input::UnitVariant("Event", 0, "Idle")),
+        (
+            "newtype_variant",
+            Input::NewtypeVariant("Event", 1, "Ping", Box::new(Input::U64(9))),
+        ),
+        (
+            "tuple_variant",
+            Input::TupleVariant(
+                "Event",
+                2,
+                "Moved",
+                vec![Input::I32(-1), Input::I32(1)],
+            ),
+        ),
+        (
+            "struct_variant",
+            Input::StructVariant(
+                "Event",
+                3,
+                "Renamed",
+                vec![("to", Input::Str("x"))],
+            ),
+        ),
+    ]
+}
+
+pub fn vectors() -> Vec<Vector> {
+    let samples = samples();
+    Profile::ALL
+        .into_iter()
+        .flat_map(|profile| {
+            let config = profile.ser_config();
+            samples.iter().map(move |(name, input)| Vector {
+                name,
+                profile,
+                input: input.clone(),
+                encoding: config
+                    .serialize_into_buffer(input.as_value())
+                    .expect("golden samples encode under every profile"),
+            })
+        })
+        .collect()
+}
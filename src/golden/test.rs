@@ -0,0 +1,90 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{samples, vectors, Input, Profile, Vector};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Person {
+    name: String,
+    age: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Event {
+    Idle,
+    Ping(u64),
+    Moved(i32, i32),
+    Renamed { to: String },
+}
+
+fn find<'a>(vectors: &'a [Vector], name: &str, profile: Profile) -> &'a [u8] {
+    &vectors
+        .iter()
+        .find(|vector| vector.name == name && vector.profile == profile)
+        .expect("vector exists")
+        .encoding
+}
+
+#[test]
+fn default_encodings_are_pinned() {
+    let vectors = vectors();
+    assert_eq!(vectors.len(), samples().len() * Profile::ALL.len());
+    assert_eq!(find(&vectors, "u16", Profile::Default), [0x2c, 0x01]);
+    assert_eq!(find(&vectors, "u16", Profile::Varint), [251, 0x2c, 0x01]);
+    assert_eq!(
+        find(&vectors, "str", Profile::Default),
+        b"\x06\0\0\0\0\0\0\0h\xc3\xa9llo"
+    );
+    assert_eq!(find(&vectors, "unit_variant", Profile::Default), [0; 4]);
+    assert!(find(&vectors, "unit", Profile::Default).is_empty());
+}
+
+#[test]
+fn vectors_decode_under_their_profile() -> Result<()> {
+    let vectors = vectors();
+    for profile in Profile::ALL {
+        let config = profile.de_config();
+        let decoded: u16 =
+            config.deserialize_buffer(find(&vectors, "u16", profile))?;
+        assert_eq!(decoded, 300);
+        let decoded: String =
+            config.deserialize_buffer(find(&vectors, "str", profile))?;
+        assert_eq!(decoded, "héllo");
+        let decoded: Person =
+            config.deserialize_buffer(find(&vectors, "struct", profile))?;
+        assert_eq!(decoded, Person { name: "ana".to_owned(), age: Some(30) });
+        let names = [
+            "unit_variant",
+            "newtype_variant",
+            "tuple_variant",
+            "struct_variant",
+        ];
+        let decoded = names
+            .iter()
+            .map(|name| {
+                config.deserialize_buffer(find(&vectors, name, profile))
+            })
+            .collect::<Result<Vec<Event>, _>>()?;
+        assert_eq!(
+            decoded,
+            [
+                Event::Idle,
+                Event::Ping(9),
+                Event::Moved(-1, 1),
+                Event::Renamed { to: "x".to_owned() },
+            ]
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn input_describes_itself() -> Result<()> {
+    let input = Input::Some(Box::new(Input::U8(7)));
+    let mut config = crate::ser::Config::new();
+    config.with_self_describing();
+    let description = config.serialize_into_buffer(&input)?;
+    let value = config.serialize_into_buffer(input.as_value())?;
+    assert_ne!(description, value);
+    Ok(())
+}
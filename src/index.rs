@@ -0,0 +1,31 @@
+pub const INDEX_MAGIC: [u8; 8] = *b"abcidx\x00\x01";
+
+pub const ARCHIVE_MAGIC: [u8; 8] = *b"abcarc\x00\x01";
+
+pub const FOOTER_SIZE: usize = 24;
+
+pub fn encode_footer(
+    magic: [u8; 8],
+    index_offset: u64,
+    count: u64,
+) -> [u8; FOOTER_SIZE] {
+    let mut footer = [0; FOOTER_SIZE];
+    footer[.. 8].copy_from_slice(&index_offset.to_le_bytes());
+    footer[8 .. 16].copy_from_slice(&count.to_le_bytes());
+    footer[16 ..].copy_from_slice(&magic);
+    footer
+}
+
+pub fn decode_footer(
+    magic: [u8; 8],
+    footer: &[u8; FOOTER_SIZE],
+) -> Option<(u64, u64)> {
+    if footer[16 ..] != magic {
+        return None;
+    }
+    let mut index_offset = [0; 8];
+    index_offset.copy_from_slice(&footer[.. 8]);
+    let mut count = [0; 8];
+    count.copy_from_slice(&footer[8 .. 16]);
+    Some((u64::from_le_bytes(index_offset), u64::from_le_bytes(count)))
+}
@@ -0,0 +1,159 @@
+use std::{borrow::Cow, fmt};
+
+use serde::Deserialize;
+
+use crate::de::{Config, Error, Segment};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Node {
+    segment: Option<Segment>,
+    start: u64,
+    end: u64,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(segment: Option<Segment>, start: u64) -> Self {
+        Self { segment, start, end: start, children: Vec::new() }
+    }
+
+    pub fn segment(&self) -> Option<Segment> {
+        self.segment
+    }
+
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    pub fn children(&self) -> &[Node] {
+        &self.children[..]
+    }
+
+    pub fn bytes<'a>(&self, buf: &'a [u8]) -> &'a [u8] {
+        let start = usize::try_from(self.start).unwrap_or(usize::MAX);
+        let end = usize::try_from(self.end).unwrap_or(usize::MAX);
+        buf.get(start .. end).unwrap_or_default()
+    }
+
+    fn fmt_tree(
+        &self,
+        f: &mut fmt::Formatter,
+        buf: &[u8],
+        indent: usize,
+    ) -> fmt::Result {
+        write!(f, "{:1$}", "", indent * 2)?;
+        match self.segment {
+            Some(segment) => write!(f, "{}", segment)?,
+            None => write!(f, "$")?,
+        }
+        write!(f, " @{}..{}", self.start, self.end)?;
+        if self.children.is_empty() {
+            write!(f, ":")?;
+            for byte in self.bytes(buf) {
+                write!(f, " {:02x}", byte)?;
+            }
+        }
+        writeln!(f)?;
+        for child in &self.children {
+            child.fmt_tree(f, buf, indent + 1)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Inspection<'a> {
+    buf: Cow<'a, [u8]>,
+    root: Node,
+    error: Option<Error>,
+}
+
+impl<'a> Inspection<'a> {
+    pub(crate) fn new(
+        buf: Cow<'a, [u8]>,
+        root: Node,
+        error: Option<Error>,
+    ) -> Self {
+        Self { buf, root, error }
+    }
+
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_error(self) -> Option<Error> {
+        self.error
+    }
+
+    pub fn find(&self, path: &[Segment]) -> Option<&Node> {
+        path.iter().try_fold(&self.root, |node, segment| {
+            node.children
+                .iter()
+                .rev()
+                .find(|child| child.segment == Some(*segment))
+        })
+    }
+}
+
+impl<'a> fmt::Display for Inspection<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.root.fmt_tree(f, &self.buf, 0)?;
+        if let Some(error) = &self.error {
+            write!(f, "error: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Tracer {
+    stack: Vec<Node>,
+}
+
+impl Tracer {
+    pub(crate) fn new() -> Self {
+        Self { stack: vec![Node::new(None, 0)] }
+    }
+
+    pub(crate) fn open(&mut self, segment: Segment, offset: u64) {
+        self.stack.push(Node::new(Some(segment), offset));
+    }
+
+    pub(crate) fn close(&mut self, offset: u64) {
+        if self.stack.len() > 1 {
+            if let Some(mut node) = self.stack.pop() {
+                node.end = offset;
+                if let Some(parent) = self.stack.last_mut() {
+                    parent.children.push(node);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn finish(mut self, offset: u64) -> Node {
+        while self.stack.len() > 1 {
+            self.close(offset);
+        }
+        let mut root = self.stack.pop().unwrap_or_default();
+        root.end = offset;
+        root
+    }
+}
+
+pub fn inspect<'a, 'de, T>(buf: &'a [u8]) -> Inspection<'a>
+where
+    T: Deserialize<'de>,
+{
+    Config::default().inspect::<T>(buf)
+}
@@ -0,0 +1,85 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::de::Segment;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Kind {
+    Plain,
+    Tagged(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Record {
+    id: u16,
+    name: String,
+    kinds: Vec<Kind>,
+}
+
+fn record() -> Record {
+    Record {
+        id: 0x0102,
+        name: "ab".to_owned(),
+        kinds: vec![Kind::Plain, Kind::Tagged(9)],
+    }
+}
+
+#[test]
+fn inspect_tree() -> Result<()> {
+    let buf = crate::serialize_into_buffer(record())?;
+    let inspection = super::inspect::<Record>(&buf);
+    assert!(inspection.error().is_none());
+
+    let root = inspection.root();
+    assert_eq!((root.start(), root.end()), (0, buf.len() as u64));
+    assert_eq!(root.children().len(), 3);
+
+    let name = inspection.find(&[Segment::Field("name")]).unwrap();
+    assert_eq!((name.start(), name.end()), (2, 12));
+    assert_eq!(&name.bytes(&buf)[8 ..], b"ab");
+
+    let tagged = inspection
+        .find(&[
+            Segment::Field("kinds"),
+            Segment::Index(1),
+            Segment::Variant("Tagged"),
+        ])
+        .unwrap();
+    assert_eq!(tagged.bytes(&buf), [9]);
+    Ok(())
+}
+
+#[test]
+fn inspect_display() -> Result<()> {
+    let buf = crate::serialize_into_buffer(record())?;
+    let rendered = super::inspect::<Record>(&buf).to_string();
+    let expected = "\
+$ @0..29
+  .id @0..2: 02 01
+  .name @2..12: 02 00 00 00 00 00 00 00 61 62
+  .kinds @12..29
+    [0] @20..24
+      ::Plain @24..24:
+    [1] @24..29
+      ::Tagged @28..29: 09
+";
+    assert_eq!(rendered, expected);
+    Ok(())
+}
+
+#[test]
+fn inspect_failure() -> Result<()> {
+    let mut buf = crate::serialize_into_buffer(record())?;
+    buf[10] = 0xff;
+    let inspection = super::inspect::<Record>(&buf);
+
+    let error = inspection.error().unwrap();
+    assert!(matches!(error.inner(), crate::de::Error::Utf8(_)));
+    assert_eq!(error.path().unwrap().to_string(), ".name");
+
+    let name = inspection.find(&[Segment::Field("name")]).unwrap();
+    assert_eq!((name.start(), name.end()), (2, 12));
+    assert_eq!(inspection.root().children().len(), 2);
+    assert!(inspection.to_string().ends_with(&error.to_string()));
+    Ok(())
+}
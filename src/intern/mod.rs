@@ -0,0 +1,115 @@
+use std::{cell::RefCell, fmt, ops::Deref, ptr, sync::Arc};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(test)]
+mod test;
+
+thread_local! {
+    static OFFERED: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn offer<F, T>(string: Arc<str>, visit: F) -> T
+where
+    F: FnOnce(&str) -> T,
+{
+    let previous =
+        OFFERED.with(|offered| offered.replace(Some(string.clone())));
+    let result = visit(&string);
+    OFFERED.with(|offered| *offered.borrow_mut() = previous);
+    result
+}
+
+fn claim(visited: &str) -> Option<Arc<str>> {
+    OFFERED.with(|offered| {
+        offered
+            .borrow()
+            .as_ref()
+            .filter(|string| ptr::eq(string.as_ptr(), visited.as_ptr()))
+            .filter(|string| string.len() == visited.len())
+            .cloned()
+    })
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SharedStr(pub Arc<str>);
+
+impl SharedStr {
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for SharedStr {
+    fn from(string: &str) -> Self {
+        Self(string.into())
+    }
+}
+
+impl From<String> for SharedStr {
+    fn from(string: String) -> Self {
+        Self(string.into())
+    }
+}
+
+impl From<Arc<str>> for SharedStr {
+    fn from(string: Arc<str>) -> Self {
+        Self(string)
+    }
+}
+
+impl fmt::Display for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for SharedStr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+struct SharedStrVisitor;
+
+impl<'de> de::Visitor<'de> for SharedStrVisitor {
+    type Value = SharedStr;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(SharedStr(claim(v).unwrap_or_else(|| v.into())))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(SharedStr(v.into()))
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedStr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(SharedStrVisitor)
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::SharedStr;
+use crate::{de, ser, varint::IntEncoding};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Level {
+    Info,
+    Warn(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Record {
+    attributes: BTreeMap<String, u64>,
+    level: Level,
+}
+
+fn records() -> Vec<Record> {
+    (0 .. 100)
+        .map(|index| Record {
+            attributes: ["host.name", "service.name", "span.kind"]
+                .into_iter()
+                .map(|name| (name.to_owned(), index))
+                .collect(),
+            level: if index % 2 == 0 { Level::Info } else { Level::Warn(1) },
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn interned_strings_roundtrip() -> Result<()> {
+    let records = records();
+    let mut ser_config = ser::Config::new();
+    ser_config.with_variant_names();
+    let plain = ser_config.serialize_into_buffer(&records)?;
+
+    ser_config.with_interned_strings();
+    let mut de_config = de::Config::new();
+    de_config.with_interned_strings().with_variant_names();
+
+    let buf = ser_config.serialize_into_buffer(&records)?;
+    assert!(buf.len() < plain.len());
+    let decoded: Vec<Record> = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, records);
+
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, records.clone()).await?;
+    let decoded: Vec<Record> = de_config.deserialize(&streamed[..]).await?;
+    assert_eq!(decoded, records);
+
+    ser_config.with_self_describing().with_int_encoding(IntEncoding::Varint);
+    de_config.with_self_describing().with_int_encoding(IntEncoding::Varint);
+    let buf = ser_config.serialize_into_buffer(&records)?;
+    let decoded: Vec<Record> = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, records);
+    Ok(())
+}
+
+#[test]
+fn shared_strings_point_to_one_allocation() -> Result<()> {
+    let names = vec!["alpha", "beta", "alpha", "alpha", "beta"];
+    let mut ser_config = ser::Config::new();
+    ser_config.with_interned_strings();
+    let buf = ser_config.serialize_into_buffer(&names)?;
+
+    let mut de_config = de::Config::new();
+    de_config.with_interned_strings();
+    let decoded: Vec<SharedStr> = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded.iter().map(|name| &**name).collect::<Vec<_>>(), names);
+    assert!(decoded[0].ptr_eq(&decoded[2]));
+    assert!(decoded[0].ptr_eq(&decoded[3]));
+    assert!(decoded[1].ptr_eq(&decoded[4]));
+    assert!(!decoded[0].ptr_eq(&decoded[1]));
+
+    let plain = crate::serialize_into_buffer(&names)?;
+    let decoded: Vec<SharedStr> = crate::deserialize_buffer(&plain)?;
+    assert_eq!(decoded.iter().map(|name| &**name).collect::<Vec<_>>(), names);
+    assert!(!decoded[0].ptr_eq(&decoded[2]));
+    Ok(())
+}
+
+#[test]
+fn interned_string_errors() -> Result<()> {
+    let mut de_config = de::Config::new();
+    de_config.with_interned_strings();
+    let buf = crate::serialize_into_buffer(3_u64)?;
+    let error = de_config.deserialize_buffer::<String>(&buf).unwrap_err();
+    assert!(matches!(error.inner(), de::Error::UnknownInternedString(3)));
+
+    let error = ser::Config::builder()
+        .with_interned_strings()
+        .with_sorted_map_keys()
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ser::ConfigError::ConflictingOptions(
+            "interned_strings",
+            "sorted_map_keys"
+        )
+    ));
+    Ok(())
+}
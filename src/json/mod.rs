@@ -0,0 +1,45 @@
+use serde_transcode::Transcoder;
+use thiserror::Error;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Ser(#[from] crate::ser::Error),
+    #[error(transparent)]
+    De(#[from] crate::de::Error),
+}
+
+pub fn to_json(buf: &[u8]) -> Result<String, Error> {
+    let mut deserializer =
+        crate::de::Config::new().with_self_describing().deserializer(buf);
+    let json = serde_json::to_string(&Transcoder::new(&mut deserializer))?;
+    deserializer.source().ensure_eof()?;
+    Ok(json)
+}
+
+pub fn to_json_pretty(buf: &[u8]) -> Result<String, Error> {
+    let mut deserializer =
+        crate::de::Config::new().with_self_describing().deserializer(buf);
+    let json =
+        serde_json::to_string_pretty(&Transcoder::new(&mut deserializer))?;
+    deserializer.source().ensure_eof()?;
+    Ok(json)
+}
+
+pub fn from_json(json: &str) -> Result<Vec<u8>, Error> {
+    let mut buffer = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    serde_transcode::transcode(
+        &mut deserializer,
+        &mut crate::ser::Config::new()
+            .with_self_describing()
+            .serializer(&mut buffer),
+    )?;
+    deserializer.end()?;
+    Ok(buffer)
+}
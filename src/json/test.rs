@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Status {
+    Draft,
+    Published { at: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    title: String,
+    score: i32,
+    ratio: f64,
+    parent: Option<u32>,
+    tags: BTreeMap<String, u16>,
+    history: Vec<Status>,
+}
+
+fn entry() -> Entry {
+    Entry {
+        title: "archived".to_owned(),
+        score: -3,
+        ratio: 0.25,
+        parent: Some(9),
+        tags: BTreeMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)]),
+        history: vec![Status::Draft, Status::Published { at: 1700 }],
+    }
+}
+
+#[test]
+fn abcode_to_json() -> Result<()> {
+    let value = entry();
+    let buf = crate::ser::Config::new()
+        .with_self_describing()
+        .serialize_into_buffer(&value)?;
+
+    assert_eq!(super::to_json(&buf)?, serde_json::to_string(&value)?);
+    assert_eq!(
+        super::to_json_pretty(&buf)?,
+        serde_json::to_string_pretty(&value)?
+    );
+    Ok(())
+}
+
+#[test]
+fn json_to_abcode() -> Result<()> {
+    let mut config = crate::de::Config::new();
+    config.with_self_describing().with_hard_eof();
+
+    let value = entry();
+    let buf = super::from_json(&serde_json::to_string(&value)?)?;
+    assert_eq!(config.deserialize_buffer::<Entry>(&buf)?, value);
+
+    let value = Entry { parent: None, ..entry() };
+    let buf = super::from_json(&serde_json::to_string(&value)?)?;
+    assert_eq!(config.deserialize_buffer::<Entry>(&buf)?, value);
+    Ok(())
+}
+
+#[test]
+fn to_json_rejects_trailing_bytes() -> Result<()> {
+    let mut buf = crate::ser::Config::new()
+        .with_self_describing()
+        .serialize_into_buffer(7_u8)?;
+    buf.push(0);
+
+    let error = super::to_json(&buf).unwrap_err();
+    assert!(matches!(
+        error,
+        super::Error::De(crate::de::Error::TrailingBytes { count: 1 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn transcode_with_public_codec() -> Result<()> {
+    let mut json = Vec::new();
+    let buf = crate::ser::Config::new()
+        .with_self_describing()
+        .serialize_into_buffer(entry())?;
+    let mut deserializer =
+        crate::de::Config::new().with_self_describing().deserializer(&buf);
+    serde_transcode::transcode(
+        &mut deserializer,
+        &mut serde_json::Serializer::new(&mut json),
+    )?;
+    assert_eq!(json, serde_json::to_vec(&entry())?);
+    Ok(())
+}
@@ -1,5 +1,75 @@
-pub use de::{deserialize, deserialize_buffer};
-pub use ser::{serialize, serialize_into_buffer, serialize_on_buffer};
+#[cfg(all(test, feature = "derive"))]
+extern crate self as abcode;
 
+pub use blocking::BlockingStrategy;
+pub use de::{
+    deserialize,
+    deserialize_armored,
+    deserialize_buffer,
+    deserialize_from_file,
+};
+pub use pool::BufferPool;
+pub use progress::{Progress, ProgressReport};
+pub use ser::{
+    serialize,
+    serialize_armored,
+    serialize_into_buffer,
+    serialize_into_slice,
+    serialize_into_writer,
+    serialize_on_buffer,
+    serialize_seekable,
+    serialize_to_file,
+};
+#[cfg(feature = "bytes")]
+pub use ser::{serialize_on_bytes_mut, serialize_to_bytes};
+#[cfg(feature = "value")]
+pub use value::Value;
+
+pub mod archive;
+pub mod armor;
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+mod blocking;
+#[cfg(feature = "bulk")]
+pub mod bulk;
+pub mod compress;
+#[cfg(feature = "crypto")]
+pub mod crypto;
 pub mod de;
+pub mod deferred;
+#[cfg(feature = "delta")]
+pub mod delta;
+pub mod dump;
+#[cfg(feature = "envelope")]
+pub mod envelope;
+pub mod fingerprint;
+pub mod fixed;
+pub mod frame;
+#[cfg(feature = "golden")]
+pub mod golden;
+mod index;
+pub mod inspect;
+pub mod intern;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod metadata;
+mod pool;
+mod progress;
+pub mod registry;
+pub mod repr;
+pub mod schema;
 pub mod ser;
+pub mod size;
+#[cfg(feature = "async-compression")]
+pub mod stream_compression;
+mod tag;
+#[cfg(feature = "testkit")]
+pub mod testkit;
+mod validate;
+#[cfg(feature = "value")]
+pub mod value;
+pub mod varint;
+pub mod with;
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,137 @@
+use std::{
+    collections::{btree_map, BTreeMap},
+    str,
+};
+
+#[cfg(test)]
+mod test;
+
+const LEN_SIZE: usize = 4;
+
+pub const MAX_LEN: usize = 1 << 20;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Metadata {
+    entries: BTreeMap<String, Vec<u8>>,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<K, V>(&mut self, key: K, value: V) -> Option<Vec<u8>>
+    where
+        K: Into<String>,
+        V: Into<Vec<u8>>,
+    {
+        self.entries.insert(key.into(), value.into())
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        self.entries.remove(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(|value| str::from_utf8(value).ok())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { inner: self.entries.iter() }
+    }
+
+    pub fn encoded_len(&self) -> usize {
+        self.entries.iter().fold(LEN_SIZE, |len, (key, value)| {
+            len + LEN_SIZE + key.len() + LEN_SIZE + value.len()
+        })
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut block = Vec::with_capacity(self.encoded_len());
+        let body_len = self.encoded_len() - LEN_SIZE;
+        block.extend_from_slice(&(body_len as u32).to_le_bytes());
+        for (key, value) in &self.entries {
+            block.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            block.extend_from_slice(key.as_bytes());
+            block.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            block.extend_from_slice(value);
+        }
+        block
+    }
+
+    pub(crate) fn decode(mut body: &[u8]) -> Option<Self> {
+        let mut entries = BTreeMap::new();
+        while !body.is_empty() {
+            let key = take_chunk(&mut body)?;
+            let key = str::from_utf8(key).ok()?.to_owned();
+            let value = take_chunk(&mut body)?.to_vec();
+            if entries.insert(key, value).is_some() {
+                return None;
+            }
+        }
+        Some(Self { entries })
+    }
+}
+
+fn take_chunk<'a>(body: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let (len, rest) = body.split_first_chunk::<LEN_SIZE>()?;
+    let len = u32::from_le_bytes(*len) as usize;
+    let chunk = rest.get(.. len)?;
+    *body = &rest[len ..];
+    Some(chunk)
+}
+
+impl<K, V> FromIterator<(K, V)> for Metadata
+where
+    K: Into<String>,
+    V: Into<Vec<u8>>,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut metadata = Self::new();
+        for (key, value) in iter {
+            metadata.insert(key, value);
+        }
+        metadata
+    }
+}
+
+impl<'a> IntoIterator for &'a Metadata {
+    type Item = (&'a str, &'a [u8]);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    inner: btree_map::Iter<'a, String, Vec<u8>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (key.as_str(), value.as_slice()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{Metadata, MAX_LEN};
+use crate::{de, fingerprint::Fingerprint, ser};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Event {
+    id: u64,
+    payload: Vec<String>,
+}
+
+fn event() -> Event {
+    Event { id: 7, payload: vec!["one".to_owned(), "two".to_owned()] }
+}
+
+fn metadata() -> Metadata {
+    [
+        ("producer.version", b"1.4.2".to_vec()),
+        ("tenant.id", b"acme".to_vec()),
+        ("blob", vec![0, 0xff, 0x80]),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[test]
+fn metadata_encoding_roundtrip() {
+    let metadata = metadata();
+    let block = metadata.encode();
+    assert_eq!(block.len(), metadata.encoded_len());
+    assert_eq!(Metadata::decode(&block[4 ..]), Some(metadata.clone()));
+    assert_eq!(metadata.get_str("tenant.id"), Some("acme"));
+    assert_eq!(metadata.get("blob"), Some(&[0, 0xff, 0x80][..]));
+    assert_eq!(metadata.get_str("blob"), None);
+    assert_eq!(
+        metadata.iter().map(|(key, _)| key).collect::<Vec<_>>(),
+        ["blob", "producer.version", "tenant.id"]
+    );
+
+    assert_eq!(Metadata::decode(&[]), Some(Metadata::new()));
+    assert_eq!(Metadata::decode(&block[4 .. block.len() - 1]), None);
+}
+
+#[tokio::test]
+async fn metadata_header_roundtrip() -> Result<()> {
+    let fingerprint = Fingerprint::of(&event())?;
+    let mut ser_config = ser::Config::new();
+    ser_config.with_fingerprint(fingerprint).with_metadata(metadata())?;
+    let buf = ser_config.serialize_into_buffer(event())?;
+
+    let mut de_config = de::Config::new();
+    de_config.with_fingerprint(fingerprint);
+    assert_eq!(de_config.read_metadata(&buf)?, metadata());
+    let (found, decoded) =
+        de_config.deserialize_buffer_with_metadata::<Event>(&buf)?;
+    assert_eq!((found, decoded), (metadata(), event()));
+
+    de_config.with_metadata();
+    let decoded: Event = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, event());
+
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, event()).await?;
+    assert_eq!(streamed, buf);
+    let decoded: Event = de_config.deserialize(&streamed[..]).await?;
+    assert_eq!(decoded, event());
+    Ok(())
+}
+
+#[test]
+fn metadata_errors() -> Result<()> {
+    let mut oversized = Metadata::new();
+    oversized.insert("blob", vec![0; MAX_LEN]);
+    let error =
+        ser::Config::new().with_metadata(oversized.clone()).unwrap_err();
+    assert!(matches!(error, ser::ConfigError::MetadataTooLarge(_)));
+    let error = ser::Config::builder().with_metadata(oversized).build();
+    assert!(matches!(error, Err(ser::ConfigError::MetadataTooLarge(_))));
+
+    let buf = crate::serialize_into_buffer(event())?;
+    let error = de::Config::new().read_metadata(&buf).unwrap_err();
+    assert!(matches!(error.inner(), de::Error::InvalidMetadata));
+
+    let error = de::Config::new().read_metadata(&[0xff; 4]).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        de::Error::ExcessiveSize(len) if *len == u64::from(u32::MAX)
+    ));
+    Ok(())
+}
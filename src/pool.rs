@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+#[derive(Debug)]
+struct PoolState {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    max_buffers: usize,
+    max_capacity: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct BufferPool {
+    state: Arc<PoolState>,
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::with_limits(64, 1 << 20)
+    }
+}
+
+impl BufferPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limits(max_buffers: usize, max_capacity: usize) -> Self {
+        Self {
+            state: Arc::new(PoolState {
+                buffers: Mutex::new(Vec::new()),
+                max_buffers,
+                max_capacity,
+            }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffers().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn take(&self) -> Vec<u8> {
+        self.buffers().pop().unwrap_or_default()
+    }
+
+    pub fn recycle(&self, mut buffer: Vec<u8>) {
+        if buffer.capacity() == 0 || buffer.capacity() > self.state.max_capacity
+        {
+            return;
+        }
+        buffer.clear();
+        let mut buffers = self.buffers();
+        if buffers.len() < self.state.max_buffers {
+            buffers.push(buffer);
+        }
+    }
+
+    fn buffers(&self) -> MutexGuard<'_, Vec<Vec<u8>>> {
+        self.state.buffers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
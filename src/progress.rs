@@ -0,0 +1,165 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ProgressReport {
+    pub bytes: u64,
+    pub elements: u64,
+}
+
+struct Callback {
+    byte_interval: u64,
+    next_bytes: AtomicU64,
+    report: Box<dyn Fn(ProgressReport) + Send + Sync>,
+}
+
+impl fmt::Debug for Callback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Callback")
+            .field("byte_interval", &self.byte_interval)
+            .field("next_bytes", &self.next_bytes)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug)]
+struct ProgressState {
+    origin: Instant,
+    bytes: AtomicU64,
+    elements: AtomicU64,
+    depth: AtomicUsize,
+    last_io_nanos: AtomicU64,
+    last_codec_nanos: AtomicU64,
+    callback: Option<Callback>,
+}
+
+impl ProgressState {
+    fn new(callback: Option<Callback>) -> Self {
+        Self {
+            origin: Instant::now(),
+            bytes: AtomicU64::new(0),
+            elements: AtomicU64::new(0),
+            depth: AtomicUsize::new(0),
+            last_io_nanos: AtomicU64::new(0),
+            last_codec_nanos: AtomicU64::new(0),
+            callback,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Progress {
+    state: Arc<ProgressState>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self { state: Arc::new(ProgressState::new(None)) }
+    }
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_callback<F>(byte_interval: u64, callback: F) -> Self
+    where
+        F: Fn(ProgressReport) + Send + Sync + 'static,
+    {
+        let byte_interval = byte_interval.max(1);
+        let callback = Callback {
+            byte_interval,
+            next_bytes: AtomicU64::new(byte_interval),
+            report: Box::new(callback),
+        };
+        Self { state: Arc::new(ProgressState::new(Some(callback))) }
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.state.bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn elements(&self) -> u64 {
+        self.state.elements.load(Ordering::Relaxed)
+    }
+
+    pub fn report(&self) -> ProgressReport {
+        ProgressReport { bytes: self.bytes(), elements: self.elements() }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.state.depth.load(Ordering::Relaxed)
+    }
+
+    pub fn last_io_activity(&self) -> Option<Instant> {
+        self.load_instant(&self.state.last_io_nanos)
+    }
+
+    pub fn last_codec_activity(&self) -> Option<Instant> {
+        self.load_instant(&self.state.last_codec_nanos)
+    }
+
+    pub(crate) fn record_io(&self, byte_count: usize) {
+        self.state.bytes.fetch_add(byte_count as u64, Ordering::Relaxed);
+        self.store_now(&self.state.last_io_nanos);
+        self.notify();
+    }
+
+    pub(crate) fn record_element(&self) {
+        self.state.elements.fetch_add(1, Ordering::Relaxed);
+        self.record_codec();
+    }
+
+    pub(crate) fn record_codec(&self) {
+        self.store_now(&self.state.last_codec_nanos);
+    }
+
+    pub(crate) fn record_depth(&self, depth: usize) {
+        self.state.depth.store(depth, Ordering::Relaxed);
+        self.record_codec();
+    }
+
+    fn notify(&self) {
+        let Some(callback) = &self.state.callback else {
+            return;
+        };
+        let bytes = self.bytes();
+        let next_bytes = callback.next_bytes.load(Ordering::Relaxed);
+        if bytes < next_bytes {
+            return;
+        }
+        let following = bytes.saturating_add(callback.byte_interval);
+        if callback
+            .next_bytes
+            .compare_exchange(
+                next_bytes,
+                following,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        {
+            (callback.report)(self.report());
+        }
+    }
+
+    fn store_now(&self, slot: &AtomicU64) {
+        let elapsed = self.state.origin.elapsed().as_nanos();
+        let nanos = u64::try_from(elapsed).unwrap_or(u64::MAX - 1);
+        slot.store(nanos + 1, Ordering::Relaxed);
+    }
+
+    fn load_instant(&self, slot: &AtomicU64) -> Option<Instant> {
+        match slot.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(self.state.origin + Duration::from_nanos(nanos - 1)),
+        }
+    }
+}
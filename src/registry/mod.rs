@@ -0,0 +1,289 @@
+use std::{
+    any::{self, Any, TypeId},
+    borrow::Cow,
+    collections::HashMap,
+    fmt,
+};
+
+use serde::{
+    de::{self, DeserializeOwned, Visitor},
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+use crate::{de as decode, ser as encode};
+
+#[cfg(test)]
+mod test;
+
+type Decoder =
+    fn(&decode::Config, &[u8]) -> Result<Box<dyn Any + Send>, decode::Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Failed to encode tagged message")]
+    Ser(
+        #[from]
+        #[source]
+        encode::Error,
+    ),
+    #[error("Failed to decode tagged message")]
+    De(
+        #[from]
+        #[source]
+        decode::Error,
+    ),
+    #[error("Type {0} is not registered")]
+    UnregisteredType(&'static str),
+    #[error("No message type is registered under tag {0}")]
+    UnknownTag(Tag),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Tag {
+    Id(u32),
+    Name(Cow<'static, str>),
+}
+
+impl From<u32> for Tag {
+    fn from(id: u32) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl From<&'static str> for Tag {
+    fn from(name: &'static str) -> Self {
+        Self::Name(Cow::Borrowed(name))
+    }
+}
+
+impl From<String> for Tag {
+    fn from(name: String) -> Self {
+        Self::Name(Cow::Owned(name))
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "#{}", id),
+            Self::Name(name) => write!(f, "{:?}", name),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Message {
+    tag: Tag,
+    value: Box<dyn Any + Send>,
+}
+
+impl Message {
+    pub fn tag(&self) -> &Tag {
+        &self.tag
+    }
+
+    pub fn is<T>(&self) -> bool
+    where
+        T: Any,
+    {
+        self.value.is::<T>()
+    }
+
+    pub fn downcast_ref<T>(&self) -> Option<&T>
+    where
+        T: Any,
+    {
+        self.value.downcast_ref()
+    }
+
+    pub fn downcast<T>(self) -> Result<T, Self>
+    where
+        T: Any,
+    {
+        match self.value.downcast() {
+            Ok(value) => Ok(*value),
+            Err(value) => Err(Self { tag: self.tag, value }),
+        }
+    }
+
+    pub fn into_inner(self) -> Box<dyn Any + Send> {
+        self.value
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Registry {
+    tags: HashMap<TypeId, Tag>,
+    decoders: HashMap<Tag, Decoder>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<T>(&mut self, tag: impl Into<Tag>) -> &mut Self
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+    {
+        let tag = tag.into();
+        assert!(
+            !self.decoders.contains_key(&tag),
+            "tag {} is already registered",
+            tag
+        );
+        let previous = self.tags.insert(TypeId::of::<T>(), tag.clone());
+        assert!(
+            previous.is_none(),
+            "type {} is already registered",
+            any::type_name::<T>()
+        );
+        self.decoders.insert(tag, decode_boxed::<T>);
+        self
+    }
+
+    pub fn tag_of<T>(&self) -> Option<&Tag>
+    where
+        T: Any,
+    {
+        self.tags.get(&TypeId::of::<T>())
+    }
+
+    pub fn serialize_tagged<T>(
+        &self,
+        value: &T,
+        config: &encode::Config,
+    ) -> Result<Vec<u8>, Error>
+    where
+        T: Serialize + Any,
+    {
+        let frame = self.frame(value, config)?;
+        Ok(config.serialize_into_buffer(frame)?)
+    }
+
+    pub fn deserialize_tagged(
+        &self,
+        buf: &[u8],
+        config: &decode::Config,
+    ) -> Result<Message, Error> {
+        let (tag, body): (Tag, Body) = config.deserialize_buffer(buf)?;
+        self.dispatch(tag, &body.0, config)
+    }
+
+    pub async fn write_tagged<T, W>(
+        &self,
+        mut device: W,
+        value: &T,
+        config: &encode::Config,
+    ) -> Result<(), Error>
+    where
+        W: AsyncWrite + Unpin,
+        T: Serialize + Any,
+    {
+        let frame = self.frame(value, config)?;
+        config.serialize(&mut device, frame).await?;
+        device.flush().await.map_err(encode::Error::from)?;
+        Ok(())
+    }
+
+    pub async fn read_tagged<R>(
+        &self,
+        device: R,
+        config: &decode::Config,
+    ) -> Result<Message, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let (tag, body): (Tag, Body) = config.deserialize(device).await?;
+        self.dispatch(tag, &body.0, config)
+    }
+
+    fn frame<T>(
+        &self,
+        value: &T,
+        config: &encode::Config,
+    ) -> Result<(Tag, Body), Error>
+    where
+        T: Serialize + Any,
+    {
+        let tag = self
+            .tag_of::<T>()
+            .ok_or(Error::UnregisteredType(any::type_name::<T>()))?;
+        // The outer frame carries the header, so the body goes without one.
+        let body = config.headerless().serialize_into_buffer(value)?;
+        Ok((tag.clone(), Body(body)))
+    }
+
+    fn dispatch(
+        &self,
+        tag: Tag,
+        body: &[u8],
+        config: &decode::Config,
+    ) -> Result<Message, Error> {
+        let Some(decoder) = self.decoders.get(&tag) else {
+            return Err(Error::UnknownTag(tag));
+        };
+        let value = decoder(&config.headerless(), body)?;
+        Ok(Message { tag, value })
+    }
+}
+
+fn decode_boxed<T>(
+    config: &decode::Config,
+    body: &[u8],
+) -> Result<Box<dyn Any + Send>, decode::Error>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    Ok(Box::new(config.deserialize_buffer::<T>(body)?))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct Body(Vec<u8>);
+
+impl Serialize for Body {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+struct BodyVisitor;
+
+impl<'de> Visitor<'de> for BodyVisitor {
+    type Value = Body;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a tagged message body")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Body(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Body(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for Body {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_byte_buf(BodyVisitor)
+    }
+}
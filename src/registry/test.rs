@@ -0,0 +1,99 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Registry, Tag};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Login {
+    user: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Heartbeat(u64);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Command {
+    Stop,
+    Resize(u16, u16),
+}
+
+fn registry() -> Registry {
+    let mut registry = Registry::new();
+    registry
+        .register::<Login>(1)
+        .register::<Heartbeat>(2)
+        .register::<Command>("command");
+    registry
+}
+
+#[test]
+fn dispatches_by_tag() -> Result<()> {
+    let registry = registry();
+    let ser_config = crate::ser::Config::new();
+    let de_config = crate::de::Config::new();
+
+    let buf = registry.serialize_tagged(&Heartbeat(9), &ser_config)?;
+    let message = registry.deserialize_tagged(&buf, &de_config)?;
+    assert_eq!(message.tag(), &Tag::Id(2));
+    assert!(message.is::<Heartbeat>() && !message.is::<Login>());
+    assert_eq!(message.downcast::<Heartbeat>().ok(), Some(Heartbeat(9)));
+
+    let buf = registry.serialize_tagged(&Command::Resize(3, 4), &ser_config)?;
+    let message = registry.deserialize_tagged(&buf, &de_config)?;
+    assert_eq!(message.tag(), &Tag::from("command"));
+    assert_eq!(message.downcast_ref(), Some(&Command::Resize(3, 4)));
+
+    let error = registry.serialize_tagged(&7_u8, &ser_config).unwrap_err();
+    assert!(matches!(error, Error::UnregisteredType("u8")));
+    let mut other = Registry::new();
+    other.register::<Heartbeat>(3);
+    let buf = other.serialize_tagged(&Heartbeat(1), &ser_config)?;
+    let error = registry.deserialize_tagged(&buf, &de_config).unwrap_err();
+    assert!(matches!(error, Error::UnknownTag(Tag::Id(3))));
+    Ok(())
+}
+
+#[test]
+fn writes_the_header_once() -> Result<()> {
+    let registry = registry();
+    let fingerprint = crate::fingerprint::Fingerprint::from_u64(0xfeed_beef);
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_fingerprint(fingerprint);
+    let mut de_config = crate::de::Config::new();
+    de_config.with_fingerprint(fingerprint);
+
+    let buf = registry.serialize_tagged(&Heartbeat(5), &ser_config)?;
+    let header = fingerprint.to_le_bytes();
+    let count = buf.windows(header.len()).filter(|w| *w == header).count();
+    assert_eq!(count, 1);
+    let message = registry.deserialize_tagged(&buf, &de_config)?;
+    assert_eq!(message.downcast_ref(), Some(&Heartbeat(5)));
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "already registered")]
+fn rejects_duplicate_tags() {
+    registry().register::<String>("command");
+}
+
+#[tokio::test]
+async fn streams_heterogeneous_messages() -> Result<()> {
+    let registry = registry();
+    let ser_config = crate::ser::Config::new();
+    let de_config = crate::de::Config::new();
+    let (mut client, mut server) = tokio::io::duplex(1024);
+    let login = Login { user: "ana".to_owned() };
+    registry.write_tagged(&mut client, &login, &ser_config).await?;
+    registry.write_tagged(&mut client, &Command::Stop, &ser_config).await?;
+    registry.write_tagged(&mut client, &Heartbeat(1), &ser_config).await?;
+
+    let mut received = Vec::new();
+    for _ in 0 .. 3 {
+        received.push(registry.read_tagged(&mut server, &de_config).await?);
+    }
+    assert_eq!(received[0].downcast_ref(), Some(&login));
+    assert_eq!(received[1].downcast_ref(), Some(&Command::Stop));
+    assert_eq!(received[2].downcast_ref(), Some(&Heartbeat(1)));
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::{fmt, marker::PhantomData};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{size::EncodedSize, varint::IntEncoding};
+
+#[cfg(test)]
+mod test;
+
+const FIXED_NAME: &str = "$abcode::repr::fixed";
+const VAR_NAME: &str = "$abcode::repr::var";
+const RAW_NAME: &str = "$abcode::repr::raw";
+const UTF8_NAME: &str = "$abcode::repr::utf8";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Override {
+    IntEncoding(IntEncoding),
+    Untagged,
+    Utf8Chars,
+}
+
+pub(crate) fn override_for(name: &str) -> Option<Override> {
+    match name {
+        FIXED_NAME => Some(Override::IntEncoding(IntEncoding::Fixint)),
+        VAR_NAME => Some(Override::IntEncoding(IntEncoding::Varint)),
+        RAW_NAME => Some(Override::Untagged),
+        UTF8_NAME => Some(Override::Utf8Chars),
+        _ => None,
+    }
+}
+
+macro_rules! repr_wrapper {
+    ($($ty:ident => $name:ident),* $(,)?) => {
+        $(
+            #[derive(
+                Debug,
+                Clone,
+                Copy,
+                Default,
+                PartialEq,
+                Eq,
+                PartialOrd,
+                Ord,
+                Hash,
+            )]
+            pub struct $ty<T>(pub T);
+
+            impl<T> $ty<T> {
+                pub fn into_inner(self) -> T {
+                    self.0
+                }
+            }
+
+            impl<T> From<T> for $ty<T> {
+                fn from(value: T) -> Self {
+                    Self(value)
+                }
+            }
+
+            impl<T> Serialize for $ty<T>
+            where
+                T: Serialize,
+            {
+                fn serialize<S>(
+                    &self,
+                    serializer: S,
+                ) -> Result<S::Ok, S::Error>
+                where
+                    S: Serializer,
+                {
+                    serializer.serialize_newtype_struct($name, &self.0)
+                }
+            }
+
+            impl<'de, T> Deserialize<'de> for $ty<T>
+            where
+                T: Deserialize<'de>,
+            {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    deserializer
+                        .deserialize_newtype_struct(
+                            $name,
+                            ReprVisitor(PhantomData),
+                        )
+                        .map(Self)
+                }
+            }
+        )*
+    };
+}
+
+repr_wrapper! {
+    Fixed => FIXED_NAME,
+    Var => VAR_NAME,
+    Raw => RAW_NAME,
+    Utf8 => UTF8_NAME,
+}
+
+impl<T> EncodedSize for Fixed<T>
+where
+    T: EncodedSize,
+{
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const MAX_SIZE: Option<usize> = T::MAX_SIZE;
+}
+
+impl<T> EncodedSize for Raw<T>
+where
+    T: EncodedSize,
+{
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const MAX_SIZE: Option<usize> = T::MAX_SIZE;
+}
+
+struct ReprVisitor<T>(PhantomData<T>);
+
+impl<'de, T> de::Visitor<'de> for ReprVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a field with a forced representation")
+    }
+
+    fn visit_newtype_struct<D>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{Fixed, Raw, Var};
+use crate::{de, ser, size, varint::IntEncoding};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Record {
+    timestamp: Fixed<u64>,
+    sequence: u64,
+    digest: Raw<[u8; 4]>,
+    count: Var<u32>,
+}
+
+fn record() -> Record {
+    Record {
+        timestamp: Fixed(0x0102_0304_0506_0708),
+        sequence: 300,
+        digest: Raw([0xde, 0xad, 0xbe, 0xef]),
+        count: Var(5),
+    }
+}
+
+#[test]
+fn fixed_field_in_varint_record() -> Result<()> {
+    let mut ser_config = ser::Config::new();
+    ser_config.with_int_encoding(IntEncoding::Varint);
+    let buf = ser_config.serialize_into_buffer(record())?;
+    assert_eq!(&buf[.. 8], &0x0102_0304_0506_0708_u64.to_le_bytes());
+    assert_eq!(&buf[8 .. 11], &[251, 0x2c, 0x01]);
+    assert_eq!(&buf[11 ..], &[0xde, 0xad, 0xbe, 0xef, 5]);
+
+    let mut de_config = de::Config::new();
+    de_config.with_int_encoding(IntEncoding::Varint);
+    let decoded: Record = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, record());
+    Ok(())
+}
+
+#[test]
+fn var_field_in_fixint_record() -> Result<()> {
+    let buf = crate::serialize_into_buffer(record())?;
+    assert_eq!(buf.len(), 8 + 8 + 4 + 1);
+    assert_eq!(buf[20], 5);
+    let decoded: Record = crate::deserialize_buffer(&buf)?;
+    assert_eq!(decoded, record());
+    Ok(())
+}
+
+#[test]
+fn raw_field_in_self_describing_record() -> Result<()> {
+    let mut ser_config = ser::Config::new();
+    ser_config.with_self_describing();
+    let buf = ser_config.serialize_into_buffer(record())?;
+    let digest = [0xde, 0xad, 0xbe, 0xef];
+    assert!(buf.windows(digest.len()).any(|window| window == digest));
+
+    let mut de_config = de::Config::new();
+    de_config.with_self_describing();
+    let decoded: Record = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, record());
+    Ok(())
+}
+
+#[test]
+fn wrappers_are_transparent_elsewhere() {
+    assert_eq!(size::exact_size::<Fixed<u64>>(), Some(8));
+    assert_eq!(size::exact_size::<Raw<[u8; 4]>>(), Some(4));
+    assert_eq!(Fixed::from(3_u8).into_inner(), 3);
+}
@@ -0,0 +1,720 @@
+use std::{fmt, mem};
+
+use serde::{ser, Deserialize, Serialize};
+use thiserror::Error;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Conflicting layouts {0:?} and {1:?}")]
+    Conflict(Box<Schema>, Box<Schema>),
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl ser::Error for Error {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Self::Custom(msg.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Schema {
+    Unknown,
+    Unit,
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    F32,
+    F64,
+    Char,
+    Str,
+    Bytes,
+    Option(Box<Schema>),
+    Seq(Box<Schema>),
+    Map(Box<Schema>, Box<Schema>),
+    Tuple(Vec<Schema>),
+    UnitStruct(String),
+    NewtypeStruct(String, Box<Schema>),
+    TupleStruct(String, Vec<Schema>),
+    Struct(String, Vec<Field>),
+    Enum(String, Vec<Variant>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Field {
+    pub name: String,
+    pub schema: Schema,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Variant {
+    pub index: u32,
+    pub name: String,
+    pub payload: Payload,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Payload {
+    Unit,
+    Newtype(Box<Schema>),
+    Tuple(Vec<Schema>),
+    Struct(Vec<Field>),
+}
+
+impl Schema {
+    pub fn of<T>(sample: &T) -> Result<Self, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        sample.serialize(Tracer)
+    }
+
+    pub fn merge(self, other: Self) -> Result<Self, Error> {
+        let merged = match (self, other) {
+            (Self::Unknown, other) | (other, Self::Unknown) => other,
+            (Self::Option(left), Self::Option(right)) => {
+                Self::Option(Box::new(left.merge(*right)?))
+            },
+            (Self::Seq(left), Self::Seq(right)) => {
+                Self::Seq(Box::new(left.merge(*right)?))
+            },
+            (Self::Map(left_key, left), Self::Map(right_key, right)) => {
+                Self::Map(
+                    Box::new(left_key.merge(*right_key)?),
+                    Box::new(left.merge(*right)?),
+                )
+            },
+            (Self::Tuple(left), Self::Tuple(right))
+                if left.len() == right.len() =>
+            {
+                Self::Tuple(merge_all(left, right)?)
+            },
+            (
+                Self::NewtypeStruct(left_name, left),
+                Self::NewtypeStruct(right_name, right),
+            ) if left_name == right_name => {
+                Self::NewtypeStruct(left_name, Box::new(left.merge(*right)?))
+            },
+            (
+                Self::TupleStruct(left_name, left),
+                Self::TupleStruct(right_name, right),
+            ) if left_name == right_name && left.len() == right.len() => {
+                Self::TupleStruct(left_name, merge_all(left, right)?)
+            },
+            (
+                Self::Struct(left_name, left),
+                Self::Struct(right_name, right),
+            ) if left_name == right_name && same_names(&left, &right) => {
+                Self::Struct(left_name, merge_fields(left, right)?)
+            },
+            (Self::Enum(left_name, left), Self::Enum(right_name, right))
+                if left_name == right_name =>
+            {
+                let variants = merge_variants(&left_name, left, right)?;
+                Self::Enum(left_name, variants)
+            },
+            (left, right) if left == right => left,
+            (left, right) => {
+                Err(Error::Conflict(Box::new(left), Box::new(right)))?
+            },
+        };
+        Ok(merged)
+    }
+
+    pub fn width(&self) -> Option<usize> {
+        match self {
+            Self::Unit | Self::UnitStruct(_) => Some(0),
+            Self::Bool | Self::I8 | Self::U8 => Some(1),
+            Self::I16 | Self::U16 => Some(2),
+            Self::I32 | Self::U32 | Self::F32 | Self::Char => Some(4),
+            Self::I64 | Self::U64 | Self::F64 => Some(8),
+            Self::I128 | Self::U128 => Some(16),
+            Self::Unknown
+            | Self::Str
+            | Self::Bytes
+            | Self::Option(_)
+            | Self::Seq(_)
+            | Self::Map(..) => None,
+            Self::NewtypeStruct(_, inner) => inner.width(),
+            Self::Tuple(elements) | Self::TupleStruct(_, elements) => {
+                sum_widths(elements)
+            },
+            Self::Struct(_, fields) => fields_width(fields),
+            Self::Enum(_, variants) => {
+                let mut widths = variants.iter().map(Variant::width);
+                let first = widths.next()??;
+                match widths.all(|width| width == Some(first)) {
+                    true => Some(4 + first),
+                    false => None,
+                }
+            },
+        }
+    }
+}
+
+impl Variant {
+    fn width(&self) -> Option<usize> {
+        match &self.payload {
+            Payload::Unit => Some(0),
+            Payload::Newtype(inner) => inner.width(),
+            Payload::Tuple(elements) => sum_widths(elements),
+            Payload::Struct(fields) => fields_width(fields),
+        }
+    }
+}
+
+fn sum_widths(elements: &[Schema]) -> Option<usize> {
+    elements.iter().try_fold(0, |total, element| Some(total + element.width()?))
+}
+
+fn fields_width(fields: &[Field]) -> Option<usize> {
+    fields
+        .iter()
+        .try_fold(0, |total, field| Some(total + field.schema.width()?))
+}
+
+fn same_names(left: &[Field], right: &[Field]) -> bool {
+    left.len() == right.len()
+        && left.iter().zip(right).all(|(left, right)| left.name == right.name)
+}
+
+fn merge_all(
+    left: Vec<Schema>,
+    right: Vec<Schema>,
+) -> Result<Vec<Schema>, Error> {
+    left.into_iter().zip(right).map(|(left, right)| left.merge(right)).collect()
+}
+
+fn merge_fields(
+    left: Vec<Field>,
+    right: Vec<Field>,
+) -> Result<Vec<Field>, Error> {
+    left.into_iter()
+        .zip(right)
+        .map(|(left, right)| {
+            Ok(Field {
+                name: left.name,
+                schema: left.schema.merge(right.schema)?,
+            })
+        })
+        .collect()
+}
+
+fn merge_variant(
+    name: &str,
+    left: Variant,
+    right: Variant,
+) -> Result<Variant, Error> {
+    let payload = match (left.payload, right.payload) {
+        (Payload::Unit, Payload::Unit) => Payload::Unit,
+        (Payload::Newtype(left), Payload::Newtype(right)) => {
+            Payload::Newtype(Box::new(left.merge(*right)?))
+        },
+        (Payload::Tuple(left), Payload::Tuple(right))
+            if left.len() == right.len() =>
+        {
+            Payload::Tuple(merge_all(left, right)?)
+        },
+        (Payload::Struct(left), Payload::Struct(right))
+            if same_names(&left, &right) =>
+        {
+            Payload::Struct(merge_fields(left, right)?)
+        },
+        (left_payload, right_payload) => {
+            let left = Variant { payload: left_payload, ..left };
+            let right = Variant { payload: right_payload, ..right };
+            return Err(Error::Conflict(
+                Box::new(Schema::Enum(name.to_owned(), vec![left])),
+                Box::new(Schema::Enum(name.to_owned(), vec![right])),
+            ));
+        },
+    };
+    Ok(Variant { payload, ..left })
+}
+
+fn merge_variants(
+    name: &str,
+    left: Vec<Variant>,
+    right: Vec<Variant>,
+) -> Result<Vec<Variant>, Error> {
+    let mut merged = left;
+    for variant in right {
+        match merged.iter().position(|known| known.index == variant.index) {
+            Some(position) => {
+                let known = merged.remove(position);
+                merged.insert(position, merge_variant(name, known, variant)?);
+            },
+            None => merged.push(variant),
+        }
+    }
+    merged.sort_by_key(|variant| variant.index);
+    Ok(merged)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tracer;
+
+impl ser::Serializer for Tracer {
+    type Ok = Schema;
+    type Error = Error;
+    type SerializeSeq = SeqTracer;
+    type SerializeTuple = TupleTracer;
+    type SerializeTupleStruct = TupleTracer;
+    type SerializeTupleVariant = TupleTracer;
+    type SerializeMap = MapTracer;
+    type SerializeStruct = StructTracer;
+    type SerializeStructVariant = StructTracer;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Bool)
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::I8)
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::I16)
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::I32)
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::I64)
+    }
+
+    fn serialize_i128(self, _v: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::I128)
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::U8)
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::U16)
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::U32)
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::U64)
+    }
+
+    fn serialize_u128(self, _v: u128) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::U128)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::F32)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::F64)
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Char)
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Str)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Bytes)
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Option(Box::new(Schema::Unknown)))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(Schema::Option(Box::new(value.serialize(self)?)))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Unit)
+    }
+
+    fn serialize_unit_struct(
+        self,
+        name: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::UnitStruct(name.to_owned()))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Enum(
+            name.to_owned(),
+            vec![Variant {
+                index: variant_index,
+                name: variant.to_owned(),
+                payload: Payload::Unit,
+            }],
+        ))
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let inner = value.serialize(self)?;
+        Ok(Schema::NewtypeStruct(name.to_owned(), Box::new(inner)))
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let inner = value.serialize(self)?;
+        Ok(Schema::Enum(
+            name.to_owned(),
+            vec![Variant {
+                index: variant_index,
+                name: variant.to_owned(),
+                payload: Payload::Newtype(Box::new(inner)),
+            }],
+        ))
+    }
+
+    fn serialize_seq(
+        self,
+        _len: Option<usize>,
+    ) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SeqTracer { element: Schema::Unknown })
+    }
+
+    fn serialize_tuple(
+        self,
+        len: usize,
+    ) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(TupleTracer::new(TupleKind::Tuple, len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Ok(TupleTracer::new(TupleKind::Struct(name), len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        let kind = TupleKind::Variant(name, variant_index, variant);
+        Ok(TupleTracer::new(kind, len))
+    }
+
+    fn serialize_map(
+        self,
+        _len: Option<usize>,
+    ) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(MapTracer { key: Schema::Unknown, value: Schema::Unknown })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(StructTracer::new(StructKind::Struct(name), len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        let kind = StructKind::Variant(name, variant_index, variant);
+        Ok(StructTracer::new(kind, len))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug)]
+struct SeqTracer {
+    element: Schema,
+}
+
+impl ser::SerializeSeq for SeqTracer {
+    type Ok = Schema;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let element = value.serialize(Tracer)?;
+        let known = mem::replace(&mut self.element, Schema::Unknown);
+        self.element = known.merge(element)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Seq(Box::new(self.element)))
+    }
+}
+
+#[derive(Debug)]
+struct MapTracer {
+    key: Schema,
+    value: Schema,
+}
+
+impl ser::SerializeMap for MapTracer {
+    type Ok = Schema;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = key.serialize(Tracer)?;
+        let known = mem::replace(&mut self.key, Schema::Unknown);
+        self.key = known.merge(key)?;
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = value.serialize(Tracer)?;
+        let known = mem::replace(&mut self.value, Schema::Unknown);
+        self.value = known.merge(value)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Schema::Map(Box::new(self.key), Box::new(self.value)))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TupleKind {
+    Tuple,
+    Struct(&'static str),
+    Variant(&'static str, u32, &'static str),
+}
+
+#[derive(Debug)]
+struct TupleTracer {
+    kind: TupleKind,
+    elements: Vec<Schema>,
+}
+
+impl TupleTracer {
+    fn new(kind: TupleKind, len: usize) -> Self {
+        Self { kind, elements: Vec::with_capacity(len) }
+    }
+
+    fn push<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.elements.push(value.serialize(Tracer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Schema {
+        match self.kind {
+            TupleKind::Tuple => Schema::Tuple(self.elements),
+            TupleKind::Struct(name) => {
+                Schema::TupleStruct(name.to_owned(), self.elements)
+            },
+            TupleKind::Variant(name, index, variant) => Schema::Enum(
+                name.to_owned(),
+                vec![Variant {
+                    index,
+                    name: variant.to_owned(),
+                    payload: Payload::Tuple(self.elements),
+                }],
+            ),
+        }
+    }
+}
+
+impl ser::SerializeTuple for TupleTracer {
+    type Ok = Schema;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for TupleTracer {
+    type Ok = Schema;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for TupleTracer {
+    type Ok = Schema;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.finish())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StructKind {
+    Struct(&'static str),
+    Variant(&'static str, u32, &'static str),
+}
+
+#[derive(Debug)]
+struct StructTracer {
+    kind: StructKind,
+    fields: Vec<Field>,
+}
+
+impl StructTracer {
+    fn new(kind: StructKind, len: usize) -> Self {
+        Self { kind, fields: Vec::with_capacity(len) }
+    }
+
+    fn push(&mut self, key: &'static str, schema: Schema) {
+        self.fields.push(Field { name: key.to_owned(), schema });
+    }
+
+    fn finish(self) -> Schema {
+        match self.kind {
+            StructKind::Struct(name) => {
+                Schema::Struct(name.to_owned(), self.fields)
+            },
+            StructKind::Variant(name, index, variant) => Schema::Enum(
+                name.to_owned(),
+                vec![Variant {
+                    index,
+                    name: variant.to_owned(),
+                    payload: Payload::Struct(self.fields),
+                }],
+            ),
+        }
+    }
+}
+
+impl ser::SerializeStruct for StructTracer {
+    type Ok = Schema;
+    type Error = Error;
+
+    fn serialize_field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(key, value.serialize(Tracer)?);
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.push(key, Schema::Unknown);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for StructTracer {
+    type Ok = Schema;
+    type Error = Error;
+
+    fn serialize_field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.push(key, value.serialize(Tracer)?);
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.push(key, Schema::Unknown);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(self.finish())
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{Error, Field, Payload, Schema, Variant};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Event {
+    Ping,
+    Move(i16, i16),
+    Rename { to: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Point(u8, u8);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Log {
+    origin: Point,
+    parent: Option<u64>,
+    events: Vec<Event>,
+}
+
+fn field(name: &str, schema: Schema) -> Field {
+    Field { name: name.to_owned(), schema }
+}
+
+fn variant(index: u32, name: &str, payload: Payload) -> Variant {
+    Variant { index, name: name.to_owned(), payload }
+}
+
+#[test]
+fn schema_of_sample() -> Result<()> {
+    let log = Log {
+        origin: Point(1, 2),
+        parent: Some(3),
+        events: vec![
+            Event::Rename { to: "x".to_owned() },
+            Event::Ping,
+            Event::Move(4, 5),
+            Event::Ping,
+        ],
+    };
+    let expected = Schema::Struct(
+        "Log".to_owned(),
+        vec![
+            field(
+                "origin",
+                Schema::TupleStruct(
+                    "Point".to_owned(),
+                    vec![Schema::U8, Schema::U8],
+                ),
+            ),
+            field("parent", Schema::Option(Box::new(Schema::U64))),
+            field(
+                "events",
+                Schema::Seq(Box::new(Schema::Enum(
+                    "Event".to_owned(),
+                    vec![
+                        variant(0, "Ping", Payload::Unit),
+                        variant(
+                            1,
+                            "Move",
+                            Payload::Tuple(vec![Schema::I16, Schema::I16]),
+                        ),
+                        variant(
+                            2,
+                            "Rename",
+                            Payload::Struct(vec![field("to", Schema::Str)]),
+                        ),
+                    ],
+                ))),
+            ),
+        ],
+    );
+    assert_eq!(Schema::of(&log)?, expected);
+    Ok(())
+}
+
+#[test]
+fn schema_merges_samples() -> Result<()> {
+    let sparse =
+        Log { origin: Point(0, 0), parent: None, events: vec![Event::Ping] };
+    let dense = Log {
+        origin: Point(0, 0),
+        parent: Some(1),
+        events: vec![Event::Move(1, 1)],
+    };
+
+    let schema = Schema::of(&sparse)?;
+    let Schema::Struct(_, fields) = &schema else { panic!("not a struct") };
+    assert_eq!(fields[1].schema, Schema::Option(Box::new(Schema::Unknown)));
+
+    let merged = schema.merge(Schema::of(&dense)?)?;
+    let Schema::Struct(_, fields) = &merged else { panic!("not a struct") };
+    assert_eq!(fields[1].schema, Schema::Option(Box::new(Schema::U64)));
+    let Schema::Seq(events) = &fields[2].schema else { panic!("not a seq") };
+    assert!(
+        matches!(&**events, Schema::Enum(_, variants) if variants.len() == 2)
+    );
+    Ok(())
+}
+
+#[test]
+fn schema_conflicts() -> Result<()> {
+    let error = Schema::U8.merge(Schema::U16).unwrap_err();
+    assert!(matches!(error, Error::Conflict(..)));
+    let error = Schema::of(&1_u8)?.merge(Schema::Str).unwrap_err();
+    assert!(matches!(error, Error::Conflict(left, _) if *left == Schema::U8));
+    Ok(())
+}
+
+#[test]
+fn schema_widths() -> Result<()> {
+    assert_eq!(Schema::of(&Point(1, 2))?.width(), Some(2));
+    assert_eq!(Schema::of(&(1_u32, 'x', 2.0_f64))?.width(), Some(16));
+    assert_eq!(Schema::of(&Some(1_u8))?.width(), None);
+    assert_eq!(Schema::of(&"text")?.width(), None);
+    assert_eq!(Schema::of(&Event::Move(1, 2))?.width(), Some(8));
+
+    let schema = Schema::of(&vec![Event::Ping, Event::Move(1, 2)])?;
+    let Schema::Seq(events) = schema else { panic!("not a seq") };
+    assert_eq!(events.width(), None);
+    Ok(())
+}
+
+#[test]
+fn schema_is_serializable() -> Result<()> {
+    let log = Log { origin: Point(0, 0), parent: None, events: vec![] };
+    let schema = Schema::of(&log)?;
+    let buf = crate::serialize_into_buffer(&schema)?;
+    let decoded: Schema = crate::deserialize_buffer(&buf)?;
+    assert_eq!(decoded, schema);
+    Ok(())
+}
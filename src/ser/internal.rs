@@ -1,10 +1,38 @@
+use std::{
+    collections::HashMap,
+    io::{SeekFrom, Write},
+    mem,
+    sync::Arc,
+    vec,
+};
+#[cfg(feature = "stream")]
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(feature = "bytes")]
+use bytes::{BufMut, BytesMut};
+#[cfg(feature = "stream")]
+use futures_sink::Sink;
 use serde::Serialize;
+use smallvec::{Array, SmallVec};
 use tokio::{
-    io::{self, AsyncWrite, AsyncWriteExt},
+    io::{self, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt},
     sync::mpsc,
 };
 
 use super::Error;
+use crate::{
+    compress::Compression,
+    dump::{Annotator, Label},
+    pool::BufferPool,
+    progress::Progress,
+    repr::{self, Override},
+    tag::Tag,
+    varint::{self, IntEncoding},
+};
 
 pub trait SerializationSink {
     fn send_raw_data(&mut self, data: &[u8]) -> Result<(), Error>;
@@ -15,6 +43,10 @@ pub trait SerializationSink {
 
     fn end_var_sized(&mut self) -> Result<(), Error>;
 
+    fn position(&self) -> Option<usize> {
+        None
+    }
+
     fn send_bool(&mut self, value: bool) -> Result<(), Error> {
         self.send_u8(u8::from(value))
     }
@@ -94,12 +126,24 @@ pub trait SerializationSink {
     }
 }
 
+/// Writes the bytes produced by a [`ChannelSink`] to an async device.
+///
+/// [`ChannelBackend::run`] must be polled concurrently with the serializer
+/// and completes once every sender feeding its channel has been dropped.
 #[derive(Debug)]
 pub struct ChannelBackend<W> {
     device: W,
     buf: Vec<u8>,
     buf_limit: usize,
     receiver: mpsc::Receiver<u8>,
+    progress: Option<Progress>,
+    header: Vec<u8>,
+    compression: Compression,
+    write_buffer: Option<usize>,
+    pending: Vec<u8>,
+    flush_at_boundary: bool,
+    shutdown_at_end: bool,
+    pool: Option<BufferPool>,
 }
 
 impl<W> ChannelBackend<W>
@@ -111,23 +155,190 @@ where
         buf_limit: usize,
         receiver: mpsc::Receiver<u8>,
     ) -> Self {
-        Self { device, buf: Vec::with_capacity(buf_limit), buf_limit, receiver }
+        Self {
+            device,
+            buf: Vec::with_capacity(buf_limit),
+            buf_limit,
+            receiver,
+            progress: None,
+            header: Vec::new(),
+            compression: Compression::None,
+            write_buffer: None,
+            pending: Vec::new(),
+            flush_at_boundary: false,
+            shutdown_at_end: false,
+            pool: None,
+        }
+    }
+
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    pub fn set_header(&mut self, header: Vec<u8>) {
+        self.header = header;
+    }
+
+    pub fn set_buffer_pool(&mut self, pool: Option<BufferPool>) {
+        if let Some(pool) = &pool {
+            self.buf = pool.take();
+            self.buf.reserve(self.buf_limit);
+        }
+        self.pool = pool;
+    }
+
+    pub fn set_compression(&mut self, compression: Compression) {
+        self.compression = compression;
+    }
+
+    pub fn set_write_buffer(&mut self, byte_count: Option<usize>) {
+        self.write_buffer = byte_count;
+        if let Some(byte_count) = byte_count {
+            self.pending.reserve(byte_count);
+        }
+    }
+
+    pub fn set_flush_at_boundary(&mut self, on: bool) {
+        self.flush_at_boundary = on;
+    }
+
+    pub fn set_shutdown_at_end(&mut self, on: bool) {
+        self.shutdown_at_end = on;
     }
 
     pub async fn run(mut self) -> io::Result<()> {
+        let header = mem::take(&mut self.header);
+        self.queue(&header).await?;
+        let encoded =
+            self.pool.as_ref().map_or_else(Vec::new, BufferPool::take);
+        let mut encoder = self.compression.encoder(encoded)?;
         while self.receiver.recv_many(&mut self.buf, self.buf_limit).await > 0 {
-            self.device.write_all(&self.buf[..]).await?;
+            encoder.write_all(&self.buf[..])?;
             self.buf.clear();
+            let encoded = encoder.get_mut();
+            self.queue(encoded).await?;
+            encoded.clear();
+        }
+        let encoded = encoder.finish()?;
+        self.queue(&encoded).await?;
+        self.flush_pending().await?;
+        if self.flush_at_boundary {
+            self.device.flush().await?;
+        }
+        if self.shutdown_at_end {
+            self.device.shutdown().await?;
+        }
+        if let Some(pool) = &self.pool {
+            pool.recycle(mem::take(&mut self.buf));
+            pool.recycle(encoded);
+        }
+        Ok(())
+    }
+
+    async fn queue(&mut self, data: &[u8]) -> io::Result<()> {
+        match self.write_buffer {
+            Some(byte_count) => {
+                self.pending.extend_from_slice(data);
+                if self.pending.len() >= byte_count {
+                    self.flush_pending().await?;
+                }
+                Ok(())
+            },
+            None => self.write_out(data).await,
+        }
+    }
+
+    async fn flush_pending(&mut self) -> io::Result<()> {
+        let mut pending = mem::take(&mut self.pending);
+        self.write_out(&pending).await?;
+        pending.clear();
+        self.pending = pending;
+        Ok(())
+    }
+
+    async fn write_out(&mut self, data: &[u8]) -> io::Result<()> {
+        if !data.is_empty() {
+            self.device.write_all(data).await?;
+            if let Some(progress) = &self.progress {
+                progress.record_io(data.len());
+            }
         }
         Ok(())
     }
 }
 
+#[cfg(feature = "stream")]
+pub struct SinkWriter<S, I> {
+    sink: Pin<Box<S>>,
+    _item: PhantomData<fn(I)>,
+}
+
+#[cfg(feature = "stream")]
+impl<S, I> SinkWriter<S, I> {
+    pub fn new(sink: S) -> Self {
+        Self { sink: Box::pin(sink), _item: PhantomData }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S, I> std::fmt::Debug for SinkWriter<S, I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SinkWriter").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<S, I> AsyncWrite for SinkWriter<S, I>
+where
+    S: Sink<I>,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    I: From<Vec<u8>>,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        match this.sink.as_mut().poll_ready(cx) {
+            Poll::Ready(Ok(())) => (),
+            Poll::Ready(Err(error)) => {
+                return Poll::Ready(Err(io::Error::other(error)));
+            },
+            Poll::Pending => return Poll::Pending,
+        }
+        match this.sink.as_mut().start_send(I::from(buf.to_vec())) {
+            Ok(()) => Poll::Ready(Ok(buf.len())),
+            Err(error) => Poll::Ready(Err(io::Error::other(error))),
+        }
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<io::Result<()>> {
+        self.get_mut().sink.as_mut().poll_flush(cx).map_err(io::Error::other)
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<io::Result<()>> {
+        self.get_mut().sink.as_mut().poll_close(cx).map_err(io::Error::other)
+    }
+}
+
+/// Sink that forwards serialized bytes to a [`ChannelBackend`].
+///
+/// Sending blocks the current thread, so a serializer writing to this sink
+/// must run outside of the async runtime (e.g. on a dedicated thread).
 #[derive(Debug, Clone)]
 pub struct ChannelSink {
     sender: mpsc::Sender<u8>,
     fallback_buffer: BufferSink,
-    multiplexing: ChannelSinkMultiplexing,
+    multiplexing: SinkMultiplexing,
+    lengths: vec::IntoIter<usize>,
+    pool: Option<BufferPool>,
 }
 
 impl ChannelSink {
@@ -135,7 +346,28 @@ impl ChannelSink {
         Self {
             sender,
             fallback_buffer: BufferSink::new(),
-            multiplexing: ChannelSinkMultiplexing::Channel,
+            multiplexing: SinkMultiplexing::Channel,
+            lengths: Vec::new().into_iter(),
+            pool: None,
+        }
+    }
+
+    pub fn set_lengths(&mut self, lengths: Vec<usize>) {
+        self.lengths = lengths.into_iter();
+    }
+
+    pub fn set_buffer_pool(&mut self, pool: Option<BufferPool>) {
+        if let Some(pool) = &pool {
+            self.fallback_buffer = BufferSink::with_buffer(pool.take());
+        }
+        self.pool = pool;
+    }
+}
+
+impl Drop for ChannelSink {
+    fn drop(&mut self) {
+        if let Some(pool) = &self.pool {
+            pool.recycle(mem::take(&mut self.fallback_buffer).into_inner());
         }
     }
 }
@@ -143,7 +375,7 @@ impl ChannelSink {
 impl SerializationSink for ChannelSink {
     fn send_raw_data(&mut self, data: &[u8]) -> Result<(), Error> {
         match self.multiplexing {
-            ChannelSinkMultiplexing::Channel => {
+            SinkMultiplexing::Channel => {
                 for element in data {
                     self.sender
                         .blocking_send(*element)
@@ -151,7 +383,7 @@ impl SerializationSink for ChannelSink {
                 }
             },
 
-            ChannelSinkMultiplexing::Buffer { .. } => {
+            SinkMultiplexing::Buffer { .. } => {
                 self.fallback_buffer.send_raw_data(data)?
             },
         }
@@ -161,19 +393,21 @@ impl SerializationSink for ChannelSink {
 
     fn start_var_sized(&mut self, size: Option<usize>) -> Result<(), Error> {
         match self.multiplexing {
-            ChannelSinkMultiplexing::Channel => match size {
-                Some(known_len) => self.send_usize(known_len)?,
-                None => {
-                    self.multiplexing = ChannelSinkMultiplexing::Buffer {
-                        outer_seq_size: 0,
-                        inner_seqs: 0,
-                    };
-                },
+            SinkMultiplexing::Channel => {
+                match size.or_else(|| self.lengths.next()) {
+                    Some(known_len) => self.send_usize(known_len)?,
+                    None => {
+                        self.multiplexing = SinkMultiplexing::Buffer {
+                            outer_seq_size: 0,
+                            inner_seqs: 0,
+                        };
+                    },
+                }
             },
 
-            ChannelSinkMultiplexing::Buffer { outer_seq_size, inner_seqs } => {
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs } => {
                 self.fallback_buffer.start_var_sized(size)?;
-                self.multiplexing = ChannelSinkMultiplexing::Buffer {
+                self.multiplexing = SinkMultiplexing::Buffer {
                     outer_seq_size,
                     inner_seqs: inner_seqs + 1,
                 };
@@ -185,12 +419,10 @@ impl SerializationSink for ChannelSink {
 
     fn end_var_sized(&mut self) -> Result<(), Error> {
         match self.multiplexing {
-            ChannelSinkMultiplexing::Channel => (),
+            SinkMultiplexing::Channel => (),
 
-            ChannelSinkMultiplexing::Buffer {
-                outer_seq_size,
-                inner_seqs: 0,
-            } => {
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs: 0 } => {
+                self.multiplexing = SinkMultiplexing::Channel;
                 self.send_usize(outer_seq_size)?;
                 for byte in self.fallback_buffer.as_slice() {
                     self.sender
@@ -200,207 +432,1512 @@ impl SerializationSink for ChannelSink {
                 self.fallback_buffer.clear();
             },
 
-            ChannelSinkMultiplexing::Buffer { outer_seq_size, inner_seqs } => {
-                self.fallback_buffer.end_var_sized()?;
-                self.multiplexing = ChannelSinkMultiplexing::Buffer {
-                    outer_seq_size,
-                    inner_seqs: inner_seqs - 1,
-                };
-            },
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs } => {
+                self.fallback_buffer.end_var_sized()?;
+                self.multiplexing = SinkMultiplexing::Buffer {
+                    outer_seq_size,
+                    inner_seqs: inner_seqs - 1,
+                };
+            },
+        }
+
+        Ok(())
+    }
+
+    fn advance_var_sized(&mut self) -> Result<(), Error> {
+        match self.multiplexing {
+            SinkMultiplexing::Channel => (),
+
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs: 0 } => {
+                self.multiplexing = SinkMultiplexing::Buffer {
+                    outer_seq_size: outer_seq_size + 1,
+                    inner_seqs: 0,
+                };
+            },
+
+            SinkMultiplexing::Buffer { .. } => {
+                self.fallback_buffer.advance_var_sized()?
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CountingSink {
+    lengths: Vec<usize>,
+    seqs: Vec<Option<usize>>,
+}
+
+impl CountingSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_lengths(self) -> Vec<usize> {
+        self.lengths
+    }
+}
+
+impl SerializationSink for CountingSink {
+    fn send_raw_data(&mut self, _data: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn start_var_sized(&mut self, size: Option<usize>) -> Result<(), Error> {
+        match size {
+            Some(_) => self.seqs.push(None),
+            None => {
+                self.seqs.push(Some(self.lengths.len()));
+                self.lengths.push(0);
+            },
+        }
+        Ok(())
+    }
+
+    fn end_var_sized(&mut self) -> Result<(), Error> {
+        self.seqs.pop();
+        Ok(())
+    }
+
+    fn advance_var_sized(&mut self) -> Result<(), Error> {
+        if let Some(Some(index)) = self.seqs.last() {
+            self.lengths[*index] += 1;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum SeekChunk {
+    Data(Vec<u8>),
+    Patch { offset: u64, len: u64 },
+}
+
+#[derive(Debug)]
+pub struct SeekBackend<W> {
+    device: W,
+    receiver: mpsc::Receiver<SeekChunk>,
+    progress: Option<Progress>,
+    header: Vec<u8>,
+    write_buffer: Option<usize>,
+    pending: Vec<u8>,
+    flush_at_boundary: bool,
+    shutdown_at_end: bool,
+    base: u64,
+    flushed: u64,
+}
+
+impl<W> SeekBackend<W>
+where
+    W: AsyncWrite + AsyncSeek + Unpin,
+{
+    pub fn new(device: W, receiver: mpsc::Receiver<SeekChunk>) -> Self {
+        Self {
+            device,
+            receiver,
+            progress: None,
+            header: Vec::new(),
+            write_buffer: None,
+            pending: Vec::new(),
+            flush_at_boundary: false,
+            shutdown_at_end: false,
+            base: 0,
+            flushed: 0,
+        }
+    }
+
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    pub fn set_header(&mut self, header: Vec<u8>) {
+        self.header = header;
+    }
+
+    pub fn set_write_buffer(&mut self, byte_count: Option<usize>) {
+        self.write_buffer = byte_count;
+        if let Some(byte_count) = byte_count {
+            self.pending.reserve(byte_count);
+        }
+    }
+
+    pub fn set_flush_at_boundary(&mut self, on: bool) {
+        self.flush_at_boundary = on;
+    }
+
+    pub fn set_shutdown_at_end(&mut self, on: bool) {
+        self.shutdown_at_end = on;
+    }
+
+    pub async fn run(mut self) -> io::Result<()> {
+        self.base = self.device.stream_position().await?;
+        let header = mem::take(&mut self.header);
+        let body_start = header.len() as u64;
+        self.queue(&header).await?;
+        while let Some(chunk) = self.receiver.recv().await {
+            match chunk {
+                SeekChunk::Data(data) => self.queue(&data).await?,
+                SeekChunk::Patch { offset, len } => {
+                    self.patch(body_start + offset, len).await?
+                },
+            }
+        }
+        self.flush_pending().await?;
+        if self.flush_at_boundary {
+            self.device.flush().await?;
+        }
+        if self.shutdown_at_end {
+            self.device.shutdown().await?;
+        }
+        Ok(())
+    }
+
+    async fn patch(&mut self, position: u64, len: u64) -> io::Result<()> {
+        let bytes = len.to_le_bytes();
+        if position >= self.flushed {
+            let start = (position - self.flushed) as usize;
+            self.pending[start .. start + bytes.len()].copy_from_slice(&bytes);
+        } else {
+            self.device.seek(SeekFrom::Start(self.base + position)).await?;
+            self.device.write_all(&bytes).await?;
+            self.device.seek(SeekFrom::Start(self.base + self.flushed)).await?;
+        }
+        Ok(())
+    }
+
+    async fn queue(&mut self, data: &[u8]) -> io::Result<()> {
+        match self.write_buffer {
+            Some(byte_count) => {
+                self.pending.extend_from_slice(data);
+                if self.pending.len() >= byte_count {
+                    self.flush_pending().await?;
+                }
+                Ok(())
+            },
+            None => self.write_out(data).await,
+        }
+    }
+
+    async fn flush_pending(&mut self) -> io::Result<()> {
+        let mut pending = mem::take(&mut self.pending);
+        self.write_out(&pending).await?;
+        pending.clear();
+        self.pending = pending;
+        Ok(())
+    }
+
+    async fn write_out(&mut self, data: &[u8]) -> io::Result<()> {
+        if !data.is_empty() {
+            self.device.write_all(data).await?;
+            self.flushed += data.len() as u64;
+            if let Some(progress) = &self.progress {
+                progress.record_io(data.len());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SeekSink {
+    sender: mpsc::Sender<SeekChunk>,
+    batch: Vec<u8>,
+    batch_limit: usize,
+    sent: u64,
+    seqs: Vec<Option<(u64, usize)>>,
+}
+
+impl SeekSink {
+    pub fn new(sender: mpsc::Sender<SeekChunk>, batch_limit: usize) -> Self {
+        Self {
+            sender,
+            batch: Vec::with_capacity(batch_limit),
+            batch_limit,
+            sent: 0,
+            seqs: Vec::new(),
+        }
+    }
+
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.send_batch()
+    }
+
+    fn send_batch(&mut self) -> Result<(), Error> {
+        if !self.batch.is_empty() {
+            let batch = mem::replace(
+                &mut self.batch,
+                Vec::with_capacity(self.batch_limit),
+            );
+            self.sent += batch.len() as u64;
+            self.send_chunk(SeekChunk::Data(batch))?;
+        }
+        Ok(())
+    }
+
+    fn send_chunk(&mut self, chunk: SeekChunk) -> Result<(), Error> {
+        self.sender.blocking_send(chunk).map_err(|_| Error::Disconnected)
+    }
+}
+
+impl SerializationSink for SeekSink {
+    fn send_raw_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.batch.extend_from_slice(data);
+        if self.batch.len() >= self.batch_limit {
+            self.send_batch()?;
+        }
+        Ok(())
+    }
+
+    fn start_var_sized(&mut self, size: Option<usize>) -> Result<(), Error> {
+        match size {
+            Some(known_len) => {
+                self.send_usize(known_len)?;
+                self.seqs.push(None);
+            },
+            None => {
+                let offset = self.sent + self.batch.len() as u64;
+                self.send_usize(0)?;
+                self.seqs.push(Some((offset, 0)));
+            },
+        }
+        Ok(())
+    }
+
+    fn end_var_sized(&mut self) -> Result<(), Error> {
+        if let Some(Some((offset, seq_size))) = self.seqs.pop() {
+            let len = u64::try_from(seq_size)
+                .map_err(|_| Error::ExcessiveSize(seq_size))?;
+            if offset >= self.sent {
+                let start = (offset - self.sent) as usize;
+                self.batch[start .. start + mem::size_of::<u64>()]
+                    .copy_from_slice(&len.to_le_bytes());
+            } else {
+                self.send_chunk(SeekChunk::Patch { offset, len })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn advance_var_sized(&mut self) -> Result<(), Error> {
+        if let Some(Some((_, seq_size))) = self.seqs.last_mut() {
+            *seq_size += 1;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WriterSink<W> {
+    writer: W,
+    fallback_buffer: BufferSink,
+    multiplexing: SinkMultiplexing,
+    lengths: vec::IntoIter<usize>,
+}
+
+impl<W> WriterSink<W>
+where
+    W: Write,
+{
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            fallback_buffer: BufferSink::new(),
+            multiplexing: SinkMultiplexing::Channel,
+            lengths: Vec::new().into_iter(),
+        }
+    }
+
+    pub fn set_lengths(&mut self, lengths: Vec<usize>) {
+        self.lengths = lengths.into_iter();
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W> SerializationSink for WriterSink<W>
+where
+    W: Write,
+{
+    fn send_raw_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        match self.multiplexing {
+            SinkMultiplexing::Channel => self.writer.write_all(data)?,
+            SinkMultiplexing::Buffer { .. } => {
+                self.fallback_buffer.send_raw_data(data)?
+            },
+        }
+        Ok(())
+    }
+
+    fn start_var_sized(&mut self, size: Option<usize>) -> Result<(), Error> {
+        match self.multiplexing {
+            SinkMultiplexing::Channel => {
+                match size.or_else(|| self.lengths.next()) {
+                    Some(known_len) => self.send_usize(known_len)?,
+                    None => {
+                        self.multiplexing = SinkMultiplexing::Buffer {
+                            outer_seq_size: 0,
+                            inner_seqs: 0,
+                        };
+                    },
+                }
+            },
+
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs } => {
+                self.fallback_buffer.start_var_sized(size)?;
+                self.multiplexing = SinkMultiplexing::Buffer {
+                    outer_seq_size,
+                    inner_seqs: inner_seqs + 1,
+                };
+            },
+        }
+
+        Ok(())
+    }
+
+    fn end_var_sized(&mut self) -> Result<(), Error> {
+        match self.multiplexing {
+            SinkMultiplexing::Channel => (),
+
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs: 0 } => {
+                self.multiplexing = SinkMultiplexing::Channel;
+                self.send_usize(outer_seq_size)?;
+                self.writer.write_all(self.fallback_buffer.as_slice())?;
+                self.fallback_buffer.clear();
+            },
+
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs } => {
+                self.fallback_buffer.end_var_sized()?;
+                self.multiplexing = SinkMultiplexing::Buffer {
+                    outer_seq_size,
+                    inner_seqs: inner_seqs - 1,
+                };
+            },
+        }
+
+        Ok(())
+    }
+
+    fn advance_var_sized(&mut self) -> Result<(), Error> {
+        match self.multiplexing {
+            SinkMultiplexing::Channel => (),
+
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs: 0 } => {
+                self.multiplexing = SinkMultiplexing::Buffer {
+                    outer_seq_size: outer_seq_size + 1,
+                    inner_seqs: 0,
+                };
+            },
+
+            SinkMultiplexing::Buffer { .. } => {
+                self.fallback_buffer.advance_var_sized()?
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bytes")]
+#[derive(Debug, Clone)]
+pub struct BufMutSink<B> {
+    buf: B,
+    fallback_buffer: BufferSink,
+    multiplexing: SinkMultiplexing,
+}
+
+#[cfg(feature = "bytes")]
+impl<B> BufMutSink<B>
+where
+    B: BufMut,
+{
+    pub fn new(buf: B) -> Self {
+        Self {
+            buf,
+            fallback_buffer: BufferSink::new(),
+            multiplexing: SinkMultiplexing::Channel,
+        }
+    }
+
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+
+    fn put(&mut self, data: &[u8]) -> Result<(), Error> {
+        if self.buf.remaining_mut() < data.len() {
+            Err(io::Error::from(io::ErrorKind::WriteZero))?;
+        }
+        self.buf.put_slice(data);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<B> SerializationSink for BufMutSink<B>
+where
+    B: BufMut,
+{
+    fn send_raw_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        match self.multiplexing {
+            SinkMultiplexing::Channel => self.put(data),
+            SinkMultiplexing::Buffer { .. } => {
+                self.fallback_buffer.send_raw_data(data)
+            },
+        }
+    }
+
+    fn start_var_sized(&mut self, size: Option<usize>) -> Result<(), Error> {
+        match self.multiplexing {
+            SinkMultiplexing::Channel => match size {
+                Some(known_len) => self.send_usize(known_len)?,
+                None => {
+                    self.multiplexing = SinkMultiplexing::Buffer {
+                        outer_seq_size: 0,
+                        inner_seqs: 0,
+                    };
+                },
+            },
+
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs } => {
+                self.fallback_buffer.start_var_sized(size)?;
+                self.multiplexing = SinkMultiplexing::Buffer {
+                    outer_seq_size,
+                    inner_seqs: inner_seqs + 1,
+                };
+            },
+        }
+
+        Ok(())
+    }
+
+    fn end_var_sized(&mut self) -> Result<(), Error> {
+        match self.multiplexing {
+            SinkMultiplexing::Channel => (),
+
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs: 0 } => {
+                self.multiplexing = SinkMultiplexing::Channel;
+                self.send_usize(outer_seq_size)?;
+                let mut buffered = mem::take(&mut self.fallback_buffer);
+                self.put(buffered.as_slice())?;
+                buffered.clear();
+                self.fallback_buffer = buffered;
+            },
+
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs } => {
+                self.fallback_buffer.end_var_sized()?;
+                self.multiplexing = SinkMultiplexing::Buffer {
+                    outer_seq_size,
+                    inner_seqs: inner_seqs - 1,
+                };
+            },
+        }
+
+        Ok(())
+    }
+
+    fn advance_var_sized(&mut self) -> Result<(), Error> {
+        match self.multiplexing {
+            SinkMultiplexing::Channel => (),
+
+            SinkMultiplexing::Buffer { outer_seq_size, inner_seqs: 0 } => {
+                self.multiplexing = SinkMultiplexing::Buffer {
+                    outer_seq_size: outer_seq_size + 1,
+                    inner_seqs: 0,
+                };
+            },
+
+            SinkMultiplexing::Buffer { .. } => {
+                self.fallback_buffer.advance_var_sized()?
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum SinkMultiplexing {
+    Channel,
+    Buffer { outer_seq_size: usize, inner_seqs: usize },
+}
+
+pub trait SinkBuffer {
+    fn as_bytes(&self) -> &[u8];
+
+    fn as_bytes_mut(&mut self) -> &mut [u8];
+
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error>;
+
+    fn clear(&mut self);
+}
+
+impl SinkBuffer for Vec<u8> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error> {
+        Vec::extend_from_slice(self, data);
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+}
+
+impl<A> SinkBuffer for SmallVec<A>
+where
+    A: Array<Item = u8>,
+{
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error> {
+        SmallVec::extend_from_slice(self, data);
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        SmallVec::clear(self)
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<const N: usize> SinkBuffer for heapless::Vec<u8, N> {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error> {
+        heapless::Vec::extend_from_slice(self, data)
+            .map_err(|()| Error::BufferOverflow(N))
+    }
+
+    fn clear(&mut self) {
+        heapless::Vec::clear(self)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl SinkBuffer for BytesMut {
+    fn as_bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error> {
+        BytesMut::extend_from_slice(self, data);
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        BytesMut::clear(self)
+    }
+}
+
+#[derive(Debug)]
+pub struct SliceBuffer<'a> {
+    slice: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> SliceBuffer<'a> {
+    pub fn new(slice: &'a mut [u8]) -> Self {
+        Self { slice, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slice.len()
+    }
+}
+
+impl<'a> SinkBuffer for SliceBuffer<'a> {
+    fn as_bytes(&self) -> &[u8] {
+        &self.slice[.. self.len]
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.slice[.. self.len]
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error> {
+        let capacity = self.slice.len();
+        let target = self
+            .slice
+            .get_mut(self.len .. self.len + data.len())
+            .ok_or(Error::BufferOverflow(capacity))?;
+        target.copy_from_slice(data);
+        self.len += data.len();
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<B> SinkBuffer for &mut B
+where
+    B: SinkBuffer + ?Sized,
+{
+    fn as_bytes(&self) -> &[u8] {
+        (**self).as_bytes()
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        (**self).as_bytes_mut()
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), Error> {
+        (**self).extend_from_slice(data)
+    }
+
+    fn clear(&mut self) {
+        (**self).clear()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BufferSink<B = Vec<u8>> {
+    buffer: B,
+    cursor: usize,
+    current_routine: BufferSinkRoutine,
+    parent_routines: Vec<BufferSinkRoutine>,
+}
+
+impl Default for BufferSink {
+    fn default() -> Self {
+        Self::with_buffer(Vec::new())
+    }
+}
+
+impl BufferSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B> BufferSink<B>
+where
+    B: SinkBuffer,
+{
+    pub fn with_buffer(buffer: B) -> Self {
+        Self {
+            cursor: buffer.as_bytes().len(),
+            buffer,
+            current_routine: BufferSinkRoutine::Resolved { seqs: 0 },
+            parent_routines: Vec::new(),
+        }
+    }
+
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.buffer.as_bytes()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.as_bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.cursor = 0;
+    }
+
+    fn push_resolved(&mut self, len: usize) -> Result<(), Error> {
+        self.send_usize(len)?;
+
+        self.current_routine = match self.current_routine {
+            BufferSinkRoutine::Resolved { seqs } => {
+                BufferSinkRoutine::Resolved { seqs: seqs + 1 }
+            },
+            BufferSinkRoutine::Resolving { .. } => {
+                self.parent_routines.push(self.current_routine);
+                BufferSinkRoutine::Resolved { seqs: 1 }
+            },
+        };
+
+        Ok(())
+    }
+
+    fn push_resolving(&mut self) -> Result<(), Error> {
+        if !matches!(
+            self.current_routine,
+            BufferSinkRoutine::Resolved { seqs: 0 }
+        ) {
+            self.parent_routines.push(self.current_routine);
+        }
+        self.current_routine =
+            BufferSinkRoutine::Resolving { cursor: self.cursor, seq_size: 0 };
+        self.send_usize(0)?;
+        Ok(())
+    }
+
+    fn push(&mut self, size: Option<usize>) -> Result<(), Error> {
+        match size {
+            Some(len) => self.push_resolved(len),
+            None => self.push_resolving(),
+        }
+    }
+
+    fn pop(&mut self) -> Result<(), Error> {
+        match self.current_routine {
+            BufferSinkRoutine::Resolved { seqs: 1 } => {
+                self.current_routine = match self.parent_routines.pop() {
+                    Some(routine) => routine,
+                    None => BufferSinkRoutine::Resolved { seqs: 0 },
+                };
+            },
+
+            BufferSinkRoutine::Resolved { seqs } => {
+                self.current_routine = BufferSinkRoutine::Resolved {
+                    seqs: seqs.saturating_sub(1),
+                };
+            },
+
+            BufferSinkRoutine::Resolving { cursor, seq_size } => {
+                self.current_routine = match self.parent_routines.pop() {
+                    Some(routine) => routine,
+                    None => BufferSinkRoutine::Resolved { seqs: 0 },
+                };
+                let previous_cursor = self.cursor;
+                self.cursor = cursor;
+                self.send_usize(seq_size)?;
+                self.cursor = previous_cursor;
+            },
+        }
+
+        Ok(())
+    }
+
+    fn inc_size(&mut self) {
+        if let BufferSinkRoutine::Resolving { cursor, seq_size } =
+            self.current_routine
+        {
+            self.current_routine =
+                BufferSinkRoutine::Resolving { cursor, seq_size: seq_size + 1 };
+        }
+    }
+}
+
+impl<B> SerializationSink for BufferSink<B>
+where
+    B: SinkBuffer,
+{
+    fn send_raw_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        let mid = data.len().min(self.buffer.as_bytes().len() - self.cursor);
+        let (overriding, extending) = data.split_at(mid);
+        #[allow(clippy::needless_borrow)]
+        self.buffer.as_bytes_mut()[self.cursor .. self.cursor + mid]
+            .copy_from_slice(&overriding);
+        if extending.is_empty() {
+            self.cursor += mid;
+        } else {
+            self.buffer.extend_from_slice(extending)?;
+            self.cursor = self.buffer.as_bytes().len();
+        }
+        Ok(())
+    }
+
+    fn start_var_sized(&mut self, size: Option<usize>) -> Result<(), Error> {
+        self.push(size)
+    }
+
+    fn end_var_sized(&mut self) -> Result<(), Error> {
+        self.pop()
+    }
+
+    fn position(&self) -> Option<usize> {
+        Some(self.cursor)
+    }
+
+    fn advance_var_sized(&mut self) -> Result<(), Error> {
+        self.inc_size();
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum BufferSinkRoutine {
+    Resolved { seqs: usize },
+    Resolving { cursor: usize, seq_size: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct LimitedSink<S> {
+    inner: S,
+    written: usize,
+    limit: Option<usize>,
+}
+
+impl<S> LimitedSink<S>
+where
+    S: SerializationSink,
+{
+    pub fn new(inner: S, limit: Option<usize>) -> Self {
+        Self { inner, written: 0, limit }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    fn account(&mut self, byte_count: usize) -> Result<(), Error> {
+        let Some(limit) = self.limit else {
+            return Ok(());
+        };
+        match self.written.checked_add(byte_count) {
+            Some(written) if written <= limit => {
+                self.written = written;
+                Ok(())
+            },
+            _ => Err(Error::MessageTooLarge(limit)),
+        }
+    }
+}
+
+impl<S> SerializationSink for LimitedSink<S>
+where
+    S: SerializationSink,
+{
+    fn send_raw_data(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.account(data.len())?;
+        self.inner.send_raw_data(data)
+    }
+
+    fn start_var_sized(&mut self, size: Option<usize>) -> Result<(), Error> {
+        self.account(mem::size_of::<u64>())?;
+        self.inner.start_var_sized(size)
+    }
+
+    fn advance_var_sized(&mut self) -> Result<(), Error> {
+        self.inner.advance_var_sized()
+    }
+
+    fn end_var_sized(&mut self) -> Result<(), Error> {
+        self.inner.end_var_sized()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct PresenceFrame {
+    bitmap: Vec<u8>,
+    count: usize,
+    body: Vec<u8>,
+}
+
+impl PresenceFrame {
+    fn push(&mut self, field: Option<Vec<u8>>) {
+        if self.count.is_multiple_of(8) {
+            self.bitmap.push(0);
+        }
+        if let Some(encoded) = field {
+            if let Some(byte) = self.bitmap.last_mut() {
+                *byte |= 1 << (self.count % 8);
+            }
+            self.body.extend_from_slice(&encoded);
+        }
+        self.count += 1;
+    }
+}
+
+#[derive(Debug)]
+pub struct Serializer<S> {
+    sink: S,
+    byte_run: Vec<u8>,
+    awaiting_element: bool,
+    bulk_element_size: Option<usize>,
+    depth: usize,
+    field_presence: bool,
+    field_count: bool,
+    variant_names: bool,
+    self_describing: bool,
+    canonical: bool,
+    canonical_nan: bool,
+    reject_floats: bool,
+    utf8_chars: bool,
+    str_chars: bool,
+    int_encoding: IntEncoding,
+    tagged_fields: bool,
+    field_tags: Arc<HashMap<&'static str, HashMap<&'static str, u32>>>,
+    tagged_structs: Vec<(Option<&'static str>, u32)>,
+    canonical_entries: Option<Vec<(Vec<u8>, Vec<u8>)>>,
+    interned_strings: Option<HashMap<Box<str>, usize>>,
+    presence_bitmap: bool,
+    presence_slot: bool,
+    absent: bool,
+    presence_frames: Vec<PresenceFrame>,
+    len_frames: Vec<(Option<usize>, usize)>,
+    progress: Option<Progress>,
+    annotator: Option<Annotator>,
+}
+
+impl<S> Serializer<S>
+where
+    S: SerializationSink,
+{
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            byte_run: Vec::new(),
+            awaiting_element: false,
+            bulk_element_size: None,
+            depth: 0,
+            field_presence: false,
+            field_count: false,
+            variant_names: false,
+            self_describing: false,
+            canonical: false,
+            canonical_nan: false,
+            reject_floats: false,
+            utf8_chars: false,
+            str_chars: false,
+            int_encoding: IntEncoding::Fixint,
+            tagged_fields: false,
+            field_tags: Arc::default(),
+            tagged_structs: Vec::new(),
+            canonical_entries: None,
+            interned_strings: None,
+            presence_bitmap: false,
+            presence_slot: false,
+            absent: false,
+            presence_frames: Vec::new(),
+            len_frames: Vec::new(),
+            progress: None,
+            annotator: None,
+        }
+    }
+
+    pub fn set_field_presence(&mut self, on: bool) {
+        self.field_presence = on;
+    }
+
+    pub fn set_field_count(&mut self, on: bool) {
+        self.field_count = on;
+    }
+
+    pub fn set_variant_names(&mut self, on: bool) {
+        self.variant_names = on;
+    }
+
+    pub fn set_self_describing(&mut self, on: bool) {
+        self.self_describing = on;
+    }
+
+    pub fn set_canonical(&mut self, on: bool) {
+        self.canonical = on;
+    }
+
+    pub fn set_canonical_nan(&mut self, on: bool) {
+        self.canonical_nan = on;
+    }
+
+    pub fn set_reject_floats(&mut self, on: bool) {
+        self.reject_floats = on;
+    }
+
+    pub fn set_utf8_chars(&mut self, on: bool) {
+        self.utf8_chars = on;
+    }
+
+    pub fn set_str_chars(&mut self, on: bool) {
+        self.str_chars = on;
+    }
+
+    pub fn set_int_encoding(&mut self, encoding: IntEncoding) {
+        self.int_encoding = encoding;
+    }
+
+    pub fn set_tagged_fields(&mut self, on: bool) {
+        self.tagged_fields = on;
+    }
+
+    pub fn set_field_tags(
+        &mut self,
+        tags: Arc<HashMap<&'static str, HashMap<&'static str, u32>>>,
+    ) {
+        self.field_tags = tags;
+    }
+
+    pub fn set_interned_strings(&mut self, on: bool) {
+        self.interned_strings = on.then(HashMap::new);
+    }
+
+    pub fn set_presence_bitmap(&mut self, on: bool) {
+        self.presence_bitmap = on;
+    }
+
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    pub(crate) fn set_annotator(&mut self, annotator: Option<Annotator>) {
+        self.annotator = annotator;
+    }
+
+    pub(crate) fn take_annotator(&mut self) -> Option<Annotator> {
+        self.annotator.take()
+    }
+
+    pub(crate) fn send_header(&mut self, header: &[u8]) -> Result<(), Error> {
+        self.sink()?.send_raw_data(header)
+    }
+
+    pub(crate) fn send_bytes_header(
+        &mut self,
+        len: usize,
+    ) -> Result<(), Error> {
+        self.tag(Tag::Bytes)?;
+        self.send_len(len)
+    }
+
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    pub(crate) fn flush_byte_run(&mut self) -> Result<&mut S, Error> {
+        if !self.byte_run.is_empty() {
+            self.sink.send_raw_data(&self.byte_run[..])?;
+            self.byte_run.clear();
         }
+        Ok(&mut self.sink)
+    }
 
-        Ok(())
+    fn enter(&mut self) {
+        self.depth += 1;
+        if let Some(progress) = &self.progress {
+            progress.record_depth(self.depth);
+        }
     }
 
-    fn advance_var_sized(&mut self) -> Result<(), Error> {
-        match self.multiplexing {
-            ChannelSinkMultiplexing::Buffer {
-                outer_seq_size,
-                inner_seqs: 0,
-            } => {
-                self.multiplexing = ChannelSinkMultiplexing::Buffer {
-                    outer_seq_size: outer_seq_size + 1,
-                    inner_seqs: 0,
-                };
+    fn exit(&mut self) {
+        self.depth -= 1;
+        if let Some(progress) = &self.progress {
+            progress.record_depth(self.depth);
+        }
+    }
+
+    fn overriding<T, F>(&mut self, repr: Override, visit: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        let outer = (
+            self.int_encoding,
+            self.self_describing,
+            self.utf8_chars,
+            self.str_chars,
+        );
+        match repr {
+            Override::IntEncoding(encoding) => self.int_encoding = encoding,
+            Override::Untagged => self.self_describing = false,
+            Override::Utf8Chars => {
+                self.utf8_chars = true;
+                self.str_chars = false;
             },
+        }
+        let result = visit(self);
+        (
+            self.int_encoding,
+            self.self_describing,
+            self.utf8_chars,
+            self.str_chars,
+        ) = outer;
+        result
+    }
 
-            _ => (),
+    fn annotate<F>(&mut self, label: F)
+    where
+        F: FnOnce() -> Label,
+    {
+        if let Some(annotator) = &mut self.annotator {
+            if let Some(position) = self.sink.position() {
+                annotator.push(
+                    position + self.byte_run.len(),
+                    self.depth,
+                    label(),
+                );
+            }
         }
+    }
 
-        Ok(())
+    fn sink(&mut self) -> Result<&mut S, Error> {
+        if let Some(progress) = &self.progress {
+            progress.record_codec();
+        }
+        self.awaiting_element = false;
+        self.presence_slot = false;
+        if !self.byte_run.is_empty() {
+            self.sink.send_raw_data(&self.byte_run[..])?;
+            self.byte_run.clear();
+        }
+        Ok(&mut self.sink)
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum ChannelSinkMultiplexing {
-    Channel,
-    Buffer { outer_seq_size: usize, inner_seqs: usize },
-}
+    fn encode_detached<T>(&self, value: &T) -> Result<Vec<u8>, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = self.detached();
+        value.serialize(&mut serializer)?;
+        Ok(serializer.into_sink().into_inner())
+    }
 
-#[derive(Debug, Clone)]
-pub struct BufferSink<B = Vec<u8>> {
-    buffer: B,
-    cursor: usize,
-    current_routine: BufferSinkRoutine,
-    parent_routines: Vec<BufferSinkRoutine>,
-}
+    fn encode_field<T>(&self, value: &T) -> Result<Option<Vec<u8>>, Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = self.detached();
+        serializer.presence_slot = true;
+        value.serialize(&mut serializer)?;
+        if serializer.absent {
+            return Ok(None);
+        }
+        Ok(Some(serializer.into_sink().into_inner()))
+    }
+
+    fn detached(&self) -> Serializer<BufferSink> {
+        let mut serializer = Serializer::new(BufferSink::new());
+        serializer.set_field_presence(self.field_presence);
+        serializer.set_field_count(self.field_count);
+        serializer.set_variant_names(self.variant_names);
+        serializer.set_self_describing(self.self_describing);
+        serializer.set_canonical(self.canonical);
+        serializer.set_canonical_nan(self.canonical_nan);
+        serializer.set_reject_floats(self.reject_floats);
+        serializer.set_utf8_chars(self.utf8_chars);
+        serializer.set_str_chars(self.str_chars);
+        serializer.set_int_encoding(self.int_encoding);
+        serializer.set_tagged_fields(self.tagged_fields);
+        serializer.set_field_tags(self.field_tags.clone());
+        serializer.set_presence_bitmap(self.presence_bitmap);
+        serializer
+    }
+
+    fn tag(&mut self, tag: Tag) -> Result<(), Error> {
+        if self.self_describing {
+            self.sink()?.send_u8(tag as u8)?;
+        }
+        Ok(())
+    }
 
-impl BufferSink {
-    pub fn new() -> Self {
-        Self::with_buffer(Vec::new())
+    fn send_varint(&mut self, value: u128) -> Result<(), Error> {
+        let mut buf = [0; varint::MAX_ENCODED_LEN];
+        let encoded = match self.int_encoding {
+            IntEncoding::Leb128 => varint::encode_leb128(value, &mut buf),
+            _ => varint::encode(value, &mut buf),
+        };
+        self.sink()?.send_raw_data(encoded)
     }
-}
 
-impl<B> BufferSink<B>
-where
-    B: AsRef<Vec<u8>> + AsMut<Vec<u8>>,
-{
-    pub fn with_buffer(buffer: B) -> Self {
-        Self {
-            buffer,
-            cursor: 0,
-            current_routine: BufferSinkRoutine::Resolved { seqs: 0 },
-            parent_routines: Vec::new(),
+    fn send_len(&mut self, len: usize) -> Result<(), Error> {
+        match self.int_encoding {
+            IntEncoding::Fixint => self.sink()?.send_usize(len),
+            IntEncoding::Varint | IntEncoding::Leb128 => {
+                self.send_varint(len as u128)
+            },
         }
     }
 
-    pub fn as_slice(&self) -> &[u8] {
-        &self.buffer.as_ref()[..]
+    fn send_bytes(&mut self, value: &[u8]) -> Result<(), Error> {
+        self.send_len(value.len())?;
+        self.sink()?.send_raw_data(value)
     }
 
-    pub fn clear(&mut self) {
-        self.buffer.as_mut().clear();
-        self.cursor = 0;
+    fn send_str(&mut self, value: &str) -> Result<(), Error> {
+        if let Some(table) = &mut self.interned_strings {
+            let reference = match table.get(value) {
+                Some(index) => index + 1,
+                None => {
+                    let index = table.len();
+                    table.insert(value.into(), index);
+                    0
+                },
+            };
+            self.send_len(reference)?;
+            if reference > 0 {
+                return Ok(());
+            }
+        }
+        self.send_bytes(value.as_bytes())
     }
 
-    fn push_resolved(&mut self, len: usize) -> Result<(), Error> {
-        self.send_usize(len)?;
-
-        self.current_routine = match self.current_routine {
-            BufferSinkRoutine::Resolved { seqs } => {
-                BufferSinkRoutine::Resolved { seqs: seqs + 1 }
-            },
-            BufferSinkRoutine::Resolving { .. } => {
-                self.parent_routines.push(self.current_routine);
-                BufferSinkRoutine::Resolved { seqs: 1 }
+    fn start_len(&mut self, len: Option<usize>) -> Result<(), Error> {
+        self.len_frames.push((len, 0));
+        match self.int_encoding {
+            IntEncoding::Fixint => self.sink()?.start_var_sized(len),
+            IntEncoding::Varint | IntEncoding::Leb128 => {
+                let len = len.ok_or(Error::LengthRequired)?;
+                self.send_varint(len as u128)
             },
-        };
+        }
+    }
 
-        Ok(())
+    fn advance_len(&mut self) -> Result<(), Error> {
+        self.credit_len(1);
+        match self.int_encoding {
+            IntEncoding::Fixint => self.sink.advance_var_sized(),
+            IntEncoding::Varint | IntEncoding::Leb128 => Ok(()),
+        }
     }
 
-    fn push_resolving(&mut self) -> Result<(), Error> {
-        if !matches!(
-            self.current_routine,
-            BufferSinkRoutine::Resolved { seqs: 0 }
-        ) {
-            self.parent_routines.push(self.current_routine);
+    pub(crate) fn credit_len(&mut self, count: usize) {
+        if let Some((_, found)) = self.len_frames.last_mut() {
+            *found += count;
         }
-        self.current_routine =
-            BufferSinkRoutine::Resolving { cursor: self.cursor, seq_size: 0 };
-        self.send_usize(0)?;
-        Ok(())
     }
 
-    fn push(&mut self, size: Option<usize>) -> Result<(), Error> {
-        match size {
-            Some(len) => self.push_resolved(len),
-            None => self.push_resolving(),
+    fn end_len(&mut self) -> Result<(), Error> {
+        if let Some((Some(expected), found)) = self.len_frames.pop() {
+            if expected != found {
+                Err(Error::SeqLengthMismatch {
+                    expected: expected as u64,
+                    found: found as u64,
+                })?;
+            }
+        }
+        match self.int_encoding {
+            IntEncoding::Fixint => self.sink()?.end_var_sized(),
+            IntEncoding::Varint | IntEncoding::Leb128 => self.sink().map(drop),
         }
     }
 
-    fn pop(&mut self) -> Result<(), Error> {
-        match self.current_routine {
-            BufferSinkRoutine::Resolved { seqs: 1 } => {
-                self.current_routine = match self.parent_routines.pop() {
-                    Some(routine) => routine,
-                    None => BufferSinkRoutine::Resolved { seqs: 0 },
-                };
-            },
+    fn send_variant(
+        &mut self,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.annotate(|| {
+            Label::Text(format!("variant {} ({})", variant, variant_index))
+        });
+        if self.variant_names {
+            self.send_str(variant)
+        } else if self.int_encoding != IntEncoding::Fixint {
+            self.send_varint(u128::from(variant_index))
+        } else {
+            self.sink()?.send_u32(variant_index)
+        }
+    }
 
-            BufferSinkRoutine::Resolved { seqs } => {
-                self.current_routine = BufferSinkRoutine::Resolved {
-                    seqs: seqs.saturating_sub(1),
-                };
-            },
+    fn start_variant(&mut self, variant: &'static str) -> Result<(), Error> {
+        self.annotate(|| Label::Text(format!("variant {}", variant)));
+        self.tag(Tag::Map)?;
+        self.send_len(1)?;
+        variant.serialize(self)
+    }
 
-            BufferSinkRoutine::Resolving { cursor, seq_size } => {
-                self.current_routine = match self.parent_routines.pop() {
-                    Some(routine) => routine,
-                    None => BufferSinkRoutine::Resolved { seqs: 0 },
-                };
-                let previous_cursor = self.cursor;
-                self.cursor = cursor;
-                self.send_usize(seq_size)?;
-                self.cursor = previous_cursor;
-            },
+    fn start_tuple(&mut self, len: usize) -> Result<(), Error> {
+        if self.self_describing {
+            self.tag(Tag::Seq)?;
+            self.send_len(len)?;
         }
-
         Ok(())
     }
 
-    fn inc_size(&mut self) {
-        if let BufferSinkRoutine::Resolving { cursor, seq_size } =
-            self.current_routine
-        {
-            self.current_routine =
-                BufferSinkRoutine::Resolving { cursor, seq_size: seq_size + 1 };
+    fn start_struct(
+        &mut self,
+        name: Option<&'static str>,
+        len: usize,
+    ) -> Result<(), Error> {
+        if self.self_describing {
+            serde::ser::Serializer::serialize_map(&mut *self, Some(len))?;
+            return Ok(());
         }
+        if self.tagged_fields {
+            self.tagged_structs.push((name, 0));
+            self.enter();
+            return Ok(());
+        }
+        if self.presence_bitmap {
+            self.presence_slot = false;
+            self.presence_frames.push(PresenceFrame::default());
+            self.enter();
+            return Ok(());
+        }
+        if self.field_count {
+            let len = if self.field_presence { None } else { Some(len) };
+            self.start_len(len)?;
+        }
+        self.enter();
+        Ok(())
     }
-}
 
-impl<B> SerializationSink for BufferSink<B>
-where
-    B: AsRef<Vec<u8>> + AsMut<Vec<u8>>,
-{
-    fn send_raw_data(&mut self, data: &[u8]) -> Result<(), Error> {
-        let mid = data.len().min(self.buffer.as_ref().len() - self.cursor);
-        let (overriding, extending) = data.split_at(mid);
-        self.buffer.as_mut()[self.cursor .. self.cursor + mid]
-            .copy_from_slice(&overriding);
-        if extending.is_empty() {
-            self.cursor += mid;
-        } else {
-            self.buffer.as_mut().extend_from_slice(extending);
-            self.cursor = self.buffer.as_ref().len();
+    fn end_struct(&mut self) -> Result<(), Error> {
+        if self.self_describing {
+            return serde::ser::SerializeMap::end(self);
+        }
+        if self.tagged_fields {
+            self.tagged_structs.pop();
+            0_u32.serialize(&mut *self)?;
+            self.exit();
+            return Ok(());
+        }
+        if self.presence_bitmap {
+            let frame = self.presence_frames.pop().unwrap_or_default();
+            self.sink()?.send_raw_data(&frame.bitmap)?;
+            self.sink()?.send_raw_data(&frame.body)?;
+            self.exit();
+            return Ok(());
         }
+        if self.field_count {
+            self.end_len()?;
+        }
+        self.exit();
         Ok(())
     }
 
-    fn start_var_sized(&mut self, size: Option<usize>) -> Result<(), Error> {
-        self.push(size)
+    fn push_field<T>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.annotate(|| Label::Text(key.to_owned()));
+        if self.self_describing {
+            return serde::ser::SerializeMap::serialize_entry(
+                &mut &mut *self,
+                key,
+                value,
+            );
+        }
+        if self.tagged_fields {
+            let tag = self.next_field_tag(key);
+            let encoded = self.encode_detached(value)?;
+            tag.serialize(&mut *self)?;
+            return self.send_bytes(&encoded);
+        }
+        if self.presence_bitmap {
+            let encoded = self.encode_field(value)?;
+            if let Some(frame) = self.presence_frames.last_mut() {
+                frame.push(encoded);
+            }
+            return Ok(());
+        }
+        if self.field_count {
+            self.sink()?;
+            self.advance_len()?;
+        }
+        if self.field_presence {
+            self.sink()?.send_u8(1)?;
+        }
+        value.serialize(self)
     }
 
-    fn end_var_sized(&mut self) -> Result<(), Error> {
-        self.pop()
+    fn omit_field(&mut self, key: &'static str) -> Result<(), Error> {
+        if self.self_describing {
+            return Ok(());
+        }
+        if self.tagged_fields {
+            self.next_field_tag(key);
+            return Ok(());
+        }
+        if self.presence_bitmap {
+            if let Some(frame) = self.presence_frames.last_mut() {
+                frame.push(None);
+            }
+            return Ok(());
+        }
+        if !self.field_presence {
+            Err(Error::SkipNotAllowed)?;
+        }
+        if self.field_count {
+            self.sink()?;
+            self.advance_len()?;
+        }
+        self.sink()?.send_u8(0)
     }
 
-    fn advance_var_sized(&mut self) -> Result<(), Error> {
-        self.inc_size();
-        Ok(())
+    fn next_field_tag(&mut self, key: &'static str) -> u32 {
+        let Some((name, position)) = self.tagged_structs.last_mut() else {
+            return 0;
+        };
+        *position += 1;
+        let tagged = name
+            .and_then(|name| self.field_tags.get(name))
+            .and_then(|tags| tags.get(key));
+        tagged.copied().unwrap_or(*position)
     }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum BufferSinkRoutine {
-    Resolved { seqs: usize },
-    Resolving { cursor: usize, seq_size: usize },
-}
 
-#[derive(Debug)]
-pub struct Serializer<S> {
-    sink: S,
+    fn push_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        if let Some(progress) = &self.progress {
+            progress.record_element();
+        }
+        self.annotate(|| Label::Element);
+        self.awaiting_element = true;
+        self.presence_slot = false;
+        let result = value.serialize(&mut *self);
+        self.awaiting_element = false;
+        result
+    }
 }
 
-impl<S> Serializer<S>
-where
-    S: SerializationSink,
-{
-    pub fn new(sink: S) -> Self {
-        Self { sink }
+fn container(kind: &str, len: Option<usize>) -> String {
+    match len {
+        Some(len) => format!("{} ({})", kind, len),
+        None => kind.to_owned(),
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, S> serde::ser::Serializer for &'a mut Serializer<S>
 where
     S: SerializationSink,
@@ -416,110 +1953,218 @@ where
     type SerializeStructVariant = Self;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_bool(v)
+        self.tag(Tag::Bool)?;
+        self.sink()?.send_bool(v)
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_i8(v)
+        self.tag(Tag::I8)?;
+        self.sink()?.send_i8(v)
     }
 
     fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_i16(v)
+        self.tag(Tag::I16)?;
+        if self.int_encoding != IntEncoding::Fixint {
+            return self.send_varint(varint::zigzag(i128::from(v)));
+        }
+        self.sink()?.send_i16(v)
     }
 
     fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_i32(v)
+        self.tag(Tag::I32)?;
+        if self.int_encoding != IntEncoding::Fixint {
+            return self.send_varint(varint::zigzag(i128::from(v)));
+        }
+        self.sink()?.send_i32(v)
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_i64(v)
+        self.tag(Tag::I64)?;
+        if self.int_encoding != IntEncoding::Fixint {
+            return self.send_varint(varint::zigzag(i128::from(v)));
+        }
+        self.sink()?.send_i64(v)
     }
 
     fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_i128(v)
+        self.tag(Tag::I128)?;
+        if self.int_encoding != IntEncoding::Fixint {
+            return self.send_varint(varint::zigzag(v));
+        }
+        self.sink()?.send_i128(v)
     }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_u8(v)
+        if self.awaiting_element && !self.self_describing {
+            self.awaiting_element = false;
+            self.byte_run.push(v);
+            Ok(())
+        } else {
+            self.tag(Tag::U8)?;
+            self.sink()?.send_u8(v)
+        }
     }
 
     fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_u16(v)
+        self.tag(Tag::U16)?;
+        if self.int_encoding != IntEncoding::Fixint {
+            return self.send_varint(u128::from(v));
+        }
+        self.sink()?.send_u16(v)
     }
 
     fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_u32(v)
+        self.tag(Tag::U32)?;
+        if self.int_encoding != IntEncoding::Fixint {
+            return self.send_varint(u128::from(v));
+        }
+        self.sink()?.send_u32(v)
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_u64(v)
+        self.tag(Tag::U64)?;
+        if self.int_encoding != IntEncoding::Fixint {
+            return self.send_varint(u128::from(v));
+        }
+        self.sink()?.send_u64(v)
     }
 
     fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_u128(v)
+        self.tag(Tag::U128)?;
+        if self.int_encoding != IntEncoding::Fixint {
+            return self.send_varint(v);
+        }
+        self.sink()?.send_u128(v)
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_f32(v)
+        if self.reject_floats {
+            Err(Error::FloatRejected)?;
+        }
+        self.tag(Tag::F32)?;
+        let v = if self.canonical_nan && v.is_nan() { f32::NAN } else { v };
+        self.sink()?.send_f32(v)
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_f64(v)
+        if self.reject_floats {
+            Err(Error::FloatRejected)?;
+        }
+        self.tag(Tag::F64)?;
+        let v = if self.canonical_nan && v.is_nan() { f64::NAN } else { v };
+        self.sink()?.send_f64(v)
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_char(v)
+        self.tag(Tag::Char)?;
+        if self.str_chars {
+            let mut buf = [0; 4];
+            return self.send_bytes(v.encode_utf8(&mut buf).as_bytes());
+        }
+        if self.utf8_chars {
+            let mut buf = [0; 4];
+            return self
+                .sink()?
+                .send_raw_data(v.encode_utf8(&mut buf).as_bytes());
+        }
+        self.sink()?.send_char(v)
     }
 
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_str(v)
+        self.tag(Tag::Str)?;
+        self.send_str(v)
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_bytes(v)
+        match self.bulk_element_size.take() {
+            Some(element_size) => {
+                let len = v.len() / element_size;
+                self.start_len(Some(len))?;
+                self.sink()?.send_raw_data(v)?;
+                self.credit_len(len);
+                self.end_len()
+            },
+            None => {
+                self.tag(Tag::Bytes)?;
+                self.send_bytes(v)
+            },
+        }
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        self.sink.send_u8(0)
+        if self.presence_slot {
+            self.presence_slot = false;
+            self.absent = true;
+            return Ok(());
+        }
+        self.annotate(|| Label::Text("None".to_owned()));
+        if self.self_describing {
+            return self.tag(Tag::None);
+        }
+        self.sink()?.send_u8(0)
     }
 
     fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        self.sink.send_u8(1)?;
+        if self.presence_slot {
+            self.presence_slot = false;
+            return value.serialize(self);
+        }
+        self.annotate(|| Label::Text("Some".to_owned()));
+        if self.self_describing {
+            self.tag(Tag::Some)?;
+        } else {
+            self.sink()?.send_u8(1)?;
+        }
         value.serialize(self)?;
         Ok(())
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.tag(Tag::Unit)
     }
 
     fn serialize_unit_struct(
         self,
         _name: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.tag(Tag::Unit)
     }
 
     fn serialize_unit_variant(
         self,
         _name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        variant_index.serialize(self)
+        if self.self_describing {
+            return variant.serialize(self);
+        }
+        self.send_variant(variant_index, variant)
     }
 
     fn serialize_newtype_struct<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + Serialize,
     {
+        self.presence_slot = false;
+        #[cfg(feature = "bulk")]
+        if self.reject_floats && crate::bulk::is_float(name) {
+            Err(Error::FloatRejected)?;
+        }
+        #[cfg(feature = "bulk")]
+        if !self.self_describing {
+            self.bulk_element_size = crate::bulk::element_size(name);
+        }
+        if let Some(repr) = repr::override_for(name) {
+            return self.overriding(repr, |this| value.serialize(this));
+        }
         value.serialize(self)
     }
 
@@ -527,13 +2172,17 @@ where
         self,
         _name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         value: &T,
     ) -> Result<Self::Ok, Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        variant_index.serialize(&mut *self)?;
+        if self.self_describing {
+            self.start_variant(variant)?;
+        } else {
+            self.send_variant(variant_index, variant)?;
+        }
         value.serialize(self)?;
         Ok(())
     }
@@ -542,22 +2191,31 @@ where
         self,
         len: Option<usize>,
     ) -> Result<Self::SerializeSeq, Self::Error> {
-        self.sink.start_var_sized(len)?;
+        self.annotate(|| Label::Text(container("seq", len)));
+        self.tag(Tag::Seq)?;
+        self.start_len(len)?;
+        self.enter();
         Ok(self)
     }
 
     fn serialize_tuple(
         self,
-        _len: usize,
+        len: usize,
     ) -> Result<Self::SerializeTuple, Self::Error> {
+        self.annotate(|| Label::Text(container("tuple", Some(len))));
+        self.start_tuple(len)?;
+        self.enter();
         Ok(self)
     }
 
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
-        _len: usize,
+        name: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.annotate(|| Label::Text(format!("tuple struct {}", name)));
+        self.start_tuple(len)?;
+        self.enter();
         Ok(self)
     }
 
@@ -565,10 +2223,16 @@ where
         self,
         _name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        self.sink.send_u32(variant_index)?;
+        if self.self_describing {
+            self.start_variant(variant)?;
+            self.start_tuple(len)?;
+        } else {
+            self.send_variant(variant_index, variant)?;
+        }
+        self.enter();
         Ok(self)
     }
 
@@ -576,15 +2240,24 @@ where
         self,
         len: Option<usize>,
     ) -> Result<Self::SerializeMap, Self::Error> {
-        self.sink.start_var_sized(len)?;
+        self.annotate(|| Label::Text(container("map", len)));
+        self.tag(Tag::Map)?;
+        if self.canonical {
+            self.canonical_entries = Some(Vec::with_capacity(len.unwrap_or(0)));
+        } else {
+            self.start_len(len)?;
+        }
+        self.enter();
         Ok(self)
     }
 
     fn serialize_struct(
         self,
-        _name: &'static str,
-        _len: usize,
+        name: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.annotate(|| Label::Text(format!("struct {}", name)));
+        self.start_struct(Some(name), len)?;
         Ok(self)
     }
 
@@ -592,10 +2265,15 @@ where
         self,
         _name: &'static str,
         variant_index: u32,
-        _variant: &'static str,
-        _len: usize,
+        variant: &'static str,
+        len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        self.sink.send_u32(variant_index)?;
+        if self.self_describing {
+            self.start_variant(variant)?;
+        } else {
+            self.send_variant(variant_index, variant)?;
+        }
+        self.start_struct(None, len)?;
         Ok(self)
     }
 
@@ -604,6 +2282,7 @@ where
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, S> serde::ser::SerializeSeq for &'a mut Serializer<S>
 where
     S: SerializationSink,
@@ -615,16 +2294,18 @@ where
     where
         T: ?Sized + Serialize,
     {
-        self.sink.advance_var_sized()?;
-        value.serialize(&mut **self)
+        self.advance_len()?;
+        self.push_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.sink.end_var_sized()?;
+        self.end_len()?;
+        self.exit();
         Ok(())
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, S> serde::ser::SerializeMap for &'a mut Serializer<S>
 where
     S: SerializationSink,
@@ -636,7 +2317,19 @@ where
     where
         T: ?Sized + Serialize,
     {
-        self.sink.advance_var_sized()?;
+        if let Some(progress) = &self.progress {
+            progress.record_element();
+        }
+        if self.canonical_entries.is_some() {
+            let key = self.encode_detached(key)?;
+            if let Some(entries) = &mut self.canonical_entries {
+                entries.push((key, Vec::new()));
+            }
+            return Ok(());
+        }
+        self.sink()?;
+        self.advance_len()?;
+        self.annotate(|| Label::Key);
         key.serialize(&mut **self)
     }
 
@@ -644,15 +2337,41 @@ where
     where
         T: ?Sized + Serialize,
     {
+        if self.canonical_entries.is_some() {
+            let value = self.encode_detached(value)?;
+            if let Some((_, entry_value)) = self
+                .canonical_entries
+                .as_mut()
+                .and_then(|entries| entries.last_mut())
+            {
+                *entry_value = value;
+            }
+            return Ok(());
+        }
+        self.annotate(|| Label::Text("value".to_owned()));
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.sink.end_var_sized()?;
+        match self.canonical_entries.take() {
+            Some(mut entries) => {
+                entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+                self.start_len(Some(entries.len()))?;
+                for (key, value) in entries {
+                    self.advance_len()?;
+                    self.sink.send_raw_data(&key)?;
+                    self.sink.send_raw_data(&value)?;
+                }
+                self.end_len()?;
+            },
+            None => self.end_len()?,
+        }
+        self.exit();
         Ok(())
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, S> serde::ser::SerializeTuple for &'a mut Serializer<S>
 where
     S: SerializationSink,
@@ -664,14 +2383,17 @@ where
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.push_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.sink()?;
+        self.exit();
         Ok(())
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, S> serde::ser::SerializeTupleStruct for &'a mut Serializer<S>
 where
     S: SerializationSink,
@@ -683,14 +2405,17 @@ where
     where
         T: ?Sized + Serialize,
     {
+        self.annotate(|| Label::Element);
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.exit();
         Ok(())
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, S> serde::ser::SerializeTupleVariant for &'a mut Serializer<S>
 where
     S: SerializationSink,
@@ -702,14 +2427,17 @@ where
     where
         T: ?Sized + Serialize,
     {
+        self.annotate(|| Label::Element);
         value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        self.exit();
         Ok(())
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, S> serde::ser::SerializeStruct for &'a mut Serializer<S>
 where
     S: SerializationSink,
@@ -719,24 +2447,25 @@ where
 
     fn serialize_field<T>(
         &mut self,
-        _key: &'static str,
+        key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.push_field(key, value)
     }
 
-    fn skip_field(&mut self, _key: &'static str) -> Result<(), Self::Error> {
-        Err(Error::SkipNotAllowed)
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.omit_field(key)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.end_struct()
     }
 }
 
+#[allow(clippy::needless_lifetimes)]
 impl<'a, S> serde::ser::SerializeStructVariant for &'a mut Serializer<S>
 where
     S: SerializationSink,
@@ -746,20 +2475,20 @@ where
 
     fn serialize_field<T>(
         &mut self,
-        _key: &'static str,
+        key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        self.push_field(key, value)
     }
 
-    fn skip_field(&mut self, _key: &'static str) -> Result<(), Self::Error> {
-        Err(Error::SkipNotAllowed)
+    fn skip_field(&mut self, key: &'static str) -> Result<(), Self::Error> {
+        self.omit_field(key)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.end_struct()
     }
 }
@@ -4,11 +4,44 @@ mod public;
 #[cfg(test)]
 mod test;
 
+#[cfg(feature = "bytes")]
+pub use internal::BufMutSink;
+#[cfg(feature = "stream")]
+pub use internal::SinkWriter;
+pub use internal::{
+    BufferSink,
+    ChannelBackend,
+    ChannelSink,
+    LimitedSink,
+    SerializationSink,
+    Serializer,
+    SinkBuffer,
+    SliceBuffer,
+    WriterSink,
+};
 pub use public::{
+    send_datagram_to,
     serialize,
+    serialize_armored,
+    serialize_blob,
+    serialize_indexed,
     serialize_into_buffer,
+    serialize_into_slice,
+    serialize_into_writer,
+    serialize_iter_as_seq,
     serialize_on_buffer,
+    serialize_seekable,
+    serialize_to_file,
+    Checkpoint,
     Config,
+    ConfigBuilder,
     ConfigError,
     Error,
+    ErrorKind,
+    IterSeq,
+    Options,
 };
+#[cfg(feature = "stream")]
+pub use public::{serialize_into_sink, serialize_stream};
+#[cfg(feature = "bytes")]
+pub use public::{serialize_on_bytes_mut, serialize_to_bytes};
@@ -1,14 +1,77 @@
-use std::{fmt, panic};
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::HashMap,
+    ffi::OsString,
+    fmt,
+    io::Write,
+    panic,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
-use serde::Serialize;
+#[cfg(feature = "bytes")]
+use bytes::{BufMut, Bytes, BytesMut};
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+#[cfg(feature = "stream")]
+use futures_sink::Sink;
+use serde::{ser::SerializeSeq, Deserialize, Serialize};
 use thiserror::Error;
 use tokio::{
-    io::{self, AsyncWrite},
+    fs,
+    io::{self, AsyncRead, AsyncReadExt, AsyncSeek, AsyncWrite, AsyncWriteExt},
+    net::{ToSocketAddrs, UdpSocket},
+    runtime::Handle,
     sync::mpsc,
     task,
 };
 
-use super::internal::{BufferSink, ChannelBackend, ChannelSink, Serializer};
+#[cfg(feature = "bytes")]
+use super::internal::BufMutSink;
+#[cfg(feature = "stream")]
+use super::internal::SinkWriter;
+use super::internal::{
+    BufferSink,
+    ChannelBackend,
+    ChannelSink,
+    CountingSink,
+    LimitedSink,
+    SeekBackend,
+    SeekSink,
+    SerializationSink,
+    Serializer,
+    SinkBuffer,
+    SliceBuffer,
+    WriterSink,
+};
+#[cfg(feature = "crypto")]
+use crate::crypto::{Key, NoncePolicy, SealWriter};
+#[cfg(feature = "async-compression")]
+use crate::stream_compression::{CompressedWriter, StreamCompression};
+use crate::{
+    armor::Armor,
+    blocking::{BlockingIo, BlockingStrategy},
+    compress::Compression,
+    dump::{Annotator, Dump},
+    fingerprint::Fingerprint,
+    index::{self, FOOTER_SIZE},
+    metadata::{self, Metadata},
+    pool::BufferPool,
+    progress::Progress,
+    varint::IntEncoding,
+};
+
+#[cfg(unix)]
+use tokio::net::UnixDatagram;
+
+const DEFAULT_MTU: usize = 1472;
+
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -18,6 +81,22 @@ pub enum Error {
     ExcessiveSize(usize),
     #[error("Size difference {0} is too big in magnitude for the protocol")]
     ExcessiveSizeDiff(isize),
+    #[error("Message exceeds the limit of {0} bytes")]
+    MessageTooLarge(usize),
+    #[error("Datagram of {len} bytes exceeds the MTU of {mtu} bytes")]
+    DatagramTooLarge { len: usize, mtu: usize },
+    #[error("Output buffer overflowed its capacity of {0} bytes")]
+    BufferOverflow(usize),
+    #[error("Floating point values are not allowed")]
+    FloatRejected,
+    #[error("Compression {0} is not supported by this encoder")]
+    UnsupportedCompression(Compression),
+    #[error("Blob source ended after {found} of {expected} bytes")]
+    BlobTruncated { expected: u64, found: u64 },
+    #[error("Sequence length must be known up front with varint encoding")]
+    LengthRequired,
+    #[error("Sequence declared {expected} elements but yielded {found}")]
+    SeqLengthMismatch { expected: u64, found: u64 },
     #[error("Skipping fields is not allowed")]
     SkipNotAllowed,
     #[error("I/O error writing to serialization target")]
@@ -30,6 +109,71 @@ pub enum Error {
     Custom(String),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Eof,
+    Io,
+    TimedOut,
+    Limit,
+    InvalidInput,
+    Unsupported,
+    Custom,
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::BlobTruncated { .. } => ErrorKind::Eof,
+            Self::Disconnected => ErrorKind::Io,
+            Self::IO(error) => match error.kind() {
+                io::ErrorKind::UnexpectedEof => ErrorKind::Eof,
+                io::ErrorKind::TimedOut => ErrorKind::TimedOut,
+                _ => ErrorKind::Io,
+            },
+            Self::ExcessiveSize(_)
+            | Self::ExcessiveSizeDiff(_)
+            | Self::MessageTooLarge(_)
+            | Self::DatagramTooLarge { .. }
+            | Self::BufferOverflow(_) => ErrorKind::Limit,
+            Self::FloatRejected
+            | Self::LengthRequired
+            | Self::SeqLengthMismatch { .. }
+            | Self::SkipNotAllowed => ErrorKind::InvalidInput,
+            Self::UnsupportedCompression(_) => ErrorKind::Unsupported,
+            Self::Custom(_) => ErrorKind::Custom,
+        }
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.kind() == ErrorKind::Eof
+    }
+
+    pub fn is_io(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Io | ErrorKind::TimedOut)
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        self.kind() == ErrorKind::TimedOut
+    }
+
+    pub fn is_limit(&self) -> bool {
+        self.kind() == ErrorKind::Limit
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::IO(inner) => inner,
+            Error::BlobTruncated { .. } => {
+                io::Error::new(io::ErrorKind::UnexpectedEof, error)
+            },
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
+
 impl serde::ser::Error for Error {
     fn custom<T>(msg: T) -> Self
     where
@@ -39,21 +183,119 @@ impl serde::ser::Error for Error {
     }
 }
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, Error)]
 pub enum ConfigError {
     #[error("Buffer limit {0} is too low")]
     BufLimitTooLow(usize),
+    #[error("Field {0} uses the reserved tag 0")]
+    ReservedFieldTag(&'static str),
+    #[error("Batch limit {batch} exceeds the channel limit {channel}")]
+    BatchExceedsChannel { batch: usize, channel: usize },
+    #[error("Options {0} and {1} cannot be combined")]
+    ConflictingOptions(&'static str, &'static str),
+    #[error("Option {0} requires option {1}")]
+    MissingOption(&'static str, &'static str),
+    #[error("Metadata block of {0} bytes exceeds the limit of {max}", max = metadata::MAX_LEN)]
+    MetadataTooLarge(usize),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Options {
+    pub max_message_bytes: Option<usize>,
+    pub flush_at_boundary: Option<bool>,
+    pub shutdown_at_end: Option<bool>,
+}
+
+impl Options {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+pub struct Checkpoint {
+    pub elements: u64,
+    pub bytes: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
     batch_limit: usize,
     channel_limit: usize,
+    write_buffer: Option<usize>,
+    flush_at_boundary: bool,
+    shutdown_at_end: bool,
+    fsync: bool,
+    two_pass: bool,
+    max_message_bytes: Option<usize>,
+    mtu: usize,
+    field_presence: bool,
+    field_count: bool,
+    presence_bitmap: bool,
+    variant_names: bool,
+    self_describing: bool,
+    sorted_map_keys: bool,
+    canonical_nan: bool,
+    deterministic: bool,
+    reject_floats: bool,
+    utf8_chars: bool,
+    str_chars: bool,
+    interned_strings: bool,
+    int_encoding: IntEncoding,
+    tagged_fields: bool,
+    field_tags: Arc<HashMap<&'static str, HashMap<&'static str, u32>>>,
+    fingerprint: Option<Fingerprint>,
+    metadata: Option<Metadata>,
+    compression: Option<Compression>,
+    #[cfg(feature = "async-compression")]
+    stream_compression: Option<StreamCompression>,
+    progress: Option<Progress>,
+    buffer_pool: Option<BufferPool>,
+    blocking: BlockingStrategy,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self { batch_limit: 64, channel_limit: 64 }
+        Self {
+            batch_limit: 64,
+            channel_limit: 64,
+            write_buffer: None,
+            flush_at_boundary: false,
+            shutdown_at_end: false,
+            fsync: false,
+            two_pass: false,
+            max_message_bytes: None,
+            mtu: DEFAULT_MTU,
+            field_presence: false,
+            field_count: false,
+            presence_bitmap: false,
+            variant_names: false,
+            self_describing: false,
+            sorted_map_keys: false,
+            canonical_nan: false,
+            deterministic: false,
+            reject_floats: false,
+            utf8_chars: false,
+            str_chars: false,
+            interned_strings: false,
+            int_encoding: IntEncoding::Fixint,
+            tagged_fields: false,
+            field_tags: Arc::default(),
+            fingerprint: None,
+            metadata: None,
+            compression: None,
+            #[cfg(feature = "async-compression")]
+            stream_compression: None,
+            progress: None,
+            buffer_pool: None,
+            blocking: BlockingStrategy::default(),
+        }
     }
 }
 
@@ -62,6 +304,185 @@ impl Config {
         Self::default()
     }
 
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    pub fn to_builder(&self) -> ConfigBuilder {
+        ConfigBuilder::from(self.clone())
+    }
+
+    pub(crate) fn headerless(&self) -> Self {
+        Self {
+            fingerprint: None,
+            metadata: None,
+            compression: None,
+            ..self.clone()
+        }
+    }
+
+    fn overridden(&self, options: &Options) -> Cow<'_, Self> {
+        if options.is_empty() {
+            return Cow::Borrowed(self);
+        }
+        let mut config = self.clone();
+        if let Some(byte_count) = options.max_message_bytes {
+            config.max_message_bytes = Some(byte_count);
+        }
+        if let Some(flush_at_boundary) = options.flush_at_boundary {
+            config.flush_at_boundary = flush_at_boundary;
+        }
+        if let Some(shutdown_at_end) = options.shutdown_at_end {
+            config.shutdown_at_end = shutdown_at_end;
+        }
+        Cow::Owned(config)
+    }
+
+    /// Runs the checks of [`ConfigBuilder::build`], for configs assembled
+    /// through the `&mut self` setters.
+    pub fn ensure_valid(&self) -> Result<(), ConfigError> {
+        if self.batch_limit == 0 {
+            Err(ConfigError::BufLimitTooLow(self.batch_limit))?;
+        }
+        if self.channel_limit == 0 {
+            Err(ConfigError::BufLimitTooLow(self.channel_limit))?;
+        }
+        if let Some(0) = self.write_buffer {
+            Err(ConfigError::BufLimitTooLow(0))?;
+        }
+        if self.mtu == 0 {
+            Err(ConfigError::BufLimitTooLow(self.mtu))?;
+        }
+        if self.batch_limit > self.channel_limit {
+            Err(ConfigError::BatchExceedsChannel {
+                batch: self.batch_limit,
+                channel: self.channel_limit,
+            })?;
+        }
+        if self.presence_bitmap && self.field_count {
+            Err(ConfigError::ConflictingOptions(
+                "presence_bitmap",
+                "field_count",
+            ))?;
+        }
+        if self.utf8_chars && self.str_chars {
+            Err(ConfigError::ConflictingOptions("utf8_chars", "str_chars"))?;
+        }
+        if let Some(metadata) = &self.metadata {
+            ensure_valid_metadata(metadata)?;
+        }
+        #[cfg(feature = "async-compression")]
+        if self.stream_compression.is_some() && !self.flush_at_boundary {
+            Err(ConfigError::MissingOption(
+                "stream_compression",
+                "flush_at_boundary",
+            ))?;
+        }
+        if self.deterministic {
+            self.ensure_deterministic()?;
+        }
+        if self.interned_strings && self.sorted_map_keys {
+            Err(ConfigError::ConflictingOptions(
+                "interned_strings",
+                "sorted_map_keys",
+            ))?;
+        }
+        if self.interned_strings && self.presence_bitmap {
+            Err(ConfigError::ConflictingOptions(
+                "interned_strings",
+                "presence_bitmap",
+            ))?;
+        }
+        if self.interned_strings && self.tagged_fields {
+            Err(ConfigError::ConflictingOptions(
+                "interned_strings",
+                "tagged_fields",
+            ))?;
+        }
+        if self.canonical_nan && self.reject_floats {
+            Err(ConfigError::ConflictingOptions(
+                "canonical_nan",
+                "floats_rejected",
+            ))?;
+        }
+        if self.int_encoding != IntEncoding::Fixint
+            && self.field_presence
+            && self.field_count
+        {
+            Err(ConfigError::ConflictingOptions(
+                "int_encoding",
+                "field_presence with field_count",
+            ))?;
+        }
+        let tags = self.field_tags.values().flat_map(|tags| tags.iter());
+        for (field, tag) in tags {
+            if *tag == 0 {
+                Err(ConfigError::ReservedFieldTag(field))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn ensure_deterministic(&self) -> Result<(), ConfigError> {
+        if self
+            .compression
+            .is_some_and(|compression| compression != Compression::None)
+        {
+            Err(ConfigError::ConflictingOptions(
+                "deterministic",
+                "compression",
+            ))?;
+        }
+        #[cfg(feature = "async-compression")]
+        if self.stream_compression.is_some() {
+            Err(ConfigError::ConflictingOptions(
+                "deterministic",
+                "stream_compression",
+            ))?;
+        }
+        if self.interned_strings {
+            Err(ConfigError::ConflictingOptions(
+                "deterministic",
+                "interned_strings",
+            ))?;
+        }
+        Ok(())
+    }
+
+    pub fn canonical() -> Self {
+        Self { sorted_map_keys: true, ..Self::default() }
+    }
+
+    /// Profile whose output depends only on the serialized value: integers
+    /// are little-endian, map entries are sorted by their encoded keys and
+    /// every NaN is written as the same bit pattern. Building a config with
+    /// this profile rejects options whose output may change between
+    /// platforms or library versions, such as compression.
+    pub fn deterministic() -> Self {
+        Self {
+            sorted_map_keys: true,
+            canonical_nan: true,
+            deterministic: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn float_free() -> Self {
+        Self { reject_floats: true, ..Self::canonical() }
+    }
+
+    pub fn bincode() -> Self {
+        Self { utf8_chars: true, ..Self::default() }
+    }
+
+    pub fn postcard() -> Self {
+        Self {
+            str_chars: true,
+            int_encoding: IntEncoding::Leb128,
+            ..Self::default()
+        }
+    }
+
     pub fn with_batch_limit(
         &mut self,
         byte_count: usize,
@@ -78,22 +499,249 @@ impl Config {
         self
     }
 
-    pub async fn serialize<T, W>(
+    pub fn with_write_buffer(
+        &mut self,
+        byte_count: usize,
+    ) -> Result<&mut Self, ConfigError> {
+        if byte_count == 0 {
+            Err(ConfigError::BufLimitTooLow(byte_count))?;
+        }
+        self.write_buffer = Some(byte_count);
+        Ok(self)
+    }
+
+    pub fn with_flush_at_boundary(&mut self) -> &mut Self {
+        self.flush_at_boundary = true;
+        self
+    }
+
+    pub fn with_shutdown_at_end(&mut self) -> &mut Self {
+        self.shutdown_at_end = true;
+        self
+    }
+
+    pub fn with_fsync(&mut self) -> &mut Self {
+        self.fsync = true;
+        self
+    }
+
+    pub fn with_two_pass(&mut self) -> &mut Self {
+        self.two_pass = true;
+        self
+    }
+
+    /// Caps the encoded message, header included. Messages compressed into
+    /// a buffer are checked once compressed; streamed ones are checked
+    /// before compression, as they are written while being encoded.
+    pub fn with_max_message_bytes(&mut self, byte_count: usize) -> &mut Self {
+        self.max_message_bytes = Some(byte_count);
+        self
+    }
+
+    /// Alias of [`Config::with_max_message_bytes`].
+    pub fn with_max_output_bytes(&mut self, byte_count: usize) -> &mut Self {
+        self.with_max_message_bytes(byte_count)
+    }
+
+    pub fn with_mtu(&mut self, byte_count: usize) -> &mut Self {
+        self.mtu = byte_count;
+        self
+    }
+
+    pub fn with_field_presence(&mut self) -> &mut Self {
+        self.field_presence = true;
+        self
+    }
+
+    pub fn with_field_count(&mut self) -> &mut Self {
+        self.field_count = true;
+        self
+    }
+
+    pub fn with_presence_bitmap(&mut self) -> &mut Self {
+        self.presence_bitmap = true;
+        self
+    }
+
+    pub fn with_variant_names(&mut self) -> &mut Self {
+        self.variant_names = true;
+        self
+    }
+
+    pub fn with_self_describing(&mut self) -> &mut Self {
+        self.self_describing = true;
+        self
+    }
+
+    pub fn with_sorted_map_keys(&mut self) -> &mut Self {
+        self.sorted_map_keys = true;
+        self
+    }
+
+    pub fn with_canonical_nan(&mut self) -> &mut Self {
+        self.canonical_nan = true;
+        self
+    }
+
+    pub fn with_deterministic(&mut self) -> &mut Self {
+        self.sorted_map_keys = true;
+        self.canonical_nan = true;
+        self.deterministic = true;
+        self
+    }
+
+    pub fn with_floats_rejected(&mut self) -> &mut Self {
+        self.reject_floats = true;
+        self
+    }
+
+    pub fn with_utf8_chars(&mut self) -> &mut Self {
+        self.utf8_chars = true;
+        self
+    }
+
+    pub fn with_str_chars(&mut self) -> &mut Self {
+        self.str_chars = true;
+        self
+    }
+
+    pub fn with_interned_strings(&mut self) -> &mut Self {
+        self.interned_strings = true;
+        self
+    }
+
+    pub fn with_int_encoding(&mut self, encoding: IntEncoding) -> &mut Self {
+        self.int_encoding = encoding;
+        self
+    }
+
+    pub fn with_tagged_fields(&mut self) -> &mut Self {
+        self.tagged_fields = true;
+        self
+    }
+
+    pub fn with_field_tags<I>(
+        &mut self,
+        struct_name: &'static str,
+        tags: I,
+    ) -> Result<&mut Self, ConfigError>
+    where
+        I: IntoIterator<Item = (&'static str, u32)>,
+    {
+        let mut struct_tags = HashMap::new();
+        for (field, tag) in tags {
+            if tag == 0 {
+                Err(ConfigError::ReservedFieldTag(field))?;
+            }
+            struct_tags.insert(field, tag);
+        }
+        Arc::make_mut(&mut self.field_tags).insert(struct_name, struct_tags);
+        self.tagged_fields = true;
+        Ok(self)
+    }
+
+    pub fn with_fingerprint(&mut self, fingerprint: Fingerprint) -> &mut Self {
+        self.fingerprint = Some(fingerprint);
+        self
+    }
+
+    pub fn with_metadata(
+        &mut self,
+        metadata: Metadata,
+    ) -> Result<&mut Self, ConfigError> {
+        ensure_valid_metadata(&metadata)?;
+        self.metadata = Some(metadata);
+        Ok(self)
+    }
+
+    pub fn with_compression(&mut self, compression: Compression) -> &mut Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Selects the codec used by [`Config::compressed_writer`]. The encoder
+    /// only emits a decodable block when it is flushed, so this requires
+    /// [`Config::with_flush_at_boundary`]; [`Config::ensure_valid`] rejects
+    /// the combination otherwise.
+    #[cfg(feature = "async-compression")]
+    pub fn with_stream_compression(
+        &mut self,
+        compression: StreamCompression,
+    ) -> &mut Self {
+        self.stream_compression = Some(compression);
+        self
+    }
+
+    #[cfg(feature = "async-compression")]
+    pub fn compressed_writer<W>(&self, device: W) -> CompressedWriter<W>
+    where
+        W: AsyncWrite,
+    {
+        match self.stream_compression {
+            Some(compression) => compression.writer(device),
+            None => CompressedWriter::Plain(device),
+        }
+    }
+
+    pub fn with_progress(&mut self, progress: Progress) -> &mut Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn with_blocking_strategy(
+        &mut self,
+        strategy: BlockingStrategy,
+    ) -> &mut Self {
+        self.blocking = strategy;
+        self
+    }
+
+    pub fn with_buffer_pool(&mut self, pool: BufferPool) -> &mut Self {
+        self.buffer_pool = Some(pool);
+        self
+    }
+
+    pub async fn serialize_with_options<T, W>(
         &self,
         device: W,
         value: T,
+        options: &Options,
     ) -> Result<(), Error>
     where
         W: AsyncWrite + Unpin,
         T: Serialize + Send + 'static,
     {
-        let (sender, receiver) = mpsc::channel(self.channel_limit);
-
-        let backend = ChannelBackend::new(device, self.batch_limit, receiver);
+        self.overridden(options).serialize(device, value).await
+    }
 
-        let mut serializer = Serializer::new(ChannelSink::new(sender));
-        let block_handle =
-            task::spawn_blocking(move || value.serialize(&mut serializer));
+    pub async fn serialize<T, W>(
+        &self,
+        device: W,
+        value: T,
+    ) -> Result<(), Error>
+    where
+        W: AsyncWrite + Unpin,
+        T: Serialize + Send + 'static,
+    {
+        if let Some(handle) = self.blocking.in_place_handle() {
+            return task::block_in_place(|| {
+                self.serialize_in_place(handle, device, value)
+            });
+        }
+        let (backend, mut serializer) = self.channel(device);
+        let counter = self.two_pass.then(|| {
+            let mut counter = Serializer::new(CountingSink::new());
+            self.configure(&mut counter);
+            counter
+        });
+        let block_handle = self.blocking.spawn(move || {
+            if let Some(mut counter) = counter {
+                value.serialize(&mut counter)?;
+                let lengths = counter.into_sink().into_lengths();
+                serializer.sink_mut().get_mut().set_lengths(lengths);
+            }
+            value.serialize(&mut serializer)
+        });
 
         backend.run().await?;
         match block_handle.await {
@@ -103,49 +751,1159 @@ impl Config {
         Ok(())
     }
 
-    pub fn serialize_into_buffer<T>(&self, value: T) -> Result<Vec<u8>, Error>
+    fn serialize_in_place<T, W>(
+        &self,
+        handle: Handle,
+        mut device: W,
+        value: T,
+    ) -> Result<(), Error>
     where
+        W: AsyncWrite + Unpin,
         T: Serialize,
     {
-        let mut buffer = Vec::new();
-        self.serialize_on_buffer(&mut buffer, value)?;
-        Ok(buffer)
+        let mut io = BlockingIo::new(&mut device, handle.clone());
+        io.set_progress(self.progress.clone());
+        // Mirrors the channel backend, which writes out a batch at a time
+        // unless a larger write buffer is configured.
+        let capacity = self.write_buffer.unwrap_or(0).max(self.batch_limit);
+        let mut writer = std::io::BufWriter::with_capacity(capacity, io);
+        let header = self.header();
+        writer.write_all(&header)?;
+        let encoder = self.compression.unwrap_or_default().encoder(writer)?;
+        let mut serializer = Serializer::new(LimitedSink::new(
+            WriterSink::new(encoder),
+            self.sink_limit(header.len()),
+        ));
+        self.configure(&mut serializer);
+        serializer.set_progress(self.progress.clone());
+        if self.two_pass {
+            let mut counter = Serializer::new(CountingSink::new());
+            self.configure(&mut counter);
+            value.serialize(&mut counter)?;
+            let lengths = counter.into_sink().into_lengths();
+            serializer.sink_mut().get_mut().set_lengths(lengths);
+        }
+        value.serialize(&mut serializer)?;
+        let writer =
+            serializer.into_sink().into_inner().into_inner().finish()?;
+        writer.into_inner().map_err(|error| error.into_error())?;
+        if self.flush_at_boundary {
+            handle.block_on(device.flush())?;
+        }
+        if self.shutdown_at_end {
+            handle.block_on(device.shutdown())?;
+        }
+        Ok(())
     }
 
-    pub fn serialize_on_buffer<T>(
+    #[cfg(feature = "stream")]
+    pub async fn serialize_into_sink<T, S, I>(
         &self,
-        buffer: &mut Vec<u8>,
+        sink: S,
         value: T,
     ) -> Result<(), Error>
     where
-        T: Serialize,
+        S: Sink<I>,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        I: From<Vec<u8>>,
+        T: Serialize + Send + 'static,
     {
-        let mut serializer = Serializer::new(BufferSink::with_buffer(buffer));
-        value.serialize(&mut serializer)
+        let mut writer = SinkWriter::new(sink);
+        self.serialize(&mut writer, value).await?;
+        if !self.shutdown_at_end {
+            writer.flush().await?;
+        }
+        Ok(())
     }
-}
 
-pub async fn serialize<T, W>(device: W, value: T) -> Result<(), Error>
-where
-    W: AsyncWrite + Unpin,
-    T: Serialize + Send + 'static,
-{
-    Config::default().serialize(device, value).await
-}
+    #[cfg(feature = "stream")]
+    pub async fn serialize_stream<S, W>(
+        &self,
+        device: W,
+        len: Option<usize>,
+        stream: S,
+    ) -> Result<(), Error>
+    where
+        S: Stream,
+        S::Item: Serialize + Send + 'static,
+        W: AsyncWrite + Unpin,
+    {
+        let (backend, mut serializer) = self.channel(device);
+        let (sender, mut receiver) = mpsc::channel(self.channel_limit);
+        let block_handle = self.blocking.spawn(move || {
+            let mut seq =
+                serde::Serializer::serialize_seq(&mut serializer, len)?;
+            let mut found = 0;
+            while let Some(element) = receiver.blocking_recv() {
+                seq.serialize_element(&element)?;
+                found += 1;
+            }
+            if let Some(len) = len {
+                if found != len as u64 {
+                    Err(Error::SeqLengthMismatch {
+                        expected: len as u64,
+                        found,
+                    })?;
+                }
+            }
+            SerializeSeq::end(seq)
+        });
 
-pub fn serialize_into_buffer<T>(value: T) -> Result<Vec<u8>, Error>
-where
-    T: Serialize,
-{
-    Config::default().serialize_into_buffer(value)
-}
+        let feed = async move {
+            let mut stream = std::pin::pin!(stream);
+            while let Some(element) =
+                std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await
+            {
+                if sender.send(element).await.is_err() {
+                    break;
+                }
+            }
+        };
+        let (backend_result, ()) = tokio::join!(backend.run(), feed);
+        backend_result?;
+        match block_handle.await {
+            Ok(actual_result) => actual_result?,
+            Err(error) => panic::resume_unwind(error.into_panic()),
+        }
+        Ok(())
+    }
 
-pub fn serialize_on_buffer<T>(
-    buffer: &mut Vec<u8>,
-    value: T,
-) -> Result<(), Error>
-where
+    pub async fn serialize_indexed<I, W>(
+        &self,
+        mut device: W,
+        len: usize,
+        elements: I,
+    ) -> Result<(), Error>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+        W: AsyncWrite + Unpin,
+    {
+        if let Some(compression) = self.compression {
+            if compression != Compression::None {
+                Err(Error::UnsupportedCompression(compression))?;
+            }
+        }
+        let mut serializer = self.serializer(self.take_buffer());
+        serializer.send_header(&self.header())?;
+        let mut seq =
+            serde::Serializer::serialize_seq(&mut serializer, Some(len))?;
+        let mut written = 0;
+        let mut offsets = Vec::with_capacity(len);
+        for element in elements {
+            let sink = seq.flush_byte_run()?.get_mut();
+            let buffered = sink.as_slice().len();
+            if buffered >= self.write_buffer.unwrap_or(0) {
+                device.write_all(sink.as_slice()).await?;
+                sink.clear();
+                written += buffered as u64;
+            }
+            offsets.push(
+                written + seq.sink_mut().get_mut().as_slice().len() as u64,
+            );
+            seq.serialize_element(&element)?;
+        }
+        if offsets.len() != len {
+            Err(Error::SeqLengthMismatch {
+                expected: len as u64,
+                found: offsets.len() as u64,
+            })?;
+        }
+        SerializeSeq::end(seq)?;
+        let sink = serializer.sink_mut().get_mut();
+        let index_offset = written + sink.as_slice().len() as u64;
+        device.write_all(sink.as_slice()).await?;
+        let mut index = Vec::with_capacity(offsets.len() * 8 + FOOTER_SIZE);
+        for offset in &offsets {
+            index.extend_from_slice(&offset.to_le_bytes());
+        }
+        index.extend_from_slice(&index::encode_footer(
+            index::INDEX_MAGIC,
+            index_offset,
+            len as u64,
+        ));
+        device.write_all(&index).await?;
+        device.flush().await?;
+        if let Some(pool) = &self.buffer_pool {
+            pool.recycle(serializer.into_sink().into_inner().into_inner());
+        }
+        Ok(())
+    }
+
+    pub async fn serialize_checkpointed<I, W, F>(
+        &self,
+        mut device: W,
+        len: usize,
+        elements: I,
+        resume: Option<Checkpoint>,
+        mut on_checkpoint: F,
+    ) -> Result<Checkpoint, Error>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+        W: AsyncWrite + Unpin,
+        F: FnMut(Checkpoint),
+    {
+        if let Some(compression) = self.compression {
+            if compression != Compression::None {
+                Err(Error::UnsupportedCompression(compression))?;
+            }
+        }
+        let mut checkpoint = resume.unwrap_or_default();
+        let mut serializer = self.serializer(self.take_buffer());
+        if resume.is_none() {
+            serializer.send_header(&self.header())?;
+        }
+        let mut seq =
+            serde::Serializer::serialize_seq(&mut serializer, Some(len))?;
+        if resume.is_some() {
+            seq.sink_mut().get_mut().clear();
+            seq.credit_len(checkpoint.elements as usize);
+        }
+        let mut pending = 0;
+        for element in elements {
+            seq.serialize_element(&element)?;
+            pending += 1;
+            let buffered = seq.flush_byte_run()?.get_mut().as_slice().len();
+            if buffered >= self.write_buffer.unwrap_or(0) {
+                let sink = seq.sink_mut().get_mut();
+                checkpoint.bytes += buffered as u64;
+                checkpoint.elements += pending;
+                device.write_all(sink.as_slice()).await?;
+                device.flush().await?;
+                sink.clear();
+                pending = 0;
+                on_checkpoint(checkpoint);
+            }
+        }
+        let found = checkpoint.elements + pending;
+        if found != len as u64 {
+            Err(Error::SeqLengthMismatch { expected: len as u64, found })?;
+        }
+        SerializeSeq::end(seq)?;
+        let sink = serializer.sink_mut().get_mut();
+        checkpoint.bytes += sink.as_slice().len() as u64;
+        checkpoint.elements = found;
+        device.write_all(sink.as_slice()).await?;
+        device.flush().await?;
+        if let Some(pool) = &self.buffer_pool {
+            pool.recycle(serializer.into_sink().into_inner().into_inner());
+        }
+        Ok(checkpoint)
+    }
+
+    pub fn channel<W>(
+        &self,
+        device: W,
+    ) -> (ChannelBackend<W>, Serializer<LimitedSink<ChannelSink>>)
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let (sender, receiver) = mpsc::channel(self.channel_limit);
+
+        let mut backend =
+            ChannelBackend::new(device, self.batch_limit, receiver);
+        backend.set_progress(self.progress.clone());
+        backend.set_header(self.header());
+        backend.set_compression(self.compression.unwrap_or_default());
+        backend.set_write_buffer(self.write_buffer);
+        backend.set_flush_at_boundary(self.flush_at_boundary);
+        backend.set_shutdown_at_end(self.shutdown_at_end);
+        backend.set_buffer_pool(self.buffer_pool.clone());
+
+        let mut sink = ChannelSink::new(sender);
+        sink.set_buffer_pool(self.buffer_pool.clone());
+        let mut serializer = Serializer::new(LimitedSink::new(
+            sink,
+            self.sink_limit(self.header().len()),
+        ));
+        self.configure(&mut serializer);
+        serializer.set_progress(self.progress.clone());
+        (backend, serializer)
+    }
+
+    pub async fn serialize_seekable<T, W>(
+        &self,
+        device: W,
+        value: T,
+    ) -> Result<(), Error>
+    where
+        W: AsyncWrite + AsyncSeek + Unpin,
+        T: Serialize + Send + 'static,
+    {
+        if self.compression.unwrap_or_default() != Compression::None {
+            return self.serialize(device, value).await;
+        }
+
+        let (sender, receiver) = mpsc::channel(self.channel_limit);
+
+        let mut backend = SeekBackend::new(device, receiver);
+        backend.set_progress(self.progress.clone());
+        backend.set_header(self.header());
+        backend.set_write_buffer(self.write_buffer);
+        backend.set_flush_at_boundary(self.flush_at_boundary);
+        backend.set_shutdown_at_end(self.shutdown_at_end);
+
+        let mut serializer = Serializer::new(LimitedSink::new(
+            SeekSink::new(sender, self.batch_limit),
+            self.sink_limit(self.header().len()),
+        ));
+        self.configure(&mut serializer);
+        serializer.set_progress(self.progress.clone());
+        let block_handle = self.blocking.spawn(move || {
+            value.serialize(&mut serializer)?;
+            serializer.into_sink().into_inner().finish()
+        });
+
+        backend.run().await?;
+        match block_handle.await {
+            Ok(actual_result) => actual_result?,
+            Err(error) => panic::resume_unwind(error.into_panic()),
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "crypto")]
+    pub async fn serialize_encrypted<T, W>(
+        &self,
+        device: W,
+        key: &Key,
+        nonce_policy: NoncePolicy,
+        value: T,
+    ) -> Result<(), Error>
+    where
+        W: AsyncWrite + Unpin,
+        T: Serialize + Send + 'static,
+    {
+        let mut writer = SealWriter::new(device, key, nonce_policy);
+        self.serialize(&mut writer, value).await?;
+        writer.finish().await?;
+        Ok(())
+    }
+
+    pub async fn serialize_to_file<T, P>(
+        &self,
+        path: P,
+        value: T,
+    ) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+        T: Serialize + Send + 'static,
+    {
+        let path = path.as_ref();
+        let temp_path = temp_path(path)?;
+        if let Err(error) = self.write_file(&temp_path, value).await {
+            let _ = fs::remove_file(&temp_path).await;
+            Err(error)?;
+        }
+        if let Err(error) = fs::rename(&temp_path, path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            Err(error)?;
+        }
+        if self.fsync {
+            sync_parent(path).await?;
+        }
+        Ok(())
+    }
+
+    async fn write_file<T>(&self, path: &Path, value: T) -> Result<(), Error>
+    where
+        T: Serialize + Send + 'static,
+    {
+        let mut file = fs::File::create(path).await?;
+        self.serialize(&mut file, value).await?;
+        file.flush().await?;
+        if self.fsync {
+            file.sync_all().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn serialize_blob<W, R>(
+        &self,
+        mut device: W,
+        len: u64,
+        reader: R,
+    ) -> Result<(), Error>
+    where
+        W: AsyncWrite + Unpin,
+        R: AsyncRead + Unpin,
+    {
+        let compression = self.compression.unwrap_or_default();
+        if compression != Compression::None {
+            Err(Error::UnsupportedCompression(compression))?;
+        }
+        let mut prefix = Vec::new();
+        let mut serializer = self.serializer(&mut prefix);
+        serializer.send_header(&self.header())?;
+        serializer.send_bytes_header(
+            usize::try_from(len)
+                .map_err(|_| Error::ExcessiveSize(usize::MAX))?,
+        )?;
+        if let Some(max) = self.sink_limit(0) {
+            if prefix.len() as u64 + len > max as u64 {
+                Err(Error::MessageTooLarge(max))?;
+            }
+        }
+
+        device.write_all(&prefix).await?;
+        let copied = io::copy(&mut reader.take(len), &mut device).await?;
+        if copied < len {
+            Err(Error::BlobTruncated { expected: len, found: copied })?;
+        }
+        device.flush().await?;
+        if let Some(progress) = &self.progress {
+            progress.record_io(prefix.len() + copied as usize);
+        }
+        Ok(())
+    }
+
+    pub fn serialize_into_buffer<T>(&self, value: T) -> Result<Vec<u8>, Error>
+    where
+        T: Serialize,
+    {
+        let mut buffer = self.take_buffer();
+        self.serialize_on_buffer(&mut buffer, value)?;
+        Ok(buffer)
+    }
+
+    pub fn dump<T>(&self, value: &T) -> Dump
+    where
+        T: ?Sized + Serialize,
+    {
+        let mut serializer = Serializer::new(BufferSink::new());
+        self.configure(&mut serializer);
+        serializer.set_annotator(Some(Annotator::new()));
+        let error = value.serialize(&mut serializer).err();
+        let annotator = serializer.take_annotator().unwrap_or_default();
+        Dump::new(serializer.into_sink().into_inner(), annotator, error)
+    }
+
+    pub fn serialize_armored<T>(
+        &self,
+        value: T,
+        armor: &Armor,
+    ) -> Result<String, Error>
+    where
+        T: Serialize,
+    {
+        Ok(armor.encode(&self.serialize_into_buffer(value)?))
+    }
+
+    pub fn serialize_into_buffer_with_options<T>(
+        &self,
+        value: T,
+        options: &Options,
+    ) -> Result<Vec<u8>, Error>
+    where
+        T: Serialize,
+    {
+        self.overridden(options).serialize_into_buffer(value)
+    }
+
+    pub async fn send_datagram<T>(
+        &self,
+        socket: &UdpSocket,
+        value: T,
+    ) -> Result<usize, Error>
+    where
+        T: Serialize,
+    {
+        let datagram = self.encode_datagram(value)?;
+        let count = socket.send(&datagram).await?;
+        self.finish_datagram(datagram);
+        Ok(count)
+    }
+
+    pub async fn send_datagram_to<T, A>(
+        &self,
+        socket: &UdpSocket,
+        target: A,
+        value: T,
+    ) -> Result<usize, Error>
+    where
+        T: Serialize,
+        A: ToSocketAddrs,
+    {
+        let datagram = self.encode_datagram(value)?;
+        let count = socket.send_to(&datagram, target).await?;
+        self.finish_datagram(datagram);
+        Ok(count)
+    }
+
+    #[cfg(unix)]
+    pub async fn send_unix_datagram<T>(
+        &self,
+        socket: &UnixDatagram,
+        value: T,
+    ) -> Result<usize, Error>
+    where
+        T: Serialize,
+    {
+        let datagram = self.encode_datagram(value)?;
+        let count = socket.send(&datagram).await?;
+        self.finish_datagram(datagram);
+        Ok(count)
+    }
+
+    #[cfg(unix)]
+    pub async fn send_unix_datagram_to<T, P>(
+        &self,
+        socket: &UnixDatagram,
+        target: P,
+        value: T,
+    ) -> Result<usize, Error>
+    where
+        T: Serialize,
+        P: AsRef<Path>,
+    {
+        let datagram = self.encode_datagram(value)?;
+        let count = socket.send_to(&datagram, target).await?;
+        self.finish_datagram(datagram);
+        Ok(count)
+    }
+
+    fn encode_datagram<T>(&self, value: T) -> Result<Vec<u8>, Error>
+    where
+        T: Serialize,
+    {
+        let datagram = self.serialize_into_buffer(value)?;
+        if datagram.len() > self.mtu {
+            let len = datagram.len();
+            self.finish_datagram(datagram);
+            return Err(Error::DatagramTooLarge { len, mtu: self.mtu });
+        }
+        Ok(datagram)
+    }
+
+    fn finish_datagram(&self, datagram: Vec<u8>) {
+        if let Some(pool) = &self.buffer_pool {
+            pool.recycle(datagram);
+        }
+    }
+
+    pub fn serialize_on_buffer<T, B>(
+        &self,
+        mut buffer: B,
+        value: T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+        B: SinkBuffer,
+    {
+        let header = self.header();
+        let compression = self.compression.unwrap_or_default();
+        if compression == Compression::None {
+            let mut serializer = self.serializer(buffer);
+            if !header.is_empty() {
+                serializer.send_header(&header)?;
+            }
+            value.serialize(&mut serializer)
+        } else {
+            let mut body = self.take_buffer();
+            let mut serializer =
+                Serializer::new(BufferSink::with_buffer(&mut body));
+            self.configure(&mut serializer);
+            value.serialize(&mut serializer)?;
+            let compressed = compression.compress(&body);
+            if let Some(pool) = &self.buffer_pool {
+                pool.recycle(body);
+            }
+            let compressed = compressed?;
+            if let Some(max) = self.max_message_bytes {
+                if header.len() + compressed.len() > max {
+                    Err(Error::MessageTooLarge(max))?;
+                }
+            }
+            buffer.extend_from_slice(&header)?;
+            buffer.extend_from_slice(&compressed)
+        }
+    }
+
+    pub fn serialize_into_writer<T, W>(
+        &self,
+        mut writer: W,
+        value: T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+        W: Write,
+    {
+        let header = self.header();
+        writer.write_all(&header)?;
+        let encoder = self.compression.unwrap_or_default().encoder(writer)?;
+        let mut serializer = Serializer::new(LimitedSink::new(
+            WriterSink::new(encoder),
+            self.sink_limit(header.len()),
+        ));
+        self.configure(&mut serializer);
+        value.serialize(&mut serializer)?;
+        serializer.into_sink().into_inner().into_inner().finish()?;
+        Ok(())
+    }
+
+    pub fn serialize_into_slice<T>(
+        &self,
+        slice: &mut [u8],
+        value: T,
+    ) -> Result<usize, Error>
+    where
+        T: Serialize,
+    {
+        let mut buffer = SliceBuffer::new(slice);
+        self.serialize_on_buffer(&mut buffer, value)?;
+        Ok(buffer.len())
+    }
+
+    #[cfg(feature = "bytes")]
+    pub fn serialize_to_bytes<T>(&self, value: T) -> Result<Bytes, Error>
+    where
+        T: Serialize,
+    {
+        self.serialize_into_buffer(value).map(Bytes::from)
+    }
+
+    #[cfg(feature = "bytes")]
+    pub fn serialize_on_bytes_mut<T>(
+        &self,
+        buffer: &mut BytesMut,
+        value: T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.serialize_on_buffer(buffer, value)
+    }
+
+    #[cfg(feature = "bytes")]
+    pub fn serialize_into_buf_mut<T, B>(
+        &self,
+        buf: B,
+        value: T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize,
+        B: BufMut,
+    {
+        if self.compression.unwrap_or_default() != Compression::None {
+            let mut sink = BufMutSink::new(buf);
+            return sink.send_raw_data(&self.serialize_into_buffer(value)?);
+        }
+        let mut serializer = Serializer::new(LimitedSink::new(
+            BufMutSink::new(buf),
+            self.sink_limit(0),
+        ));
+        self.configure(&mut serializer);
+        let header = self.header();
+        if !header.is_empty() {
+            serializer.send_header(&header)?;
+        }
+        value.serialize(&mut serializer)
+    }
+
+    pub fn serializer<B>(
+        &self,
+        buffer: B,
+    ) -> Serializer<LimitedSink<BufferSink<B>>>
+    where
+        B: SinkBuffer,
+    {
+        let mut serializer = Serializer::new(LimitedSink::new(
+            BufferSink::with_buffer(buffer),
+            self.sink_limit(0),
+        ));
+        self.configure(&mut serializer);
+        serializer
+    }
+
+    fn sink_limit(&self, header_len: usize) -> Option<usize> {
+        self.max_message_bytes.map(|max| max.saturating_sub(header_len))
+    }
+
+    fn take_buffer(&self) -> Vec<u8> {
+        self.buffer_pool.as_ref().map_or_else(Vec::new, BufferPool::take)
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut header = Vec::new();
+        if let Some(fingerprint) = self.fingerprint {
+            header.extend_from_slice(&fingerprint.to_le_bytes());
+        }
+        if let Some(metadata) = &self.metadata {
+            header.extend_from_slice(&metadata.encode());
+        }
+        if let Some(compression) = self.compression {
+            header.push(compression.id());
+        }
+        header
+    }
+
+    fn configure<S>(&self, serializer: &mut Serializer<S>)
+    where
+        S: SerializationSink,
+    {
+        serializer.set_field_presence(self.field_presence);
+        serializer.set_field_count(self.field_count);
+        serializer.set_presence_bitmap(self.presence_bitmap);
+        serializer.set_variant_names(self.variant_names);
+        serializer.set_self_describing(self.self_describing);
+        serializer.set_canonical(self.sorted_map_keys);
+        serializer.set_canonical_nan(self.canonical_nan);
+        serializer.set_reject_floats(self.reject_floats);
+        serializer.set_utf8_chars(self.utf8_chars);
+        serializer.set_str_chars(self.str_chars);
+        serializer.set_interned_strings(self.interned_strings);
+        serializer.set_int_encoding(self.int_encoding);
+        serializer.set_tagged_fields(self.tagged_fields);
+        serializer.set_field_tags(self.field_tags.clone());
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+    error: Option<ConfigError>,
+}
+
+impl From<Config> for ConfigBuilder {
+    fn from(config: Config) -> Self {
+        Self { config, error: None }
+    }
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_batch_limit(self, byte_count: usize) -> Self {
+        self.try_set(|config| config.with_batch_limit(byte_count))
+    }
+
+    pub fn with_channel_limit(mut self, byte_count: usize) -> Self {
+        self.config.with_channel_limit(byte_count);
+        self
+    }
+
+    pub fn with_write_buffer(self, byte_count: usize) -> Self {
+        self.try_set(|config| config.with_write_buffer(byte_count))
+    }
+
+    pub fn with_flush_at_boundary(mut self) -> Self {
+        self.config.with_flush_at_boundary();
+        self
+    }
+
+    pub fn with_shutdown_at_end(mut self) -> Self {
+        self.config.with_shutdown_at_end();
+        self
+    }
+
+    pub fn with_fsync(mut self) -> Self {
+        self.config.with_fsync();
+        self
+    }
+
+    pub fn with_two_pass(mut self) -> Self {
+        self.config.with_two_pass();
+        self
+    }
+
+    pub fn with_max_message_bytes(mut self, byte_count: usize) -> Self {
+        self.config.with_max_message_bytes(byte_count);
+        self
+    }
+
+    pub fn with_max_output_bytes(self, byte_count: usize) -> Self {
+        self.with_max_message_bytes(byte_count)
+    }
+
+    pub fn with_mtu(mut self, byte_count: usize) -> Self {
+        self.config.with_mtu(byte_count);
+        self
+    }
+
+    pub fn with_field_presence(mut self) -> Self {
+        self.config.with_field_presence();
+        self
+    }
+
+    pub fn with_field_count(mut self) -> Self {
+        self.config.with_field_count();
+        self
+    }
+
+    pub fn with_presence_bitmap(mut self) -> Self {
+        self.config.with_presence_bitmap();
+        self
+    }
+
+    pub fn with_variant_names(mut self) -> Self {
+        self.config.with_variant_names();
+        self
+    }
+
+    pub fn with_self_describing(mut self) -> Self {
+        self.config.with_self_describing();
+        self
+    }
+
+    pub fn with_sorted_map_keys(mut self) -> Self {
+        self.config.with_sorted_map_keys();
+        self
+    }
+
+    pub fn with_canonical_nan(mut self) -> Self {
+        self.config.with_canonical_nan();
+        self
+    }
+
+    pub fn with_deterministic(mut self) -> Self {
+        self.config.with_deterministic();
+        self
+    }
+
+    pub fn with_floats_rejected(mut self) -> Self {
+        self.config.with_floats_rejected();
+        self
+    }
+
+    pub fn with_utf8_chars(mut self) -> Self {
+        self.config.with_utf8_chars();
+        self
+    }
+
+    pub fn with_str_chars(mut self) -> Self {
+        self.config.with_str_chars();
+        self
+    }
+
+    pub fn with_interned_strings(mut self) -> Self {
+        self.config.with_interned_strings();
+        self
+    }
+
+    pub fn with_int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.config.with_int_encoding(encoding);
+        self
+    }
+
+    pub fn with_tagged_fields(mut self) -> Self {
+        self.config.with_tagged_fields();
+        self
+    }
+
+    pub fn with_field_tags<I>(self, struct_name: &'static str, tags: I) -> Self
+    where
+        I: IntoIterator<Item = (&'static str, u32)>,
+    {
+        self.try_set(|config| config.with_field_tags(struct_name, tags))
+    }
+
+    pub fn with_fingerprint(mut self, fingerprint: Fingerprint) -> Self {
+        self.config.with_fingerprint(fingerprint);
+        self
+    }
+
+    pub fn with_metadata(self, metadata: Metadata) -> Self {
+        self.try_set(|config| config.with_metadata(metadata))
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.config.with_compression(compression);
+        self
+    }
+
+    /// See [`Config::with_stream_compression`].
+    #[cfg(feature = "async-compression")]
+    pub fn with_stream_compression(
+        mut self,
+        compression: StreamCompression,
+    ) -> Self {
+        self.config.with_stream_compression(compression);
+        self
+    }
+
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.config.with_progress(progress);
+        self
+    }
+
+    pub fn with_blocking_strategy(
+        mut self,
+        strategy: BlockingStrategy,
+    ) -> Self {
+        self.config.with_blocking_strategy(strategy);
+        self
+    }
+
+    pub fn with_buffer_pool(mut self, pool: BufferPool) -> Self {
+        self.config.with_buffer_pool(pool);
+        self
+    }
+
+    pub fn build(self) -> Result<Config, ConfigError> {
+        if let Some(error) = self.error {
+            Err(error)?;
+        }
+        self.config.ensure_valid()?;
+        Ok(self.config)
+    }
+
+    // Setters shared with `Config` check their arguments right away; the
+    // first failure is kept until `build`.
+    fn try_set<F>(mut self, set: F) -> Self
+    where
+        F: FnOnce(&mut Config) -> Result<&mut Config, ConfigError>,
+    {
+        if self.error.is_none() {
+            if let Err(error) = set(&mut self.config) {
+                self.error = Some(error);
+            }
+        }
+        self
+    }
+}
+
+fn ensure_valid_metadata(metadata: &Metadata) -> Result<(), ConfigError> {
+    let len = metadata.encoded_len();
+    if len > metadata::MAX_LEN {
+        Err(ConfigError::MetadataTooLarge(len))?;
+    }
+    Ok(())
+}
+
+pub async fn serialize<T, W>(device: W, value: T) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize + Send + 'static,
+{
+    Config::default().serialize(device, value).await
+}
+
+pub async fn serialize_to_file<T, P>(path: P, value: T) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    T: Serialize + Send + 'static,
+{
+    Config::default().serialize_to_file(path, value).await
+}
+
+pub async fn serialize_blob<W, R>(
+    device: W,
+    len: u64,
+    reader: R,
+) -> Result<(), Error>
+where
+    W: AsyncWrite + Unpin,
+    R: AsyncRead + Unpin,
+{
+    Config::default().serialize_blob(device, len, reader).await
+}
+
+pub async fn serialize_seekable<T, W>(device: W, value: T) -> Result<(), Error>
+where
+    W: AsyncWrite + AsyncSeek + Unpin,
+    T: Serialize + Send + 'static,
+{
+    Config::default().serialize_seekable(device, value).await
+}
+
+#[cfg(feature = "stream")]
+pub async fn serialize_into_sink<T, S, I>(
+    sink: S,
+    value: T,
+) -> Result<(), Error>
+where
+    S: Sink<I>,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    I: From<Vec<u8>>,
+    T: Serialize + Send + 'static,
+{
+    Config::default().serialize_into_sink(sink, value).await
+}
+
+#[cfg(feature = "stream")]
+pub async fn serialize_stream<S, W>(
+    device: W,
+    len: Option<usize>,
+    stream: S,
+) -> Result<(), Error>
+where
+    S: Stream,
+    S::Item: Serialize + Send + 'static,
+    W: AsyncWrite + Unpin,
+{
+    Config::default().serialize_stream(device, len, stream).await
+}
+
+pub async fn serialize_indexed<I, W>(
+    device: W,
+    len: usize,
+    elements: I,
+) -> Result<(), Error>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+    W: AsyncWrite + Unpin,
+{
+    Config::default().serialize_indexed(device, len, elements).await
+}
+
+pub async fn send_datagram_to<T, A>(
+    socket: &UdpSocket,
+    target: A,
+    value: T,
+) -> Result<usize, Error>
+where
+    T: Serialize,
+    A: ToSocketAddrs,
+{
+    Config::default().send_datagram_to(socket, target, value).await
+}
+
+pub fn serialize_into_buffer<T>(value: T) -> Result<Vec<u8>, Error>
+where
+    T: Serialize,
+{
+    Config::default().serialize_into_buffer(value)
+}
+
+pub fn serialize_armored<T>(value: T) -> Result<String, Error>
+where
+    T: Serialize,
+{
+    Config::default().serialize_armored(value, &Armor::default())
+}
+
+#[cfg(feature = "bytes")]
+pub fn serialize_to_bytes<T>(value: T) -> Result<Bytes, Error>
+where
+    T: Serialize,
+{
+    Config::default().serialize_to_bytes(value)
+}
+
+#[cfg(feature = "bytes")]
+pub fn serialize_on_bytes_mut<T>(
+    buffer: &mut BytesMut,
+    value: T,
+) -> Result<(), Error>
+where
+    T: Serialize,
+{
+    Config::default().serialize_on_bytes_mut(buffer, value)
+}
+
+pub fn serialize_into_slice<T>(
+    slice: &mut [u8],
+    value: T,
+) -> Result<usize, Error>
+where
+    T: Serialize,
+{
+    Config::default().serialize_into_slice(slice, value)
+}
+
+pub fn serialize_into_writer<T, W>(writer: W, value: T) -> Result<(), Error>
+where
+    T: Serialize,
+    W: Write,
+{
+    Config::default().serialize_into_writer(writer, value)
+}
+
+pub fn serialize_on_buffer<T>(
+    buffer: &mut Vec<u8>,
+    value: T,
+) -> Result<(), Error>
+where
     T: Serialize,
 {
     Config::default().serialize_on_buffer(buffer, value)
 }
+
+/// Serializes the elements of an iterator as one sequence, without
+/// collecting them first. The length is written upfront when the iterator
+/// reports an exact size, as every [`ExactSizeIterator`] does; otherwise the
+/// sequence falls back to an unknown length, which only
+/// [`IntEncoding::Fixint`] supports.
+pub fn serialize_iter_as_seq<I, S>(
+    elements: I,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+    S: serde::Serializer,
+{
+    let elements = elements.into_iter();
+    let len = match elements.size_hint() {
+        (lower, Some(upper)) if lower == upper => Some(lower),
+        _ => None,
+    };
+    let mut seq = serializer.serialize_seq(len)?;
+    for element in elements {
+        seq.serialize_element(&element)?;
+    }
+    seq.end()
+}
+
+#[derive(Default)]
+pub struct IterSeq<I>(Cell<Option<I>>);
+
+impl<I> fmt::Debug for IterSeq<I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IterSeq").finish_non_exhaustive()
+    }
+}
+
+impl<I> IterSeq<I> {
+    pub fn new(elements: I) -> Self {
+        Self(Cell::new(Some(elements)))
+    }
+}
+
+impl<I> Serialize for IterSeq<I>
+where
+    I: IntoIterator,
+    I::Item: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let elements = self.0.take().ok_or_else(|| {
+            serde::ser::Error::custom("iterator was already serialized")
+        })?;
+        serialize_iter_as_seq(elements, serializer)
+    }
+}
+
+fn temp_path(path: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    Ok(path.with_file_name(temp_name))
+}
+
+#[cfg(unix)]
+async fn sync_parent(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::File::open(parent).await?.sync_all().await
+}
+
+#[cfg(not(unix))]
+async fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -3,6 +3,8 @@ use std::collections::BTreeMap;
 use anyhow::Result;
 use serde::Serialize;
 
+use super::internal::{ChannelSink, SerializationSink, Serializer};
+
 #[tokio::test]
 async fn serialize_bool() -> Result<()> {
     let mut buf = Vec::new();
@@ -67,6 +69,7 @@ async fn serialize_i32() -> Result<()> {
 #[tokio::test]
 async fn serialize_u64() -> Result<()> {
     let mut buf = Vec::new();
+    #[allow(clippy::unusual_byte_groupings)]
     crate::serialize(&mut buf, 0x02_4c_e8_72__12_34_56_78_u64).await?;
     assert_eq!(buf, &[0x78, 0x56, 0x34, 0x12, 0x72, 0xe8, 0x4c, 0x02]);
     Ok(())
@@ -83,6 +86,7 @@ async fn serialize_i64() -> Result<()> {
 #[tokio::test]
 async fn serialize_u128() -> Result<()> {
     let mut buf = Vec::new();
+    #[allow(clippy::unusual_byte_groupings)]
     crate::serialize(
         &mut buf,
         0x02_4c_e8_72__12_34_56_78__23_43_53_73__17_37_57_77_u128,
@@ -174,7 +178,7 @@ async fn serialize_some() -> Result<()> {
 async fn serialize_unit() -> Result<()> {
     let mut buf = Vec::new();
     crate::serialize(&mut buf, ()).await?;
-    assert_eq!(buf, &[]);
+    assert_eq!(buf, &[0_u8; 0]);
     Ok(())
 }
 
@@ -185,7 +189,7 @@ async fn serialize_unit_struct() -> Result<()> {
 
     let mut buf = Vec::new();
     crate::serialize(&mut buf, Top).await?;
-    assert_eq!(buf, &[]);
+    assert_eq!(buf, &[0_u8; 0]);
     Ok(())
 }
 
@@ -275,6 +279,7 @@ async fn serialize_seq_non_empty() -> Result<()> {
 #[tokio::test]
 async fn serialize_tuple() -> Result<()> {
     let mut buf = Vec::new();
+    #[allow(clippy::unusual_byte_groupings)]
     crate::serialize(&mut buf, ("foo", 0x02_4c_e8_72__12_34_56_78_u64, -2_i8))
         .await?;
     assert_eq!(&buf[.. 8], &[3, 0, 0, 0, 0, 0, 0, 0]);
@@ -293,6 +298,7 @@ async fn serialize_tuple_struct() -> Result<()> {
     struct MyTuple(&'static str, u64, i8);
 
     let mut buf = Vec::new();
+    #[allow(clippy::unusual_byte_groupings)]
     crate::serialize(
         &mut buf,
         MyTuple("foo", 0x02_4c_e8_72__12_34_56_78_u64, -2_i8),
@@ -401,6 +407,7 @@ async fn serialize_struct() -> Result<()> {
     }
 
     let mut buf = Vec::new();
+    #[allow(clippy::unusual_byte_groupings)]
     crate::serialize(
         &mut buf,
         MyStruct {
@@ -482,6 +489,7 @@ async fn serialize_into_buffer() -> Result<()> {
         bar: i8,
     }
 
+    #[allow(clippy::unusual_byte_groupings)]
     let value = MyStruct {
         name: "foo",
         foo: 0x02_4c_e8_72__12_34_56_78_u64,
@@ -520,6 +528,7 @@ async fn serialize_on_buffer() -> Result<()> {
         bar: i8,
     }
 
+    #[allow(clippy::unusual_byte_groupings)]
     let value = MyStruct {
         name: "foo",
         foo: 0x02_4c_e8_72__12_34_56_78_u64,
@@ -548,3 +557,816 @@ async fn serialize_on_buffer() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn serialize_byte_vec() -> Result<()> {
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, vec![1_u8, 3, 2, 5]).await?;
+    assert_eq!(&buf[.. 8], &[4, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&buf[8 ..], &[1, 3, 2, 5]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn serialize_byte_array() -> Result<()> {
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, ([7_u8, 8, 9], 0x1_02_u16)).await?;
+    assert_eq!(buf, &[7, 8, 9, 2, 1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn serialize_byte_runs_interleaved() -> Result<()> {
+    #[derive(Debug, Clone, Serialize)]
+    struct Pair(u8, u16);
+
+    let value = vec![Pair(1, 0x3_02), Pair(4, 0x6_05)];
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, value).await?;
+    assert_eq!(&buf[.. 8], &[2, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&buf[8 ..], &[1, 2, 3, 4, 5, 6]);
+    Ok(())
+}
+
+#[test]
+fn serialize_byte_vec_single_write() -> Result<()> {
+    #[derive(Debug, Default)]
+    struct CountingSink {
+        writes: Vec<Vec<u8>>,
+    }
+
+    impl SerializationSink for CountingSink {
+        fn send_raw_data(
+            &mut self,
+            data: &[u8],
+        ) -> Result<(), crate::ser::Error> {
+            self.writes.push(data.to_vec());
+            Ok(())
+        }
+
+        fn start_var_sized(
+            &mut self,
+            size: Option<usize>,
+        ) -> Result<(), crate::ser::Error> {
+            self.send_usize(size.unwrap_or(0))
+        }
+
+        fn advance_var_sized(&mut self) -> Result<(), crate::ser::Error> {
+            Ok(())
+        }
+
+        fn end_var_sized(&mut self) -> Result<(), crate::ser::Error> {
+            Ok(())
+        }
+    }
+
+    let blob: Vec<u8> = (0 .. 200).collect();
+    let mut serializer = Serializer::new(CountingSink::default());
+    blob.serialize(&mut serializer)?;
+    let writes = serializer.into_sink().writes;
+    assert_eq!(writes.len(), 2);
+    assert_eq!(writes[0], 200_u64.to_le_bytes());
+    assert_eq!(writes[1], blob);
+    Ok(())
+}
+
+#[tokio::test]
+async fn serialize_seq_unknown_len() -> Result<()> {
+    struct Unsized(Vec<Vec<u16>>);
+
+    impl Serialize for Unsized {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            serializer.collect_seq(self.0.iter().filter(|_| true))
+        }
+    }
+
+    let value = Unsized(vec![vec![1, 2], vec![3]]);
+    let expected = crate::serialize_into_buffer(&value)?;
+    assert_eq!(&expected[.. 8], &[2, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&expected[8 .. 16], &[2, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&expected[16 .. 20], &[1, 0, 2, 0]);
+    assert_eq!(&expected[20 .. 28], &[1, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&expected[28 ..], &[3, 0]);
+
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, value).await?;
+    assert_eq!(buf, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn serialize_seq_unknown_len_nested() -> Result<()> {
+    struct Unsized<T>(Vec<T>);
+
+    impl<T> Serialize for Unsized<T>
+    where
+        T: Serialize,
+    {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            serializer.collect_seq(self.0.iter().filter(|_| true))
+        }
+    }
+
+    let value =
+        (Unsized(vec![Unsized(vec![5_u8, 6, 7]), Unsized(vec![])]), 9_u8);
+    let expected = crate::serialize_into_buffer(&value)?;
+    assert_eq!(&expected[.. 8], &[2, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&expected[8 .. 16], &[3, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&expected[16 .. 19], &[5, 6, 7]);
+    assert_eq!(&expected[19 .. 27], &[0, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(&expected[27 ..], &[9]);
+
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, value).await?;
+    assert_eq!(buf, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_message_bytes_within_limit() -> Result<()> {
+    let value = (vec![1_u32, 2, 3], "abc");
+    let mut config = crate::ser::Config::new();
+    config.with_max_message_bytes(31);
+
+    let buf = config.serialize_into_buffer(&value)?;
+    assert_eq!(buf.len(), 31);
+
+    let mut streamed = Vec::new();
+    config.serialize(&mut streamed, value).await?;
+    assert_eq!(streamed, buf);
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_message_bytes_exceeded() -> Result<()> {
+    let value = (vec![1_u32, 2, 3], "abc");
+    let mut config = crate::ser::Config::new();
+    config.with_max_message_bytes(30);
+
+    let result = config.serialize_into_buffer(&value);
+    assert!(matches!(result, Err(crate::ser::Error::MessageTooLarge(30))));
+
+    let mut streamed = Vec::new();
+    let result = config.serialize(&mut streamed, value).await;
+    assert!(matches!(result, Err(crate::ser::Error::MessageTooLarge(30))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_output_bytes_counts_header() -> Result<()> {
+    let value = (vec![1_u32, 2, 3], "abc");
+    let mut config = crate::ser::Config::new();
+    config
+        .with_compression(crate::compress::Compression::None)
+        .with_max_output_bytes(32);
+
+    let buf = config.serialize_into_buffer(&value)?;
+    assert_eq!(buf.len(), 32);
+    let mut written = Vec::new();
+    config.serialize_into_writer(&mut written, &value)?;
+    assert_eq!(written, buf);
+
+    config.with_max_output_bytes(31);
+    let result = config.serialize_into_buffer(&value);
+    assert!(matches!(result, Err(crate::ser::Error::MessageTooLarge(31))));
+    let result = config.serialize_into_writer(Vec::new(), &value);
+    assert!(matches!(result, Err(crate::ser::Error::MessageTooLarge(_))));
+    let mut streamed = Vec::new();
+    let result = config.serialize(&mut streamed, value).await;
+    assert!(matches!(result, Err(crate::ser::Error::MessageTooLarge(_))));
+    Ok(())
+}
+
+#[cfg(feature = "lz4")]
+#[test]
+fn max_message_bytes_caps_compressed_output() -> Result<()> {
+    let value = vec![0_u8; 4096];
+    let mut config = crate::ser::Config::new();
+    config
+        .with_compression(crate::compress::Compression::Lz4)
+        .with_max_message_bytes(1024);
+    let buf = config.serialize_into_buffer(&value)?;
+    assert!(buf.len() <= 1024);
+
+    config.with_max_output_bytes(16);
+    let result = config.serialize_into_buffer(&value);
+    assert!(matches!(result, Err(crate::ser::Error::MessageTooLarge(16))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn serialize_with_progress() -> Result<()> {
+    let progress = crate::Progress::new();
+    assert_eq!(progress.bytes(), 0);
+    assert!(progress.last_io_activity().is_none());
+    assert!(progress.last_codec_activity().is_none());
+
+    let mut buf = Vec::new();
+    crate::ser::Config::new()
+        .with_progress(progress.clone())
+        .serialize(&mut buf, vec![vec![1_u16, 2], vec![3]])
+        .await?;
+
+    assert_eq!(progress.bytes(), buf.len() as u64);
+    assert_eq!(progress.elements(), 5);
+    assert_eq!(progress.depth(), 0);
+    assert!(progress.last_io_activity().is_some());
+    assert!(progress.last_codec_activity().is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn skip_field_without_presence() -> Result<()> {
+    #[derive(Serialize)]
+    struct Record {
+        id: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        note: Option<u8>,
+    }
+
+    let result = crate::serialize_into_buffer(Record { id: 1, note: None });
+    assert!(matches!(result, Err(crate::ser::Error::SkipNotAllowed)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn serialize_with_field_presence() -> Result<()> {
+    #[derive(Serialize)]
+    enum Entry {
+        Record {
+            id: u8,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            note: Option<u8>,
+        },
+    }
+
+    let mut config = crate::ser::Config::new();
+    config.with_field_presence();
+
+    let value = Entry::Record { id: 7, note: None };
+    let buf = config.serialize_into_buffer(&value)?;
+    assert_eq!(buf, [0, 0, 0, 0, 1, 7, 0]);
+
+    let value = Entry::Record { id: 7, note: Some(3) };
+    let mut streamed = Vec::new();
+    config.serialize(&mut streamed, value).await?;
+    assert_eq!(streamed, [0, 0, 0, 0, 1, 7, 1, 1, 3]);
+    Ok(())
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn serialize_into_buf_mut() -> Result<()> {
+    struct Unsized<T>(Vec<T>);
+
+    impl<T> Serialize for Unsized<T>
+    where
+        T: Serialize,
+    {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            serializer.collect_seq(self.0.iter().filter(|_| true))
+        }
+    }
+
+    let value = (
+        "head",
+        Unsized(vec![Unsized(vec![5_u8, 6, 7]), Unsized(vec![])]),
+        9_u8,
+    );
+    let expected = crate::serialize_into_buffer(&value)?;
+
+    let mut buf = bytes::BytesMut::new();
+    crate::ser::Config::new().serialize_into_buf_mut(&mut buf, &value)?;
+    assert_eq!(&buf[..], &expected[..]);
+
+    let mut small = [0; 16];
+    let error = crate::ser::Config::new()
+        .serialize_into_buf_mut(&mut small[..], &value)
+        .unwrap_err();
+    assert!(matches!(error, crate::ser::Error::IO(_)));
+    Ok(())
+}
+
+#[test]
+fn serialize_on_small_vec() -> Result<()> {
+    let value = (7_u16, "tiny", Some(true));
+    let expected = crate::serialize_into_buffer(value)?;
+
+    let mut buf = smallvec::SmallVec::<[u8; 32]>::new();
+    crate::ser::Config::new().serialize_on_buffer(&mut buf, value)?;
+    assert!(!buf.spilled());
+    assert_eq!(&buf[..], &expected[..]);
+    Ok(())
+}
+
+#[cfg(feature = "heapless")]
+#[test]
+fn serialize_on_heapless_vec() -> Result<()> {
+    let value = (7_u16, "tiny", Some(true));
+    let expected = crate::serialize_into_buffer(value)?;
+
+    let mut buf = heapless::Vec::<u8, 32>::new();
+    crate::ser::Config::new().serialize_on_buffer(&mut buf, value)?;
+    assert_eq!(&buf[..], &expected[..]);
+
+    let mut small = heapless::Vec::<u8, 8>::new();
+    let error = crate::ser::Config::new()
+        .serialize_on_buffer(&mut small, value)
+        .unwrap_err();
+    assert!(matches!(error, crate::ser::Error::BufferOverflow(8)));
+    Ok(())
+}
+
+#[test]
+fn serialize_into_slice() -> Result<()> {
+    let value = (vec![1_u32, 2], "packet");
+    let expected = crate::serialize_into_buffer(&value)?;
+
+    let mut packet = [0xee; 64];
+    let written = crate::serialize_into_slice(&mut packet, &value)?;
+    assert_eq!(written, expected.len());
+    assert_eq!(&packet[.. written], &expected[..]);
+    assert!(packet[written ..].iter().all(|byte| *byte == 0xee));
+
+    let mut exact = vec![0; expected.len()];
+    assert_eq!(crate::serialize_into_slice(&mut exact, &value)?, exact.len());
+
+    let mut short = [0; 20];
+    let error = crate::serialize_into_slice(&mut short, &value).unwrap_err();
+    assert!(matches!(error, crate::ser::Error::BufferOverflow(20)));
+    Ok(())
+}
+
+#[derive(Default)]
+struct CountingWriter {
+    buf: Vec<u8>,
+    writes: usize,
+    flushes: usize,
+    shutdowns: usize,
+}
+
+impl tokio::io::AsyncWrite for CountingWriter {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        this.writes += 1;
+        this.buf.extend_from_slice(buf);
+        std::task::Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.get_mut().flushes += 1;
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.get_mut().shutdowns += 1;
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn serialize_with_write_buffer() -> Result<()> {
+    let value: Vec<u64> = (0 .. 2000).collect();
+    let expected = crate::serialize_into_buffer(&value)?;
+
+    let mut device = CountingWriter::default();
+    crate::ser::Config::new()
+        .with_write_buffer(4096)?
+        .serialize(&mut device, value.clone())
+        .await?;
+    assert_eq!(device.buf, expected);
+    assert!(device.writes <= expected.len() / 4096 + 1);
+    assert_eq!(device.flushes, 0);
+
+    let mut device = CountingWriter::default();
+    crate::ser::Config::new()
+        .with_write_buffer(1 << 20)?
+        .with_flush_at_boundary()
+        .serialize(&mut device, value)
+        .await?;
+    assert_eq!(device.buf, expected);
+    assert_eq!(device.writes, 1);
+    assert_eq!(device.flushes, 1);
+
+    let error = crate::ser::Config::new().with_write_buffer(0).unwrap_err();
+    assert!(matches!(error, crate::ser::ConfigError::BufLimitTooLow(0)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn flush_and_shutdown_policy() -> Result<()> {
+    let value: Vec<u64> = (0 .. 200).collect();
+    let expected = crate::serialize_into_buffer(&value)?;
+
+    let mut device = CountingWriter::default();
+    crate::serialize(&mut device, value.clone()).await?;
+    assert_eq!((device.flushes, device.shutdowns), (0, 0));
+
+    let mut device = CountingWriter::default();
+    crate::ser::Config::new()
+        .with_flush_at_boundary()
+        .with_shutdown_at_end()
+        .serialize(&mut device, value.clone())
+        .await?;
+    assert_eq!(device.buf, expected);
+    assert_eq!((device.flushes, device.shutdowns), (1, 1));
+
+    let options = crate::ser::Options {
+        shutdown_at_end: Some(true),
+        ..crate::ser::Options::new()
+    };
+    let mut device = CountingWriter::default();
+    crate::ser::Config::new()
+        .serialize_with_options(&mut device, value, &options)
+        .await?;
+    assert_eq!(device.buf, expected);
+    assert_eq!((device.flushes, device.shutdowns), (0, 1));
+    Ok(())
+}
+
+struct Evens(u32);
+
+impl Serialize for Evens {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_seq((0 .. self.0).filter(|n| n % 2 == 0))
+    }
+}
+
+struct NestedEvens(u32);
+
+impl Serialize for NestedEvens {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_seq((0 .. self.0).filter(|n| n % 3 == 0).map(Evens))
+    }
+}
+
+#[tokio::test]
+async fn serialize_seekable_backpatches_lengths() -> Result<()> {
+    let expected = crate::serialize_into_buffer(NestedEvens(300))?;
+
+    let mut device = std::io::Cursor::new(vec![0xaa; 5]);
+    device.set_position(5);
+    crate::serialize_seekable(&mut device, NestedEvens(300)).await?;
+    let written = device.into_inner();
+    assert_eq!(&written[.. 5], &[0xaa; 5]);
+    assert_eq!(&written[5 ..], &expected[..]);
+
+    let fingerprint = crate::fingerprint::Fingerprint::from_u64(0x5eed);
+    let expected = crate::ser::Config::new()
+        .with_fingerprint(fingerprint)
+        .serialize_into_buffer((Evens(9), NestedEvens(40)))?;
+    let mut device = std::io::Cursor::new(Vec::new());
+    crate::ser::Config::new()
+        .with_fingerprint(fingerprint)
+        .with_write_buffer(256)?
+        .serialize_seekable(&mut device, (Evens(9), NestedEvens(40)))
+        .await?;
+    assert_eq!(device.into_inner(), expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn serialize_two_pass() -> Result<()> {
+    let expected = crate::serialize_into_buffer(NestedEvens(300))?;
+    let mut buf = Vec::new();
+    crate::ser::Config::new()
+        .with_two_pass()
+        .serialize(&mut buf, NestedEvens(300))
+        .await?;
+    assert_eq!(buf, expected);
+    Ok(())
+}
+
+#[test]
+fn channel_sink_uses_precomputed_lengths() -> Result<()> {
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(64);
+    let mut sink = ChannelSink::new(sender);
+    sink.set_lengths(vec![3]);
+    sink.start_var_sized(None)?;
+    let mut header = Vec::new();
+    while let Ok(byte) = receiver.try_recv() {
+        header.push(byte);
+    }
+    assert_eq!(header, 3_u64.to_le_bytes());
+    Ok(())
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn serialize_to_bytes() -> Result<()> {
+    let value = (vec!["frame"; 3], 42_u64);
+    let bytes = crate::serialize_to_bytes(&value)?;
+    assert_eq!(&bytes[..], &crate::serialize_into_buffer(&value)?[..]);
+
+    let shared = bytes.clone();
+    assert_eq!(shared.as_ptr(), bytes.as_ptr());
+    Ok(())
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn serialize_on_bytes_mut() -> Result<()> {
+    let mut buffer = bytes::BytesMut::new();
+    crate::serialize_on_bytes_mut(&mut buffer, NestedEvens(20))?;
+    let first_len = buffer.len();
+    crate::serialize_on_bytes_mut(&mut buffer, ("second", 2_u8))?;
+
+    let first = buffer.split_to(first_len);
+    assert_eq!(&first[..], &crate::serialize_into_buffer(NestedEvens(20))?[..]);
+    assert_eq!(
+        &buffer[..],
+        &crate::serialize_into_buffer(("second", 2_u8))?[..]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn serialize_with_buffer_pool() -> Result<()> {
+    let pool = crate::BufferPool::with_limits(4, 1 << 16);
+    let mut config = crate::ser::Config::new();
+    config.with_buffer_pool(pool.clone());
+
+    let first = config.serialize_into_buffer(NestedEvens(30))?;
+    let expected = first.clone();
+    let address = first.as_ptr();
+    pool.recycle(first);
+    assert_eq!(pool.len(), 1);
+    let second = config.serialize_into_buffer(NestedEvens(30))?;
+    assert_eq!(second.as_ptr(), address);
+    assert_eq!(second, expected);
+    assert!(pool.is_empty());
+
+    let mut buf = Vec::new();
+    config.serialize(&mut buf, NestedEvens(30)).await?;
+    assert_eq!(buf, expected);
+    assert!(!pool.is_empty());
+    let pooled = pool.len();
+    config.serialize(&mut buf, NestedEvens(30)).await?;
+    assert_eq!(pool.len(), pooled);
+
+    pool.recycle(Vec::with_capacity(1 << 20));
+    assert_eq!(pool.len(), pooled);
+    Ok(())
+}
+
+#[test]
+fn serialize_into_writer() -> Result<()> {
+    let value = ("head", NestedEvens(40), Evens(9), 7_u8);
+    let expected = crate::serialize_into_buffer(&value)?;
+    let mut written = Vec::new();
+    crate::serialize_into_writer(&mut written, &value)?;
+    assert_eq!(written, expected);
+
+    let fingerprint = crate::fingerprint::Fingerprint::from_u64(0x5eed);
+    let mut config = crate::ser::Config::new();
+    config.with_fingerprint(fingerprint);
+    let mut written = Vec::new();
+    config.serialize_into_writer(&mut written, &value)?;
+    assert_eq!(written, config.serialize_into_buffer(&value)?);
+
+    let mut small = [0; 16];
+    let error = crate::ser::Config::new()
+        .serialize_into_writer(&mut small[..], &value)
+        .unwrap_err();
+    assert!(matches!(error, crate::ser::Error::IO(_)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn builder_validates_upfront() -> Result<()> {
+    use crate::{ser::ConfigError, varint::IntEncoding};
+
+    let config = crate::ser::Config::builder()
+        .with_batch_limit(16)
+        .with_channel_limit(32)
+        .with_int_encoding(IntEncoding::Varint)
+        .build()?;
+    let mut buf = Vec::new();
+    config.serialize(&mut buf, (300_u32, "abc".to_owned())).await?;
+    assert_eq!(buf, [251, 0x2c, 1, 3, b'a', b'b', b'c']);
+
+    let derived = config.to_builder().with_utf8_chars().build()?;
+    assert_eq!(derived.serialize_into_buffer(300_u32)?, [251, 0x2c, 1]);
+
+    let error =
+        crate::ser::Config::builder().with_batch_limit(0).build().unwrap_err();
+    assert!(matches!(error, ConfigError::BufLimitTooLow(0)));
+
+    let error = crate::ser::Config::builder()
+        .with_batch_limit(128)
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigError::BatchExceedsChannel { batch: 128, channel: 64 }
+    ));
+
+    let error = crate::ser::ConfigBuilder::from(crate::ser::Config::postcard())
+        .with_utf8_chars()
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigError::ConflictingOptions("utf8_chars", "str_chars")
+    ));
+
+    let error = crate::ser::Config::builder()
+        .with_field_presence()
+        .with_field_count()
+        .with_int_encoding(IntEncoding::Leb128)
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigError::ConflictingOptions("int_encoding", _)
+    ));
+
+    let error = crate::ser::Config::builder()
+        .with_field_tags("Point", [("x", 1), ("y", 0)])
+        .build()
+        .unwrap_err();
+    assert!(matches!(error, ConfigError::ReservedFieldTag("y")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn error_classification() -> Result<()> {
+    use crate::ser::ErrorKind;
+
+    let mut buf = [0; 4];
+    let error = crate::serialize_into_slice(&mut buf, 1_u64).unwrap_err();
+    assert!(error.is_limit());
+    assert_eq!(error.kind(), ErrorKind::Limit);
+
+    let error = crate::ser::Config::new()
+        .with_max_message_bytes(4)
+        .serialize_into_buffer("too long")
+        .unwrap_err();
+    assert!(error.is_limit());
+
+    let error = crate::ser::Config::new()
+        .with_floats_rejected()
+        .serialize_into_buffer(1.5_f32)
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+    let io = std::io::Error::from(std::io::ErrorKind::BrokenPipe);
+    let error = crate::ser::Error::from(io);
+    assert!(error.is_io());
+    assert!(!error.is_eof());
+    Ok(())
+}
+
+#[test]
+fn error_into_io_error() -> Result<()> {
+    let io = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "closed");
+    let error = std::io::Error::from(crate::ser::Error::from(io));
+    assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe);
+    assert_eq!(error.to_string(), "closed");
+
+    let error = crate::ser::Config::new()
+        .with_floats_rejected()
+        .serialize_into_buffer(1.5_f64)
+        .unwrap_err();
+    let error = std::io::Error::from(error);
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(error.get_ref().unwrap().is::<crate::ser::Error>());
+    Ok(())
+}
+
+#[test]
+fn buffer_sink_introspection() -> Result<()> {
+    use super::internal::BufferSink;
+
+    let mut serializer = Serializer::new(BufferSink::new());
+    assert!(serializer.sink_mut().is_empty());
+    let mut boundaries = Vec::new();
+    for value in ["first", "second", "third"] {
+        value.serialize(&mut serializer)?;
+        serializer.sink_mut().send_raw_data(b"\n")?;
+        boundaries.push(serializer.sink_mut().position());
+    }
+    let sink = serializer.into_sink();
+    assert_eq!(sink.len(), 8 + 5 + 1 + 8 + 6 + 1 + 8 + 5 + 1);
+    assert_eq!(sink.position(), sink.len());
+    assert_eq!(boundaries, [14, 29, 43]);
+
+    let buf = sink.into_inner();
+    let mut start = 0;
+    for (end, expected) in
+        boundaries.into_iter().zip(["first", "second", "third"])
+    {
+        let decoded: String =
+            crate::deserialize_buffer(&buf[start .. end - 1])?;
+        assert_eq!(decoded, expected);
+        start = end;
+    }
+
+    let mut sink = BufferSink::with_buffer(vec![0xaa, 0xbb]);
+    assert_eq!((sink.len(), sink.position()), (2, 2));
+    sink.send_raw_data(&[0xcc])?;
+    assert_eq!(sink.as_slice(), [0xaa, 0xbb, 0xcc]);
+    Ok(())
+}
+
+struct Misdeclared {
+    declared: usize,
+    emitted: u8,
+    as_map: bool,
+}
+
+impl Serialize for Misdeclared {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::{SerializeMap, SerializeSeq};
+
+        if self.as_map {
+            let mut map = serializer.serialize_map(Some(self.declared))?;
+            for index in 0 .. self.emitted {
+                map.serialize_entry(&index, &index)?;
+            }
+            map.end()
+        } else {
+            let mut seq = serializer.serialize_seq(Some(self.declared))?;
+            for index in 0 .. self.emitted {
+                seq.serialize_element(&index)?;
+            }
+            seq.end()
+        }
+    }
+}
+
+#[test]
+fn declared_len_mismatch() -> Result<()> {
+    for as_map in [false, true] {
+        let value = Misdeclared { declared: 3, emitted: 3, as_map };
+        crate::serialize_into_buffer(&value)?;
+
+        let value = Misdeclared { declared: 3, emitted: 2, as_map };
+        let error = crate::serialize_into_buffer(&value).unwrap_err();
+        assert!(matches!(
+            error,
+            crate::ser::Error::SeqLengthMismatch { expected: 3, found: 2 }
+        ));
+
+        let value = vec![Misdeclared { declared: 1, emitted: 4, as_map }];
+        let error = crate::ser::Config::new()
+            .with_int_encoding(crate::varint::IntEncoding::Varint)
+            .serialize_into_buffer(&value)
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            crate::ser::Error::SeqLengthMismatch { expected: 1, found: 4 }
+        ));
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn iter_as_seq() -> Result<()> {
+    let expected = crate::serialize_into_buffer(vec![1_u16, 4, 9])?;
+    let squares = (1 ..= 3_u16).map(|n| n * n);
+    let value = crate::ser::IterSeq::new(squares);
+    assert_eq!(crate::serialize_into_buffer(&value)?, expected);
+    assert!(crate::serialize_into_buffer(&value).is_err());
+
+    let odd = (0 .. 10_u16).filter(|n| n % 2 == 1);
+    let mut buf = Vec::new();
+    crate::serialize(&mut buf, crate::ser::IterSeq::new(odd.clone())).await?;
+    let decoded: Vec<u16> = crate::deserialize_buffer(&buf)?;
+    assert_eq!(decoded, [1, 3, 5, 7, 9]);
+
+    let error = crate::ser::Config::new()
+        .with_int_encoding(crate::varint::IntEncoding::Varint)
+        .serialize_into_buffer(crate::ser::IterSeq::new(odd))
+        .unwrap_err();
+    assert!(matches!(error, crate::ser::Error::LengthRequired));
+    Ok(())
+}
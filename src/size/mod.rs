@@ -0,0 +1,191 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    marker::PhantomData,
+};
+
+#[cfg(feature = "derive")]
+pub use abcode_derive::EncodedSize;
+
+#[cfg(test)]
+mod test;
+
+const LEN_SIZE: usize = 8;
+
+pub trait EncodedSize {
+    const MIN_SIZE: usize;
+    const MAX_SIZE: Option<usize>;
+}
+
+pub const fn exact_size<T>() -> Option<usize>
+where
+    T: EncodedSize + ?Sized,
+{
+    match T::MAX_SIZE {
+        Some(max) if max == T::MIN_SIZE => Some(max),
+        _ => None,
+    }
+}
+
+pub const fn fits<T>(limit: usize) -> bool
+where
+    T: EncodedSize + ?Sized,
+{
+    matches!(T::MAX_SIZE, Some(max) if max <= limit)
+}
+
+#[doc(hidden)]
+pub const fn add_max(
+    first: Option<usize>,
+    second: Option<usize>,
+) -> Option<usize> {
+    match (first, second) {
+        (Some(first), Some(second)) => first.checked_add(second),
+        _ => None,
+    }
+}
+
+#[doc(hidden)]
+pub const fn mul_max(max: Option<usize>, count: usize) -> Option<usize> {
+    match max {
+        Some(max) => max.checked_mul(count),
+        None => None,
+    }
+}
+
+#[doc(hidden)]
+pub const fn larger_max(
+    first: Option<usize>,
+    second: Option<usize>,
+) -> Option<usize> {
+    match (first, second) {
+        (Some(first), Some(second)) if first >= second => Some(first),
+        (Some(_), Some(second)) => Some(second),
+        _ => None,
+    }
+}
+
+#[doc(hidden)]
+pub const fn smaller_min(first: usize, second: usize) -> usize {
+    if first <= second {
+        first
+    } else {
+        second
+    }
+}
+
+macro_rules! encoded_size_fixed {
+    ($($ty:ty => $size:expr),* $(,)?) => {
+        $(
+            impl EncodedSize for $ty {
+                const MIN_SIZE: usize = $size;
+                const MAX_SIZE: Option<usize> = Some($size);
+            }
+        )*
+    };
+}
+
+encoded_size_fixed!(
+    () => 0,
+    bool => 1,
+    u8 => 1,
+    u16 => 2,
+    u32 => 4,
+    u64 => 8,
+    u128 => 16,
+    usize => 8,
+    i8 => 1,
+    i16 => 2,
+    i32 => 4,
+    i64 => 8,
+    i128 => 16,
+    isize => 8,
+    f32 => 4,
+    f64 => 8,
+    char => 4,
+);
+
+macro_rules! encoded_size_unbounded {
+    ($(<$($param:ident),*> $ty:ty),* $(,)?) => {
+        $(
+            impl<$($param),*> EncodedSize for $ty {
+                const MIN_SIZE: usize = LEN_SIZE;
+                const MAX_SIZE: Option<usize> = None;
+            }
+        )*
+    };
+}
+
+encoded_size_unbounded!(
+    <> str,
+    <> String,
+    <T> [T],
+    <T> Vec<T>,
+    <T> VecDeque<T>,
+    <T> BTreeSet<T>,
+    <T, S> HashSet<T, S>,
+    <K, V> BTreeMap<K, V>,
+    <K, V, S> HashMap<K, V, S>,
+);
+
+impl<T> EncodedSize for PhantomData<T>
+where
+    T: ?Sized,
+{
+    const MIN_SIZE: usize = 0;
+    const MAX_SIZE: Option<usize> = Some(0);
+}
+
+impl<T> EncodedSize for Option<T>
+where
+    T: EncodedSize,
+{
+    const MIN_SIZE: usize = 1;
+    const MAX_SIZE: Option<usize> = add_max(Some(1), T::MAX_SIZE);
+}
+
+impl<T, const N: usize> EncodedSize for [T; N]
+where
+    T: EncodedSize,
+{
+    const MIN_SIZE: usize = T::MIN_SIZE * N;
+    const MAX_SIZE: Option<usize> = mul_max(T::MAX_SIZE, N);
+}
+
+impl<T> EncodedSize for &T
+where
+    T: EncodedSize + ?Sized,
+{
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const MAX_SIZE: Option<usize> = T::MAX_SIZE;
+}
+
+impl<T> EncodedSize for Box<T>
+where
+    T: EncodedSize + ?Sized,
+{
+    const MIN_SIZE: usize = T::MIN_SIZE;
+    const MAX_SIZE: Option<usize> = T::MAX_SIZE;
+}
+
+macro_rules! encoded_size_tuple {
+    ($($name:ident),+) => {
+        impl<$($name),+> EncodedSize for ($($name,)+)
+        where
+            $($name: EncodedSize,)+
+        {
+            const MIN_SIZE: usize = 0 $(+ $name::MIN_SIZE)+;
+            const MAX_SIZE: Option<usize> = {
+                let max = Some(0);
+                $(let max = add_max(max, $name::MAX_SIZE);)+
+                max
+            };
+        }
+    };
+}
+
+encoded_size_tuple!(T0);
+encoded_size_tuple!(T0, T1);
+encoded_size_tuple!(T0, T1, T2);
+encoded_size_tuple!(T0, T1, T2, T3);
+encoded_size_tuple!(T0, T1, T2, T3, T4);
+encoded_size_tuple!(T0, T1, T2, T3, T4, T5);
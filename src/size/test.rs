@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use super::{exact_size, fits, EncodedSize};
+
+#[test]
+fn builtin_sizes() -> Result<()> {
+    assert_eq!(exact_size::<(u8, u16, char, bool)>(), Some(8));
+    assert_eq!(exact_size::<[u32; 4]>(), Some(16));
+    assert_eq!(<Option<u64>>::MIN_SIZE, 1);
+    assert_eq!(<Option<u64>>::MAX_SIZE, Some(9));
+    assert_eq!(exact_size::<Option<u64>>(), None);
+    assert_eq!(<HashMap<String, u8>>::MIN_SIZE, 8);
+    assert_eq!(<Vec<u8>>::MAX_SIZE, None);
+    assert!(fits::<[u64; 8]>(64));
+    assert!(!fits::<[u64; 8]>(63));
+    assert!(!fits::<String>(usize::MAX));
+
+    let value = (7_u16, Some(3_i64), ['x', 'y']);
+    let buf = crate::serialize_into_buffer(value)?;
+    assert_eq!(Some(buf.len()), <(u16, Option<i64>, [char; 2])>::MAX_SIZE);
+    Ok(())
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derived_sizes() -> Result<()> {
+    use serde::Serialize;
+
+    #[derive(Serialize, super::EncodedSize)]
+    struct Header {
+        id: u32,
+        flags: [bool; 4],
+        parent: Option<u64>,
+    }
+
+    #[derive(Serialize, super::EncodedSize)]
+    enum Packet<T> {
+        Ping,
+        Data(Header, T),
+        Close { code: u16 },
+    }
+
+    #[derive(Serialize, super::EncodedSize)]
+    struct Named(String);
+
+    assert_eq!(Header::MIN_SIZE, 9);
+    assert_eq!(Header::MAX_SIZE, Some(17));
+    assert_eq!(<Packet<u8>>::MIN_SIZE, 4);
+    assert_eq!(<Packet<u8>>::MAX_SIZE, Some(4 + 17 + 1));
+    assert_eq!(<Packet<Named>>::MAX_SIZE, None);
+    assert_eq!(exact_size::<Named>(), None);
+
+    let header = Header { id: 1, flags: [true; 4], parent: Some(2) };
+    let packet = Packet::Data(header, 9_u8);
+    let buf = crate::serialize_into_buffer(&packet)?;
+    assert_eq!(Some(buf.len()), <Packet<u8>>::MAX_SIZE);
+    let buf = crate::serialize_into_buffer(Packet::<u8>::Ping)?;
+    assert_eq!(buf.len(), <Packet<u8>>::MIN_SIZE);
+    let buf = crate::serialize_into_buffer(Packet::<u8>::Close { code: 5 })?;
+    assert_eq!(buf.len(), 6);
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use std::{
+    fmt,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_compression::tokio::{
+    bufread::{BrotliDecoder, GzipDecoder, ZstdDecoder},
+    write::{BrotliEncoder, GzipEncoder, ZstdEncoder},
+};
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, ReadBuf};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamCompression {
+    Gzip,
+    Zstd,
+    Brotli,
+}
+
+impl StreamCompression {
+    pub fn writer<W>(self, device: W) -> CompressedWriter<W>
+    where
+        W: AsyncWrite,
+    {
+        match self {
+            Self::Gzip => CompressedWriter::Gzip(GzipEncoder::new(device)),
+            Self::Zstd => CompressedWriter::Zstd(ZstdEncoder::new(device)),
+            Self::Brotli => {
+                CompressedWriter::Brotli(Box::new(BrotliEncoder::new(device)))
+            },
+        }
+    }
+
+    pub fn reader<R>(self, device: R) -> CompressedReader<R>
+    where
+        R: AsyncBufRead,
+    {
+        match self {
+            Self::Gzip => {
+                let mut decoder = GzipDecoder::new(device);
+                decoder.multiple_members(true);
+                CompressedReader::Gzip(decoder)
+            },
+            Self::Zstd => {
+                let mut decoder = ZstdDecoder::new(device);
+                decoder.multiple_members(true);
+                CompressedReader::Zstd(decoder)
+            },
+            Self::Brotli => {
+                let mut decoder = BrotliDecoder::new(device);
+                decoder.multiple_members(true);
+                CompressedReader::Brotli(decoder)
+            },
+        }
+    }
+}
+
+impl fmt::Display for StreamCompression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Gzip => write!(f, "gzip"),
+            Self::Zstd => write!(f, "zstd"),
+            Self::Brotli => write!(f, "brotli"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CompressedWriter<W> {
+    Plain(W),
+    Gzip(GzipEncoder<W>),
+    Zstd(ZstdEncoder<W>),
+    Brotli(Box<BrotliEncoder<W>>),
+}
+
+impl<W> CompressedWriter<W> {
+    pub fn get_ref(&self) -> &W {
+        match self {
+            Self::Plain(device) => device,
+            Self::Gzip(encoder) => encoder.get_ref(),
+            Self::Zstd(encoder) => encoder.get_ref(),
+            Self::Brotli(encoder) => encoder.get_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        match self {
+            Self::Plain(device) => device,
+            Self::Gzip(encoder) => encoder.get_mut(),
+            Self::Zstd(encoder) => encoder.get_mut(),
+            Self::Brotli(encoder) => encoder.get_mut(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        match self {
+            Self::Plain(device) => device,
+            Self::Gzip(encoder) => encoder.into_inner(),
+            Self::Zstd(encoder) => encoder.into_inner(),
+            Self::Brotli(encoder) => (*encoder).into_inner(),
+        }
+    }
+}
+
+impl<W> AsyncWrite for CompressedWriter<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Plain(device) => Pin::new(device).poll_write(cx, buf),
+            Self::Gzip(encoder) => Pin::new(encoder).poll_write(cx, buf),
+            Self::Zstd(encoder) => Pin::new(encoder).poll_write(cx, buf),
+            Self::Brotli(encoder) => Pin::new(encoder).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(device) => Pin::new(device).poll_flush(cx),
+            Self::Gzip(encoder) => Pin::new(encoder).poll_flush(cx),
+            Self::Zstd(encoder) => Pin::new(encoder).poll_flush(cx),
+            Self::Brotli(encoder) => Pin::new(encoder).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(device) => Pin::new(device).poll_shutdown(cx),
+            Self::Gzip(encoder) => Pin::new(encoder).poll_shutdown(cx),
+            Self::Zstd(encoder) => Pin::new(encoder).poll_shutdown(cx),
+            Self::Brotli(encoder) => Pin::new(encoder).poll_shutdown(cx),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CompressedReader<R> {
+    Plain(R),
+    Gzip(GzipDecoder<R>),
+    Zstd(ZstdDecoder<R>),
+    Brotli(BrotliDecoder<R>),
+}
+
+impl<R> CompressedReader<R> {
+    pub fn get_ref(&self) -> &R {
+        match self {
+            Self::Plain(device) => device,
+            Self::Gzip(decoder) => decoder.get_ref(),
+            Self::Zstd(decoder) => decoder.get_ref(),
+            Self::Brotli(decoder) => decoder.get_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        match self {
+            Self::Plain(device) => device,
+            Self::Gzip(decoder) => decoder.get_mut(),
+            Self::Zstd(decoder) => decoder.get_mut(),
+            Self::Brotli(decoder) => decoder.get_mut(),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        match self {
+            Self::Plain(device) => device,
+            Self::Gzip(decoder) => decoder.into_inner(),
+            Self::Zstd(decoder) => decoder.into_inner(),
+            Self::Brotli(decoder) => decoder.into_inner(),
+        }
+    }
+}
+
+impl<R> AsyncRead for CompressedReader<R>
+where
+    R: AsyncBufRead + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut ReadBuf,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Plain(device) => Pin::new(device).poll_read(cx, buf),
+            Self::Gzip(decoder) => Pin::new(decoder).poll_read(cx, buf),
+            Self::Zstd(decoder) => Pin::new(decoder).poll_read(cx, buf),
+            Self::Brotli(decoder) => Pin::new(decoder).poll_read(cx, buf),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use tokio::io::{self, AsyncWriteExt};
+
+use super::StreamCompression;
+use crate::{de, ser};
+
+#[tokio::test]
+async fn messages_decode_at_flush_boundaries() -> Result<()> {
+    let compressions = [
+        StreamCompression::Gzip,
+        StreamCompression::Zstd,
+        StreamCompression::Brotli,
+    ];
+    for compression in compressions {
+        let mut ser_config = ser::Config::new();
+        ser_config
+            .with_stream_compression(compression)
+            .with_flush_at_boundary();
+        ser_config.ensure_valid()?;
+        let mut de_config = de::Config::new();
+        de_config.with_stream_compression(compression);
+
+        let (writer, reader) = io::duplex(1 << 16);
+        let mut writer = ser_config.compressed_writer(writer);
+        let mut reader = de_config.compressed_reader(reader);
+        for index in 0 .. 3_u32 {
+            let message = (index, "message".repeat(index as usize + 1));
+            ser_config.serialize(&mut writer, message.clone()).await?;
+            let decoded: (u32, String) =
+                de_config.deserialize(&mut reader).await?;
+            assert_eq!(decoded, message);
+        }
+        writer.shutdown().await?;
+        drop(writer);
+        de_config.with_hard_eof();
+        let error =
+            de_config.deserialize::<u32, _>(&mut reader).await.unwrap_err();
+        assert!(error.is_eof());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn plain_config_passes_through() -> Result<()> {
+    let ser_config = ser::Config::new();
+    let mut writer = ser_config.compressed_writer(Vec::new());
+    ser_config.serialize(&mut writer, 42_u64).await?;
+    let buf = writer.into_inner();
+    assert_eq!(buf, crate::serialize_into_buffer(42_u64)?);
+
+    let de_config = de::Config::new();
+    let mut reader = de_config.compressed_reader(&buf[..]);
+    let decoded: u64 = de_config.deserialize(&mut reader).await?;
+    assert_eq!(decoded, 42);
+    Ok(())
+}
+
+#[test]
+fn stream_compression_requires_flush_at_boundary() {
+    let mut ser_config = ser::Config::new();
+    ser_config.with_stream_compression(StreamCompression::Zstd);
+    assert!(matches!(
+        ser_config.ensure_valid(),
+        Err(ser::ConfigError::MissingOption(
+            "stream_compression",
+            "flush_at_boundary"
+        ))
+    ));
+    let error = ser::ConfigBuilder::new()
+        .with_stream_compression(StreamCompression::Zstd)
+        .build()
+        .unwrap_err();
+    assert!(matches!(error, ser::ConfigError::MissingOption(..)));
+}
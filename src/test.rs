@@ -0,0 +1,2076 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Document {
+    title: String,
+    published: bool,
+    tags: HashMap<String, u32>,
+    parent: Option<u64>,
+}
+
+fn document() -> Document {
+    let tags =
+        (0 .. 32).map(|i| (format!("tag-{:02}", i * 7 % 32), i)).collect();
+    Document {
+        title: "canonical".to_owned(),
+        published: true,
+        tags,
+        parent: Some(3),
+    }
+}
+
+#[tokio::test]
+async fn canonical_maps_are_sorted() -> Result<()> {
+    let value = document();
+    let canonical =
+        crate::ser::Config::canonical().serialize_into_buffer(&value)?;
+
+    let sorted: BTreeMap<_, _> = value.tags.clone().into_iter().collect();
+    let expected = crate::serialize_into_buffer((
+        &value.title,
+        value.published,
+        &sorted,
+        value.parent,
+    ))?;
+    assert_eq!(canonical, expected);
+
+    let mut streamed = Vec::new();
+    crate::ser::Config::canonical().serialize(&mut streamed, value).await?;
+    assert_eq!(streamed, canonical);
+    Ok(())
+}
+
+#[tokio::test]
+async fn canonical_encoding_is_unique() -> Result<()> {
+    let first =
+        crate::ser::Config::canonical().serialize_into_buffer(document())?;
+    for _ in 0 .. 8 {
+        let other =
+            crate::ser::Config::canonical().serialize_into_buffer(document())?;
+        assert_eq!(other, first);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn canonical_roundtrip() -> Result<()> {
+    let value = document();
+    let buf = crate::ser::Config::canonical().serialize_into_buffer(&value)?;
+
+    let decoded: Document =
+        crate::de::Config::canonical().deserialize_buffer(&buf)?;
+    assert_eq!(decoded, value);
+
+    let decoded: Document =
+        crate::de::Config::canonical().deserialize(&buf[..]).await?;
+    assert_eq!(decoded, value);
+    Ok(())
+}
+
+#[tokio::test]
+async fn canonical_nested_map_keys() -> Result<()> {
+    let mut value = HashMap::new();
+    value.insert(
+        BTreeMap::from([(2_u8, 0_u8), (1, 0)]),
+        HashMap::from([(9_u8, 'x'), (4, 'y'), (7, 'z')]),
+    );
+    value.insert(BTreeMap::from([(1_u8, 9_u8)]), HashMap::new());
+    value.insert(BTreeMap::new(), HashMap::from([(0_u8, 'w')]));
+
+    let buf = crate::ser::Config::canonical().serialize_into_buffer(&value)?;
+    let decoded: HashMap<BTreeMap<u8, u8>, HashMap<u8, char>> =
+        crate::de::Config::canonical().deserialize(&buf[..]).await?;
+    assert_eq!(decoded, value);
+    Ok(())
+}
+
+#[tokio::test]
+async fn canonical_rejects_unsorted_map() -> Result<()> {
+    let mut buf = crate::serialize_into_buffer(2_usize)?;
+    buf.extend_from_slice(&[5, 0, 3, 0]);
+
+    let value: HashMap<u8, u8> = crate::deserialize_buffer(&buf)?;
+    assert_eq!(value, HashMap::from([(5, 0), (3, 0)]));
+
+    let result: Result<HashMap<u8, u8>, _> =
+        crate::de::Config::canonical().deserialize(&buf[..]).await;
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::UnsortedMapKeys)
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn canonical_rejects_duplicate_map_keys() -> Result<()> {
+    let mut buf = crate::serialize_into_buffer(2_usize)?;
+    buf.extend_from_slice(&[5, 0, 5, 1]);
+
+    let result: Result<BTreeMap<u8, u8>, _> =
+        crate::de::Config::canonical().deserialize_buffer(&buf);
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::UnsortedMapKeys)
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn canonical_rejects_scalar_tags() -> Result<()> {
+    let result: Result<bool, _> =
+        crate::de::Config::canonical().deserialize_buffer(&[3]);
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::InvalidBool(3))
+    ));
+
+    let result: Result<Option<u8>, _> =
+        crate::de::Config::canonical().deserialize(&[2_u8, 0][..]).await;
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::InvalidOptionTag(2))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn canonical_rejects_trailing_bytes() -> Result<()> {
+    let result: Result<u8, _> =
+        crate::de::Config::canonical().deserialize_buffer(&[3, 4]);
+    assert!(matches!(
+        result.map_err(crate::de::Error::into_inner),
+        Err(crate::de::Error::TrailingBytes { count: 1 })
+    ));
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Rect(u16, u16),
+    Polygon { sides: u8, label: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Metadata {
+    author: String,
+    revision: u32,
+    shape: Shape,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Drawing {
+    id: u64,
+    #[serde(flatten)]
+    metadata: Metadata,
+    layers: Vec<Shape>,
+}
+
+fn drawing() -> Drawing {
+    Drawing {
+        id: 42,
+        metadata: Metadata {
+            author: "ana".to_owned(),
+            revision: 7,
+            shape: Shape::Polygon { sides: 6, label: Some("hex".to_owned()) },
+        },
+        layers: vec![
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Rect(3, 4),
+            Shape::Polygon { sides: 3, label: None },
+        ],
+    }
+}
+
+#[tokio::test]
+async fn self_describing_flatten_roundtrip() -> Result<()> {
+    let value = drawing();
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_self_describing();
+    let mut de_config = crate::de::Config::new();
+    de_config.with_self_describing().with_hard_eof();
+
+    let buf = ser_config.serialize_into_buffer(&value)?;
+    let decoded: Drawing = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, value);
+
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, value.clone()).await?;
+    assert_eq!(streamed, buf);
+    let decoded: Drawing = de_config.deserialize(&streamed[..]).await?;
+    assert_eq!(decoded, value);
+    Ok(())
+}
+
+#[tokio::test]
+async fn self_describing_layout() -> Result<()> {
+    let mut config = crate::ser::Config::new();
+    config.with_self_describing();
+
+    let buf = config.serialize_into_buffer(Shape::Empty)?;
+    assert_eq!(buf, [15, 5, 0, 0, 0, 0, 0, 0, 0, b'E', b'm', b'p', b't', b'y']);
+
+    let buf = config.serialize_into_buffer(Shape::Rect(3, 4))?;
+    let mut expected = vec![20, 1, 0, 0, 0, 0, 0, 0, 0];
+    expected.extend_from_slice(&[15, 4, 0, 0, 0, 0, 0, 0, 0]);
+    expected.extend_from_slice(b"Rect");
+    expected.extend_from_slice(&[19, 2, 0, 0, 0, 0, 0, 0, 0]);
+    expected.extend_from_slice(&[8, 3, 0, 8, 4, 0]);
+    assert_eq!(buf, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn self_describing_canonical_roundtrip() -> Result<()> {
+    let value = drawing();
+    let mut ser_config = crate::ser::Config::canonical();
+    ser_config.with_self_describing();
+    let mut de_config = crate::de::Config::canonical();
+    de_config.with_self_describing();
+
+    let buf = ser_config.serialize_into_buffer(&value)?;
+    let decoded: Drawing = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, value);
+    Ok(())
+}
+
+#[tokio::test]
+async fn self_describing_rejects_invalid_tag() -> Result<()> {
+    let mut config = crate::de::Config::new();
+    config.with_self_describing();
+
+    let error = config.deserialize_buffer::<u8>(&[0xee, 1]).unwrap_err();
+    assert_eq!(error.offset(), Some(1));
+    assert!(matches!(error.inner(), crate::de::Error::InvalidTag(0xee)));
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct DrawingSummary {
+    id: u64,
+    author: String,
+}
+
+#[tokio::test]
+async fn self_describing_skips_unknown_fields() -> Result<()> {
+    let mut value = drawing();
+    value
+        .layers
+        .push(Shape::Polygon { sides: 4, label: Some("a".repeat(600)) });
+    let buf = crate::ser::Config::new()
+        .with_self_describing()
+        .serialize_into_buffer(&value)?;
+    let mut de_config = crate::de::Config::new();
+    de_config.with_self_describing().with_hard_eof();
+
+    let expected = DrawingSummary { id: 42, author: "ana".to_owned() };
+    let decoded: DrawingSummary = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, expected);
+    let decoded: DrawingSummary = de_config.deserialize(&buf[..]).await?;
+    assert_eq!(decoded, expected);
+
+    let error = de_config
+        .deserialize_buffer::<DrawingSummary>(&buf[.. buf.len() - 1])
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::PrematureEof));
+
+    let error = de_config
+        .clone()
+        .with_max_bytes_len(512)
+        .deserialize_buffer::<DrawingSummary>(&buf)
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::LimitExceeded { max: 512, found: 600, .. }
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn ignored_any_requires_self_describing() -> Result<()> {
+    let buf = crate::serialize_into_buffer(7_u32)?;
+    let error =
+        crate::deserialize_buffer::<serde::de::IgnoredAny>(&buf).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::UnsupportedAny));
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecordV1 {
+    id: u32,
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecordV2 {
+    id: u32,
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    score: Option<u16>,
+    #[serde(default)]
+    flags: Vec<u8>,
+}
+
+#[tokio::test]
+async fn field_count_reads_older_records() -> Result<()> {
+    let old = RecordV1 { id: 9, name: "old".to_owned() };
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_field_count();
+    let mut de_config = crate::de::Config::new();
+    de_config.with_field_count().with_hard_eof();
+
+    let buf = ser_config.serialize_into_buffer(&old)?;
+    let expected = crate::serialize_into_buffer((2_usize, &old))?;
+    assert_eq!(buf, expected);
+
+    let upgraded: RecordV2 = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(
+        upgraded,
+        RecordV2 { id: 9, name: "old".to_owned(), score: None, flags: vec![] }
+    );
+
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, vec![old.clone(), old]).await?;
+    let upgraded: Vec<RecordV2> = de_config.deserialize(&streamed[..]).await?;
+    assert_eq!(upgraded.len(), 2);
+    assert_eq!(upgraded[1].name, "old");
+    Ok(())
+}
+
+#[tokio::test]
+async fn field_count_rejects_newer_records() -> Result<()> {
+    let new = RecordV2 {
+        id: 1,
+        name: "new".to_owned(),
+        score: Some(5),
+        flags: vec![1],
+    };
+    let buf = crate::ser::Config::new()
+        .with_field_count()
+        .serialize_into_buffer(&new)?;
+
+    let error = crate::de::Config::new()
+        .with_field_count()
+        .deserialize_buffer::<RecordV1>(&buf)
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::TooManyFields { expected: 2, found: 4 }
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn field_count_with_field_presence() -> Result<()> {
+    let value = RecordV2 {
+        id: 3,
+        name: "both".to_owned(),
+        score: None,
+        flags: vec![7, 8],
+    };
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_field_count().with_field_presence();
+    let mut de_config = crate::de::Config::new();
+    de_config.with_field_count().with_field_presence().with_hard_eof();
+
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, value.clone()).await?;
+    assert_eq!(streamed, ser_config.serialize_into_buffer(&value)?);
+    assert_eq!(&streamed[.. 8], &4_u64.to_le_bytes());
+
+    let decoded: RecordV2 = de_config.deserialize(&streamed[..]).await?;
+    assert_eq!(decoded, value);
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SparseRow {
+    id: u32,
+    a: Option<u8>,
+    b: Option<String>,
+    c: Option<u64>,
+    d: Option<u8>,
+    e: Option<u8>,
+    f: Option<u8>,
+    g: Option<u8>,
+    h: Option<(Option<u8>, u8)>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    tags: Vec<u8>,
+    nested: Option<Box<SparseRow>>,
+}
+
+#[tokio::test]
+async fn presence_bitmap_for_sparse_structs() -> Result<()> {
+    let empty = SparseRow {
+        id: 1,
+        a: None,
+        b: None,
+        c: None,
+        d: None,
+        e: None,
+        f: None,
+        g: None,
+        h: None,
+        tags: vec![],
+        nested: None,
+    };
+    let row = SparseRow {
+        id: 2,
+        b: Some("two".to_owned()),
+        h: Some((None, 3)),
+        tags: vec![4, 5],
+        nested: Some(Box::new(empty.clone())),
+        ..empty.clone()
+    };
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_presence_bitmap();
+    let mut de_config = crate::de::Config::new();
+    de_config.with_presence_bitmap().with_hard_eof();
+
+    let buf = ser_config.serialize_into_buffer(&empty)?;
+    assert_eq!(buf, [0b0000_0001, 0b0000_0000, 1, 0, 0, 0]);
+    assert_eq!(de_config.deserialize_buffer::<SparseRow>(&buf)?, empty);
+
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, row.clone()).await?;
+    assert_eq!(streamed, ser_config.serialize_into_buffer(&row)?);
+    assert_eq!(&streamed[.. 2], [0b0000_0101, 0b0000_0111]);
+    let decoded: SparseRow = de_config.deserialize(&streamed[..]).await?;
+    assert_eq!(decoded, row);
+
+    let error = crate::ser::Config::builder()
+        .with_presence_bitmap()
+        .with_field_count()
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        crate::ser::ConfigError::ConflictingOptions(
+            "presence_bitmap",
+            "field_count"
+        )
+    ));
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum ShapeV1 {
+    Circle(f64),
+    Rect { width: u32, height: u32 },
+    Empty,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum ShapeV2 {
+    Empty,
+    Line(u32, u32),
+    Rect { width: u32, height: u32 },
+    Circle(f64),
+}
+
+#[tokio::test]
+async fn variant_names_survive_reordering() -> Result<()> {
+    let shapes = vec![
+        ShapeV1::Circle(1.5),
+        ShapeV1::Rect { width: 3, height: 4 },
+        ShapeV1::Empty,
+    ];
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_variant_names();
+    let mut de_config = crate::de::Config::new();
+    de_config.with_variant_names().with_hard_eof();
+
+    let buf = ser_config.serialize_into_buffer(&shapes)?;
+    let expected = crate::serialize_into_buffer((
+        3_usize,
+        ("Circle", 1.5_f64),
+        ("Rect", 3_u32, 4_u32),
+        "Empty",
+    ))?;
+    assert_eq!(buf, expected);
+
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, shapes).await?;
+    assert_eq!(streamed, buf);
+
+    let decoded: Vec<ShapeV2> = de_config.deserialize(&streamed[..]).await?;
+    assert_eq!(
+        decoded,
+        vec![
+            ShapeV2::Circle(1.5),
+            ShapeV2::Rect { width: 3, height: 4 },
+            ShapeV2::Empty,
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn variant_names_reject_unknown_variant() -> Result<()> {
+    let buf = crate::ser::Config::new()
+        .with_variant_names()
+        .serialize_into_buffer(ShapeV2::Line(1, 2))?;
+
+    let error = crate::de::Config::new()
+        .with_variant_names()
+        .deserialize_buffer::<ShapeV1>(&buf)
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::UnknownVariantName { name: "ShapeV1", variant }
+            if variant == "Line"
+    ));
+    assert_eq!(error.offset(), Some(12));
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Level {
+    Low,
+    High,
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum LevelV2 {
+    Low,
+    High,
+    Unknown,
+    Critical,
+    Custom(String),
+}
+
+#[tokio::test]
+async fn unknown_variant_index_is_rejected() -> Result<()> {
+    let buf = crate::serialize_into_buffer(LevelV2::Critical)?;
+
+    let error = crate::deserialize_buffer::<Level>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::UnknownVariant { name: "Level", index: 3, count: 3 }
+    ));
+    assert_eq!(error.offset(), Some(4));
+
+    let error = crate::deserialize::<Level, _>(&buf[..]).await.unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::UnknownVariant { name: "Level", index: 3, count: 3 }
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_variant_fallback_needs_self_describing() -> Result<()> {
+    let buf = crate::serialize_into_buffer(vec![
+        LevelV2::High,
+        LevelV2::Critical,
+        LevelV2::Low,
+    ])?;
+    let mut de_config = crate::de::Config::new();
+    de_config.with_variant_fallback("Level", "Unknown").with_hard_eof();
+
+    let error = de_config.deserialize_buffer::<Vec<Level>>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::UnknownVariant { name: "Level", index: 3, count: 3 }
+    ));
+    let error =
+        de_config.deserialize::<Vec<Level>, _>(&buf[..]).await.unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::UnknownVariant { name: "Level", index: 3, count: 3 }
+    ));
+
+    let buf = crate::serialize_into_buffer(vec![
+        LevelV2::Custom("x".to_owned()),
+        LevelV2::Low,
+    ])?;
+    let error = de_config.deserialize_buffer::<Vec<Level>>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::UnknownVariant { name: "Level", index: 4, count: 3 }
+    ));
+
+    let mut by_name = de_config.clone();
+    by_name.with_variant_names();
+    let buf =
+        crate::ser::Config::new().with_variant_names().serialize_into_buffer(
+            vec![LevelV2::Custom("x".to_owned()), LevelV2::High],
+        )?;
+    let error = by_name.deserialize_buffer::<Vec<Level>>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::UnknownVariantName { name: "Level", variant }
+            if variant == "Custom"
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_variant_fallback_skips_self_describing_payload() -> Result<()>
+{
+    let buf = crate::ser::Config::new()
+        .with_self_describing()
+        .serialize_into_buffer(vec![
+            LevelV2::Custom("boiling".to_owned()),
+            LevelV2::Low,
+        ])?;
+
+    let decoded: Vec<Level> = crate::de::Config::new()
+        .with_self_describing()
+        .with_variant_fallback("Level", "Unknown")
+        .with_hard_eof()
+        .deserialize_buffer(&buf)?;
+    assert_eq!(decoded, vec![Level::Unknown, Level::Low]);
+
+    let error = crate::de::Config::new()
+        .with_self_describing()
+        .deserialize_buffer::<Vec<Level>>(&buf)
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::UnknownVariantName { name: "Level", variant }
+            if variant == "Custom"
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn sorted_map_keys_without_canonical_profile() -> Result<()> {
+    let value = HashMap::from([(30_u16, "c"), (10, "a"), (20, "b")]);
+    let buf = crate::ser::Config::new()
+        .with_sorted_map_keys()
+        .serialize_into_buffer(&value)?;
+    assert_eq!(
+        buf,
+        crate::ser::Config::canonical().serialize_into_buffer(&value)?
+    );
+
+    let mut trailing = buf.clone();
+    trailing.push(0xff);
+    let decoded: HashMap<u16, String> = crate::de::Config::new()
+        .with_sorted_map_keys()
+        .deserialize(&trailing[..])
+        .await?;
+    assert_eq!(decoded.len(), 3);
+
+    let mut unsorted = crate::serialize_into_buffer(2_usize)?;
+    unsorted.extend_from_slice(&[5, 0, 3, 0]);
+    let error = crate::de::Config::new()
+        .with_sorted_map_keys()
+        .deserialize_buffer::<HashMap<u8, u8>>(&unsorted)
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::UnsortedMapKeys));
+    Ok(())
+}
+
+#[tokio::test]
+async fn float_policies() -> Result<()> {
+    let quiet = f64::from_bits(0x7ff8_0000_0000_0001);
+    let negative = -f64::NAN;
+    let payload = f32::from_bits(0x7fc0_0abc);
+
+    let mut config = crate::ser::Config::new();
+    config.with_canonical_nan();
+    let first = config.serialize_into_buffer((quiet, payload))?;
+    let second = config.serialize_into_buffer((negative, f32::NAN))?;
+    assert_eq!(first, second);
+    assert_ne!(
+        crate::serialize_into_buffer(quiet)?,
+        crate::serialize_into_buffer(negative)?
+    );
+    let finite = config.serialize_into_buffer(1.5_f64)?;
+    assert_eq!(finite, crate::serialize_into_buffer(1.5_f64)?);
+
+    let mut config = crate::de::Config::new();
+    config.with_finite_floats();
+    let value: f64 = config.deserialize(&finite[..]).await?;
+    assert_eq!(value, 1.5);
+    for buf in [
+        crate::serialize_into_buffer(f64::INFINITY)?,
+        crate::serialize_into_buffer(f64::NAN)?,
+    ] {
+        let error = config.deserialize_buffer::<f64>(&buf).unwrap_err();
+        assert!(matches!(error.inner(), crate::de::Error::NonFiniteFloat(_)));
+    }
+    let buf = crate::serialize_into_buffer(f32::NEG_INFINITY)?;
+    let error = config.deserialize_buffer::<f32>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::NonFiniteFloat(value) if *value == f64::NEG_INFINITY
+    ));
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Ledger {
+    account: String,
+    balance: u64,
+    rate: Option<f64>,
+}
+
+#[tokio::test]
+async fn float_free_profile() -> Result<()> {
+    let exact = Ledger { account: "alice".to_owned(), balance: 10, rate: None };
+    let buf = crate::ser::Config::float_free().serialize_into_buffer(&exact)?;
+    let decoded: Ledger =
+        crate::de::Config::float_free().deserialize(&buf[..]).await?;
+    assert_eq!(decoded, exact);
+
+    let inexact = Ledger { rate: Some(0.5), ..exact };
+    let error = crate::ser::Config::float_free()
+        .serialize_into_buffer(&inexact)
+        .unwrap_err();
+    assert!(matches!(error, crate::ser::Error::FloatRejected));
+
+    let buf = crate::serialize_into_buffer(&inexact)?;
+    let error = crate::de::Config::float_free()
+        .deserialize_buffer::<Ledger>(&buf)
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::FloatRejected));
+
+    let buf = crate::ser::Config::new()
+        .with_self_describing()
+        .serialize_into_buffer((1_u8, 2.5_f32))?;
+    let error = crate::de::Config::new()
+        .with_self_describing()
+        .with_floats_rejected()
+        .deserialize_buffer::<(u8, serde::de::IgnoredAny)>(&buf)
+        .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::FloatRejected));
+    Ok(())
+}
+
+#[tokio::test]
+async fn utf8_chars() -> Result<()> {
+    let chars = vec!['a', 'é', '€', '🦀'];
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_utf8_chars();
+    let buf = ser_config.serialize_into_buffer(&chars)?;
+    assert_eq!(buf.len(), 8 + 1 + 2 + 3 + 4);
+    assert_eq!(crate::serialize_into_buffer(&chars)?.len(), 8 + 16);
+
+    let mut de_config = crate::de::Config::new();
+    de_config.with_utf8_chars();
+    let decoded: Vec<char> = de_config.deserialize(&buf[..]).await?;
+    assert_eq!(decoded, chars);
+
+    let buf =
+        ser_config.with_self_describing().serialize_into_buffer(('ß', 7_u8))?;
+    let (_, byte): (serde::de::IgnoredAny, u8) =
+        de_config.with_self_describing().deserialize_buffer(&buf)?;
+    assert_eq!(byte, 7);
+
+    let mut config = crate::de::Config::new();
+    config.with_utf8_chars();
+    for invalid in [&[0x80][..], &[0xc0, 0x80], &[0xed, 0xa0, 0x80], &[0xf8]] {
+        let error = config.deserialize_buffer::<char>(invalid).unwrap_err();
+        assert!(matches!(
+            error.inner(),
+            crate::de::Error::InvalidUtf8Char(bytes) if bytes == invalid
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Route {
+    Idle,
+    Loop(u32),
+    Leg { east: i64, north: i64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Fleet {
+    name: String,
+    initial: char,
+    routes: Vec<Route>,
+    owners: BTreeMap<u16, Option<String>>,
+    offset: i32,
+    total: u128,
+    ratio: f64,
+}
+
+struct Unsized(u32);
+
+impl Serialize for Unsized {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_seq((0 .. self.0).filter(|n| n % 2 == 0))
+    }
+}
+
+fn fleet() -> Fleet {
+    Fleet {
+        name: "migration".to_owned(),
+        initial: 'µ',
+        routes: vec![
+            Route::Idle,
+            Route::Loop(70_000),
+            Route::Leg { east: -3, north: 1 << 40 },
+        ],
+        owners: [(1, Some("ana".to_owned())), (300, None)].into(),
+        offset: -250,
+        total: u128::MAX,
+        ratio: 0.25,
+    }
+}
+
+#[tokio::test]
+async fn bincode_fixint_compatible() -> Result<()> {
+    let value = fleet();
+    let buf = crate::ser::Config::bincode().serialize_into_buffer(&value)?;
+    assert_eq!(buf, bincode::serialize(&value)?);
+
+    let mut streamed = Vec::new();
+    crate::ser::Config::bincode()
+        .serialize(&mut streamed, value.clone())
+        .await?;
+    assert_eq!(streamed, buf);
+
+    let decoded: Fleet =
+        crate::de::Config::bincode().deserialize(&buf[..]).await?;
+    assert_eq!(decoded, value);
+    Ok(())
+}
+
+#[tokio::test]
+async fn bincode_varint_compatible() -> Result<()> {
+    use bincode::Options;
+
+    let value = fleet();
+    let expected = bincode::DefaultOptions::new().serialize(&value)?;
+    let mut ser_config = crate::ser::Config::bincode();
+    ser_config.with_int_encoding(crate::varint::IntEncoding::Varint);
+    let buf = ser_config.serialize_into_buffer(&value)?;
+    assert_eq!(buf, expected);
+
+    let mut de_config = crate::de::Config::bincode();
+    de_config.with_int_encoding(crate::varint::IntEncoding::Varint);
+    let decoded: Fleet = de_config.deserialize(&buf[..]).await?;
+    assert_eq!(decoded, value);
+
+    let error = ser_config.serialize_into_buffer(Unsized(4)).unwrap_err();
+    assert!(matches!(error, crate::ser::Error::LengthRequired));
+
+    let error =
+        de_config.deserialize_buffer::<u16>(&[252, 0, 0, 1, 0]).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::VarintOverflow(65536)));
+    let error = de_config.deserialize_buffer::<u16>(&[255]).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::InvalidVarintMarker(255)
+    ));
+    for non_minimal in [&[251, 5, 0][..], &[252, 0, 1, 0, 0]] {
+        let error =
+            de_config.deserialize_buffer::<u32>(non_minimal).unwrap_err();
+        assert!(matches!(
+            error.inner(),
+            crate::de::Error::NonMinimalVarint(5 | 256)
+        ));
+    }
+
+    let mut canonical = crate::de::Config::canonical();
+    canonical.with_int_encoding(crate::varint::IntEncoding::Varint);
+    let error = canonical.deserialize_buffer::<u16>(&[251, 5, 0]).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::NonMinimalVarint(5)));
+    let mut lenient = crate::de::Config::new();
+    lenient.with_int_encoding(crate::varint::IntEncoding::Varint);
+    assert_eq!(lenient.deserialize_buffer::<u16>(&[251, 5, 0])?, 5);
+    Ok(())
+}
+
+#[tokio::test]
+async fn postcard_compatible() -> Result<()> {
+    let value = fleet();
+    let buf = crate::ser::Config::postcard().serialize_into_buffer(&value)?;
+    assert_eq!(buf, postcard::to_allocvec(&value)?);
+
+    let mut streamed = Vec::new();
+    crate::ser::Config::postcard()
+        .serialize(&mut streamed, value.clone())
+        .await?;
+    assert_eq!(streamed, buf);
+
+    let decoded: Fleet =
+        crate::de::Config::postcard().deserialize(&buf[..]).await?;
+    assert_eq!(decoded, value);
+    let decoded: Fleet = postcard::from_bytes(&buf)?;
+    assert_eq!(decoded, value);
+
+    let config = crate::de::Config::postcard();
+    let error =
+        config.deserialize_buffer::<u16>(&[0x80, 0x80, 0x04]).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::VarintOverflow(65536)));
+    let error = config.deserialize_buffer::<u128>(&[0xff; 20]).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::VarintTooLong));
+    for non_minimal in [&[0x85, 0x00][..], &[0x80, 0x80, 0x00]] {
+        let error = config.deserialize_buffer::<u32>(non_minimal).unwrap_err();
+        assert!(matches!(
+            error.inner(),
+            crate::de::Error::NonMinimalVarint(5 | 0)
+        ));
+    }
+    assert_eq!(config.deserialize_buffer::<u32>(&[0x80, 0x01])?, 128);
+    let mut canonical = crate::de::Config::canonical();
+    canonical.with_int_encoding(crate::varint::IntEncoding::Leb128);
+    let error = canonical.deserialize_buffer::<u16>(&[0x85, 0x00]).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::NonMinimalVarint(5)));
+    let error = config.deserialize_buffer::<char>(&[5, b'a']).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::CharTooLong(5)));
+    let error =
+        config.deserialize_buffer::<char>(&[2, b'a', b'b']).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::InvalidUtf8Char(_)));
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "Account")]
+struct AccountV1 {
+    id: u64,
+    name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "Account")]
+struct AccountV2 {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    email: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "Account")]
+struct AccountV3 {
+    email: Option<String>,
+    name: String,
+    id: u64,
+}
+
+#[tokio::test]
+async fn tagged_fields_evolve() -> Result<()> {
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_tagged_fields();
+    let mut de_config = crate::de::Config::new();
+    de_config.with_tagged_fields().with_hard_eof();
+
+    let v2 = AccountV2 {
+        id: 9,
+        name: "ana".to_owned(),
+        email: Some("ana@example.com".to_owned()),
+    };
+    let buf = ser_config.serialize_into_buffer(vec![v2.clone(), v2.clone()])?;
+    let old: Vec<AccountV1> = de_config.deserialize(&buf[..]).await?;
+    assert_eq!(old, vec![AccountV1 { id: 9, name: "ana".to_owned() }; 2]);
+
+    let buf = ser_config.serialize_into_buffer(&old[0])?;
+    let new: AccountV2 = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(new, AccountV2 { email: None, ..v2.clone() });
+
+    let tags = [("id", 1), ("name", 2), ("email", 3)];
+    let buf = ser_config.serialize_into_buffer(&v2)?;
+    let reordered: AccountV3 =
+        de_config.with_field_tags("Account", tags)?.deserialize_buffer(&buf)?;
+    assert_eq!(reordered.email, v2.email);
+    assert_eq!((reordered.id, &reordered.name), (v2.id, &v2.name));
+    let buf = crate::ser::Config::new()
+        .with_field_tags("Account", tags)?
+        .serialize_into_buffer(&reordered)?;
+    assert_eq!(de_config.deserialize_buffer::<AccountV2>(&buf)?, v2);
+
+    let error = crate::de::Config::new()
+        .with_field_tags("Account", [("id", 0)])
+        .unwrap_err();
+    assert!(matches!(error, crate::de::ConfigError::ReservedFieldTag("id")));
+    Ok(())
+}
+
+struct RawBytes<'a>(&'a [u8]);
+
+impl Serialize for RawBytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+#[tokio::test]
+async fn blob_streaming() -> Result<()> {
+    use tokio::io::AsyncReadExt;
+
+    let blob: Vec<u8> = (0 .. 100_000).map(|i| (i % 253) as u8).collect();
+    let mut ser_config = crate::ser::Config::new();
+    ser_config
+        .with_self_describing()
+        .with_int_encoding(crate::varint::IntEncoding::Varint);
+    let mut de_config = crate::de::Config::new();
+    de_config
+        .with_self_describing()
+        .with_int_encoding(crate::varint::IntEncoding::Varint);
+
+    let mut buf = Vec::new();
+    ser_config.serialize_blob(&mut buf, blob.len() as u64, &blob[..]).await?;
+    assert_eq!(buf, ser_config.serialize_into_buffer(RawBytes(&blob))?);
+
+    buf.extend_from_slice(b"rest");
+    let mut reader = de_config.deserialize_blob(&buf[..]).await?;
+    assert_eq!(reader.len(), blob.len() as u64);
+    let mut streamed = Vec::new();
+    reader.read_to_end(&mut streamed).await?;
+    assert_eq!(streamed, blob);
+    assert_eq!(reader.remaining(), 0);
+    assert_eq!(reader.into_inner(), b"rest");
+
+    let error = crate::ser::serialize_blob(Vec::new(), 10, &blob[.. 4])
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        crate::ser::Error::BlobTruncated { expected: 10, found: 4 }
+    ));
+
+    let buf = crate::serialize_into_buffer(RawBytes(&blob[.. 8]))?;
+    let mut reader = crate::de::deserialize_blob(&buf[.. 12]).await?;
+    let error = reader.read_to_end(&mut Vec::new()).await.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    let error = de_config.deserialize_blob(&[0xff_u8][..]).await.unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::InvalidTag(0xff)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn chunked_strings() -> Result<()> {
+    let text = "façade € 😀 ".repeat(20_000);
+    let buf = crate::serialize_into_buffer(&text)?;
+    let mut decoded = Vec::new();
+    let count = crate::de::deserialize_str_into(&buf[..], &mut decoded).await?;
+    assert_eq!(count, text.len() as u64);
+    assert_eq!(String::from_utf8(decoded)?, text);
+
+    let mut de_config = crate::de::Config::new();
+    de_config.with_self_describing();
+    let buf = crate::ser::Config::new()
+        .with_self_describing()
+        .serialize_into_buffer(RawBytes(text.as_bytes()))?;
+    let mut decoded = Vec::new();
+    de_config.deserialize_bytes_into(&buf[..], &mut decoded).await?;
+    assert_eq!(decoded, text.as_bytes());
+    let error =
+        de_config.deserialize_str_into(&buf[..], Vec::new()).await.unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::InvalidTag(_)));
+
+    let mut buf = 3_u64.to_le_bytes().to_vec();
+    buf.extend_from_slice(&[b'a', 0xff, b'b']);
+    let error = crate::de::deserialize_str_into(&buf[..], Vec::new())
+        .await
+        .unwrap_err();
+    assert_eq!(error.offset(), Some(9));
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::InvalidUtf8Char(bytes) if bytes == &[0xff]
+    ));
+
+    let mut buf = 1_u64.to_le_bytes().to_vec();
+    buf.push(0xc3);
+    let error = crate::de::deserialize_str_into(&buf[..], Vec::new())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::InvalidUtf8Char(bytes) if bytes == &[0xc3]
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn progress_callbacks() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{Progress, ProgressReport};
+
+    let value: Vec<(u32, String)> =
+        (0 .. 20_000).map(|i| (i, format!("entry-{}", i))).collect();
+
+    let reports = Arc::new(Mutex::new(Vec::<ProgressReport>::new()));
+    let sink = reports.clone();
+    let progress = Progress::with_callback(16 * 1024, move |report| {
+        sink.lock().unwrap().push(report);
+    });
+    let mut buf = Vec::new();
+    crate::ser::Config::new()
+        .with_progress(progress.clone())
+        .serialize(&mut buf, value.clone())
+        .await?;
+    let serialized = std::mem::take(&mut *reports.lock().unwrap());
+    assert!(serialized.len() >= buf.len() / (32 * 1024));
+    assert!(serialized.windows(2).all(|pair| pair[0].bytes < pair[1].bytes));
+    assert!(serialized.iter().all(|report| report.bytes <= buf.len() as u64));
+    assert_eq!(progress.report().elements, 60_000);
+
+    let sink = reports.clone();
+    let progress = Progress::with_callback(16 * 1024, move |report| {
+        sink.lock().unwrap().push(report);
+    });
+    let decoded: Vec<(u32, String)> = crate::de::Config::new()
+        .with_progress(progress.clone())
+        .deserialize(&buf[..])
+        .await?;
+    assert_eq!(decoded, value);
+    let deserialized = reports.lock().unwrap();
+    assert!(deserialized.len() >= buf.len() / (32 * 1024));
+    assert!(deserialized
+        .windows(2)
+        .all(|pair| pair[0].elements <= pair[1].elements));
+    assert_eq!(
+        progress.report(),
+        ProgressReport { bytes: buf.len() as u64, elements: 60_000 }
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn structured_decode_errors() -> Result<()> {
+    use crate::de::Error;
+
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_self_describing();
+    let mut de_config = crate::de::Config::new();
+    de_config.with_self_describing();
+
+    let buf = ser_config.serialize_into_buffer("seven")?;
+    let error = de_config.deserialize_buffer::<u8>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        Error::InvalidType { found, .. } if found == "string \"seven\""
+    ));
+
+    let buf = ser_config.serialize_into_buffer(vec![1_u8, 2])?;
+    let error = de_config.deserialize_buffer::<(u8, u8, u8)>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        Error::InvalidLength { expected, found: 2 }
+            if expected == "a tuple of size 3"
+    ));
+
+    let buf = crate::serialize_into_buffer(0_u8)?;
+    let error =
+        crate::deserialize_buffer::<std::num::NonZeroU8>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        Error::InvalidValue { found, .. } if found == "integer `0`"
+    ));
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Strict {
+        #[allow(dead_code)]
+        id: u8,
+    }
+    let buf = ser_config
+        .serialize_into_buffer(BTreeMap::from([("id", 1_u8), ("extra", 2)]))?;
+    let error = de_config.deserialize_buffer::<Strict>(&buf).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        Error::UnknownField { field, expected: &["id"] } if field == "extra"
+    ));
+
+    let buf = ser_config.serialize_into_buffer(BTreeMap::<&str, u8>::new())?;
+    let error = de_config.deserialize_buffer::<Strict>(&buf).unwrap_err();
+    assert!(matches!(error.inner(), Error::MissingField("id")));
+    Ok(())
+}
+
+#[tokio::test]
+async fn datagram_roundtrip() -> Result<()> {
+    use std::time::Duration;
+
+    use tokio::net::UdpSocket;
+
+    let server = UdpSocket::bind("127.0.0.1:0").await?;
+    let client = UdpSocket::bind("127.0.0.1:0").await?;
+    let value = document();
+
+    crate::ser::send_datagram_to(&client, server.local_addr()?, &value).await?;
+    let (decoded, source): (Document, _) =
+        crate::de::recv_datagram_from(&server).await?;
+    assert_eq!(decoded, value);
+    assert_eq!(source, client.local_addr()?);
+
+    let error = crate::ser::Config::new()
+        .with_mtu(8)
+        .send_datagram_to(&client, server.local_addr()?, &value)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        crate::ser::Error::DatagramTooLarge { mtu: 8, .. }
+    ));
+
+    client.connect(server.local_addr()?).await?;
+    crate::ser::Config::new().send_datagram(&client, [7_u64; 4]).await?;
+    let error = crate::de::Config::new()
+        .with_mtu(16)
+        .recv_datagram::<[u64; 4]>(&server)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        crate::de::Error::DatagramTooLarge { len: 17, mtu: 16 }
+    ));
+
+    let error = crate::de::Config::new()
+        .with_read_timeout(Duration::from_millis(10))
+        .recv_datagram::<u8>(&server)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, crate::de::Error::TimedOut));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_datagram_roundtrip() -> Result<()> {
+    let (left, right) = tokio::net::UnixDatagram::pair()?;
+    let value = document();
+    crate::ser::Config::new().send_unix_datagram(&left, &value).await?;
+    let decoded: Document =
+        crate::de::Config::new().recv_unix_datagram(&right).await?;
+    assert_eq!(decoded, value);
+    Ok(())
+}
+
+#[tokio::test]
+async fn per_call_options() -> Result<()> {
+    use crate::de::{Limit, Options};
+
+    let ser_config = crate::ser::Config::new();
+    let de_config = crate::de::Config::new();
+    let mut buf = ser_config.serialize_into_buffer(vec![1_u32, 2, 3])?;
+    buf.push(0);
+
+    let decoded: Vec<u32> = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, [1, 2, 3]);
+
+    let strict = Options { hard_eof: Some(true), ..Options::new() };
+    let error = de_config
+        .deserialize_buffer_with_options::<Vec<u32>>(&buf, &strict)
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::TrailingBytes { count: 1 }
+    ));
+
+    let small = Options { max_seq_len: Some(2), ..Options::new() };
+    let error = de_config
+        .deserialize_with_options::<Vec<u32>, _>(&buf[..], &small)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::LimitExceeded {
+            limit: Limit::SeqLen,
+            max: 2,
+            found: 3
+        }
+    ));
+    let decoded: Vec<u32> =
+        de_config.deserialize_with_options(&buf[..], &Options::new()).await?;
+    assert_eq!(decoded, [1, 2, 3]);
+
+    let capped = crate::ser::Options {
+        max_message_bytes: Some(8),
+        ..Default::default()
+    };
+    let error = ser_config
+        .serialize_into_buffer_with_options(vec![1_u32, 2, 3], &capped)
+        .unwrap_err();
+    assert!(matches!(error, crate::ser::Error::MessageTooLarge(8)));
+    let mut device = Vec::new();
+    let error = ser_config
+        .serialize_with_options(&mut device, vec![1_u32, 2, 3], &capped)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, crate::ser::Error::MessageTooLarge(8)));
+    assert_eq!(ser_config.serialize_into_buffer(vec![1_u32, 2, 3])?.len(), 20);
+    Ok(())
+}
+
+#[tokio::test]
+async fn custom_channel_wiring() -> Result<()> {
+    let fingerprint = crate::fingerprint::Fingerprint::from_u64(0xc0de);
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_fingerprint(fingerprint).with_variant_names();
+    let mut de_config = crate::de::Config::new();
+    de_config.with_fingerprint(fingerprint).with_variant_names();
+
+    let value = document();
+    let mut buf = Vec::new();
+    let (backend, mut serializer) = ser_config.channel(&mut buf);
+    let encoder = std::thread::spawn({
+        let value = value.clone();
+        move || value.serialize(&mut serializer)
+    });
+    backend.run().await?;
+    encoder.join().unwrap()?;
+    assert_eq!(&buf[.. 8], &fingerprint.to_le_bytes());
+
+    let (ticks_sender, mut ticks) = tokio::sync::mpsc::channel(4);
+    ticks_sender.send(()).await?;
+    drop(ticks_sender);
+
+    let mut device = &buf[..];
+    let (backend, mut deserializer) = de_config.channel(&mut device);
+    let config = de_config.clone();
+    let decoder = std::thread::spawn(move || {
+        config.deserialize_from::<Document, _>(&mut deserializer)
+    });
+    let mut tick_count = 0;
+    {
+        let run = backend.run();
+        tokio::pin!(run);
+        loop {
+            tokio::select! {
+                Some(()) = ticks.recv() => tick_count += 1,
+                result = &mut run => {
+                    result?;
+                    break;
+                },
+            }
+        }
+    }
+    assert_eq!(decoder.join().unwrap()?, value);
+    assert!(device.is_empty());
+    assert!(tick_count <= 1);
+
+    let (backend, mut deserializer) = de_config.channel(&[0_u8; 8][..]);
+    let config = de_config.clone();
+    let decoder = std::thread::spawn(move || {
+        config.deserialize_from::<Document, _>(&mut deserializer)
+    });
+    let _ = backend.run().await;
+    let error = decoder.join().unwrap().unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::FingerprintMismatch { .. }
+    ));
+    Ok(())
+}
+
+struct CrashingWriter {
+    buf: Vec<u8>,
+    budget: usize,
+}
+
+impl tokio::io::AsyncWrite for CrashingWriter {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context,
+        data: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        if self.budget < data.len() {
+            let error = std::io::Error::other("simulated crash");
+            return std::task::Poll::Ready(Err(error));
+        }
+        self.budget -= data.len();
+        self.buf.extend_from_slice(data);
+        std::task::Poll::Ready(Ok(data.len()))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn checkpointed_serialization_resumes() -> Result<()> {
+    let documents: Vec<_> = (0 .. 20_u64)
+        .map(|index| Document { parent: Some(index), ..document() })
+        .collect();
+    let mut config = crate::ser::Config::new();
+    config
+        .with_fingerprint(crate::fingerprint::Fingerprint::from_u64(9))
+        .with_write_buffer(2000)?;
+
+    let mut buf = Vec::new();
+    let done = config
+        .serialize_checkpointed(&mut buf, 20, &documents, None, |_| ())
+        .await?;
+    assert_eq!(
+        done,
+        crate::ser::Checkpoint { elements: 20, bytes: buf.len() as u64 }
+    );
+
+    let mut writer = CrashingWriter { buf: Vec::new(), budget: buf.len() / 2 };
+    let mut last = None;
+    let error = config
+        .serialize_checkpointed(
+            &mut writer,
+            20,
+            &documents,
+            None,
+            |checkpoint| last = Some(checkpoint),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(error, crate::ser::Error::IO(_)));
+    let checkpoint = last.unwrap();
+    assert!(checkpoint.elements > 0 && checkpoint.elements < 20);
+
+    let mut resumed = writer.buf;
+    resumed.truncate(checkpoint.bytes as usize);
+    let remaining = documents.iter().skip(checkpoint.elements as usize);
+    let done = config
+        .serialize_checkpointed(
+            &mut resumed,
+            20,
+            remaining,
+            Some(checkpoint),
+            |_| (),
+        )
+        .await?;
+    assert_eq!(done.elements, 20);
+    assert_eq!(resumed, buf);
+
+    let mut de_config = crate::de::Config::new();
+    de_config
+        .with_hard_eof()
+        .with_fingerprint(crate::fingerprint::Fingerprint::from_u64(9));
+    let decoded: Vec<Document> = de_config.deserialize_buffer(&resumed)?;
+    assert_eq!(decoded, documents);
+
+    let error = crate::ser::Config::new()
+        .serialize_checkpointed(Vec::new(), 3, [1_u8, 2], None, |_| ())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        crate::ser::Error::SeqLengthMismatch { expected: 3, found: 2 }
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn indexed_archive_lookup() -> Result<()> {
+    use std::io::Cursor;
+
+    let records: Vec<_> = (0 .. 500_u32)
+        .map(|index| (index, "x".repeat(index as usize % 7)))
+        .collect();
+    let fingerprint = crate::fingerprint::Fingerprint::from_u64(0x1d);
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_fingerprint(fingerprint).with_write_buffer(256)?;
+    let mut buf = Vec::new();
+    ser_config.serialize_indexed(&mut buf, records.len(), &records).await?;
+
+    let mut de_config = crate::de::Config::new();
+    de_config.with_fingerprint(fingerprint);
+    let decoded: Vec<(u32, String)> = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, records);
+
+    assert_eq!(de_config.indexed_len(Cursor::new(&buf)).await?, 500);
+    for index in [0, 1, 250, 499] {
+        let record: (u32, String) =
+            de_config.deserialize_nth(Cursor::new(&buf), index).await?;
+        assert_eq!(record, records[index as usize]);
+    }
+
+    let error = de_config
+        .deserialize_nth::<(u32, String), _>(Cursor::new(&buf), 500)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::IndexOutOfRange { index: 500, len: 500 }
+    ));
+
+    let mut other = crate::de::Config::new();
+    other.with_fingerprint(crate::fingerprint::Fingerprint::from_u64(0));
+    let error = other
+        .deserialize_nth::<(u32, String), _>(Cursor::new(&buf), 3)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::FingerprintMismatch { .. }
+    ));
+
+    let plain = crate::serialize_into_buffer(&records)?;
+    let error =
+        crate::de::deserialize_nth::<(u32, String), _>(Cursor::new(&plain), 0)
+            .await
+            .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::MissingIndex));
+    Ok(())
+}
+
+#[tokio::test]
+async fn atomic_file_roundtrip() -> Result<()> {
+    let dir = std::env::temp_dir()
+        .join(format!("abcode-file-test-{}", std::process::id()));
+    tokio::fs::create_dir_all(&dir).await?;
+    let path = dir.join("document.bin");
+
+    crate::serialize_to_file(&path, document()).await?;
+    let decoded: Document = crate::deserialize_from_file(&path).await?;
+    assert_eq!(decoded, document());
+
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_fsync();
+    ser_config.serialize_to_file(&path, 42_u64).await?;
+    let decoded: u64 = crate::deserialize_from_file(&path).await?;
+    assert_eq!(decoded, 42);
+
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_max_message_bytes(4);
+    let error =
+        ser_config.serialize_to_file(&path, document()).await.unwrap_err();
+    assert!(matches!(error, crate::ser::Error::MessageTooLarge(4)));
+    let decoded: u64 = crate::deserialize_from_file(&path).await?;
+    assert_eq!(decoded, 42);
+
+    let mut entries = tokio::fs::read_dir(&dir).await?;
+    let mut names = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        names.push(entry.file_name());
+    }
+    assert_eq!(names, ["document.bin"]);
+
+    let error = crate::deserialize_from_file::<u64, _>(dir.join("missing"))
+        .await
+        .unwrap_err();
+    assert!(error.is_io());
+    tokio::fs::remove_dir_all(&dir).await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Reading {
+    sensor: u16,
+    labels: HashMap<String, i32>,
+    value: f64,
+}
+
+const READING_BYTES: &[u8] = &[
+    0x02, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x61, 0x02, 0x00, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x62, 0xff, 0xff, 0xff, 0xff,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf8, 0x7f,
+];
+
+#[tokio::test]
+async fn deterministic_profile_is_stable() -> Result<()> {
+    let reading = Reading {
+        sensor: 0x0102,
+        labels: [("b", -1), ("a", 2)]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value))
+            .collect(),
+        value: f64::from_bits(0x7ff8_dead_beef_0001),
+    };
+    let ser_config = crate::ser::Config::deterministic();
+    assert_eq!(ser_config.serialize_into_buffer(&reading)?, READING_BYTES);
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, reading.clone()).await?;
+    assert_eq!(streamed, READING_BYTES);
+
+    let de_config = crate::de::Config::deterministic();
+    let decoded: Reading = de_config.deserialize_buffer(READING_BYTES)?;
+    assert_eq!(decoded.labels, reading.labels);
+    assert_eq!(decoded.value.to_bits(), f64::NAN.to_bits());
+
+    let plain = crate::serialize_into_buffer(reading.value)?;
+    let error = de_config.deserialize_buffer::<f64>(&plain).unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::NonCanonicalNan(0x7ff8_dead_beef_0001)
+    ));
+
+    let error = crate::ser::Config::builder()
+        .with_deterministic()
+        .with_interned_strings()
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        crate::ser::ConfigError::ConflictingOptions(
+            "deterministic",
+            "interned_strings"
+        )
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "stream")]
+struct ReceiverStream<T>(tokio::sync::mpsc::Receiver<T>);
+
+#[cfg(feature = "stream")]
+impl<T> futures_core::Stream for ReceiverStream<T> {
+    type Item = T;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context,
+    ) -> std::task::Poll<Option<T>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+#[cfg(feature = "stream")]
+fn produce_async(count: u32) -> ReceiverStream<(u32, String)> {
+    let (sender, receiver) = tokio::sync::mpsc::channel(1);
+    tokio::spawn(async move {
+        for index in 0 .. count {
+            tokio::task::yield_now().await;
+            if sender.send((index, format!("row-{}", index))).await.is_err() {
+                break;
+            }
+        }
+    });
+    ReceiverStream(receiver)
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn stream_serializes_as_seq() -> Result<()> {
+    let expected: Vec<_> =
+        (0 .. 200).map(|index| (index, format!("row-{}", index))).collect();
+    let plain = crate::serialize_into_buffer(&expected)?;
+
+    let mut buf = Vec::new();
+    crate::ser::serialize_stream(&mut buf, None, produce_async(200)).await?;
+    assert_eq!(buf, plain);
+
+    let mut buf = Vec::new();
+    crate::ser::serialize_stream(&mut buf, Some(200), produce_async(200))
+        .await?;
+    assert_eq!(buf, plain);
+    let decoded: Vec<(u32, String)> = crate::deserialize(&buf[..]).await?;
+    assert_eq!(decoded, expected);
+
+    let error =
+        crate::ser::serialize_stream(Vec::new(), Some(10), produce_async(3))
+            .await
+            .unwrap_err();
+    assert!(matches!(
+        error,
+        crate::ser::Error::SeqLengthMismatch { expected: 10, found: 3 }
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "stream")]
+fn chunked(
+    buf: Vec<u8>,
+    chunk_size: usize,
+    failure: Option<std::io::Error>,
+) -> ReceiverStream<Result<Vec<u8>, std::io::Error>> {
+    let (sender, receiver) = tokio::sync::mpsc::channel(1);
+    tokio::spawn(async move {
+        for chunk in buf.chunks(chunk_size) {
+            if sender.send(Ok(chunk.to_vec())).await.is_err() {
+                return;
+            }
+            tokio::task::yield_now().await;
+        }
+        if let Some(error) = failure {
+            let _ = sender.send(Err(error)).await;
+        }
+    });
+    ReceiverStream(receiver)
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn stream_of_chunks_deserializes() -> Result<()> {
+    let expected: Vec<_> =
+        (0 .. 100).map(|index| (index, format!("row-{}", index))).collect();
+    let buf = crate::serialize_into_buffer(&expected)?;
+
+    for chunk_size in [1, 7, buf.len()] {
+        let decoded: Vec<(u32, String)> = crate::de::deserialize_stream(
+            chunked(buf.clone(), chunk_size, None),
+        )
+        .await?;
+        assert_eq!(decoded, expected);
+    }
+
+    let half = buf[.. buf.len() / 2].to_vec();
+    let failure = std::io::Error::other("reset");
+    let error = crate::de::deserialize_stream::<Vec<(u32, String)>, _, _, _>(
+        chunked(half, 16, Some(failure)),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::IO(_)));
+    Ok(())
+}
+
+#[cfg(feature = "stream")]
+#[derive(Debug, Default)]
+struct SinkState {
+    delivered: Vec<u8>,
+    chunks: usize,
+    stalls: usize,
+    closed: bool,
+}
+
+#[cfg(feature = "stream")]
+struct CollectSink {
+    state: std::sync::Arc<std::sync::Mutex<SinkState>>,
+    pending: Vec<Vec<u8>>,
+    stall: bool,
+    fail_after: Option<usize>,
+}
+
+#[cfg(feature = "stream")]
+impl CollectSink {
+    fn deliver(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        for chunk in self.pending.drain(..) {
+            state.delivered.extend_from_slice(&chunk);
+            state.chunks += 1;
+        }
+    }
+}
+
+#[cfg(feature = "stream")]
+impl futures_sink::Sink<Vec<u8>> for CollectSink {
+    type Error = std::io::Error;
+
+    fn poll_ready(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        if self.pending.len() >= 2 {
+            self.deliver();
+        }
+        if self.stall {
+            self.stall = false;
+            self.state.lock().unwrap().stalls += 1;
+            cx.waker().wake_by_ref();
+            return std::task::Poll::Pending;
+        }
+        self.stall = true;
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn start_send(
+        mut self: std::pin::Pin<&mut Self>,
+        item: Vec<u8>,
+    ) -> std::io::Result<()> {
+        if self
+            .fail_after
+            .is_some_and(|limit| self.state.lock().unwrap().chunks >= limit)
+        {
+            return Err(std::io::Error::other("peer went away"));
+        }
+        self.pending.push(item);
+        Ok(())
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.deliver();
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.deliver();
+        self.state.lock().unwrap().closed = true;
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn sink_receives_encoded_chunks() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    let value: Vec<_> =
+        (0 .. 2000).map(|index| (index, format!("row-{}", index))).collect();
+    let expected = crate::serialize_into_buffer(&value)?;
+
+    let state = Arc::new(Mutex::new(SinkState::default()));
+    let sink = CollectSink {
+        state: state.clone(),
+        pending: Vec::new(),
+        stall: false,
+        fail_after: None,
+    };
+    let mut config = crate::ser::Config::new();
+    config.with_batch_limit(64)?;
+    config.serialize_into_sink(sink, value.clone()).await?;
+    {
+        let state = state.lock().unwrap();
+        assert_eq!(state.delivered, expected);
+        assert!(state.chunks > 1);
+        assert!(state.stalls > 0);
+        assert!(!state.closed);
+    }
+
+    let state = Arc::new(Mutex::new(SinkState::default()));
+    let sink = CollectSink {
+        state: state.clone(),
+        pending: Vec::new(),
+        stall: false,
+        fail_after: None,
+    };
+    config.with_shutdown_at_end();
+    config.serialize_into_sink(sink, value.clone()).await?;
+    assert_eq!(state.lock().unwrap().delivered, expected);
+    assert!(state.lock().unwrap().closed);
+
+    let sink = CollectSink {
+        state: Arc::new(Mutex::new(SinkState::default())),
+        pending: Vec::new(),
+        stall: false,
+        fail_after: Some(1),
+    };
+    let error = config.serialize_into_sink(sink, value).await.unwrap_err();
+    assert!(matches!(error, crate::ser::Error::IO(_)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn chained_parts_deserialize_as_one() -> Result<()> {
+    let expected: Vec<_> =
+        (0 .. 300).map(|index| (index, format!("part-{}", index))).collect();
+    let buf = crate::serialize_into_buffer(&expected)?;
+    let cuts = [0, 0, 3, 100, 101, 2000, buf.len()];
+
+    let mut discovered = 0;
+    let parts = cuts.windows(2).map(|window| {
+        discovered += 1;
+        std::io::Cursor::new(buf[window[0] .. window[1]].to_vec())
+    });
+    let decoded: Vec<(u32, String)> =
+        crate::de::deserialize_chain(parts).await?;
+    assert_eq!(decoded, expected);
+    assert_eq!(discovered, cuts.len() - 1);
+
+    let mut reader =
+        crate::de::ChainReader::new(vec![&buf[.. 10], &buf[10 ..]]);
+    let mut collected = Vec::new();
+    tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut collected).await?;
+    assert_eq!(collected, buf);
+    assert_eq!(reader.parts_read(), 2);
+
+    let truncated = vec![&buf[.. 10], &buf[20 .. 30]];
+    let mut config = crate::de::Config::new();
+    config.with_hard_eof();
+    let error = config
+        .deserialize_chain::<Vec<(u32, String)>, _>(truncated)
+        .await
+        .unwrap_err();
+    assert!(error.inner().kind() == crate::de::ErrorKind::Eof);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn indexed_parallel_decode() -> Result<()> {
+    use std::io::Cursor;
+
+    use crate::de::Limit;
+
+    let records: Vec<_> = (0 .. 1000_u32)
+        .map(|index| (index, "y".repeat(index as usize % 11)))
+        .collect();
+    let fingerprint = crate::fingerprint::Fingerprint::from_u64(0x2e);
+    let mut ser_config = crate::ser::Config::new();
+    ser_config.with_fingerprint(fingerprint);
+    let mut buf = Vec::new();
+    ser_config.serialize_indexed(&mut buf, records.len(), &records).await?;
+
+    let mut de_config = crate::de::Config::new();
+    de_config.with_fingerprint(fingerprint);
+    for workers in [1, 3, 8] {
+        let mut decoder = de_config
+            .deserialize_parallel::<(u32, String), _>(
+                Cursor::new(&buf),
+                workers,
+            )
+            .await?;
+        let mut decoded = Vec::new();
+        while let Some(result) = decoder.next().await {
+            let (index, record) = result?;
+            assert_eq!(index, decoded.len() as u64);
+            decoded.push(record);
+        }
+        assert_eq!(decoded, records);
+    }
+
+    let mut decoder = de_config
+        .deserialize_parallel_unordered::<(u32, String), _>(
+            Cursor::new(&buf),
+            4,
+        )
+        .await?;
+    let mut decoded = Vec::new();
+    while let Some(result) = decoder.next().await {
+        decoded.push(result?);
+    }
+    decoded.sort();
+    let decoded: Vec<_> =
+        decoded.into_iter().map(|(_, record)| record).collect();
+    assert_eq!(decoded, records);
+
+    let mut decoder = de_config
+        .deserialize_parallel::<(u32, u8), _>(Cursor::new(&buf), 2)
+        .await?;
+    assert!(decoder.next().await.unwrap().is_err());
+    assert!(decoder.next().await.is_none());
+
+    de_config.with_max_total_bytes(4);
+    let mut decoder = de_config
+        .deserialize_parallel::<(u32, String), _>(Cursor::new(&buf), 2)
+        .await?;
+    let error = decoder.next().await.unwrap().unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::LimitExceeded {
+            limit: Limit::TotalBytes,
+            max: 4,
+            ..
+        }
+    ));
+    assert!(decoder.next().await.is_none());
+
+    let plain = crate::serialize_into_buffer(&records)?;
+    let error = crate::de::deserialize_parallel::<(u32, String), _>(
+        Cursor::new(&plain),
+        2,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::MissingIndex));
+    Ok(())
+}
+
+async fn roundtrip_with_strategy(
+    strategy: crate::BlockingStrategy,
+) -> Result<()> {
+    let mut ser_config = crate::ser::Config::new();
+    ser_config
+        .with_blocking_strategy(strategy.clone())
+        .with_flush_at_boundary();
+    let mut de_config = crate::de::Config::new();
+    de_config.with_blocking_strategy(strategy);
+
+    let (writer, mut reader) = tokio::io::duplex(1 << 16);
+    let mut writer = writer;
+    for _ in 0 .. 3 {
+        ser_config.serialize(&mut writer, document()).await?;
+        let decoded: Document = de_config.deserialize(&mut reader).await?;
+        assert_eq!(decoded, document());
+    }
+    drop(writer);
+
+    let value = document();
+    let buf = ser_config.serialize_into_buffer(&value)?;
+    let mut streamed = Vec::new();
+    ser_config.serialize(&mut streamed, value).await?;
+    assert_eq!(streamed, buf);
+
+    de_config.with_hard_eof();
+    let mut trailing = buf.clone();
+    trailing.push(0);
+    let error =
+        de_config.deserialize::<Document, _>(&trailing[..]).await.unwrap_err();
+    assert!(matches!(
+        error.inner(),
+        crate::de::Error::TrailingBytes { count: 1 }
+    ));
+    let error =
+        de_config.deserialize::<Document, _>(&buf[.. 10]).await.unwrap_err();
+    assert!(error.is_eof());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn block_in_place_strategy() -> Result<()> {
+    roundtrip_with_strategy(crate::BlockingStrategy::BlockInPlace).await
+}
+
+#[tokio::test]
+async fn block_in_place_falls_back_on_current_thread() -> Result<()> {
+    roundtrip_with_strategy(crate::BlockingStrategy::BlockInPlace).await
+}
+
+async fn output_with_strategy(
+    strategy: crate::BlockingStrategy,
+) -> Result<(Vec<u8>, u64, u64)> {
+    let progress = crate::Progress::new();
+    let mut config = crate::ser::Config::new();
+    config
+        .with_write_buffer(64)?
+        .with_blocking_strategy(strategy)
+        .with_two_pass()
+        .with_progress(progress.clone());
+    let mut buf = Vec::new();
+    let value = (fleet(), vec![Unsized(10), Unsized(100)]);
+    config.serialize(&mut buf, value).await?;
+    Ok((buf, progress.bytes(), progress.elements()))
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn strategies_write_the_same_output() -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .build()?;
+    let handle = crate::BlockingStrategy::Handle(runtime.handle().clone());
+    let result = output_with_strategy(handle).await;
+    runtime.shutdown_background();
+    let output = result?;
+
+    let (buf, bytes, elements) = &output;
+    assert_eq!(*bytes, buf.len() as u64);
+    assert!(*elements > 0);
+    let strategies = [
+        crate::BlockingStrategy::SpawnBlocking,
+        crate::BlockingStrategy::BlockInPlace,
+    ];
+    for strategy in strategies {
+        assert_eq!(output_with_strategy(strategy).await?, output);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn handle_strategy_uses_given_runtime() -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("abcode-pool")
+        .build()?;
+    let result = roundtrip_with_strategy(crate::BlockingStrategy::Handle(
+        runtime.handle().clone(),
+    ))
+    .await;
+    runtime.shutdown_background();
+    result
+}
@@ -0,0 +1,309 @@
+use std::fmt;
+
+use proptest::{prelude::*, sample, test_runner::TestCaseError};
+use serde::{de::DeserializeOwned, Serialize};
+
+#[cfg(feature = "lz4")]
+use crate::compress::Compression;
+use crate::{
+    de,
+    fingerprint::Fingerprint,
+    metadata::Metadata,
+    ser,
+    varint::IntEncoding,
+};
+
+#[cfg(test)]
+mod test;
+
+/// Option applied to both sides of a [`ConfigPair`]. Options that only
+/// bound or route the data (limits, buffering, blocking strategy, stream
+/// compression) and options tied to particular types (field tags, variant
+/// fallbacks) are left out, since they do not change the encoding of an
+/// arbitrary value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Setting {
+    SelfDescribing,
+    FieldPresence,
+    FieldCount,
+    PresenceBitmap,
+    VariantNames,
+    SortedMapKeys,
+    Utf8Chars,
+    StrChars,
+    InternedStrings,
+    Varint,
+    Leb128,
+    TaggedFields,
+    Fingerprint,
+    Metadata,
+    #[cfg(feature = "lz4")]
+    Compression,
+    Canonical,
+    Deterministic,
+}
+
+impl Setting {
+    pub const ALL: &'static [Self] = &[
+        Self::SelfDescribing,
+        Self::FieldPresence,
+        Self::FieldCount,
+        Self::PresenceBitmap,
+        Self::VariantNames,
+        Self::SortedMapKeys,
+        Self::Utf8Chars,
+        Self::StrChars,
+        Self::InternedStrings,
+        Self::Varint,
+        Self::Leb128,
+        Self::TaggedFields,
+        Self::Fingerprint,
+        Self::Metadata,
+        #[cfg(feature = "lz4")]
+        Self::Compression,
+        Self::Canonical,
+        Self::Deterministic,
+    ];
+
+    fn apply(self, ser_config: &mut ser::Config, de_config: &mut de::Config) {
+        match self {
+            Self::SelfDescribing => {
+                ser_config.with_self_describing();
+                de_config.with_self_describing();
+            },
+            Self::FieldPresence => {
+                ser_config.with_field_presence();
+                de_config.with_field_presence();
+            },
+            Self::FieldCount => {
+                ser_config.with_field_count();
+                de_config.with_field_count();
+            },
+            Self::PresenceBitmap => {
+                ser_config.with_presence_bitmap();
+                de_config.with_presence_bitmap();
+            },
+            Self::VariantNames => {
+                ser_config.with_variant_names();
+                de_config.with_variant_names();
+            },
+            Self::SortedMapKeys => {
+                ser_config.with_sorted_map_keys();
+                de_config.with_sorted_map_keys();
+            },
+            Self::Utf8Chars => {
+                ser_config.with_utf8_chars();
+                de_config.with_utf8_chars();
+            },
+            Self::StrChars => {
+                ser_config.with_str_chars();
+                de_config.with_str_chars();
+            },
+            Self::InternedStrings => {
+                ser_config.with_interned_strings();
+                de_config.with_interned_strings();
+            },
+            Self::Varint => {
+                ser_config.with_int_encoding(IntEncoding::Varint);
+                de_config.with_int_encoding(IntEncoding::Varint);
+            },
+            Self::Leb128 => {
+                ser_config.with_int_encoding(IntEncoding::Leb128);
+                de_config.with_int_encoding(IntEncoding::Leb128);
+            },
+            Self::TaggedFields => {
+                ser_config.with_tagged_fields();
+                de_config.with_tagged_fields();
+            },
+            Self::Fingerprint => {
+                let fingerprint = Fingerprint::from_u64(0x7e57);
+                ser_config.with_fingerprint(fingerprint);
+                de_config.with_fingerprint(fingerprint);
+            },
+            Self::Metadata => {
+                let metadata = Metadata::from_iter([("testkit", "1")]);
+                ser_config
+                    .with_metadata(metadata)
+                    .expect("metadata within the size limit");
+                de_config.with_metadata();
+            },
+            #[cfg(feature = "lz4")]
+            Self::Compression => {
+                ser_config.with_compression(Compression::Lz4);
+                de_config.with_compression();
+            },
+            Self::Canonical => {
+                ser_config.with_sorted_map_keys();
+                de_config.with_strict_scalars().with_sorted_map_keys();
+            },
+            Self::Deterministic => {
+                ser_config.with_deterministic();
+                de_config
+                    .with_strict_scalars()
+                    .with_sorted_map_keys()
+                    .with_canonical_nan();
+            },
+        }
+    }
+
+    fn compatible(settings: &[Setting]) -> bool {
+        let has = |setting| settings.contains(&setting);
+        let varint = has(Self::Varint) || has(Self::Leb128);
+        let ambiguous_encoding = has(Self::Varint) && has(Self::Leb128);
+        let unknown_len = has(Self::FieldPresence) && has(Self::FieldCount);
+        !(ambiguous_encoding || varint && unknown_len)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigPair {
+    pub settings: Vec<Setting>,
+    pub ser: ser::Config,
+    pub de: de::Config,
+}
+
+impl ConfigPair {
+    pub fn new(settings: &[Setting]) -> Self {
+        let mut ser_config = ser::Config::new();
+        let mut de_config = de::Config::new();
+        de_config.with_hard_eof();
+        for setting in settings {
+            setting.apply(&mut ser_config, &mut de_config);
+        }
+        Self { settings: settings.to_vec(), ser: ser_config, de: de_config }
+    }
+
+    /// Whether both configs pass validation and their settings can share
+    /// an encoding.
+    pub fn is_valid(&self) -> bool {
+        Setting::compatible(&self.settings)
+            && self.ser.ensure_valid().is_ok()
+            && self.de.ensure_valid().is_ok()
+    }
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0 .. 1_u32 << Setting::ALL.len()).filter_map(|mask| {
+            let settings: Vec<_> = Setting::ALL
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| mask & (1 << index) != 0)
+                .map(|(_, setting)| *setting)
+                .collect();
+            Some(Self::new(&settings)).filter(Self::is_valid)
+        })
+    }
+
+    pub fn roundtrip<T>(&self, value: &T) -> Result<T, RoundtripError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let buf = self.ser.serialize_into_buffer(value)?;
+        Ok(self.de.deserialize_buffer(&buf)?)
+    }
+
+    pub async fn roundtrip_async<T>(
+        &self,
+        value: T,
+    ) -> Result<T, RoundtripError>
+    where
+        T: Serialize + DeserializeOwned + Send + 'static,
+    {
+        let mut buf = Vec::new();
+        self.ser.serialize(&mut buf, value).await?;
+        Ok(self.de.deserialize(&buf[..]).await?)
+    }
+}
+
+#[derive(Debug)]
+pub enum RoundtripError {
+    Ser(ser::Error),
+    De(de::Error),
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Ser(error) => write!(f, "serialization failed: {}", error),
+            Self::De(error) => write!(f, "deserialization failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for RoundtripError {}
+
+impl From<ser::Error> for RoundtripError {
+    fn from(error: ser::Error) -> Self {
+        Self::Ser(error)
+    }
+}
+
+impl From<de::Error> for RoundtripError {
+    fn from(error: de::Error) -> Self {
+        Self::De(error)
+    }
+}
+
+pub fn assert_roundtrip<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+{
+    for pair in ConfigPair::all() {
+        match pair.roundtrip(value) {
+            Ok(decoded) => assert_eq!(
+                &decoded, value,
+                "roundtrip changed the value under {:?}",
+                pair.settings
+            ),
+            Err(error) => {
+                panic!("roundtrip failed under {:?}: {}", pair.settings, error)
+            },
+        }
+    }
+}
+
+pub async fn assert_roundtrip_async<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+    T: Clone + Send + 'static,
+{
+    for pair in ConfigPair::all() {
+        match pair.roundtrip_async(value.clone()).await {
+            Ok(decoded) => assert_eq!(
+                &decoded, value,
+                "roundtrip changed the value under {:?}",
+                pair.settings
+            ),
+            Err(error) => {
+                panic!("roundtrip failed under {:?}: {}", pair.settings, error)
+            },
+        }
+    }
+}
+
+pub fn config_pair() -> impl Strategy<Value = ConfigPair> {
+    sample::subsequence(Setting::ALL.to_vec(), 0 ..= Setting::ALL.len())
+        .prop_map(|settings| ConfigPair::new(&settings))
+        .prop_filter("incompatible settings", ConfigPair::is_valid)
+}
+
+pub fn check_roundtrip<T>(
+    pair: &ConfigPair,
+    value: &T,
+) -> Result<(), TestCaseError>
+where
+    T: Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+{
+    let decoded = pair.roundtrip(value).map_err(|error| {
+        TestCaseError::fail(format!(
+            "roundtrip failed under {:?}: {}",
+            pair.settings, error
+        ))
+    })?;
+    prop_assert_eq!(
+        &decoded,
+        value,
+        "roundtrip changed the value under {:?}",
+        pair.settings
+    );
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use std::collections::{BTreeMap, HashMap};
+
+use proptest::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    assert_roundtrip,
+    assert_roundtrip_async,
+    check_roundtrip,
+    config_pair,
+    ConfigPair,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Event {
+    Idle,
+    Moved(i32, i32),
+    Renamed { from: String, to: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Session {
+    id: u64,
+    user: Option<String>,
+    initial: char,
+    score: f64,
+    tags: HashMap<String, u16>,
+    events: Vec<Event>,
+}
+
+fn session() -> Session {
+    Session {
+        id: 300,
+        user: Some("ana".to_owned()),
+        initial: 'ç',
+        score: -0.5,
+        tags: HashMap::from([("a".to_owned(), 1), ("b".to_owned(), 60_000)]),
+        events: vec![
+            Event::Idle,
+            Event::Moved(-1, 1 << 20),
+            Event::Renamed { from: "x".to_owned(), to: "y".to_owned() },
+        ],
+    }
+}
+
+#[test]
+fn every_combination_roundtrips() {
+    let expected = if cfg!(feature = "lz4") { 19392 } else { 12768 };
+    assert_eq!(ConfigPair::all().count(), expected);
+    assert_roundtrip(&session());
+    assert_roundtrip(&BTreeMap::from([(i128::MIN, [u128::MAX; 2])]));
+}
+
+#[tokio::test]
+async fn every_combination_roundtrips_async() {
+    assert_roundtrip_async(&session()).await;
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_values_roundtrip(
+        pair in config_pair(),
+        id in any::<u64>(),
+        user in proptest::option::of(".*"),
+        initial in any::<char>(),
+        events in proptest::collection::vec(
+            (any::<i32>(), any::<i32>()),
+            0 .. 8,
+        ),
+    ) {
+        let value = Session {
+            id,
+            user,
+            initial,
+            score: 1.0,
+            tags: HashMap::new(),
+            events: events
+                .into_iter()
+                .map(|(x, y)| Event::Moved(x, y))
+                .collect(),
+        };
+        check_roundtrip(&pair, &value)?;
+    }
+}
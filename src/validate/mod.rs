@@ -0,0 +1,1072 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    sync::{Mutex, PoisonError},
+};
+
+use serde::{
+    de::{
+        self,
+        DeserializeSeed,
+        Deserializer,
+        IgnoredAny,
+        IntoDeserializer,
+        Visitor,
+    },
+    Deserialize,
+};
+use thiserror::Error;
+
+use crate::{
+    schema::{self, Schema},
+    tag::Tag,
+};
+
+#[cfg(test)]
+mod test;
+
+const SKIP_STR_NAME: &str = "$abcode::validate::str";
+const SKIP_BYTES_NAME: &str = "$abcode::validate::bytes";
+const MAX_PROBE_DEPTH: usize = 128;
+const MAX_PROBE_PASSES: usize = 4096;
+
+static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+static NAME_LISTS: Mutex<BTreeSet<&'static [&'static str]>> =
+    Mutex::new(BTreeSet::new());
+
+pub(crate) fn skipped_blob(name: &str) -> Option<Tag> {
+    match name {
+        SKIP_STR_NAME => Some(Tag::Str),
+        SKIP_BYTES_NAME => Some(Tag::Bytes),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) enum Shape {
+    #[default]
+    Unknown,
+    Unit,
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    F32,
+    F64,
+    Char,
+    Str,
+    Bytes,
+    Option(Box<Shape>),
+    Seq(Box<Shape>),
+    Map(Box<Shape>, Box<Shape>),
+    Tuple(Vec<Shape>),
+    UnitStruct(&'static str),
+    NewtypeStruct(&'static str, Box<Shape>),
+    TupleStruct(&'static str, Vec<Shape>),
+    Struct(&'static str, &'static [&'static str], Vec<Shape>),
+    Enum(&'static str, &'static [&'static str], Vec<Option<Payload>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Payload {
+    Unit,
+    Newtype(Shape),
+    Tuple(Vec<Shape>),
+    Struct(&'static [&'static str], Vec<Shape>),
+}
+
+impl Payload {
+    fn shapes(&self) -> &[Shape] {
+        match self {
+            Self::Unit => &[],
+            Self::Newtype(shape) => std::slice::from_ref(shape),
+            Self::Tuple(shapes) | Self::Struct(_, shapes) => shapes,
+        }
+    }
+
+    fn shapes_mut(&mut self) -> &mut [Shape] {
+        match self {
+            Self::Unit => &mut [],
+            Self::Newtype(shape) => std::slice::from_mut(shape),
+            Self::Tuple(shapes) | Self::Struct(_, shapes) => shapes,
+        }
+    }
+}
+
+impl Shape {
+    pub(crate) fn of<'de, T>() -> Option<Self>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut shape = Prober::run::<T>(HashMap::new())?;
+        for _ in 0 .. MAX_PROBE_PASSES {
+            let mut plan = HashMap::new();
+            if !shape.find_gap(&mut Vec::new(), &mut plan) {
+                return Some(shape);
+            }
+            shape.merge(Prober::run::<T>(plan)?);
+        }
+        None
+    }
+
+    fn children(&self) -> &[Shape] {
+        match self {
+            Self::Option(inner)
+            | Self::Seq(inner)
+            | Self::NewtypeStruct(_, inner) => std::slice::from_ref(inner),
+            Self::Tuple(shapes)
+            | Self::TupleStruct(_, shapes)
+            | Self::Struct(_, _, shapes) => shapes,
+            _ => &[],
+        }
+    }
+
+    fn find_gap(&self, path: &mut Vec<usize>, plan: &mut Plan) -> bool {
+        let Self::Enum(_, _, payloads) = self else {
+            if let Self::Map(key, value) = self {
+                return descend(key, 0, path, plan)
+                    || descend(value, 1, path, plan);
+            }
+            return self
+                .children()
+                .iter()
+                .enumerate()
+                .any(|(index, child)| descend(child, index, path, plan));
+        };
+        if let Some(index) = payloads.iter().position(Option::is_none) {
+            plan.insert(path.clone(), index as u32);
+            return true;
+        }
+        for (variant, payload) in payloads.iter().flatten().enumerate() {
+            plan.insert(path.clone(), variant as u32);
+            path.push(variant);
+            let found = payload
+                .shapes()
+                .iter()
+                .enumerate()
+                .any(|(index, child)| descend(child, index, path, plan));
+            path.pop();
+            if found {
+                return true;
+            }
+        }
+        plan.remove(path);
+        false
+    }
+
+    fn merge(&mut self, other: Self) {
+        match (self, other) {
+            (this @ Self::Unknown, other) => *this = other,
+            (Self::Enum(_, _, payloads), Self::Enum(_, _, others)) => {
+                for (payload, other) in payloads.iter_mut().zip(others) {
+                    match (payload, other) {
+                        (payload @ None, other) => *payload = other,
+                        (Some(payload), Some(other)) => {
+                            merge_all(payload.shapes_mut(), other.shapes())
+                        },
+                        _ => (),
+                    }
+                }
+            },
+            (Self::Map(key, value), Self::Map(other_key, other_value)) => {
+                key.merge(*other_key);
+                value.merge(*other_value);
+            },
+            (this, other) => {
+                let children = other.children().to_vec();
+                match this {
+                    Self::Option(inner)
+                    | Self::Seq(inner)
+                    | Self::NewtypeStruct(_, inner) => {
+                        merge_all(std::slice::from_mut(&mut **inner), &children)
+                    },
+                    Self::Tuple(shapes)
+                    | Self::TupleStruct(_, shapes)
+                    | Self::Struct(_, _, shapes) => {
+                        merge_all(shapes, &children)
+                    },
+                    _ => (),
+                }
+            },
+        }
+    }
+}
+
+impl From<&Schema> for Shape {
+    fn from(schema: &Schema) -> Self {
+        let all = |schemas: &[Schema]| schemas.iter().map(Self::from).collect();
+        match schema {
+            Schema::Unknown => Self::Unknown,
+            Schema::Unit => Self::Unit,
+            Schema::Bool => Self::Bool,
+            Schema::I8 => Self::I8,
+            Schema::I16 => Self::I16,
+            Schema::I32 => Self::I32,
+            Schema::I64 => Self::I64,
+            Schema::I128 => Self::I128,
+            Schema::U8 => Self::U8,
+            Schema::U16 => Self::U16,
+            Schema::U32 => Self::U32,
+            Schema::U64 => Self::U64,
+            Schema::U128 => Self::U128,
+            Schema::F32 => Self::F32,
+            Schema::F64 => Self::F64,
+            Schema::Char => Self::Char,
+            Schema::Str => Self::Str,
+            Schema::Bytes => Self::Bytes,
+            Schema::Option(inner) => Self::Option(Box::new((&**inner).into())),
+            Schema::Seq(element) => Self::Seq(Box::new((&**element).into())),
+            Schema::Map(key, value) => Self::Map(
+                Box::new((&**key).into()),
+                Box::new((&**value).into()),
+            ),
+            Schema::Tuple(elements) => Self::Tuple(all(elements)),
+            Schema::UnitStruct(name) => Self::UnitStruct(static_name(name)),
+            Schema::NewtypeStruct(name, inner) => Self::NewtypeStruct(
+                static_name(name),
+                Box::new((&**inner).into()),
+            ),
+            Schema::TupleStruct(name, elements) => {
+                Self::TupleStruct(static_name(name), all(elements))
+            },
+            Schema::Struct(name, fields) => {
+                let (names, shapes) = static_fields(fields);
+                Self::Struct(static_name(name), names, shapes)
+            },
+            Schema::Enum(name, variants) => {
+                let count = variants
+                    .iter()
+                    .map(|variant| variant.index as usize + 1)
+                    .max()
+                    .unwrap_or_default();
+                let mut names = vec![""; count];
+                let mut payloads = vec![None; count];
+                for variant in variants {
+                    let index = variant.index as usize;
+                    names[index] = static_name(&variant.name);
+                    payloads[index] = Some(match &variant.payload {
+                        schema::Payload::Unit => Payload::Unit,
+                        schema::Payload::Newtype(inner) => {
+                            Payload::Newtype((&**inner).into())
+                        },
+                        schema::Payload::Tuple(elements) => {
+                            Payload::Tuple(all(elements))
+                        },
+                        schema::Payload::Struct(fields) => {
+                            let (names, shapes) = static_fields(fields);
+                            Payload::Struct(names, shapes)
+                        },
+                    });
+                }
+                Self::Enum(static_name(name), static_names(names), payloads)
+            },
+        }
+    }
+}
+
+fn static_name(name: &str) -> &'static str {
+    let mut names = NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(known) = names.get(name) {
+        return known;
+    }
+    let leaked: &'static str = Box::leak(name.into());
+    names.insert(leaked);
+    leaked
+}
+
+fn static_names(list: Vec<&'static str>) -> &'static [&'static str] {
+    let mut lists = NAME_LISTS.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(known) = lists.get(&list[..]) {
+        return known;
+    }
+    let leaked: &'static [&'static str] = Box::leak(list.into_boxed_slice());
+    lists.insert(leaked);
+    leaked
+}
+
+fn static_fields(
+    fields: &[schema::Field],
+) -> (&'static [&'static str], Vec<Shape>) {
+    let names = fields.iter().map(|field| static_name(&field.name)).collect();
+    let shapes =
+        fields.iter().map(|field| Shape::from(&field.schema)).collect();
+    (static_names(names), shapes)
+}
+
+fn descend(
+    shape: &Shape,
+    index: usize,
+    path: &mut Vec<usize>,
+    plan: &mut Plan,
+) -> bool {
+    path.push(index);
+    let found = shape.find_gap(path, plan);
+    path.pop();
+    found
+}
+
+fn merge_all(shapes: &mut [Shape], others: &[Shape]) {
+    for (shape, other) in shapes.iter_mut().zip(others) {
+        shape.merge(other.clone());
+    }
+}
+
+type Plan = HashMap<Vec<usize>, u32>;
+
+#[derive(Debug, Error)]
+#[error("Type cannot be probed for its shape")]
+struct Unprobeable;
+
+impl de::Error for Unprobeable {
+    fn custom<T>(_msg: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Self
+    }
+}
+
+#[derive(Debug)]
+struct Prober {
+    plan: Plan,
+    path: Vec<usize>,
+    names: Vec<&'static str>,
+}
+
+impl Prober {
+    fn run<'de, T>(plan: Plan) -> Option<Shape>
+    where
+        T: Deserialize<'de>,
+    {
+        let mut prober = Self { plan, path: Vec::new(), names: Vec::new() };
+        let mut shape = Shape::Unknown;
+        T::deserialize(Probe { prober: &mut prober, slot: &mut shape }).ok()?;
+        Some(shape)
+    }
+
+    fn child<T, F>(&mut self, index: usize, probe: F) -> Result<T, Unprobeable>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Unprobeable>,
+    {
+        if self.path.len() >= MAX_PROBE_DEPTH {
+            Err(Unprobeable)?;
+        }
+        self.path.push(index);
+        let result = probe(self);
+        self.path.pop();
+        result
+    }
+
+    fn named<T, F>(
+        &mut self,
+        name: &'static str,
+        probe: F,
+    ) -> Result<T, Unprobeable>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Unprobeable>,
+    {
+        if self.names.contains(&name) {
+            Err(Unprobeable)?;
+        }
+        self.names.push(name);
+        let result = probe(self);
+        self.names.pop();
+        result
+    }
+}
+
+struct Probe<'a> {
+    prober: &'a mut Prober,
+    slot: &'a mut Shape,
+}
+
+fn probe_seq<'de, V>(
+    prober: &mut Prober,
+    len: usize,
+    visitor: V,
+) -> Result<(V::Value, Vec<Shape>), Unprobeable>
+where
+    V: Visitor<'de>,
+{
+    let mut shapes = vec![Shape::Unknown; len];
+    let value = visitor.visit_seq(ProbeSeq {
+        prober,
+        shapes: &mut shapes,
+        index: 0,
+    })?;
+    Ok((value, shapes))
+}
+
+macro_rules! probe_primitive {
+    ($($method:ident => $shape:ident, $visit:ident($($value:expr)?)),* $(,)?) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                *self.slot = Shape::$shape;
+                visitor.$visit($($value)?)
+            }
+        )*
+    };
+}
+
+impl<'a, 'de> Deserializer<'de> for Probe<'a> {
+    type Error = Unprobeable;
+
+    probe_primitive! {
+        deserialize_bool => Bool, visit_bool(false),
+        deserialize_i8 => I8, visit_i8(1),
+        deserialize_i16 => I16, visit_i16(1),
+        deserialize_i32 => I32, visit_i32(1),
+        deserialize_i64 => I64, visit_i64(1),
+        deserialize_i128 => I128, visit_i128(1),
+        deserialize_u8 => U8, visit_u8(1),
+        deserialize_u16 => U16, visit_u16(1),
+        deserialize_u32 => U32, visit_u32(1),
+        deserialize_u64 => U64, visit_u64(1),
+        deserialize_u128 => U128, visit_u128(1),
+        deserialize_f32 => F32, visit_f32(0.0),
+        deserialize_f64 => F64, visit_f64(0.0),
+        deserialize_char => Char, visit_char('\0'),
+        deserialize_str => Str, visit_borrowed_str(""),
+        deserialize_string => Str, visit_borrowed_str(""),
+        deserialize_bytes => Bytes, visit_borrowed_bytes(b""),
+        deserialize_byte_buf => Bytes, visit_borrowed_bytes(b""),
+        deserialize_unit => Unit, visit_unit(),
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(Unprobeable)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut inner = Shape::Unknown;
+        let value = self.prober.child(0, |prober| {
+            visitor.visit_some(Probe { prober, slot: &mut inner })
+        })?;
+        *self.slot = Shape::Option(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        *self.slot = Shape::UnitStruct(name);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut inner = Shape::Unknown;
+        let value = self.prober.named(name, |prober| {
+            prober.child(0, |prober| {
+                visitor.visit_newtype_struct(Probe { prober, slot: &mut inner })
+            })
+        })?;
+        *self.slot = Shape::NewtypeStruct(name, Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (value, mut shapes) = probe_seq(self.prober, 1, visitor)?;
+        *self.slot = Shape::Seq(Box::new(shapes.pop().unwrap_or_default()));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (value, shapes) = probe_seq(self.prober, len, visitor)?;
+        *self.slot = Shape::Tuple(shapes);
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (value, shapes) = self
+            .prober
+            .named(name, |prober| probe_seq(prober, len, visitor))?;
+        *self.slot = Shape::TupleStruct(name, shapes);
+        Ok(value)
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let mut shapes = [Shape::Unknown, Shape::Unknown];
+        let value = visitor.visit_map(ProbeMap {
+            prober: self.prober,
+            shapes: &mut shapes,
+            index: 0,
+        })?;
+        let [key, value_shape] = shapes;
+        *self.slot = Shape::Map(Box::new(key), Box::new(value_shape));
+        Ok(value)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (value, shapes) = self
+            .prober
+            .named(name, |prober| probe_seq(prober, fields.len(), visitor))?;
+        *self.slot = Shape::Struct(name, fields, shapes);
+        Ok(value)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let variant = self.prober.plan.get(&self.prober.path).copied();
+        let variant = variant.unwrap_or_default();
+        if variant as usize >= variants.len() {
+            Err(Unprobeable)?;
+        }
+        let mut payload = None;
+        let value = self.prober.named(name, |prober| {
+            prober.child(variant as usize, |prober| {
+                visitor.visit_enum(ProbeEnum {
+                    prober,
+                    variant,
+                    payload: &mut payload,
+                })
+            })
+        })?;
+        let mut payloads = vec![None; variants.len()];
+        payloads[variant as usize] = Some(payload.ok_or(Unprobeable)?);
+        *self.slot = Shape::Enum(name, variants, payloads);
+        Ok(value)
+    }
+
+    fn deserialize_identifier<V>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(Unprobeable)
+    }
+
+    fn deserialize_ignored_any<V>(
+        self,
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(Unprobeable)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct ProbeSeq<'a> {
+    prober: &'a mut Prober,
+    shapes: &'a mut [Shape],
+    index: usize,
+}
+
+impl<'a, 'de> de::SeqAccess<'de> for ProbeSeq<'a> {
+    type Error = Unprobeable;
+
+    fn next_element_seed<T>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let index = self.index;
+        let Some(slot) = self.shapes.get_mut(index) else {
+            return Ok(None);
+        };
+        self.index += 1;
+        self.prober
+            .child(index, |prober| seed.deserialize(Probe { prober, slot }))
+            .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.shapes.len() - self.index)
+    }
+}
+
+struct ProbeMap<'a> {
+    prober: &'a mut Prober,
+    shapes: &'a mut [Shape; 2],
+    index: usize,
+}
+
+impl<'a, 'de> de::MapAccess<'de> for ProbeMap<'a> {
+    type Error = Unprobeable;
+
+    fn next_key_seed<K>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.index > 0 {
+            return Ok(None);
+        }
+        self.index += 1;
+        let slot = &mut self.shapes[0];
+        self.prober
+            .child(0, |prober| seed.deserialize(Probe { prober, slot }))
+            .map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let slot = &mut self.shapes[1];
+        self.prober.child(1, |prober| seed.deserialize(Probe { prober, slot }))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(1 - self.index)
+    }
+}
+
+struct ProbeEnum<'a> {
+    prober: &'a mut Prober,
+    variant: u32,
+    payload: &'a mut Option<Payload>,
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for ProbeEnum<'a> {
+    type Error = Unprobeable;
+    type Variant = Self;
+
+    fn variant_seed<V>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'de> de::VariantAccess<'de> for ProbeEnum<'a> {
+    type Error = Unprobeable;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        *self.payload = Some(Payload::Unit);
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let mut shape = Shape::Unknown;
+        let value = self.prober.child(0, |prober| {
+            seed.deserialize(Probe { prober, slot: &mut shape })
+        })?;
+        *self.payload = Some(Payload::Newtype(shape));
+        Ok(value)
+    }
+
+    fn tuple_variant<V>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (value, shapes) = probe_seq(self.prober, len, visitor)?;
+        *self.payload = Some(Payload::Tuple(shapes));
+        Ok(value)
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let (value, shapes) = probe_seq(self.prober, fields.len(), visitor)?;
+        *self.payload = Some(Payload::Struct(fields, shapes));
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ShapeSeed<'s>(pub &'s Shape);
+
+impl<'s, 'de> DeserializeSeed<'de> for ShapeSeed<'s> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match self.0 {
+            Shape::Unknown => Err(de::Error::custom("unknown shape")),
+            Shape::Unit => <()>::deserialize(deserializer),
+            Shape::Bool => bool::deserialize(deserializer).map(drop),
+            Shape::I8 => i8::deserialize(deserializer).map(drop),
+            Shape::I16 => i16::deserialize(deserializer).map(drop),
+            Shape::I32 => i32::deserialize(deserializer).map(drop),
+            Shape::I64 => i64::deserialize(deserializer).map(drop),
+            Shape::I128 => i128::deserialize(deserializer).map(drop),
+            Shape::U8 => u8::deserialize(deserializer).map(drop),
+            Shape::U16 => u16::deserialize(deserializer).map(drop),
+            Shape::U32 => u32::deserialize(deserializer).map(drop),
+            Shape::U64 => u64::deserialize(deserializer).map(drop),
+            Shape::U128 => u128::deserialize(deserializer).map(drop),
+            Shape::F32 => f32::deserialize(deserializer).map(drop),
+            Shape::F64 => f64::deserialize(deserializer).map(drop),
+            Shape::Char => char::deserialize(deserializer).map(drop),
+            Shape::Str => {
+                deserializer.deserialize_newtype_struct(SKIP_STR_NAME, Skip)
+            },
+            Shape::Bytes => {
+                deserializer.deserialize_newtype_struct(SKIP_BYTES_NAME, Skip)
+            },
+            Shape::Option(inner) => {
+                deserializer.deserialize_option(OptionVisitor(inner))
+            },
+            Shape::Seq(element) => {
+                deserializer.deserialize_seq(SeqVisitor(element))
+            },
+            Shape::Map(key, value) => {
+                deserializer.deserialize_map(MapVisitor(key, value))
+            },
+            Shape::Tuple(shapes) => deserializer
+                .deserialize_tuple(shapes.len(), TupleVisitor(shapes)),
+            Shape::UnitStruct(name) => {
+                deserializer.deserialize_unit_struct(name, Skip)
+            },
+            Shape::NewtypeStruct(name, inner) => deserializer
+                .deserialize_newtype_struct(name, NewtypeVisitor(inner)),
+            Shape::TupleStruct(name, shapes) => deserializer
+                .deserialize_tuple_struct(
+                    name,
+                    shapes.len(),
+                    TupleVisitor(shapes),
+                ),
+            Shape::Struct(name, fields, shapes) => deserializer
+                .deserialize_struct(
+                    name,
+                    fields,
+                    StructVisitor(fields, shapes),
+                ),
+            Shape::Enum(name, variants, payloads) => deserializer
+                .deserialize_enum(
+                    name,
+                    variants,
+                    EnumVisitor(variants, payloads),
+                ),
+        }
+    }
+}
+
+struct Skip;
+
+impl<'de> Visitor<'de> for Skip {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a skipped value")
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        Ok(())
+    }
+}
+
+struct OptionVisitor<'s>(&'s Shape);
+
+impl<'s, 'de> Visitor<'de> for OptionVisitor<'s> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an option")
+    }
+
+    fn visit_none<E>(self) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        Ok(())
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        Ok(())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        ShapeSeed(self.0).deserialize(deserializer)
+    }
+}
+
+struct NewtypeVisitor<'s>(&'s Shape);
+
+impl<'s, 'de> Visitor<'de> for NewtypeVisitor<'s> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a newtype struct")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        ShapeSeed(self.0).deserialize(deserializer)
+    }
+
+    fn visit_bytes<E>(self, _v: &[u8]) -> Result<(), E>
+    where
+        E: de::Error,
+    {
+        Ok(())
+    }
+}
+
+struct SeqVisitor<'s>(&'s Shape);
+
+impl<'s, 'de> Visitor<'de> for SeqVisitor<'s> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a sequence")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        while seq.next_element_seed(ShapeSeed(self.0))?.is_some() {}
+        Ok(())
+    }
+}
+
+struct MapVisitor<'s>(&'s Shape, &'s Shape);
+
+impl<'s, 'de> Visitor<'de> for MapVisitor<'s> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a map")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        while map.next_key_seed(ShapeSeed(self.0))?.is_some() {
+            map.next_value_seed(ShapeSeed(self.1))?;
+        }
+        Ok(())
+    }
+}
+
+struct TupleVisitor<'s>(&'s [Shape]);
+
+impl<'s, 'de> Visitor<'de> for TupleVisitor<'s> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a tuple of {} elements", self.0.len())
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        for (index, shape) in self.0.iter().enumerate() {
+            if seq.next_element_seed(ShapeSeed(shape))?.is_none() {
+                Err(de::Error::invalid_length(index, &self))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct StructVisitor<'s>(&'static [&'static str], &'s [Shape]);
+
+impl<'s, 'de> Visitor<'de> for StructVisitor<'s> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a struct")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        for shape in self.1 {
+            if seq.next_element_seed(ShapeSeed(shape))?.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        while let Some(index) = map.next_key_seed(NameSeed(self.0))? {
+            match index.and_then(|index| self.1.get(index)) {
+                Some(shape) => map.next_value_seed(ShapeSeed(shape))?,
+                None => map.next_value::<IgnoredAny>().map(drop)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+struct EnumVisitor<'s>(&'static [&'static str], &'s [Option<Payload>]);
+
+impl<'s, 'de> Visitor<'de> for EnumVisitor<'s> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "one of the variants {:?}", self.0)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<(), A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        use de::VariantAccess;
+
+        let (index, variant) = data.variant_seed(NameSeed(self.0))?;
+        let payload = index.and_then(|index| self.1.get(index)?.as_ref());
+        let Some(payload) = payload else {
+            return Err(de::Error::custom("unknown variant"));
+        };
+        match payload {
+            Payload::Unit => variant.unit_variant(),
+            Payload::Newtype(shape) => {
+                variant.newtype_variant_seed(ShapeSeed(shape))
+            },
+            Payload::Tuple(shapes) => {
+                variant.tuple_variant(shapes.len(), TupleVisitor(shapes))
+            },
+            Payload::Struct(fields, shapes) => {
+                variant.struct_variant(fields, StructVisitor(fields, shapes))
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct NameSeed(&'static [&'static str]);
+
+impl<'de> DeserializeSeed<'de> for NameSeed {
+    type Value = Option<usize>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de> Visitor<'de> for NameSeed {
+    type Value = Option<usize>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "an identifier among {:?}", self.0)
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(usize::try_from(v).ok().filter(|&index| index < self.0.len()))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.0.iter().position(|name| *name == v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(self.0.iter().position(|name| name.as_bytes() == v))
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{Payload, Shape};
+use crate::{de, schema::Schema, ser};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Event {
+    Ping,
+    Move(i16, i16),
+    Rename { to: String },
+    Tagged(Option<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Log {
+    origin: (u8, char),
+    parent: Option<u64>,
+    title: String,
+    events: Vec<Event>,
+    counters: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Tree {
+    label: u8,
+    children: Vec<Tree>,
+}
+
+fn log() -> Log {
+    Log {
+        origin: (3, 'λ'),
+        parent: Some(9),
+        title: "ça va ".repeat(100),
+        events: vec![
+            Event::Ping,
+            Event::Move(-1, 2),
+            Event::Rename { to: "dst".to_owned() },
+            Event::Tagged(None),
+            Event::Tagged(Some("tag".to_owned())),
+        ],
+        counters: BTreeMap::from([("a".to_owned(), 1), ("b".to_owned(), 2)]),
+    }
+}
+
+#[test]
+fn probes_every_variant() {
+    let Some(Shape::Enum("Event", _, payloads)) = Shape::of::<Event>() else {
+        panic!("expected an enum shape");
+    };
+    assert_eq!(payloads.len(), 4);
+    assert_eq!(payloads[0], Some(Payload::Unit));
+    assert_eq!(payloads[1], Some(Payload::Tuple(vec![Shape::I16, Shape::I16])));
+    assert_eq!(
+        payloads[3],
+        Some(Payload::Newtype(Shape::Option(Box::new(Shape::Str))))
+    );
+    assert_eq!(Shape::of::<Tree>(), None);
+}
+
+#[test]
+fn validates_without_decoding() -> Result<()> {
+    let buf = ser::serialize_into_buffer(log())?;
+    de::validate::<Log>(&buf)?;
+    assert!(de::validate::<Log>(&buf[.. buf.len() - 1]).is_err());
+
+    let title = buf.windows(3).position(|window| window == "ça".as_bytes());
+    let mut corrupted = buf.clone();
+    corrupted[title.expect("title is encoded") + 1] = 0xff;
+    let error = de::validate::<Log>(&corrupted).unwrap_err();
+    assert_eq!(error.kind(), de::ErrorKind::InvalidData);
+    Ok(())
+}
+
+#[test]
+fn rejects_unknown_variant() -> Result<()> {
+    let mut buf = ser::serialize_into_buffer(Event::Ping)?;
+    de::validate::<Event>(&buf)?;
+    buf[0] = 7;
+    assert!(de::validate::<Event>(&buf).is_err());
+    Ok(())
+}
+
+#[test]
+fn follows_the_config() -> Result<()> {
+    let mut ser_config = ser::Config::new();
+    ser_config.with_self_describing().with_interned_strings();
+    let buf = ser_config.serialize_into_buffer(log())?;
+    let mut de_config = de::Config::new();
+    de_config.with_self_describing().with_interned_strings();
+    de_config.validate::<Log>(&buf)?;
+    assert!(de::validate::<Log>(&buf).is_err());
+    Ok(())
+}
+
+#[test]
+fn falls_back_for_recursive_types() -> Result<()> {
+    let tree = Tree {
+        label: 0,
+        children: vec![Tree { label: 1, children: Vec::new() }],
+    };
+    let buf = ser::serialize_into_buffer(tree)?;
+    de::validate::<Tree>(&buf)?;
+    assert!(de::validate::<Tree>(&buf[.. buf.len() - 1]).is_err());
+    Ok(())
+}
+
+#[test]
+fn skips_one_value_by_schema() -> Result<()> {
+    let schema = Schema::of(&log())?;
+    let mut buf = ser::serialize_into_buffer(log())?;
+    buf.extend(ser::serialize_into_buffer(7_u32)?);
+    let mut source = de::BufferSource::new(&buf[..]);
+    de::skip_value(&mut source, &schema)?;
+    assert_eq!(de::deserialize_buffer::<u32>(source.remaining())?, 7);
+
+    let partial = Schema::of(&Event::Ping)?;
+    let buf = ser::serialize_into_buffer(Event::Move(1, 2))?;
+    let mut source = de::BufferSource::new(&buf[..]);
+    assert!(de::skip_value(&mut source, &partial).is_err());
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{from_value, to_value, Value};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Point,
+    Circle { radius: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Drawing {
+    name: String,
+    layer: Option<u8>,
+    shapes: Vec<Shape>,
+}
+
+#[test]
+fn walk_without_type() -> Result<()> {
+    let drawing = Drawing {
+        name: "sketch".to_owned(),
+        layer: Some(2),
+        shapes: vec![Shape::Point, Shape::Circle { radius: 1.5 }],
+    };
+    let buf = crate::ser::Config::new()
+        .with_self_describing()
+        .serialize_into_buffer(&drawing)?;
+
+    let value = to_value(&buf)?;
+    let str = |text: &str| Value::Str(text.to_owned());
+    assert_eq!(
+        value,
+        Value::Map(vec![
+            (str("name"), str("sketch")),
+            (str("layer"), Value::Some(Box::new(Value::U8(2)))),
+            (
+                str("shapes"),
+                Value::Seq(vec![
+                    str("Point"),
+                    Value::Map(vec![(
+                        str("Circle"),
+                        Value::Map(vec![(str("radius"), Value::F32(1.5))]),
+                    )]),
+                ]),
+            ),
+        ])
+    );
+
+    let buf = from_value(&value)?;
+    let decoded: Drawing = crate::de::Config::new()
+        .with_self_describing()
+        .deserialize_buffer(&buf)?;
+    assert_eq!(decoded, drawing);
+    Ok(())
+}
+
+#[test]
+fn scalars_roundtrip() -> Result<()> {
+    let value = Value::Seq(vec![
+        Value::Unit,
+        Value::Bool(true),
+        Value::I128(-7),
+        Value::U16(300),
+        Value::F64(0.5),
+        Value::Char('ç'),
+        Value::Bytes(vec![1, 2, 3]),
+        Value::None,
+        Value::Map(vec![(Value::U8(1), Value::I8(-1))]),
+    ]);
+    assert_eq!(to_value(&from_value(&value)?)?, value);
+
+    let map = BTreeMap::from([(1_u32, "one")]);
+    let buf = crate::ser::Config::new()
+        .with_self_describing()
+        .serialize_into_buffer(&map)?;
+    assert_eq!(
+        to_value(&buf)?,
+        Value::Map(vec![(Value::U32(1), Value::Str("one".to_owned()))])
+    );
+
+    let error = to_value(&crate::serialize_into_buffer(0xff_u8)?).unwrap_err();
+    assert!(matches!(error.inner(), crate::de::Error::InvalidTag(0xff)));
+    Ok(())
+}
@@ -0,0 +1,80 @@
+const U16_MARKER: u8 = 251;
+const U32_MARKER: u8 = 252;
+const U64_MARKER: u8 = 253;
+const U128_MARKER: u8 = 254;
+
+pub(crate) const MAX_ENCODED_LEN: usize = 19;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum IntEncoding {
+    #[default]
+    Fixint,
+    Varint,
+    Leb128,
+}
+
+pub(crate) fn encode(value: u128, buf: &mut [u8; MAX_ENCODED_LEN]) -> &[u8] {
+    if value < u128::from(U16_MARKER) {
+        buf[0] = value as u8;
+        return &buf[.. 1];
+    }
+    let (marker, len) = if value <= u128::from(u16::MAX) {
+        (U16_MARKER, 2)
+    } else if value <= u128::from(u32::MAX) {
+        (U32_MARKER, 4)
+    } else if value <= u128::from(u64::MAX) {
+        (U64_MARKER, 8)
+    } else {
+        (U128_MARKER, 16)
+    };
+    buf[0] = marker;
+    buf[1 ..= len].copy_from_slice(&value.to_le_bytes()[.. len]);
+    &buf[..= len]
+}
+
+pub(crate) fn encoded_len(value: u128) -> usize {
+    encode(value, &mut [0; MAX_ENCODED_LEN]).len()
+}
+
+pub(crate) fn encode_leb128(
+    mut value: u128,
+    buf: &mut [u8; MAX_ENCODED_LEN],
+) -> &[u8] {
+    let mut len = 0;
+    while value >= 0x80 {
+        buf[len] = value as u8 | 0x80;
+        value >>= 7;
+        len += 1;
+    }
+    buf[len] = value as u8;
+    &buf[..= len]
+}
+
+pub(crate) fn payload_len(marker: u8) -> Option<usize> {
+    match marker {
+        U16_MARKER => Some(2),
+        U32_MARKER => Some(4),
+        U64_MARKER => Some(8),
+        U128_MARKER => Some(16),
+        0 .. U16_MARKER => Some(0),
+        _ => None,
+    }
+}
+
+pub(crate) fn decode(encoded: &[u8]) -> u128 {
+    let mut bytes = [0; 16];
+    match encoded {
+        [marker] => bytes[0] = *marker,
+        [_, payload @ ..] => bytes[.. payload.len()].copy_from_slice(payload),
+        [] => (),
+    }
+    u128::from_le_bytes(bytes)
+}
+
+pub(crate) fn zigzag(value: i128) -> u128 {
+    ((value << 1) ^ (value >> 127)) as u128
+}
+
+pub(crate) fn unzigzag(value: u128) -> i128 {
+    ((value >> 1) as i128) ^ -((value & 1) as i128)
+}
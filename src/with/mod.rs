@@ -0,0 +1,161 @@
+#[cfg(test)]
+mod test;
+
+pub mod fixed {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::repr::Fixed;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        Fixed(value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Fixed::deserialize(deserializer).map(Fixed::into_inner)
+    }
+}
+
+pub mod varint {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::repr::Var;
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        Var(value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Var::deserialize(deserializer).map(Var::into_inner)
+    }
+}
+
+pub mod utf8_char {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::repr::Utf8;
+
+    pub fn serialize<S>(value: &char, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Utf8(value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<char, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Utf8::deserialize(deserializer).map(Utf8::into_inner)
+    }
+}
+
+pub mod compact_duration {
+    use std::time::Duration;
+
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::repr::Var;
+
+    pub fn serialize<S>(
+        value: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Var((value.as_secs(), value.subsec_nanos())).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let Var((secs, nanos)) = Var::<(u64, u32)>::deserialize(deserializer)?;
+        if nanos >= 1_000_000_000 {
+            Err(de::Error::invalid_value(
+                de::Unexpected::Unsigned(u64::from(nanos)),
+                &"fewer than 10^9 nanoseconds",
+            ))?;
+        }
+        Ok(Duration::new(secs, nanos))
+    }
+}
+
+pub mod hex {
+    use std::fmt::{self, Write};
+
+    use serde::{de, Deserializer, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: AsRef<[u8]>,
+        S: Serializer,
+    {
+        let bytes = value.as_ref();
+        let mut encoded = String::with_capacity(bytes.len() * 2);
+        for byte in bytes {
+            write!(encoded, "{:02x}", byte).expect("writing to a string");
+        }
+        serializer.serialize_str(&encoded)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: TryFrom<Vec<u8>>,
+        D: Deserializer<'de>,
+    {
+        let bytes = deserializer.deserialize_str(HexVisitor)?;
+        let len = bytes.len();
+        T::try_from(bytes).map_err(|_| {
+            de::Error::invalid_length(len, &"a fitting byte count")
+        })
+    }
+
+    struct HexVisitor;
+
+    impl de::Visitor<'_> for HexVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "a string of hexadecimal byte pairs")
+        }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            if !v.len().is_multiple_of(2) {
+                Err(E::invalid_length(v.len(), &self))?;
+            }
+            (0 .. v.len())
+                .step_by(2)
+                .map(|start| {
+                    v.get(start .. start + 2)
+                        .filter(|pair| {
+                            pair.bytes().all(|byte| byte.is_ascii_hexdigit())
+                        })
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                        .ok_or_else(|| {
+                            E::invalid_value(de::Unexpected::Str(v), &self)
+                        })
+                })
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{de, ser, varint::IntEncoding};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Sample {
+    #[serde(with = "crate::with::fixed")]
+    timestamp: u64,
+    #[serde(with = "crate::with::varint")]
+    count: u64,
+    #[serde(with = "crate::with::utf8_char")]
+    initial: char,
+    #[serde(with = "crate::with::compact_duration")]
+    elapsed: Duration,
+    #[serde(with = "crate::with::hex")]
+    digest: [u8; 3],
+}
+
+fn sample() -> Sample {
+    Sample {
+        timestamp: 1_700_000_000,
+        count: 3,
+        initial: 'é',
+        elapsed: Duration::new(2, 500),
+        digest: [0xca, 0xfe, 0x01],
+    }
+}
+
+#[test]
+fn with_helpers_roundtrip() -> Result<()> {
+    let buf = crate::serialize_into_buffer(sample())?;
+    let mut expected = 1_700_000_000_u64.to_le_bytes().to_vec();
+    expected.push(3);
+    expected.extend_from_slice("é".as_bytes());
+    expected.extend_from_slice(&[2, 251, 0xf4, 0x01]);
+    expected.extend_from_slice(&6_u64.to_le_bytes());
+    expected.extend_from_slice(b"cafe01");
+    assert_eq!(buf, expected);
+    let decoded: Sample = crate::deserialize_buffer(&buf)?;
+    assert_eq!(decoded, sample());
+
+    let mut ser_config = ser::Config::new();
+    ser_config.with_int_encoding(IntEncoding::Varint).with_self_describing();
+    let mut de_config = de::Config::new();
+    de_config.with_int_encoding(IntEncoding::Varint).with_self_describing();
+    let buf = ser_config.serialize_into_buffer(sample())?;
+    let decoded: Sample = de_config.deserialize_buffer(&buf)?;
+    assert_eq!(decoded, sample());
+    Ok(())
+}
+
+#[test]
+fn with_helper_errors() -> Result<()> {
+    let mut buf = crate::serialize_into_buffer(sample())?;
+    let len = buf.len();
+    buf[len - 1] = b'g';
+    let error = crate::deserialize_buffer::<Sample>(&buf).unwrap_err();
+    assert!(matches!(error.inner(), de::Error::InvalidValue { .. }));
+
+    let mut buf = crate::serialize_into_buffer(sample())?;
+    buf.truncate(len - 2);
+    buf[len - 14] = 4;
+    let error = crate::deserialize_buffer::<Sample>(&buf).unwrap_err();
+    assert!(matches!(error.inner(), de::Error::InvalidLength { .. }));
+    Ok(())
+}